@@ -0,0 +1,217 @@
+use byteorder::{BigEndian, ByteOrder};
+use std::{convert::TryInto, io};
+
+/// Returned by [`Vec::from_bytes()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("{message}")]
+    Corrupt { message: &'static str },
+}
+
+/// A bitmap compressed with the EWAH run-length encoding, which alternates between marker words describing runs of
+/// words with all bits set or cleared, and literal words that are taken as is.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Vec {
+    num_bits: u32,
+    words: std::vec::Vec<u64>,
+}
+
+const HEADER_LEN: usize = 8;
+const RLW_POSITION_LEN: usize = 4;
+const WORD_LEN: usize = 8;
+const WORD_BITS: usize = 64;
+
+const RUNNING_LEN_BITS: u32 = 32;
+const RUNNING_LEN_MASK: u64 = (1 << RUNNING_LEN_BITS) - 1;
+const LITERAL_WORDS_MAX: usize = (1 << (64 - 1 - RUNNING_LEN_BITS)) - 1;
+
+fn num_words_for(num_bits: u32) -> usize {
+    ((num_bits as u64 + 63) >> 6) as usize
+}
+
+fn run_bit(marker: u64) -> bool {
+    marker & 1 == 1
+}
+
+fn running_len(marker: u64) -> usize {
+    ((marker >> 1) & RUNNING_LEN_MASK) as usize
+}
+
+fn literal_words(marker: u64) -> usize {
+    (marker >> (1 + RUNNING_LEN_BITS)) as usize
+}
+
+impl Vec {
+    /// Decode a serialized EWAH bitmap from the beginning of `data`, returning it along with all bytes following it.
+    pub fn from_bytes(data: &[u8]) -> Result<(Vec, &[u8]), Error> {
+        if data.len() < HEADER_LEN {
+            return Err(corrupt("EWAH bitmap header is truncated"));
+        }
+        let num_bits = BigEndian::read_u32(data);
+        let num_words = BigEndian::read_u32(&data[4..]) as usize;
+        let words_end = num_words
+            .checked_mul(WORD_LEN)
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            .ok_or_else(|| corrupt("EWAH bitmap word count is too large"))?;
+        let end = words_end + RLW_POSITION_LEN;
+        if data.len() < end {
+            return Err(corrupt("EWAH bitmap is truncated"));
+        }
+        let words: std::vec::Vec<u64> = data[HEADER_LEN..words_end]
+            .chunks(WORD_LEN)
+            .map(BigEndian::read_u64)
+            .collect();
+
+        let max_words = num_words_for(num_bits);
+        let mut decompressed_words = 0;
+        let mut pos = 0;
+        while pos < words.len() {
+            let marker = words[pos];
+            pos += 1 + literal_words(marker);
+            decompressed_words += running_len(marker) + literal_words(marker);
+        }
+        if pos != words.len() {
+            return Err(corrupt("EWAH bitmap literal words extend beyond the end of the bitmap"));
+        }
+        if decompressed_words > max_words {
+            return Err(corrupt("EWAH bitmap contains more words than bits"));
+        }
+        let rlw_position = BigEndian::read_u32(data[words_end..end].try_into().expect("4 bytes")) as usize;
+        if num_words != 0 && rlw_position >= num_words {
+            return Err(corrupt("EWAH bitmap position of last marker word is out of bounds"));
+        }
+        Ok((Vec { num_bits, words }, &data[end..]))
+    }
+
+    /// Compress the uncompressed bitmap `words`, with the first bit being the least significant bit of the first word,
+    /// leaving out all trailing words without any set bit.
+    pub fn from_words(words: &[u64]) -> Vec {
+        let mut input = words;
+        while let Some((0, most)) = input.split_last() {
+            input = most;
+        }
+        Vec {
+            num_bits: (input.len() * WORD_BITS) as u32,
+            words: compress(input),
+        }
+    }
+
+    /// Create a bitmap from `bits`, the indices of all set bits, which ends with the highest set bit just like in git.
+    pub fn from_set_bits(bits: &[usize]) -> Vec {
+        let num_bits = bits.iter().max().map_or(0, |bit| bit + 1);
+        let mut words = vec![0u64; num_words_for(num_bits as u32)];
+        for bit in bits {
+            words[bit / WORD_BITS] |= 1 << (bit % WORD_BITS);
+        }
+        Vec {
+            num_bits: num_bits as u32,
+            words: compress(&words),
+        }
+    }
+
+    /// Serialize this bitmap into `out` the way git stores it.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        let mut buf = [0u8; WORD_LEN];
+        BigEndian::write_u32(&mut buf[..4], self.num_bits);
+        BigEndian::write_u32(&mut buf[4..], self.words.len() as u32);
+        out.write_all(&buf)?;
+        let mut last_marker = 0;
+        let mut next_marker = 0;
+        for (pos, word) in self.words.iter().enumerate() {
+            if pos == next_marker {
+                last_marker = pos;
+                next_marker = pos + 1 + literal_words(*word);
+            }
+            BigEndian::write_u64(&mut buf, *word);
+            out.write_all(&buf)?;
+        }
+        BigEndian::write_u32(&mut buf[..4], last_marker as u32);
+        out.write_all(&buf[..4])
+    }
+
+    /// The amount of words needed to store this bitmap, which is a measure for its size on disk.
+    pub fn num_words(&self) -> usize {
+        self.words.len()
+    }
+
+    /// The amount of bits represented by this bitmap.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits as usize
+    }
+
+    /// Decompress this bitmap into its words, with the first bit being the least significant bit of the first word.
+    pub fn decompress(&self) -> std::vec::Vec<u64> {
+        let mut out = std::vec::Vec::with_capacity(num_words_for(self.num_bits));
+        let mut pos = 0;
+        while pos < self.words.len() {
+            let marker = self.words[pos];
+            pos += 1;
+            let run_word = if run_bit(marker) { u64::MAX } else { 0 };
+            out.resize(out.len() + running_len(marker), run_word);
+            let num_literals = literal_words(marker);
+            out.extend_from_slice(&self.words[pos..pos + num_literals]);
+            pos += num_literals;
+        }
+        out
+    }
+
+    /// Call `f(index)` with the index of each set bit in ascending order.
+    pub fn for_each_set_bit(&self, mut f: impl FnMut(usize)) {
+        let mut index = 0;
+        let mut pos = 0;
+        while pos < self.words.len() {
+            let marker = self.words[pos];
+            pos += 1;
+            let run_bits = running_len(marker) * WORD_BITS;
+            if run_bit(marker) {
+                (index..index + run_bits).for_each(&mut f);
+            }
+            index += run_bits;
+            for word in &self.words[pos..pos + literal_words(marker)] {
+                (0..WORD_BITS)
+                    .filter(|bit| word & (1 << bit) != 0)
+                    .for_each(|bit| f(index + bit));
+                index += WORD_BITS;
+            }
+            pos += literal_words(marker);
+        }
+    }
+}
+
+/// Encode `input` as alternating marker words and literal words.
+fn compress(input: &[u64]) -> std::vec::Vec<u64> {
+    let is_clean = |word: u64| word == 0 || word == u64::MAX;
+
+    let mut words = std::vec::Vec::new();
+    let mut pos = 0;
+    loop {
+        let mut marker = 0;
+        if let Some(&run_word) = input.get(pos).filter(|word| is_clean(**word)) {
+            let run_len = input[pos..]
+                .iter()
+                .take(RUNNING_LEN_MASK as usize)
+                .take_while(|word| **word == run_word)
+                .count();
+            marker |= (run_word & 1) | ((run_len as u64) << 1);
+            pos += run_len;
+        }
+        let num_literals = input[pos..]
+            .iter()
+            .take(LITERAL_WORDS_MAX)
+            .take_while(|word| !is_clean(**word))
+            .count();
+        marker |= (num_literals as u64) << (1 + RUNNING_LEN_BITS);
+        words.push(marker);
+        words.extend_from_slice(&input[pos..pos + num_literals]);
+        pos += num_literals;
+        if pos == input.len() {
+            break;
+        }
+    }
+    words
+}
+
+fn corrupt(message: &'static str) -> Error {
+    Error::Corrupt { message }
+}
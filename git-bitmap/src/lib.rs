@@ -0,0 +1,6 @@
+//! Compressed bitmaps as used by git in `.bitmap` files next to packs, and in the split-index and untracked-cache
+//! extensions of the index.
+#![deny(missing_docs, unsafe_code, rust_2018_idioms)]
+
+///
+pub mod ewah;
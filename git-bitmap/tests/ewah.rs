@@ -0,0 +1,63 @@
+use git_bitmap::ewah;
+
+fn roundtrip(bitmap: &ewah::Vec) -> ewah::Vec {
+    let mut buf = Vec::new();
+    bitmap.write_to(&mut buf).expect("in-memory writes don't fail");
+    let (decoded, rest) = ewah::Vec::from_bytes(&buf).expect("valid bitmap");
+    assert!(rest.is_empty(), "the whole bitmap is consumed");
+    decoded
+}
+
+fn set_bits(bitmap: &ewah::Vec) -> Vec<usize> {
+    let mut out = Vec::new();
+    bitmap.for_each_set_bit(|bit| out.push(bit));
+    out
+}
+
+#[test]
+fn runs_of_clean_words_are_compressed() {
+    let words = [u64::MAX, u64::MAX, u64::MAX, 0, 0, 0b101, 0, 0];
+    let bitmap = ewah::Vec::from_words(&words);
+    assert_eq!(
+        bitmap.num_words(),
+        3,
+        "one marker for the ones, one for the zeros with a literal word"
+    );
+    assert_eq!(
+        bitmap.num_bits(),
+        6 * 64,
+        "trailing words without set bits are left out"
+    );
+    assert_eq!(bitmap.decompress(), &words[..6]);
+
+    let decoded = roundtrip(&bitmap);
+    assert_eq!(decoded, bitmap);
+    assert_eq!(
+        set_bits(&decoded),
+        (0..3 * 64).chain(vec![5 * 64, 5 * 64 + 2]).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn set_bits_end_with_the_highest_bit() {
+    let bits = vec![1, 63, 64, 200];
+    let bitmap = ewah::Vec::from_set_bits(&bits);
+    assert_eq!(bitmap.num_bits(), 201);
+    assert_eq!(set_bits(&roundtrip(&bitmap)), bits);
+
+    let empty = ewah::Vec::from_set_bits(&[]);
+    assert_eq!(empty.num_bits(), 0);
+    assert!(set_bits(&roundtrip(&empty)).is_empty());
+}
+
+#[test]
+fn corrupt_bitmaps_are_rejected() {
+    let mut buf = Vec::new();
+    ewah::Vec::from_set_bits(&[1, 100]).write_to(&mut buf).unwrap();
+    assert!(ewah::Vec::from_bytes(&buf[..buf.len() - 1]).is_err(), "truncated");
+    buf[3] = 1;
+    assert!(
+        ewah::Vec::from_bytes(&buf).is_err(),
+        "fewer bits than the words it contains"
+    );
+}
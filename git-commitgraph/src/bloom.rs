@@ -0,0 +1,175 @@
+//! Changed-path Bloom filters, which make it cheap to learn that a commit did *not* change a path.
+//!
+//! Each commit in a commit-graph file may have a filter containing all paths, including their leading directories,
+//! that were changed compared to its first parent, or compared to the empty tree for root commits.
+use bstr::{BStr, ByteSlice};
+#[cfg(feature = "write")]
+use std::collections::HashSet;
+
+const SEED0: u32 = 0x293a_e76f;
+const SEED1: u32 = 0x7e64_6e2c;
+const BITS_PER_WORD: u64 = 8;
+
+/// Filters of commits with more changed paths than this have all bits set instead.
+#[cfg(feature = "write")]
+pub(crate) const MAX_CHANGED_PATHS: usize = 512;
+
+/// The settings used to create all Bloom filters of a commit-graph file.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Settings {
+    /// The version of the hash function, either 1 or 2.
+    ///
+    /// Version 1 is what git writes by default. It hashes bytes as signed values, which differs from
+    /// version 2 for paths with non-ASCII characters.
+    pub hash_version: u32,
+    /// The amount of bits to set per path.
+    pub num_hashes: u32,
+    /// The amount of bits in a filter per changed path.
+    pub bits_per_entry: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            hash_version: 1,
+            num_hashes: 7,
+            bits_per_entry: 10,
+        }
+    }
+}
+
+impl Settings {
+    /// Returns true if filters created with these settings can be queried.
+    pub fn is_supported(&self) -> bool {
+        matches!(self.hash_version, 1 | 2)
+    }
+
+    fn hashes(&self, key: &[u8]) -> impl Iterator<Item = u32> {
+        let signed = self.hash_version == 1;
+        let hash0 = murmur3_seeded(SEED0, key, signed);
+        let hash1 = murmur3_seeded(SEED1, key, signed);
+        (0..self.num_hashes).map(move |i| hash0.wrapping_add(i.wrapping_mul(hash1)))
+    }
+}
+
+/// The Bloom filter of a single commit.
+#[derive(Clone, Copy, Debug)]
+pub struct Filter<'a> {
+    data: &'a [u8],
+    settings: Settings,
+}
+
+impl<'a> Filter<'a> {
+    pub(crate) fn new(data: &'a [u8], settings: Settings) -> Self {
+        Filter { data, settings }
+    }
+
+    /// Returns the raw bytes of this filter.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns false if `path` was certainly not changed, or true if it may have been changed.
+    ///
+    /// `path` is relative to the repository root and uses slashes as separators, like `dir/file`.
+    /// Empty filters weren't computed and hence can't rule out any change.
+    pub fn may_contain(&self, path: &BStr) -> bool {
+        if self.data.is_empty() {
+            return true;
+        }
+        // All leading directories of a changed path are in the filter as well, and each of them may rule out the path.
+        let mut path: &[u8] = path;
+        loop {
+            if !self.contains_key(path) {
+                return false;
+            }
+            match path.rfind_byte(b'/') {
+                Some(pos) => path = &path[..pos],
+                None => return true,
+            }
+        }
+    }
+
+    fn contains_key(&self, key: &[u8]) -> bool {
+        let num_bits = self.data.len() as u64 * BITS_PER_WORD;
+        self.settings.hashes(key).all(|hash| {
+            let bit = u64::from(hash) % num_bits;
+            self.data[(bit / BITS_PER_WORD) as usize] & (1 << (bit % BITS_PER_WORD)) != 0
+        })
+    }
+}
+
+/// The data of a filter for commits with more than [`MAX_CHANGED_PATHS`], which may contain any path.
+#[cfg(feature = "write")]
+pub(crate) fn too_many_changes() -> Vec<u8> {
+    vec![0xff]
+}
+
+/// Compute the data of a filter from all `changed_paths` of a commit.
+///
+/// Leading directories of the paths are added automatically.
+#[cfg(feature = "write")]
+pub(crate) fn compute<'p>(changed_paths: impl IntoIterator<Item = &'p BStr>, settings: Settings) -> Vec<u8> {
+    let mut keys = HashSet::new();
+    for path in changed_paths {
+        let mut path: &[u8] = path;
+        while keys.insert(path) {
+            match path.rfind_byte(b'/') {
+                Some(pos) => path = &path[..pos],
+                None => break,
+            }
+        }
+    }
+
+    let num_bits =
+        ((keys.len() as u64 * u64::from(settings.bits_per_entry)) + BITS_PER_WORD - 1) / BITS_PER_WORD * BITS_PER_WORD;
+    if num_bits == 0 {
+        return vec![0];
+    }
+    let mut data = vec![0; (num_bits / BITS_PER_WORD) as usize];
+    for key in keys {
+        for hash in settings.hashes(key) {
+            let bit = u64::from(hash) % num_bits;
+            data[(bit / BITS_PER_WORD) as usize] |= 1 << (bit % BITS_PER_WORD);
+        }
+    }
+    data
+}
+
+/// The 32 bit variant of murmur3, which sign-extends bytes in the `signed` variant just like git's first implementation.
+fn murmur3_seeded(seed: u32, data: &[u8], signed: bool) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let byte = |b: u8| if signed { b as i8 as u32 } else { u32::from(b) };
+    let scramble = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut hash = seed;
+    let chunks = data.chunks_exact(4);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let k = byte(chunk[0]) | byte(chunk[1]) << 8 | byte(chunk[2]) << 16 | byte(chunk[3]) << 24;
+        hash ^= scramble(k);
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let mut k = 0;
+    if tail.len() == 3 {
+        k ^= byte(tail[2]) << 16;
+    }
+    if tail.len() >= 2 {
+        k ^= byte(tail[1]) << 8;
+    }
+    if !tail.is_empty() {
+        k ^= byte(tail[0]);
+        hash ^= scramble(k);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash
+}
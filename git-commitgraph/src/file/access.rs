@@ -1,4 +1,8 @@
-use crate::file::{self, commit::Commit, File, COMMIT_DATA_ENTRY_SIZE};
+use crate::{
+    bloom,
+    file::{self, commit::Commit, File, COMMIT_DATA_ENTRY_SIZE},
+};
+use byteorder::{BigEndian, ByteOrder};
 use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 use std::{
     convert::TryInto,
@@ -6,6 +10,9 @@ use std::{
     path::Path,
 };
 
+// Keep this in sync with the value in `file::write`.
+const GENERATION_DATA_OVERFLOW_MASK: u32 = 0x8000_0000;
+
 /// Access
 impl File {
     /// The number of base graphs that this file depends on.
@@ -13,6 +20,41 @@ impl File {
         self.base_graph_count
     }
 
+    /// Returns the changed-path Bloom filter of the commit at the given lexigraphical position, if this file
+    /// contains Bloom filters.
+    ///
+    /// Filters whose location in the file is invalid are treated as if they didn't exist.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of bounds.
+    pub fn bloom_filter_at(&self, pos: file::Position) -> Option<bloom::Filter<'_>> {
+        assert!(
+            pos.0 < self.num_commits(),
+            "expected lexigraphical position less than {}, got {}",
+            self.num_commits(),
+            pos.0
+        );
+        let index_offset = self.bloom_filter_index_offset?;
+        let (range, settings) = self.bloom_filter_data.clone()?;
+        let read_end = |pos: usize| BigEndian::read_u32(&self.data[index_offset + pos * 4..]) as usize;
+        let pos = pos.0 as usize;
+        let end = read_end(pos);
+        let start = if pos == 0 { 0 } else { read_end(pos - 1) };
+        if start > end || end > range.len() {
+            return None;
+        }
+        Some(bloom::Filter::new(
+            &self.data[range.start + start..range.start + end],
+            settings,
+        ))
+    }
+
+    /// Returns the settings used to create the changed-path Bloom filters in this file, if it has any.
+    pub fn bloom_filter_settings(&self) -> Option<bloom::Settings> {
+        self.bloom_filter_data.as_ref().map(|(_, settings)| *settings)
+    }
+
     /// Returns the commit data for the commit located at the given lexigraphical position.
     ///
     /// `pos` must range from 0 to self.num_commits().
@@ -24,6 +66,14 @@ impl File {
         Commit::new(self, pos)
     }
 
+    /// Returns true if this file stores the corrected commit dates of its commits, also known as generation numbers v2.
+    ///
+    /// In a commit-graph consisting of multiple files, these are only usable if
+    /// [all files have them][crate::Graph::has_corrected_commit_dates()].
+    pub fn has_corrected_commit_dates(&self) -> bool {
+        self.generation_data_offset.is_some()
+    }
+
     /// The kind of hash used in this File.
     ///
     /// Note that it is always conforming to the hash used in the owning repository.
@@ -125,6 +175,24 @@ impl File {
         &self.data[start..start + COMMIT_DATA_ENTRY_SIZE]
     }
 
+    /// Returns the offset of the corrected commit date of the given commit to its commit date as stored in this file's
+    /// Generation Data (GDA2) chunk, or `None` if there is no such chunk or the offset overflows into a non-existing entry
+    /// of the Generation Data Overflow (GDO2) chunk.
+    pub(crate) fn corrected_commit_date_offset(&self, pos: file::Position) -> Option<u64> {
+        let offset = self.generation_data_offset?;
+        let pos: usize = pos
+            .0
+            .try_into()
+            .expect("an architecture able to hold 32 bits of integer");
+        let value = BigEndian::read_u32(&self.data[offset + pos * 4..]);
+        if value & GENERATION_DATA_OVERFLOW_MASK == 0 {
+            return Some(u64::from(value));
+        }
+        let overflow = &self.data[self.generation_data_overflow_range.clone()?];
+        let start = (value & !GENERATION_DATA_OVERFLOW_MASK) as usize * 8;
+        overflow.get(start..start + 8).map(BigEndian::read_u64)
+    }
+
     /// Returns the byte slice for this file's entire Extra Edge List (EDGE) chunk.
     pub(crate) fn extra_edges_data(&self) -> Option<&[u8]> {
         Some(&self.data[self.extra_edges_list_range.clone()?])
@@ -1,8 +1,10 @@
 //! Low-level operations on individual commits.
 use crate::{
+    bloom,
     file::{self, File},
     graph,
 };
+use bstr::BStr;
 use byteorder::{BigEndian, ByteOrder};
 use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 use std::{
@@ -56,6 +58,11 @@ impl<'a> Commit<'a> {
         }
     }
 
+    /// Returns the changed-path Bloom filter of this commit, if its file has Bloom filters.
+    pub fn bloom_filter(&self) -> Option<bloom::Filter<'a>> {
+        self.file.bloom_filter_at(self.pos)
+    }
+
     /// Returns the committer timestamp of this commit.
     ///
     /// The value is the number of seconds since 1970-01-01 00:00:00 UTC.
@@ -63,6 +70,19 @@ impl<'a> Commit<'a> {
         self.commit_timestamp
     }
 
+    /// Returns the corrected commit date of this commit, or `None` if its file doesn't store them.
+    ///
+    /// It's the largest of the commit's committer timestamp and the corrected commit dates of its parents plus one, which
+    /// makes it usable as generation number (v2) that is more effective in ruling out reachability than
+    /// [topological levels][Commit::generation()].
+    /// In a commit-graph consisting of multiple files, these are only usable if
+    /// [all files have them][crate::Graph::has_corrected_commit_dates()].
+    pub fn corrected_commit_date(&self) -> Option<u64> {
+        self.file
+            .corrected_commit_date_offset(self.pos)
+            .map(|offset| self.commit_timestamp.saturating_add(offset))
+    }
+
     /// Returns the generation number of this commit.
     ///
     /// Commits without parents have generation number 1. Commits with parents have a generation
@@ -87,6 +107,14 @@ impl<'a> Commit<'a> {
         self.file.id_at(self.pos)
     }
 
+    /// Returns `Some(false)` if this commit certainly didn't change `path` compared to its first parent, `Some(true)`
+    /// if it may have changed it, or `None` if there is no Bloom filter to tell.
+    ///
+    /// `path` is relative to the repository root and uses slashes as separators, like `dir/file`.
+    pub fn may_have_changed(&self, path: &BStr) -> Option<bool> {
+        self.bloom_filter().map(|filter| filter.may_contain(path))
+    }
+
     /// Returns the first parent of this commit.
     pub fn parent1(&self) -> Result<Option<graph::Position>, Error> {
         self.iter_parents().next().transpose()
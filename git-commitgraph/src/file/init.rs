@@ -1,4 +1,7 @@
-use crate::file::{File, COMMIT_DATA_ENTRY_SIZE, FAN_LEN, SIGNATURE};
+use crate::{
+    bloom,
+    file::{File, COMMIT_DATA_ENTRY_SIZE, FAN_LEN, SIGNATURE},
+};
 use bstr::ByteSlice;
 use byteorder::{BigEndian, ByteOrder};
 use filebuffer::FileBuffer;
@@ -9,7 +12,7 @@ use std::{
     path::Path,
 };
 
-type ChunkId = [u8; 4];
+pub(crate) type ChunkId = [u8; 4];
 
 /// The error used in [`File::at()`].
 #[derive(thiserror::Error, Debug)]
@@ -49,20 +52,26 @@ pub enum Error {
     UnsupportedVersion(u8),
 }
 
-const CHUNK_LOOKUP_SIZE: usize = 12;
-const HEADER_LEN: usize = 8;
+pub(crate) const CHUNK_LOOKUP_SIZE: usize = 12;
+pub(crate) const HEADER_LEN: usize = 8;
 const TRAILER_LEN: usize = SHA1_SIZE;
 const MIN_FILE_SIZE: usize = HEADER_LEN + ((MIN_CHUNKS + 1) * CHUNK_LOOKUP_SIZE) + TRAILER_LEN;
 const OID_LOOKUP_ENTRY_SIZE: usize = SHA1_SIZE;
+pub(crate) const BLOOM_FILTER_DATA_HEADER_LEN: usize = 12;
 
 // Required chunks: OIDF, OIDL, CDAT
 const MIN_CHUNKS: usize = 3;
-const BASE_GRAPHS_LIST_CHUNK_ID: ChunkId = *b"BASE";
-const COMMIT_DATA_CHUNK_ID: ChunkId = *b"CDAT";
-const EXTENDED_EDGES_LIST_CHUNK_ID: ChunkId = *b"EDGE";
-const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
-const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
-const SENTINEL_CHUNK_ID: ChunkId = [0u8; 4];
+pub(crate) const BASE_GRAPHS_LIST_CHUNK_ID: ChunkId = *b"BASE";
+pub(crate) const BLOOM_FILTER_DATA_CHUNK_ID: ChunkId = *b"BDAT";
+pub(crate) const BLOOM_FILTER_INDEX_CHUNK_ID: ChunkId = *b"BIDX";
+pub(crate) const COMMIT_DATA_CHUNK_ID: ChunkId = *b"CDAT";
+pub(crate) const EXTENDED_EDGES_LIST_CHUNK_ID: ChunkId = *b"EDGE";
+// git 2.36 replaced the "GDAT" and "GDOV" chunks as they were written incorrectly, and ignores them since.
+pub(crate) const GENERATION_DATA_CHUNK_ID: ChunkId = *b"GDA2";
+pub(crate) const GENERATION_DATA_OVERFLOW_CHUNK_ID: ChunkId = *b"GDO2";
+pub(crate) const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
+pub(crate) const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
+pub(crate) const SENTINEL_CHUNK_ID: ChunkId = [0u8; 4];
 
 impl File {
     /// Try to parse the commit graph file at `path`.
@@ -127,10 +136,16 @@ impl TryFrom<&Path> for File {
         }
 
         let mut base_graphs_list_offset: Option<usize> = None;
+        let mut bloom_filter_data: Option<(Range<usize>, bloom::Settings)> = None;
+        let mut bloom_filter_index_offset: Option<usize> = None;
+        let mut bloom_filter_index_size = 0usize;
         let mut commit_data_offset: Option<usize> = None;
         let mut commit_data_count = 0u32;
         let mut extra_edges_list_range: Option<Range<usize>> = None;
         let mut fan_offset: Option<usize> = None;
+        let mut generation_data_offset: Option<usize> = None;
+        let mut generation_data_count = 0u32;
+        let mut generation_data_overflow_range: Option<Range<usize>> = None;
         let mut oid_lookup_offset: Option<usize> = None;
         let mut oid_lookup_count = 0u32;
 
@@ -193,6 +208,39 @@ impl TryFrom<&Path> for File {
                     }
                     base_graphs_list_offset = Some(chunk_offset);
                 }
+                BLOOM_FILTER_DATA_CHUNK_ID => {
+                    if bloom_filter_data.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    if chunk_size < BLOOM_FILTER_DATA_HEADER_LEN {
+                        return Err(Error::InvalidChunkSize {
+                            id: chunk_id,
+                            msg: format!(
+                                "expected chunk length of at least {}, got {}",
+                                BLOOM_FILTER_DATA_HEADER_LEN, chunk_size
+                            ),
+                        });
+                    }
+                    let settings = bloom::Settings {
+                        hash_version: BigEndian::read_u32(&data[chunk_offset..]),
+                        num_hashes: BigEndian::read_u32(&data[chunk_offset + 4..]),
+                        bits_per_entry: BigEndian::read_u32(&data[chunk_offset + 8..]),
+                    };
+                    bloom_filter_data = Some((
+                        Range {
+                            start: chunk_offset + BLOOM_FILTER_DATA_HEADER_LEN,
+                            end: next_chunk_offset,
+                        },
+                        settings,
+                    ));
+                }
+                BLOOM_FILTER_INDEX_CHUNK_ID => {
+                    if bloom_filter_index_offset.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    bloom_filter_index_offset = Some(chunk_offset);
+                    bloom_filter_index_size = chunk_size;
+                }
                 COMMIT_DATA_CHUNK_ID => {
                     if commit_data_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
@@ -221,6 +269,36 @@ impl TryFrom<&Path> for File {
                         end: next_chunk_offset,
                     })
                 }
+                GENERATION_DATA_CHUNK_ID => {
+                    if generation_data_offset.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    if chunk_size % 4 != 0 {
+                        return Err(Error::InvalidChunkSize {
+                            id: chunk_id,
+                            msg: format!("chunk size {} is not a multiple of 4", chunk_size),
+                        });
+                    }
+                    generation_data_offset = Some(chunk_offset);
+                    generation_data_count = (chunk_size / 4)
+                        .try_into()
+                        .expect("number of commits in GDA2 chunk to fit in 32 bits");
+                }
+                GENERATION_DATA_OVERFLOW_CHUNK_ID => {
+                    if generation_data_overflow_range.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    if chunk_size % 8 != 0 {
+                        return Err(Error::InvalidChunkSize {
+                            id: chunk_id,
+                            msg: format!("chunk size {} is not a multiple of 8", chunk_size),
+                        });
+                    }
+                    generation_data_overflow_range = Some(Range {
+                        start: chunk_offset,
+                        end: next_chunk_offset,
+                    });
+                }
                 OID_FAN_CHUNK_ID => {
                     if fan_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
@@ -306,13 +384,34 @@ impl TryFrom<&Path> for File {
                 chunk2_commits: commit_data_count,
             });
         }
+        if generation_data_offset.is_some() && generation_data_count != fan[255] {
+            return Err(Error::CommitCountMismatch {
+                chunk1_id: OID_FAN_CHUNK_ID,
+                chunk1_commits: fan[255],
+                chunk2_id: GENERATION_DATA_CHUNK_ID,
+                chunk2_commits: generation_data_count,
+            });
+        }
+        // Like git, ignore Bloom filters that are incomplete, don't cover all commits or which we can't query.
+        let (bloom_filter_index_offset, bloom_filter_data) = match (bloom_filter_index_offset, bloom_filter_data) {
+            (Some(index_offset), Some((range, settings)))
+                if settings.is_supported() && bloom_filter_index_size == fan[255] as usize * 4 =>
+            {
+                (Some(index_offset), Some((range, settings)))
+            }
+            _ => (None, None),
+        };
         Ok(File {
             base_graph_count,
             base_graphs_list_offset,
+            bloom_filter_data,
+            bloom_filter_index_offset,
             commit_data_offset,
             data,
             extra_edges_list_range,
             fan,
+            generation_data_offset,
+            generation_data_overflow_range,
             oid_lookup_offset,
             path: path.to_owned(),
         })
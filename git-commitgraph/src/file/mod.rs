@@ -6,6 +6,10 @@ pub mod commit;
 pub use commit::Commit;
 
 mod init;
+#[cfg(feature = "write")]
+mod write;
+#[cfg(feature = "write")]
+pub(crate) use write::{write, Entry};
 pub mod verify;
 
 pub use init::Error;
@@ -29,10 +33,14 @@ const SIGNATURE: &[u8] = b"CGPH";
 pub struct File {
     base_graph_count: u8,
     base_graphs_list_offset: Option<usize>,
+    bloom_filter_data: Option<(Range<usize>, crate::bloom::Settings)>,
+    bloom_filter_index_offset: Option<usize>,
     commit_data_offset: usize,
     data: FileBuffer,
     extra_edges_list_range: Option<Range<usize>>,
     fan: [u32; FAN_LEN],
+    generation_data_offset: Option<usize>,
+    generation_data_overflow_range: Option<Range<usize>>,
     oid_lookup_offset: usize,
     path: PathBuf,
 }
@@ -28,6 +28,8 @@ pub enum Error<E: std::error::Error + 'static> {
         pos: file::Position,
         predecessor_id: git_hash::ObjectId,
     },
+    #[error("commit {id} has a corrected commit date offset that refers to a missing overflow entry")]
+    CorrectedCommitDate { id: git_hash::ObjectId },
     #[error("commit-graph filename should be {0}")]
     Filename(String),
     #[error("commit {id} has invalid generation {generation}")]
@@ -120,6 +122,10 @@ impl File {
                 });
             }
 
+            if self.has_corrected_commit_dates() && commit.corrected_commit_date().is_none() {
+                return Err(Error::CorrectedCommitDate { id: commit.id().into() });
+            }
+
             processor(&commit).map_err(Error::Processor)?;
 
             stats.max_generation = max(stats.max_generation, commit.generation());
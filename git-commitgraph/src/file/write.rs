@@ -0,0 +1,191 @@
+use crate::{
+    bloom,
+    file::{
+        init::{
+            ChunkId, BASE_GRAPHS_LIST_CHUNK_ID, BLOOM_FILTER_DATA_CHUNK_ID, BLOOM_FILTER_DATA_HEADER_LEN,
+            BLOOM_FILTER_INDEX_CHUNK_ID, CHUNK_LOOKUP_SIZE, COMMIT_DATA_CHUNK_ID, EXTENDED_EDGES_LIST_CHUNK_ID,
+            GENERATION_DATA_CHUNK_ID, GENERATION_DATA_OVERFLOW_CHUNK_ID, HEADER_LEN, OID_FAN_CHUNK_ID,
+            OID_LOOKUP_CHUNK_ID, SENTINEL_CHUNK_ID,
+        },
+        COMMIT_DATA_ENTRY_SIZE, FAN_LEN, SIGNATURE,
+    },
+    graph,
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_hash::ObjectId;
+use std::io::{self, Write};
+
+// Keep these in sync with the values in `file::commit`.
+const NO_PARENT: u32 = 0x7000_0000;
+const EXTENDED_EDGES_MASK: u32 = 0x8000_0000;
+const LAST_EXTENDED_EDGE_MASK: u32 = 0x8000_0000;
+// Keep this in sync with the value in `file::access`.
+const GENERATION_DATA_OVERFLOW_MASK: u32 = 0x8000_0000;
+
+/// A commit as it is to be written into a commit-graph file.
+pub(crate) struct Entry {
+    pub id: ObjectId,
+    pub root_tree_id: ObjectId,
+    /// The positions of all parents in the graph this file is a part of.
+    pub parents: Vec<graph::Position>,
+    pub generation: u32,
+    pub commit_timestamp: u64,
+    pub corrected_commit_date: u64,
+    /// The data of the changed-path Bloom filter, or empty if it wasn't computed.
+    pub bloom_filter: Vec<u8>,
+}
+
+/// Write a commit-graph file with all `entries`, which must be sorted by id, to `out` and return its checksum.
+///
+/// `base_graph_ids` are the checksums of the files this one is based on, starting with the lowest one in the chain.
+/// If `corrected_commit_dates` is true, the corrected commit dates of all entries are written, and if `bloom_settings`
+/// are set, the Bloom filters of all entries are written as well.
+pub(crate) fn write(
+    entries: &[Entry],
+    base_graph_ids: &[ObjectId],
+    corrected_commit_dates: bool,
+    bloom_settings: Option<bloom::Settings>,
+    out: impl io::Write,
+) -> io::Result<ObjectId> {
+    debug_assert!(entries.windows(2).all(|w| w[0].id < w[1].id), "entries are sorted");
+    let extra_edges: Vec<u32> = entries
+        .iter()
+        .filter(|e| e.parents.len() > 2)
+        .flat_map(|e| {
+            let last = e.parents.len() - 1;
+            e.parents[1..].iter().enumerate().map(move |(i, p)| {
+                if i + 1 == last {
+                    p.0 | LAST_EXTENDED_EDGE_MASK
+                } else {
+                    p.0
+                }
+            })
+        })
+        .collect();
+
+    let generation_data_overflows: Vec<u64> = entries
+        .iter()
+        .map(|e| e.corrected_commit_date - e.commit_timestamp)
+        .filter(|offset| *offset > u64::from(!GENERATION_DATA_OVERFLOW_MASK))
+        .collect();
+
+    let mut chunks: Vec<(ChunkId, usize)> = vec![
+        (OID_FAN_CHUNK_ID, FAN_LEN * 4),
+        (OID_LOOKUP_CHUNK_ID, entries.len() * git_hash::SIZE_OF_SHA1_DIGEST),
+        (COMMIT_DATA_CHUNK_ID, entries.len() * COMMIT_DATA_ENTRY_SIZE),
+    ];
+    if corrected_commit_dates {
+        chunks.push((GENERATION_DATA_CHUNK_ID, entries.len() * 4));
+        if !generation_data_overflows.is_empty() {
+            chunks.push((GENERATION_DATA_OVERFLOW_CHUNK_ID, generation_data_overflows.len() * 8));
+        }
+    }
+    if !extra_edges.is_empty() {
+        chunks.push((EXTENDED_EDGES_LIST_CHUNK_ID, extra_edges.len() * 4));
+    }
+    if bloom_settings.is_some() {
+        chunks.push((BLOOM_FILTER_INDEX_CHUNK_ID, entries.len() * 4));
+        chunks.push((
+            BLOOM_FILTER_DATA_CHUNK_ID,
+            BLOOM_FILTER_DATA_HEADER_LEN + entries.iter().map(|e| e.bloom_filter.len()).sum::<usize>(),
+        ));
+    }
+    if !base_graph_ids.is_empty() {
+        chunks.push((
+            BASE_GRAPHS_LIST_CHUNK_ID,
+            base_graph_ids.len() * git_hash::SIZE_OF_SHA1_DIGEST,
+        ));
+    }
+
+    let mut out = git_features::hash::Write::new(io::BufWriter::new(out), git_hash::Kind::Sha1);
+    out.write_all(SIGNATURE)?;
+    out.write_u8(1 /* version */)?;
+    out.write_u8(1 /* SHA1 */)?;
+    out.write_u8(chunks.len() as u8)?;
+    out.write_u8(base_graph_ids.len() as u8)?;
+
+    let mut offset = (HEADER_LEN + (chunks.len() + 1) * CHUNK_LOOKUP_SIZE) as u64;
+    for (id, size) in &chunks {
+        out.write_all(id)?;
+        out.write_u64::<BigEndian>(offset)?;
+        offset += *size as u64;
+    }
+    out.write_all(&SENTINEL_CHUNK_ID)?;
+    out.write_u64::<BigEndian>(offset)?;
+
+    let mut fan = [0u32; FAN_LEN];
+    for entry in entries {
+        fan[usize::from(entry.id.first_byte())] += 1;
+    }
+    let mut count = 0;
+    for value in fan.iter_mut() {
+        count += *value;
+        *value = count;
+    }
+    for value in fan.iter() {
+        out.write_u32::<BigEndian>(*value)?;
+    }
+    for entry in entries {
+        out.write_all(entry.id.as_slice())?;
+    }
+
+    let mut extra_edge_index = 0;
+    for entry in entries {
+        out.write_all(entry.root_tree_id.as_slice())?;
+        let parent1 = entry.parents.first().map_or(NO_PARENT, |p| p.0);
+        let parent2 = match entry.parents.len() {
+            0 | 1 => NO_PARENT,
+            2 => entry.parents[1].0,
+            num_parents => {
+                let index = extra_edge_index | EXTENDED_EDGES_MASK;
+                extra_edge_index += num_parents as u32 - 1;
+                index
+            }
+        };
+        out.write_u32::<BigEndian>(parent1)?;
+        out.write_u32::<BigEndian>(parent2)?;
+        out.write_u32::<BigEndian>(entry.generation << 2 | ((entry.commit_timestamp >> 32) & 0x3) as u32)?;
+        out.write_u32::<BigEndian>(entry.commit_timestamp as u32)?;
+    }
+    if corrected_commit_dates {
+        let mut overflow_index = 0;
+        for entry in entries {
+            let offset = entry.corrected_commit_date - entry.commit_timestamp;
+            if offset > u64::from(!GENERATION_DATA_OVERFLOW_MASK) {
+                out.write_u32::<BigEndian>(overflow_index | GENERATION_DATA_OVERFLOW_MASK)?;
+                overflow_index += 1;
+            } else {
+                out.write_u32::<BigEndian>(offset as u32)?;
+            }
+        }
+        for offset in generation_data_overflows {
+            out.write_u64::<BigEndian>(offset)?;
+        }
+    }
+    for edge in extra_edges {
+        out.write_u32::<BigEndian>(edge)?;
+    }
+    if let Some(settings) = bloom_settings {
+        let mut end = 0;
+        for entry in entries {
+            end += entry.bloom_filter.len() as u32;
+            out.write_u32::<BigEndian>(end)?;
+        }
+        out.write_u32::<BigEndian>(settings.hash_version)?;
+        out.write_u32::<BigEndian>(settings.num_hashes)?;
+        out.write_u32::<BigEndian>(settings.bits_per_entry)?;
+        for entry in entries {
+            out.write_all(&entry.bloom_filter)?;
+        }
+    }
+    for id in base_graph_ids {
+        out.write_all(id.as_slice())?;
+    }
+
+    let git_features::hash::Write { hash, inner } = out;
+    let checksum = ObjectId::new_sha1(hash.digest());
+    let mut out = inner.into_inner().map_err(|err| err.into_error())?;
+    out.write_all(checksum.as_slice())?;
+    out.flush()?;
+    Ok(checksum)
+}
@@ -20,6 +20,12 @@ impl Graph {
         Some(r.file.commit_at(r.file_pos))
     }
 
+    /// Returns true if all files of this graph store [corrected commit dates][Commit::corrected_commit_date()], which
+    /// are to be ignored otherwise.
+    pub fn has_corrected_commit_dates(&self) -> bool {
+        self.files.iter().all(|f| f.has_corrected_commit_dates())
+    }
+
     /// Returns the `hash` at the given position `pos`.
     ///
     /// # Panics
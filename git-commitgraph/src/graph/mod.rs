@@ -2,6 +2,10 @@
 mod access;
 mod init;
 pub mod verify;
+#[cfg(feature = "write")]
+pub mod write;
+
+pub use init::Error;
 
 use crate::file::File;
 use std::fmt;
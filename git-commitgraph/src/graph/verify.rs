@@ -25,6 +25,12 @@ pub enum Error<E: std::error::Error + 'static> {
     },
     #[error(transparent)]
     Commit(#[from] commit::Error),
+    #[error("Commit {id}'s corrected commit date should be {expected} but is {actual}")]
+    CorrectedCommitDate {
+        actual: u64,
+        expected: u64,
+        id: git_hash::ObjectId,
+    },
     #[error("{}: {err}", .path.display())]
     File {
         // Use zero-size error type. We will never return
@@ -92,6 +98,7 @@ impl Graph {
             parent_counts: BTreeMap::new(),
         };
         let mut max_generation = 0u32;
+        let verify_corrected_commit_dates = self.has_corrected_commit_dates();
 
         // TODO: Detect duplicate commit IDs across different files. Not sure how to do this without
         //   a separate loop, e.g. self.iter_sorted_ids().
@@ -130,6 +137,7 @@ impl Graph {
             let file_stats = file
                 .traverse(|commit| {
                     let mut max_parent_generation = 0u32;
+                    let mut min_corrected_commit_date = commit.committer_timestamp();
                     for parent_pos in commit.iter_parents() {
                         let parent_pos = parent_pos.map_err(Error::Commit)?;
                         if parent_pos >= next_file_start_pos {
@@ -141,6 +149,9 @@ impl Graph {
                         }
                         let parent = self.commit_at(parent_pos);
                         max_parent_generation = max(max_parent_generation, parent.generation());
+                        if let Some(date) = parent.corrected_commit_date() {
+                            min_corrected_commit_date = max(min_corrected_commit_date, date + 1);
+                        }
                     }
 
                     // If the max parent generation is GENERATION_NUMBER_MAX, then this commit's
@@ -154,6 +165,19 @@ impl Graph {
                         });
                     }
 
+                    if verify_corrected_commit_dates {
+                        let actual = commit
+                            .corrected_commit_date()
+                            .expect("files with corrected commit dates verify each commit has one");
+                        if actual != min_corrected_commit_date {
+                            return Err(Error::CorrectedCommitDate {
+                                actual,
+                                expected: min_corrected_commit_date,
+                                id: commit.id().into(),
+                            });
+                        }
+                    }
+
                     processor(commit).map_err(Error::Processor)?;
 
                     Ok(())
@@ -170,6 +194,9 @@ impl Graph {
                         file::verify::Error::Generation { generation, id } => {
                             file::verify::Error::Generation { generation, id }
                         }
+                        file::verify::Error::CorrectedCommitDate { id } => {
+                            file::verify::Error::CorrectedCommitDate { id }
+                        }
                         file::verify::Error::Filename(expected) => file::verify::Error::Filename(expected),
                         file::verify::Error::Commit(err) => file::verify::Error::Commit(err),
                         file::verify::Error::CommitId { id, pos } => file::verify::Error::CommitId { id, pos },
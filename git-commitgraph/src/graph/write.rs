@@ -0,0 +1,497 @@
+//! Write commit graphs, either as single file or as chain of files.
+use crate::{bloom, file, graph, Graph, GENERATION_NUMBER_MAX, MAX_COMMITS};
+use bstr::BStr;
+use git_hash::{oid, ObjectId};
+use git_object::immutable;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The error used in [`Graph::write_to_info_dir()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Commit(#[from] file::commit::Error),
+    #[error("An object could not be decoded")]
+    Decode(#[from] immutable::object::decode::Error),
+    #[error("Could not compute the paths changed by a commit")]
+    Diff(#[from] git_diff::tree::changes::Error),
+    #[error("Could not read the existing commit-graph")]
+    Graph(#[from] graph::Error),
+    #[error("Could not write commit-graph file at '{}'", .path.display())]
+    Io {
+        #[source]
+        err: io::Error,
+        path: PathBuf,
+    },
+    #[error("A lock for the commit-graph could not be obtained")]
+    LockAcquire(#[from] git_lock::acquire::Error),
+    #[error("The commit {id} doesn't have a tree")]
+    MissingTree { id: ObjectId },
+    #[error("The commit {id} could not be found")]
+    NotFound { id: ObjectId },
+    #[error("Could not move a temporary file into its desired place")]
+    Persist(#[from] tempfile::PersistError),
+    #[error(
+        "Commit-graph would contain {0} commits altogether, but only {} commits are allowed",
+        MAX_COMMITS
+    )]
+    TooManyCommits(u64),
+    #[error("Commit-graph would be composed of {0} files, but at most 256 files are allowed")]
+    TooManyFiles(usize),
+    #[error("The tree {id} could not be found")]
+    TreeNotFound { id: ObjectId },
+}
+
+/// A function to look up a tree by its id, writing its data into the buffer and returning an iterator over its entries,
+/// or `None` if the tree wasn't found.
+///
+/// It's used to compute the paths changed by each commit for storage in changed-path Bloom filters, and is typically
+/// implemented like `Box::new(|id, buf| db.find_existing_tree_iter(id, buf, &mut pack::cache::Never).ok())`.
+pub type FindTreeFn<'a> = Box<dyn for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::TreeIter<'b>> + 'a>;
+
+/// Determines how the commit graph is written by [`Graph::write_to_info_dir()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Write all commits reachable from the tips into a single `objects/info/commit-graph` file, replacing an existing
+    /// commit-graph chain.
+    Single,
+    /// Write all commits reachable from the tips which aren't yet in the commit graph into a new file of the
+    /// `objects/info/commit-graphs` chain, like `git commit-graph write --split` does.
+    Split(Split),
+}
+
+/// Configure how files of a commit-graph chain are merged when writing in [`Mode::Split`].
+///
+/// The topmost file of the chain is merged into the new one if it doesn't contain more than `size_multiple` times the amount
+/// of commits to be written, or if there are more than `max_commits` commits to be written. This is repeated with the
+/// next file in the chain until neither condition holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Split {
+    /// The factor by which a file must be larger than the commits to write to not be merged, with `0` to never merge,
+    /// like `git commit-graph write --split=no-merge`. Defaults to `2`.
+    pub size_multiple: u32,
+    /// If set, merge files until the new file contains at least this many commits.
+    pub max_commits: Option<u32>,
+}
+
+impl Default for Split {
+    fn default() -> Self {
+        Split {
+            size_multiple: 2,
+            max_commits: None,
+        }
+    }
+}
+
+/// The successful result of [`Graph::write_to_info_dir()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde1", derive(serde::Deserialize, serde::Serialize))]
+pub struct Outcome {
+    /// The path to the newly written commit-graph file.
+    pub path: PathBuf,
+    /// The checksum of the newly written file.
+    pub checksum: ObjectId,
+    /// The amount of commits in the newly written file.
+    pub num_commits: u32,
+    /// The amount of files the commit graph consists of after writing, which is always 1 in [`Mode::Single`].
+    pub num_files: usize,
+}
+
+/// A commit as read from the object database or an existing commit-graph file.
+struct CommitData {
+    root_tree_id: ObjectId,
+    parents: Vec<ObjectId>,
+    commit_timestamp: u64,
+    bloom_filter: Option<Vec<u8>>,
+}
+
+impl CommitData {
+    fn from_iter(id: ObjectId, iter: immutable::CommitIter<'_>) -> Result<Self, Error> {
+        let mut root_tree_id = None;
+        let mut parents = Vec::new();
+        let mut commit_timestamp = 0;
+        for token in iter {
+            use immutable::commit::iter::Token;
+            match token? {
+                Token::Tree { id } => root_tree_id = Some(id),
+                Token::Parent { id } => parents.push(id),
+                Token::Committer { signature } => {
+                    commit_timestamp = u64::from(signature.time.time);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok(CommitData {
+            root_tree_id: root_tree_id.ok_or(Error::MissingTree { id })?,
+            parents,
+            commit_timestamp,
+            bloom_filter: None,
+        })
+    }
+}
+
+const CHAIN_FILE_NAME: &str = "commit-graph-chain";
+
+/// Writing
+impl Graph {
+    /// Write a commit graph containing all commits reachable from `tips` into `info_dir`, typically `.git/objects/info`, as
+    /// configured by `mode`.
+    ///
+    /// * `tips` - the commits to start the traversal from, typically the peeled targets of all references.
+    /// * `find` - a way to lookup commits by their id, writing their data into the buffer and returning an iterator over
+    ///   their tokens, or `None` if the commit wasn't found or is not a commit, which is an error.
+    /// * `find_tree_for_changed_paths` - if set, a way to lookup trees to compute the paths changed by each commit compared
+    ///   to its first parent, which are stored in [changed-path Bloom filters][crate::bloom] like
+    ///   `git commit-graph write --changed-paths` does.
+    ///
+    /// In [`Mode::Split`], commits already in the existing commit graph aren't looked up but copied from their file if it is
+    /// merged into the new one, along with their Bloom filter if it was created with the [default settings][bloom::Settings].
+    /// If there is nothing new to write, no file is written and `None` is returned.
+    /// An existing `objects/info/commit-graph` file becomes the base of the chain in this mode.
+    ///
+    /// Like git, the `commit-graph.lock` or `commit-graphs/commit-graph-chain.lock` file is held while writing, acquired
+    /// according to the `lock` mode.
+    pub fn write_to_info_dir<Find>(
+        info_dir: impl AsRef<Path>,
+        tips: impl IntoIterator<Item = impl Into<ObjectId>>,
+        mut find: Find,
+        mut find_tree_for_changed_paths: Option<FindTreeFn<'_>>,
+        mode: Mode,
+        lock: git_lock::acquire::Fail,
+    ) -> Result<Option<Outcome>, Error>
+    where
+        Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+    {
+        let info_dir = info_dir.as_ref();
+        let single_file_path = info_dir.join("commit-graph");
+        let graphs_dir = info_dir.join("commit-graphs");
+        let chain_file_path = graphs_dir.join(CHAIN_FILE_NAME);
+
+        let mut graph_lock = match mode {
+            Mode::Single => git_lock::File::acquire_to_update_resource(&single_file_path, lock, None)?,
+            Mode::Split(_) => {
+                git_lock::File::acquire_to_update_resource(&chain_file_path, lock, Some(info_dir.to_owned()))?
+            }
+        };
+        let existing = match mode {
+            Mode::Single => None,
+            Mode::Split(_) => {
+                if single_file_path.is_file() {
+                    Some(Graph::from_file(&single_file_path)?)
+                } else if chain_file_path.is_file() {
+                    Some(Graph::from_commit_graphs_dir(&graphs_dir)?)
+                } else {
+                    None
+                }
+            }
+        };
+
+        let mut commits = HashMap::<ObjectId, CommitData>::new();
+        let mut buf = Vec::new();
+        let mut next: Vec<ObjectId> = tips.into_iter().map(Into::into).collect();
+        while let Some(id) = next.pop() {
+            if commits.contains_key(&id) || existing.as_ref().and_then(|g| g.lookup(id)).is_some() {
+                continue;
+            }
+            let commit = CommitData::from_iter(id, find(&id, &mut buf).ok_or(Error::NotFound { id })?)?;
+            next.extend(commit.parents.iter().filter(|id| !commits.contains_key(*id)).cloned());
+            commits.insert(id, commit);
+        }
+
+        let bloom_settings = bloom::Settings::default();
+        let num_files = existing.as_ref().map_or(0, |g| g.files.len());
+        let mut num_kept_files = num_files;
+        if let (Mode::Split(split), Some(existing)) = (mode, existing.as_ref()) {
+            let mut num_commits = commits.len() as u64;
+            while num_kept_files > 0 {
+                let top_commits = u64::from(existing.files[num_kept_files - 1].num_commits());
+                if top_commits <= u64::from(split.size_multiple) * num_commits
+                    || matches!(split.max_commits, Some(max) if num_commits > u64::from(max))
+                {
+                    num_commits += top_commits;
+                    num_kept_files -= 1;
+                } else {
+                    break;
+                }
+            }
+            if commits.is_empty() && num_kept_files == num_files {
+                return Ok(None);
+            }
+            for file in &existing.files[num_kept_files..] {
+                let reuse_bloom_filters = file.bloom_filter_settings() == Some(bloom_settings);
+                for commit in file.iter_commits() {
+                    let parents = commit
+                        .iter_parents()
+                        .map(|pos| pos.map(|pos| existing.id_at(pos).to_owned()))
+                        .collect::<Result<_, _>>()?;
+                    commits.insert(
+                        commit.id().to_owned(),
+                        CommitData {
+                            root_tree_id: commit.root_tree_id().to_owned(),
+                            parents,
+                            commit_timestamp: commit.committer_timestamp(),
+                            bloom_filter: commit
+                                .bloom_filter()
+                                .filter(|_| reuse_bloom_filters)
+                                .map(|filter| filter.as_bytes().to_owned()),
+                        },
+                    );
+                }
+            }
+        }
+        if num_kept_files + 1 > 256 {
+            return Err(Error::TooManyFiles(num_kept_files + 1));
+        }
+
+        let base_files = existing.as_ref().map_or(&[][..], |g| &g.files[..num_kept_files]);
+        let num_base_commits: u32 = base_files.iter().map(|f| f.num_commits()).sum();
+        let total_commits = u64::from(num_base_commits) + commits.len() as u64;
+        if total_commits > u64::from(MAX_COMMITS) {
+            return Err(Error::TooManyCommits(total_commits));
+        }
+        let base_graph_ids: Vec<_> = base_files.iter().map(|f| f.checksum().to_owned()).collect();
+        if let Some(find_tree) = find_tree_for_changed_paths.as_mut() {
+            let mut filters = Vec::new();
+            for (id, commit) in commits.iter().filter(|(_, c)| c.bloom_filter.is_none()) {
+                let parent_tree_id = match commit.parents.first() {
+                    Some(parent) => Some(match commits.get(parent) {
+                        Some(parent) => parent.root_tree_id,
+                        None => {
+                            let existing = existing.as_ref().ok_or(Error::NotFound { id: *parent })?;
+                            let pos = existing.lookup(parent).ok_or(Error::NotFound { id: *parent })?;
+                            existing.commit_at(pos).root_tree_id().to_owned()
+                        }
+                    }),
+                    None => None,
+                };
+                filters.push((
+                    *id,
+                    changed_paths_filter(find_tree, parent_tree_id, commit.root_tree_id, bloom_settings)?,
+                ));
+            }
+            for (id, filter) in filters {
+                commits.get_mut(&id).expect("filters belong to commits").bloom_filter = Some(filter);
+            }
+        }
+        let bloom_settings = find_tree_for_changed_paths.map(|_| bloom_settings);
+        // Like git, don't write corrected commit dates on top of files that lack them as they would be ignored anyway.
+        let corrected_commit_dates = base_files.iter().all(|f| f.has_corrected_commit_dates());
+        let entries = {
+            let mut ids: Vec<_> = commits.keys().cloned().collect();
+            ids.sort();
+            let position_by_id: HashMap<_, _> = ids
+                .iter()
+                .enumerate()
+                .map(|(idx, id)| (*id, graph::Position(num_base_commits + idx as u32)))
+                .collect();
+            let mut entries = Vec::with_capacity(ids.len());
+            for id in ids {
+                let commit = commits.remove(&id).expect("each id belongs to a commit");
+                let parents = commit
+                    .parents
+                    .iter()
+                    .map(|parent| {
+                        position_by_id
+                            .get(parent)
+                            .copied()
+                            .or_else(|| existing.as_ref().and_then(|g| g.lookup(parent)))
+                            .ok_or(Error::NotFound { id: *parent })
+                    })
+                    .collect::<Result<_, _>>()?;
+                entries.push(file::Entry {
+                    id,
+                    root_tree_id: commit.root_tree_id,
+                    parents,
+                    generation: 0,
+                    commit_timestamp: commit.commit_timestamp,
+                    corrected_commit_date: 0,
+                    bloom_filter: commit.bloom_filter.unwrap_or_default(),
+                });
+            }
+            compute_generations(&mut entries, num_base_commits, existing.as_ref());
+            entries
+        };
+
+        let num_commits = entries.len() as u32;
+        let io_err = |path: &Path| {
+            let path = path.to_owned();
+            move |err| Error::Io { err, path }
+        };
+        match mode {
+            Mode::Single => {
+                let checksum = graph_lock
+                    .with_mut(|out| file::write(&entries, &[], corrected_commit_dates, bloom_settings, out))
+                    .map_err(io_err(&single_file_path))?;
+                graph_lock.commit().map_err(io_err(&single_file_path))?;
+
+                if chain_file_path.is_file() {
+                    let chain = read_chain(&chain_file_path)?;
+                    std::fs::remove_file(&chain_file_path).map_err(io_err(&chain_file_path))?;
+                    for id in chain {
+                        let path = graphs_dir.join(format!("graph-{}.graph", id));
+                        std::fs::remove_file(&path).map_err(io_err(&path))?;
+                    }
+                }
+                Ok(Some(Outcome {
+                    path: single_file_path,
+                    checksum,
+                    num_commits,
+                    num_files: 1,
+                }))
+            }
+            Mode::Split(_) => {
+                let mut tempfile = tempfile::NamedTempFile::new_in(&graphs_dir).map_err(io_err(&graphs_dir))?;
+                let checksum = file::write(
+                    &entries,
+                    &base_graph_ids,
+                    corrected_commit_dates,
+                    bloom_settings,
+                    &mut tempfile,
+                )
+                .map_err(io_err(tempfile.path()))?;
+                let path = graphs_dir.join(format!("graph-{}.graph", checksum));
+                tempfile.persist(&path)?;
+
+                let previous_paths: Vec<_> = existing
+                    .map(|g| g.files.into_iter().map(|f| f.path().to_owned()).collect())
+                    .unwrap_or_default();
+                graph_lock
+                    .with_mut(|chain| {
+                        for id in base_graph_ids.iter().chain(std::iter::once(&checksum)) {
+                            writeln!(chain, "{}", id)?;
+                        }
+                        Ok(())
+                    })
+                    .map_err(io_err(&chain_file_path))?;
+                let (kept_paths, merged_paths) = previous_paths.split_at(num_kept_files);
+                let moved_single_file = kept_paths.first() == Some(&single_file_path);
+                if moved_single_file {
+                    // The single file stays in place until the chain referring to its new location is committed, so the
+                    // previous commit-graph remains usable if anything fails.
+                    let path = graphs_dir.join(format!("graph-{}.graph", base_graph_ids[0]));
+                    if !path.is_file() {
+                        std::fs::hard_link(&single_file_path, &path)
+                            .or_else(|_| std::fs::copy(&single_file_path, &path).map(|_| ()))
+                            .map_err(io_err(&path))?;
+                    }
+                }
+                graph_lock.commit().map_err(io_err(&chain_file_path))?;
+                if moved_single_file {
+                    std::fs::remove_file(&single_file_path).map_err(io_err(&single_file_path))?;
+                }
+                for merged_path in merged_paths {
+                    std::fs::remove_file(merged_path).map_err(io_err(merged_path))?;
+                }
+                Ok(Some(Outcome {
+                    path,
+                    checksum,
+                    num_commits,
+                    num_files: num_kept_files + 1,
+                }))
+            }
+        }
+    }
+}
+
+fn read_chain(path: &Path) -> Result<Vec<String>, Error> {
+    Ok(std::fs::read_to_string(path)
+        .map_err(|err| Error::Io {
+            err,
+            path: path.to_owned(),
+        })?
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+/// Compute the Bloom filter data for the changes between the trees with `parent_tree_id`, or the empty tree if `None`,
+/// and `tree_id`.
+fn changed_paths_filter(
+    find_tree: &mut FindTreeFn<'_>,
+    parent_tree_id: Option<ObjectId>,
+    tree_id: ObjectId,
+    settings: bloom::Settings,
+) -> Result<Vec<u8>, Error> {
+    let (mut parent_buf, mut buf) = (Vec::new(), Vec::new());
+    let parent_tree = match parent_tree_id {
+        Some(id) => Some(find_tree(&id, &mut parent_buf).ok_or(Error::TreeNotFound { id })?),
+        None => None,
+    };
+    let tree = find_tree(&tree_id, &mut buf).ok_or(Error::TreeNotFound { id: tree_id })?;
+    let mut recorder = git_diff::tree::Recorder::default();
+    git_diff::tree::Changes::from(parent_tree).needed_to_obtain(
+        tree,
+        git_diff::tree::State::default(),
+        |id, buf| find_tree(id, buf),
+        &mut recorder,
+    )?;
+
+    // Trees are implied by the paths of their changed entries.
+    let changed_paths: Vec<&BStr> = recorder
+        .records
+        .iter()
+        .filter_map(|change| {
+            use git_diff::tree::recorder::Change::*;
+            match change {
+                Addition { entry_mode, path, .. }
+                | Deletion { entry_mode, path, .. }
+                | Modification { entry_mode, path, .. } => (!entry_mode.is_tree()).then(|| path.as_ref()),
+            }
+        })
+        .collect();
+    Ok(if changed_paths.len() > bloom::MAX_CHANGED_PATHS {
+        bloom::too_many_changes()
+    } else {
+        bloom::compute(changed_paths, settings)
+    })
+}
+
+/// Set the topological level and the corrected commit date of all `entries`, whose parents are either entries themselves
+/// or are contained in the `base` graph consisting of `num_base_commits`.
+fn compute_generations(entries: &mut [file::Entry], num_base_commits: u32, base: Option<&Graph>) {
+    let mut stack = Vec::new();
+    for idx in 0..entries.len() {
+        stack.push(idx);
+        while let Some(&current) = stack.last() {
+            if entries[current].generation != 0 {
+                stack.pop();
+                continue;
+            }
+            let mut max_parent_generation = 0;
+            let mut min_corrected_commit_date = entries[current].commit_timestamp;
+            let mut has_pending_parents = false;
+            for parent in &entries[current].parents {
+                let (generation, corrected_commit_date) = match parent.0.checked_sub(num_base_commits) {
+                    Some(parent_idx) => {
+                        let parent = &entries[parent_idx as usize];
+                        if parent.generation == 0 {
+                            stack.push(parent_idx as usize);
+                            has_pending_parents = true;
+                        }
+                        (parent.generation, parent.corrected_commit_date)
+                    }
+                    None => {
+                        let parent = base
+                            .expect("base graph for positions below its commits")
+                            .commit_at(*parent);
+                        // Corrected commit dates are only written if the base has them.
+                        (parent.generation(), parent.corrected_commit_date().unwrap_or_default())
+                    }
+                };
+                max_parent_generation = max_parent_generation.max(generation);
+                min_corrected_commit_date = min_corrected_commit_date.max(corrected_commit_date + 1);
+            }
+            if !has_pending_parents {
+                let entry = &mut entries[current];
+                entry.generation = (max_parent_generation + 1).min(GENERATION_NUMBER_MAX);
+                entry.corrected_commit_date = min_corrected_commit_date;
+                stack.pop();
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{check_common, inspect_refs, make_readonly_repo};
 use git_commitgraph::Graph;
+use std::convert::TryInto;
 
 #[test]
 fn single_parent() -> crate::Result {
@@ -68,3 +69,111 @@ fn two_parents() -> crate::Result {
 
     Ok(())
 }
+
+#[test]
+fn changed_path_bloom_filters() -> crate::Result {
+    let repo_dir = make_readonly_repo("bloom.sh");
+    let refs = inspect_refs(
+        &repo_dir,
+        &[
+            "main~8", "main~7", "main~6", "main~5", "main~4", "main~3", "main~2", "main~1^2", "main~1", "main",
+        ],
+    );
+    let cg = Graph::from_info_dir(repo_dir.join(".git").join("objects").join("info"))?;
+    check_common(&cg, &refs);
+    let may_have_changed = |name: &str, path: &str| {
+        cg.commit_at(refs[name].pos())
+            .may_have_changed(path.into())
+            .expect("all commits have filters")
+    };
+
+    assert!(!may_have_changed("main~7", "a"), "empty commits change nothing");
+    assert!(may_have_changed("main~6", "dir/sub/c"));
+    assert!(
+        may_have_changed("main~6", "dir/sub"),
+        "leading directories are changed as well"
+    );
+    assert!(!may_have_changed("main~6", "dir/b"));
+    assert!(!may_have_changed("main~6", "a"));
+    assert!(may_have_changed("main~5", "dir/ümlaut"));
+    assert!(!may_have_changed("main~5", "dir/sub/c"));
+    assert!(may_have_changed("main~4", "a"), "mode changes are changes");
+    assert!(may_have_changed("main~3", "dir/b"));
+    assert!(may_have_changed("main~2", "a/inner"));
+    assert!(
+        may_have_changed("main~1", "side"),
+        "merges are compared to their first parent"
+    );
+    assert!(!may_have_changed("main~1", "a/inner"));
+    assert!(
+        may_have_changed("main", "does-not-exist"),
+        "too many changes mean that everything may have changed"
+    );
+    Ok(())
+}
+
+#[test]
+fn bloom_filters_not_covering_all_commits_are_ignored() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_writable("bloom.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    let path = info_dir.join("commit-graph");
+    let mut data = std::fs::read(&path)?;
+
+    // Remove the last entry of the BIDX chunk and shift all following chunks accordingly.
+    const HEADER_LEN: usize = 8;
+    const CHUNK_LOOKUP_SIZE: usize = 12;
+    let offset_of = |data: &[u8], chunk: usize| {
+        let start = HEADER_LEN + chunk * CHUNK_LOOKUP_SIZE + 4;
+        u64::from_be_bytes(data[start..start + 8].try_into().expect("8 bytes")) as usize
+    };
+    let num_chunks = usize::from(data[6]);
+    let bidx = (0..num_chunks)
+        .find(|chunk| &data[HEADER_LEN + chunk * CHUNK_LOOKUP_SIZE..][..4] == b"BIDX")
+        .expect("BIDX chunk");
+    let bidx_end = offset_of(&data, bidx + 1);
+    data.drain(bidx_end - 4..bidx_end);
+    for chunk in bidx + 1..=num_chunks {
+        let start = HEADER_LEN + chunk * CHUNK_LOOKUP_SIZE + 4;
+        let offset = offset_of(&data, chunk) as u64 - 4;
+        data[start..start + 8].copy_from_slice(&offset.to_be_bytes());
+    }
+    std::fs::write(&path, data)?;
+
+    let cg = Graph::from_info_dir(&info_dir)?;
+    assert_eq!(cg.num_commits(), 10);
+    assert!(
+        cg.iter_commits()
+            .all(|commit| commit.may_have_changed("a".into()).is_none()),
+        "the file is usable, but without Bloom filters"
+    );
+    Ok(())
+}
+
+#[test]
+fn corrected_commit_dates() -> crate::Result {
+    let repo_dir = make_readonly_repo("generation_data.sh");
+    let refs = inspect_refs(&repo_dir, &["main~4", "main~3", "main~2", "main~1", "main"]);
+    let cg = Graph::from_info_dir(repo_dir.join(".git").join("objects").join("info"))?;
+    check_common(&cg, &refs);
+    assert!(cg.has_corrected_commit_dates());
+    cg.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+
+    let corrected_commit_date = |name: &str| cg.commit_at(refs[name].pos()).corrected_commit_date();
+    let root_date = 946_771_200;
+    let future_date = 4_102_444_800;
+    assert_eq!(corrected_commit_date("main~4"), Some(root_date));
+    assert_eq!(
+        corrected_commit_date("main~3"),
+        Some(root_date + 1),
+        "commits are never older than their parents"
+    );
+    assert_eq!(corrected_commit_date("main~2"), Some(future_date));
+    assert_eq!(
+        corrected_commit_date("main~1"),
+        Some(future_date + 1),
+        "offsets that don't fit into 31 bits are stored separately"
+    );
+    assert_eq!(corrected_commit_date("main"), Some(future_date + 2));
+    assert_eq!(cg.commit_at(refs["main"].pos()).committer_timestamp(), root_date);
+    Ok(())
+}
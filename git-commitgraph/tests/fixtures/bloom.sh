@@ -0,0 +1,48 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.quotepath false
+
+git checkout -q -b main
+mkdir -p dir/sub
+echo a > a
+echo b > dir/b
+echo c > dir/sub/c
+git add .
+git commit -q -m root
+
+git commit -q --allow-empty -m empty
+
+echo changed > dir/sub/c
+git commit -q -am 'modify nested file'
+
+echo umlaut > "dir/ümlaut"
+git add .
+git commit -q -m 'add non-ascii path'
+
+chmod +x a
+git commit -q -am 'change mode only'
+
+git rm -q dir/b
+git commit -q -m 'delete file'
+
+rm a && mkdir a && echo inner > a/inner
+git add -A
+git commit -q -m 'turn file into directory'
+
+git checkout -q -b side main~3
+echo side > side
+git add side
+git commit -q -m side
+
+git checkout -q main
+git merge -q -m merge --no-ff side >/dev/null
+
+mkdir many
+for i in $(seq 513); do echo $i > many/$i; done
+git add many
+git commit -q -m 'too many changes'
+
+git commit-graph write --no-progress --reachable --changed-paths
@@ -18,8 +18,9 @@ run() {
     trap - EXIT
 }
 
-#run bloom
+run bloom
 #run bloom_too_large
+run generation_data
 run octopus_merges
 run single_commit
 run single_parent
@@ -0,0 +1,14 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+git commit -q --allow-empty -m root
+GIT_COMMITTER_DATE="1990-01-01 00:00:00 +0000" git commit -q --allow-empty -m 'committed before its parent'
+GIT_COMMITTER_DATE="@4102444800 +0000" git commit -q --allow-empty -m 'committed in the future'
+GIT_COMMITTER_DATE="1971-01-01 00:00:00 +0000" git commit -q --allow-empty -m 'committed long before its parent'
+git commit -q --allow-empty -m 'committed after root'
+
+git commit-graph write --no-progress --reachable
@@ -0,0 +1,382 @@
+use crate::{hex_to_id, make_readonly_repo};
+use git_commitgraph::{
+    graph::write::{FindTreeFn, Mode, Split},
+    Graph,
+};
+use git_hash::ObjectId;
+use git_odb::{linked::Store, pack, FindExt};
+use std::{collections::BTreeMap, io::Write, path::Path, process::Command};
+
+type CommitInfo = (ObjectId, Vec<ObjectId>, u32, u64);
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn commits_of(graph: &Graph) -> BTreeMap<ObjectId, CommitInfo> {
+    graph
+        .iter_commits()
+        .map(|commit| {
+            let parents = commit
+                .iter_parents()
+                .map(|pos| pos.map(|pos| graph.id_at(pos).to_owned()))
+                .collect::<std::result::Result<_, _>>()
+                .expect("valid parents");
+            (
+                commit.id().to_owned(),
+                (
+                    commit.root_tree_id().to_owned(),
+                    parents,
+                    commit.generation(),
+                    commit.committer_timestamp(),
+                ),
+            )
+        })
+        .collect()
+}
+
+fn rev_parse(repo_dir: &Path, names: &[&str]) -> Vec<ObjectId> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .arg("rev-parse")
+        .args(names)
+        .env_remove("GIT_DIR")
+        .output()
+        .expect("failed to execute `git rev-parse`");
+    String::from_utf8(output.stdout)
+        .expect("ascii output")
+        .lines()
+        .map(|hex| hex_to_id(hex.as_bytes()))
+        .collect()
+}
+
+/// Run `git commit-graph verify` and return the amount of commits it verified across all files, to be sure the graph was used.
+fn git_verified_commits(repo_dir: &Path) -> u32 {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(["commit-graph", "verify", "--progress"])
+        .env_remove("GIT_DIR")
+        .env("GIT_PROGRESS_DELAY", "0")
+        .output()
+        .expect("failed to execute `git commit-graph verify`");
+    assert!(
+        output.status.success(),
+        "git verifies the commit graph: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stderr)
+        .split('\r')
+        .flat_map(|line| line.lines())
+        .filter_map(|line| line.strip_suffix("), done."))
+        .map(|line| {
+            line.rsplit('/')
+                .next()
+                .and_then(|count| count.parse::<u32>().ok())
+                .expect("a count of commits")
+        })
+        .sum()
+}
+
+fn write(repo_dir: &Path, tips: &[&str], mode: Mode) -> Result<Option<git_commitgraph::graph::write::Outcome>> {
+    write_inner(repo_dir, tips, mode, false)
+}
+
+fn write_with_changed_paths(
+    repo_dir: &Path,
+    tips: &[&str],
+    mode: Mode,
+) -> Result<Option<git_commitgraph::graph::write::Outcome>> {
+    write_inner(repo_dir, tips, mode, true)
+}
+
+fn write_inner(
+    repo_dir: &Path,
+    tips: &[&str],
+    mode: Mode,
+    changed_paths: bool,
+) -> Result<Option<git_commitgraph::graph::write::Outcome>> {
+    let objects_dir = repo_dir.join(".git").join("objects");
+    let db = Store::at(&objects_dir)?;
+    let find_tree: Option<FindTreeFn<'_>> = if changed_paths {
+        Some(Box::new(|id, buf| {
+            db.find_existing_tree_iter(id, buf, &mut pack::cache::Never).ok()
+        }))
+    } else {
+        None
+    };
+    Ok(Graph::write_to_info_dir(
+        objects_dir.join("info"),
+        rev_parse(repo_dir, tips),
+        |id, buf| db.find_existing_commit_iter(id, buf, &mut pack::cache::Never).ok(),
+        find_tree,
+        mode,
+        git_lock::acquire::Fail::Immediately,
+    )?)
+}
+
+const ALL_TIPS: &[&str] = &["four_parents", "three_parents", "parent4"];
+
+fn expected_commits() -> Result<BTreeMap<ObjectId, CommitInfo>> {
+    let repo_dir = make_readonly_repo("octopus_merges.sh");
+    Ok(commits_of(&Graph::from_info_dir(
+        repo_dir.join(".git").join("objects").join("info"),
+    )?))
+}
+
+#[test]
+fn single_file_is_the_same_as_the_one_written_by_git() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_writable("octopus_merges.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+
+    let outcome = write(repo.path(), ALL_TIPS, Mode::Single)?.expect("always written");
+    assert_eq!(outcome.num_commits, 7);
+    assert_eq!(outcome.num_files, 1);
+    assert_eq!(outcome.path, info_dir.join("commit-graph"));
+
+    let graph = Graph::from_info_dir(&info_dir)?;
+    assert_eq!(commits_of(&graph), expected_commits()?);
+    graph.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+    assert_eq!(git_verified_commits(repo.path()), 7);
+    Ok(())
+}
+
+#[test]
+fn writing_fails_if_the_commit_graph_is_locked() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_writable("octopus_merges.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    let lock_path = info_dir.join("commit-graph.lock");
+    std::fs::write(&lock_path, b"")?;
+
+    assert!(matches!(
+        write(repo.path(), ALL_TIPS, Mode::Single)
+            .expect_err("locked")
+            .downcast_ref::<git_commitgraph::graph::write::Error>(),
+        Some(git_commitgraph::graph::write::Error::LockAcquire(_))
+    ));
+    assert!(lock_path.is_file(), "the lock of someone else is left alone");
+    assert_eq!(git_verified_commits(repo.path()), 7, "the existing file is unchanged");
+    Ok(())
+}
+
+#[test]
+fn split_layers_are_added_and_merged() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_writable("octopus_merges.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+    let no_merge = Mode::Split(Split {
+        size_multiple: 0,
+        max_commits: None,
+    });
+
+    for (tips, expected_commits, expected_files, expected_total) in &[
+        (&["root"][..], 1, 1, 1),
+        (&["parent1", "parent2"][..], 2, 2, 3),
+        (&["three_parents"][..], 2, 3, 5),
+    ] {
+        let outcome = write(repo.path(), tips, no_merge)?.expect("new commits");
+        assert_eq!(outcome.num_commits, *expected_commits);
+        assert_eq!(outcome.num_files, *expected_files);
+        assert_eq!(git_verified_commits(repo.path()), *expected_total);
+    }
+    assert!(
+        write(repo.path(), &["parent1"], no_merge)?.is_none(),
+        "there is nothing to do if all commits are already present"
+    );
+
+    let outcome = write(repo.path(), &["four_parents"], Mode::Split(Split::default()))?.expect("new commits");
+    assert_eq!(outcome.num_files, 1, "all layers were small enough to be merged");
+    assert_eq!(outcome.num_commits, 7);
+    assert_eq!(
+        std::fs::read_dir(info_dir.join("commit-graphs"))?.count(),
+        2,
+        "merged layers are removed, leaving only the new one and the chain file"
+    );
+    assert_eq!(git_verified_commits(repo.path()), 7);
+    assert_eq!(commits_of(&Graph::from_info_dir(&info_dir)?), expected_commits()?);
+    Ok(())
+}
+
+#[test]
+fn split_layers_can_be_merged_by_commit_count() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_writable("octopus_merges.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+
+    let outcome = write(repo.path(), &["parent1"], Mode::Split(Split::default()))?.expect("new commits");
+    assert_eq!(outcome.num_files, 1);
+    let outcome = write(
+        repo.path(),
+        ALL_TIPS,
+        Mode::Split(Split {
+            size_multiple: 0,
+            max_commits: Some(1),
+        }),
+    )?
+    .expect("new commits");
+    assert_eq!(
+        outcome.num_files, 1,
+        "the new commits exceed the maximum, forcing a merge"
+    );
+    assert_eq!(outcome.num_commits, 7);
+    assert_eq!(git_verified_commits(repo.path()), 7);
+    Ok(())
+}
+
+#[test]
+fn an_existing_single_file_becomes_the_base_of_a_split_graph() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_writable("octopus_merges.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    write(repo.path(), &["three_parents"], Mode::Single)?;
+
+    let outcome = write(
+        repo.path(),
+        &["four_parents"],
+        Mode::Split(Split {
+            size_multiple: 0,
+            max_commits: None,
+        }),
+    )?
+    .expect("new commits");
+    assert_eq!(outcome.num_commits, 2, "parent4 and four_parents");
+    assert_eq!(outcome.num_files, 2);
+    assert!(!info_dir.join("commit-graph").exists(), "it was moved into the chain");
+    assert_eq!(git_verified_commits(repo.path()), 7);
+    assert_eq!(commits_of(&Graph::from_info_dir(&info_dir)?), expected_commits()?);
+
+    let outcome = write(repo.path(), ALL_TIPS, Mode::Single)?.expect("always written");
+    assert_eq!(outcome.num_files, 1);
+    assert!(
+        !info_dir.join("commit-graphs").join("commit-graph-chain").exists(),
+        "the chain is replaced by the single file"
+    );
+    assert_eq!(git_verified_commits(repo.path()), 7);
+    Ok(())
+}
+
+#[test]
+fn single_file_is_byte_for_byte_the_same_as_the_one_written_by_git() -> crate::Result {
+    for (fixture, changed_paths) in &[("generation_data.sh", false), ("bloom.sh", true)] {
+        let expected = std::fs::read(
+            make_readonly_repo(fixture)
+                .join(".git")
+                .join("objects")
+                .join("info")
+                .join("commit-graph"),
+        )?;
+        let repo = git_testtools::scripted_fixture_repo_writable(fixture)?;
+        let outcome = write_inner(repo.path(), &["main"], Mode::Single, *changed_paths)?.expect("always written");
+        assert_eq!(std::fs::read(outcome.path)?, expected, "{}", fixture);
+    }
+    Ok(())
+}
+
+#[test]
+fn corrected_commit_dates_are_not_written_on_top_of_files_without_them() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_writable("generation_data.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+    let mut git = Command::new("git")
+        .arg("-C")
+        .arg(repo.path())
+        .args([
+            "-c",
+            "commitGraph.generationVersion=1",
+            "commit-graph",
+            "write",
+            "--no-progress",
+            "--split",
+            "--stdin-commits",
+        ])
+        .env_remove("GIT_DIR")
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    for id in rev_parse(repo.path(), &["main~2"]) {
+        writeln!(git.stdin.as_mut().expect("piped"), "{}", id)?;
+    }
+    assert!(git.wait()?.success());
+    assert!(!Graph::from_info_dir(&info_dir)?.has_corrected_commit_dates());
+
+    let no_merge = Mode::Split(Split {
+        size_multiple: 0,
+        max_commits: None,
+    });
+    let outcome = write(repo.path(), &["main~1"], no_merge)?.expect("new commits");
+    assert!(!git_commitgraph::file::File::at(&outcome.path)?.has_corrected_commit_dates());
+    assert_eq!(git_verified_commits(repo.path()), 4);
+
+    let outcome = write(repo.path(), &["main"], Mode::Split(Split::default()))?.expect("new commits");
+    assert_eq!(outcome.num_files, 1, "all files are merged");
+    let graph = Graph::from_info_dir(&info_dir)?;
+    assert!(graph.has_corrected_commit_dates());
+    graph.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+    assert_eq!(git_verified_commits(repo.path()), 5);
+    Ok(())
+}
+
+/// Return the raw Bloom filter of each commit in `graph`.
+fn bloom_filters_of(graph: &Graph) -> BTreeMap<ObjectId, Vec<u8>> {
+    graph
+        .iter_commits()
+        .map(|commit| {
+            (
+                commit.id().to_owned(),
+                commit
+                    .bloom_filter()
+                    .expect("all commits have filters")
+                    .as_bytes()
+                    .to_owned(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn changed_path_bloom_filters_are_the_same_as_the_ones_written_by_git() -> crate::Result {
+    let expected = bloom_filters_of(&Graph::from_info_dir(
+        make_readonly_repo("bloom.sh").join(".git").join("objects").join("info"),
+    )?);
+    let repo = git_testtools::scripted_fixture_repo_writable("bloom.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+
+    let outcome = write_with_changed_paths(repo.path(), &["main"], Mode::Single)?.expect("always written");
+    assert_eq!(
+        git_commitgraph::file::File::at(&outcome.path)?.bloom_filter_settings(),
+        Some(git_commitgraph::bloom::Settings::default())
+    );
+    assert_eq!(bloom_filters_of(&Graph::from_info_dir(&info_dir)?), expected);
+    assert_eq!(git_verified_commits(repo.path()), 10);
+    Ok(())
+}
+
+#[test]
+fn changed_path_bloom_filters_are_computed_or_reused_when_merging_split_layers() -> crate::Result {
+    let expected = bloom_filters_of(&Graph::from_info_dir(
+        make_readonly_repo("bloom.sh").join(".git").join("objects").join("info"),
+    )?);
+    let repo = git_testtools::scripted_fixture_repo_writable("bloom.sh")?;
+    let info_dir = repo.path().join(".git").join("objects").join("info");
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+    let no_merge = Mode::Split(Split {
+        size_multiple: 0,
+        max_commits: None,
+    });
+
+    let outcome = write(repo.path(), &["main~5"], no_merge)?.expect("new commits");
+    assert_eq!(
+        git_commitgraph::file::File::at(&outcome.path)?.bloom_filter_settings(),
+        None
+    );
+    let outcome = write_with_changed_paths(repo.path(), &["main~2"], no_merge)?.expect("new commits");
+    assert_eq!(
+        git_commitgraph::file::File::at(&outcome.path)?.bloom_filter_settings(),
+        Some(Default::default())
+    );
+
+    let outcome =
+        write_with_changed_paths(repo.path(), &["main"], Mode::Split(Split::default()))?.expect("new commits");
+    assert_eq!(outcome.num_files, 1, "all layers were merged");
+    assert_eq!(bloom_filters_of(&Graph::from_info_dir(&info_dir)?), expected);
+    assert_eq!(git_verified_commits(repo.path()), 10);
+    Ok(())
+}
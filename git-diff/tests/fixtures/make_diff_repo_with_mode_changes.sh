@@ -0,0 +1,21 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+git config core.fileMode true
+
+git checkout -q -b main
+
+touch f
+git add f
+git commit -qm 'f added'
+
+chmod +x f
+git add f
+git commit -qm 'f made executable'
+
+chmod -x f
+git add f
+git commit -qm 'f made non-executable'
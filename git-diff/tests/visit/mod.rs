@@ -19,6 +19,15 @@ mod changes {
             .map_err(Into::into)
         }
 
+        fn db_with_mode_changes() -> crate::Result<linked::Store> {
+            linked::Store::at(
+                git_testtools::scripted_fixture_repo_read_only("make_diff_repo_with_mode_changes.sh")?
+                    .join(".git")
+                    .join("objects"),
+            )
+            .map_err(Into::into)
+        }
+
         fn locate_tree_by_commit<'a>(
             db: &linked::Store,
             commit: &oid,
@@ -606,5 +615,36 @@ mod changes {
             );
             Ok(())
         }
+
+        #[test]
+        fn mode_changes_without_content_changes_are_modifications() -> crate::Result {
+            let db = db_with_mode_changes()?;
+            let all_commits = all_commits(&db);
+            let empty_blob = hex_to_id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391");
+
+            assert_eq!(
+                diff_with_previous_commit_from(&db, &all_commits[1])?,
+                vec![Modification {
+                    previous_entry_mode: EntryMode::Blob,
+                    previous_oid: empty_blob,
+                    entry_mode: EntryMode::BlobExecutable,
+                    oid: empty_blob,
+                    path: "f".into()
+                }],
+                ":100644 100755 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 M	f"
+            );
+            assert_eq!(
+                diff_with_previous_commit_from(&db, &all_commits[2])?,
+                vec![Modification {
+                    previous_entry_mode: EntryMode::BlobExecutable,
+                    previous_oid: empty_blob,
+                    entry_mode: EntryMode::Blob,
+                    oid: empty_blob,
+                    path: "f".into()
+                }],
+                ":100755 100644 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 M	f"
+            );
+            Ok(())
+        }
     }
 }
@@ -20,6 +20,22 @@ pub mod walkdir {
         WalkDir::new(root).skip_hidden(false)
     }
 
+    /// Instantiate a new directory iterator which will not skip hidden files and which yields paths in byte-wise sorted order,
+    /// as if directories had a trailing slash.
+    pub fn walkdir_sorted_new(root: impl AsRef<Path>) -> WalkDir {
+        WalkDir::new(root)
+            .skip_hidden(false)
+            .process_read_dir(|_depth, _path, _read_dir_state, children| {
+                children.sort_by(|a, b| match (a, b) {
+                    (Ok(a), Ok(b)) => super::sort_key(a.file_name(), a.file_type().is_dir())
+                        .cmp(&super::sort_key(b.file_name(), b.file_type().is_dir())),
+                    (Ok(_), Err(_)) => std::cmp::Ordering::Greater,
+                    (Err(_), Ok(_)) => std::cmp::Ordering::Less,
+                    (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+                })
+            })
+    }
+
     /// The Iterator yielding directory items
     pub type DirEntryIter = DirEntryIterGeneric<((), ())>;
 }
@@ -35,9 +51,47 @@ pub mod walkdir {
         WalkDir::new(root)
     }
 
+    /// Instantiate a new directory iterator which will not skip hidden files and which yields paths in byte-wise sorted order,
+    /// as if directories had a trailing slash.
+    pub fn walkdir_sorted_new(root: impl AsRef<Path>) -> WalkDir {
+        WalkDir::new(root).sort_by(|a, b| {
+            super::sort_key(a.file_name(), a.file_type().is_dir())
+                .cmp(&super::sort_key(b.file_name(), b.file_type().is_dir()))
+        })
+    }
+
     /// The Iterator yielding directory items
     pub type DirEntryIter = walkdir::IntoIter;
 }
 
 #[cfg(any(feature = "walkdir", feature = "jwalk"))]
-pub use self::walkdir::{walkdir_new, WalkDir};
+pub use self::walkdir::{walkdir_new, walkdir_sorted_new, WalkDir};
+
+/// A directory iterator which reads one directory at a time, even if the `parallel` feature is enabled.
+///
+/// Use it if entering a directory depends on what was seen before, and skip the contents of the directory that was
+/// returned last with [`IntoIter::skip_current_dir()`].
+#[cfg(feature = "walkdir")]
+pub mod sequential {
+    pub use ::walkdir::{DirEntry, Error, IntoIter, WalkDir};
+    use std::path::Path;
+
+    /// Instantiate a new directory iterator which will not skip hidden files.
+    pub fn walkdir_new(root: impl AsRef<Path>) -> WalkDir {
+        WalkDir::new(root)
+    }
+}
+
+/// Directories sort as if they had a trailing slash to assure that `a/b` comes after `a-b`, just like it would
+/// when comparing the full paths byte by byte.
+#[cfg(any(feature = "walkdir", feature = "jwalk"))]
+fn sort_key(name: &std::ffi::OsStr, is_dir: bool) -> std::borrow::Cow<'_, str> {
+    let name = name.to_string_lossy();
+    if is_dir {
+        let mut name = name.into_owned();
+        name.push('/');
+        name.into()
+    } else {
+        name
+    }
+}
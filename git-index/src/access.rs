@@ -0,0 +1,65 @@
+use crate::{extension, Backing, Entry, State, Version};
+use bstr::BStr;
+
+/// Access
+impl State {
+    /// Return the version of the index format this state was decoded from.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Return all entries sorted by path and stage.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Return the buffer containing the paths of all entries.
+    pub fn path_backing(&self) -> &[u8] {
+        match &self.path_backing {
+            Some(paths) => paths,
+            None => self.data(),
+        }
+    }
+
+    /// Return the entry with `path` at `stage`, if present.
+    pub fn entry_by_path_and_stage(&self, path: &BStr, stage: u32) -> Option<&Entry> {
+        self.entries
+            .binary_search_by(|e| e.path(self).cmp(path).then_with(|| e.stage().cmp(&stage)))
+            .ok()
+            .map(|idx| &self.entries[idx])
+    }
+
+    /// Return the root of the cached tree extension, if present.
+    pub fn tree(&self) -> Option<&extension::Tree> {
+        self.tree.as_ref()
+    }
+
+    /// Return the paths whose conflicts were resolved as stored in the resolve-undo extension, if present.
+    pub fn resolve_undo(&self) -> Option<&[extension::ResolvePath]> {
+        self.resolve_undo.as_deref()
+    }
+
+    /// Return the untracked cache extension, if present.
+    pub fn untracked(&self) -> Option<&extension::UntrackedCache> {
+        self.untracked.as_ref()
+    }
+
+    /// Return the link to the shared index if this is a split index.
+    pub fn link(&self) -> Option<&extension::Link> {
+        self.link.as_ref()
+    }
+
+    /// Return all extensions this implementation doesn't understand as pairs of their signature and their raw data.
+    pub fn unknown_extensions(&self) -> impl Iterator<Item = (extension::Signature, &[u8])> + '_ {
+        self.unknown_extensions
+            .iter()
+            .map(move |(signature, range)| (*signature, &self.data()[range.clone()]))
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        match &self.data {
+            Backing::InMemory(data) => data,
+            Backing::Mapped(data) => data,
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    checkout::{Error, Options, Outcome},
+    entry, Backing, Entry, State, Version,
+};
+use bstr::ByteSlice;
+use git_features::parallel::in_parallel_if;
+use git_hash::oid;
+use git_object::tree::EntryMode;
+use git_odb::{pack::cache::DecodeEntry, FindExt};
+use git_traverse::tree::{breadthfirst, recorder};
+use std::{fs, io::Write, path::Path};
+
+/// The amount of files written by a thread at a time.
+const CHUNK_SIZE: usize = 64;
+
+/// Check out the tree with `tree_id` from `db` into the `worktree` directory according to `options`, and return an
+/// index matching the written files.
+///
+/// The `worktree` is created if needed and is expected to be empty, as existing files are never overwritten.
+/// Blobs are written in parallel, using a pack cache created by `make_cache` on each thread. Files are marked
+/// executable and symbolic links are created according to the mode of their tree entry, while submodules
+/// are represented by empty directories.
+///
+/// The stat information of each index entry is obtained from the file just written, so the index can be written to
+/// disk as is to make the worktree appear unchanged.
+pub fn tree<Find, Cache>(
+    tree_id: impl AsRef<oid>,
+    worktree: impl AsRef<Path>,
+    db: Find,
+    make_cache: impl Fn() -> Cache + Send + Sync,
+    options: Options,
+) -> Result<(State, Outcome), Error<Find::Error>>
+where
+    Find: git_odb::Find + Sync,
+    Find::Error: Send,
+    Cache: DecodeEntry,
+{
+    let worktree = worktree.as_ref();
+    let mut records = {
+        let mut buf = Vec::new();
+        let mut cache = make_cache();
+        let root = db.find_existing_tree_iter(tree_id, &mut buf, &mut cache)?;
+        let mut recorder = git_traverse::tree::Recorder::default();
+        breadthfirst(
+            root,
+            breadthfirst::State::default(),
+            |id, buf| db.find_existing_tree_iter(id, buf, &mut cache).ok(),
+            &mut recorder,
+        )?;
+        recorder.records
+    };
+    records.sort_by(|a, b| a.filepath.cmp(&b.filepath));
+
+    let io_err = |err, path: &Path| Error::Io {
+        err,
+        path: path.to_owned(),
+    };
+    let mut outcome = Outcome::default();
+    fs::create_dir_all(worktree).map_err(|err| io_err(err, worktree))?;
+    // Parents sort before their children, so directories can be created in order.
+    for record in records
+        .iter()
+        .filter(|r| matches!(r.mode, EntryMode::Tree | EntryMode::Commit))
+    {
+        let path = worktree.join(record.filepath.to_path_lossy());
+        fs::create_dir(&path).map_err(|err| io_err(err, &path))?;
+        outcome.directories += 1;
+    }
+
+    records.retain(|r| r.mode != EntryMode::Tree);
+    let chunks = records.chunks(CHUNK_SIZE);
+    let num_chunks = chunks.len();
+    let written = in_parallel_if(
+        || num_chunks > 1,
+        chunks.enumerate(),
+        options.thread_limit,
+        |_| (Vec::new(), make_cache()),
+        |(chunk_index, records), (buf, cache)| {
+            let written = records
+                .iter()
+                .map(|record| checkout_entry(record, worktree, &db, buf, cache))
+                .collect();
+            (chunk_index, written)
+        },
+        reduce::Chunks::with_capacity(num_chunks),
+    )?;
+
+    let mut path_backing = Vec::new();
+    let mut entries = Vec::with_capacity(records.len());
+    for (record, written) in records.iter().zip(written.into_iter().flatten()) {
+        if let Some(bytes_written) = written.bytes {
+            outcome.files += 1;
+            outcome.bytes_written += bytes_written;
+        }
+        let path_start = path_backing.len();
+        path_backing.extend_from_slice(&record.filepath);
+        entries.push(Entry {
+            stat: written.stat,
+            id: record.oid,
+            flags: entry::Flags::default(),
+            mode: record.mode,
+            path: path_start..path_backing.len(),
+        });
+    }
+
+    Ok((
+        State {
+            version: Version::V2,
+            entries,
+            data: Backing::InMemory(Vec::new()),
+            path_backing: Some(path_backing),
+            tree: None,
+            resolve_undo: None,
+            untracked: None,
+            link: None,
+            unknown_extensions: Vec::new(),
+        },
+        outcome,
+    ))
+}
+
+pub(crate) struct Written {
+    stat: entry::Stat,
+    /// The amount of bytes written, or `None` if no file was written.
+    bytes: Option<u64>,
+}
+
+fn checkout_entry<Find>(
+    record: &recorder::Entry,
+    worktree: &Path,
+    db: &Find,
+    buf: &mut Vec<u8>,
+    cache: &mut impl DecodeEntry,
+) -> Result<Written, Error<Find::Error>>
+where
+    Find: git_odb::Find,
+{
+    if record.mode == EntryMode::Commit {
+        return Ok(Written {
+            stat: entry::Stat::default(),
+            bytes: None,
+        });
+    }
+    let obj = db.find_existing(record.oid, buf, cache)?;
+    if obj.kind != git_object::Kind::Blob {
+        return Err(Error::ObjectKind {
+            id: record.oid,
+            kind: obj.kind,
+        });
+    }
+    let path = worktree.join(record.filepath.to_path_lossy());
+    let io_err = |err| Error::Io {
+        err,
+        path: path.clone(),
+    };
+    if record.mode == EntryMode::Link && cfg!(unix) {
+        symlink(obj.data, &path).map_err(io_err)?;
+    } else {
+        let mut options = fs::OpenOptions::new();
+        options.create_new(true).write(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(if record.mode == EntryMode::BlobExecutable {
+                0o777
+            } else {
+                0o666
+            });
+        }
+        let mut file = options.open(&path).map_err(io_err)?;
+        file.write_all(obj.data).map_err(io_err)?;
+    }
+    let metadata = fs::symlink_metadata(&path).map_err(io_err)?;
+    Ok(Written {
+        stat: entry::Stat::from_metadata(&metadata),
+        bytes: Some(obj.data.len() as u64),
+    })
+}
+
+#[cfg(unix)]
+fn symlink(target: &[u8], path: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target.to_path_lossy(), path)
+}
+
+#[cfg(not(unix))]
+fn symlink(_target: &[u8], _path: &Path) -> std::io::Result<()> {
+    unreachable!("symbolic links are written as files on this platform")
+}
+
+mod reduce {
+    use super::Written;
+    use crate::checkout::Error;
+
+    /// Collect the written files of chunks of entries in the order of the entries.
+    pub(crate) struct Chunks<E: std::error::Error + 'static>(Vec<(usize, Vec<Written>)>, std::marker::PhantomData<E>);
+
+    impl<E: std::error::Error + 'static> Chunks<E> {
+        pub fn with_capacity(capacity: usize) -> Self {
+            Chunks(Vec::with_capacity(capacity), std::marker::PhantomData)
+        }
+    }
+
+    impl<E: std::error::Error + 'static> git_features::parallel::Reduce for Chunks<E> {
+        type Input = (usize, Result<Vec<Written>, Error<E>>);
+        type FeedProduce = ();
+        type Output = Vec<Vec<Written>>;
+        type Error = Error<E>;
+
+        fn feed(&mut self, (chunk_index, written): Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+            self.0.push((chunk_index, written?));
+            Ok(())
+        }
+
+        fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+            self.0.sort_by_key(|(chunk_index, _)| *chunk_index);
+            Ok(self.0.into_iter().map(|(_, written)| written).collect())
+        }
+    }
+}
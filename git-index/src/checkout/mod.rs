@@ -0,0 +1,43 @@
+use git_hash::ObjectId;
+use std::path::PathBuf;
+
+/// Options to control how a tree is checked out.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Options {
+    /// The amount of threads to use for writing files, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+}
+
+/// Information about a checkout, returned by [`tree()`].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Outcome {
+    /// The amount of files written, including symbolic links.
+    pub files: usize,
+    /// The amount of directories created, including empty ones for submodules.
+    pub directories: usize,
+    /// The total amount of bytes written into files.
+    pub bytes_written: u64,
+}
+
+/// The error returned by [`tree()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + 'static> {
+    #[error(transparent)]
+    FindTree(#[from] git_odb::pack::find::existing_iter::Error<E>),
+    #[error(transparent)]
+    FindBlob(#[from] git_odb::pack::find::existing::Error<E>),
+    #[error("The object {id} was expected to be a blob, but is a {kind}")]
+    ObjectKind { id: ObjectId, kind: git_object::Kind },
+    #[error(transparent)]
+    Traverse(#[from] git_traverse::tree::breadthfirst::Error),
+    #[error("Could not write '{}'", .path.display())]
+    Io {
+        #[source]
+        err: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+mod function;
+pub use function::tree;
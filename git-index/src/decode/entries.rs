@@ -0,0 +1,165 @@
+use crate::{
+    decode::{mode_from_u32, var_int, Error},
+    entry::{self, Flags},
+    Entry, Version,
+};
+use bstr::ByteSlice;
+use byteorder::{BigEndian, ByteOrder};
+use git_hash::ObjectId;
+use std::ops::Range;
+
+/// The size of an entry without its path and without extended flags.
+const ENTRY_LEN: usize = 62;
+/// The bits of the flags storing the length of the path, which is not needed as paths are null-terminated.
+const PATH_LEN_MASK: u32 = 0x0fff;
+
+/// Entries decoded from a contiguous portion of the index data.
+pub(crate) struct Block {
+    pub entries: Vec<Entry>,
+    /// The decompressed paths of version 4 indices, with the paths of all entries pointing into it.
+    ///
+    /// If `None`, the paths point into the index data itself.
+    pub path_backing: Option<Vec<u8>>,
+    /// The offset right past the last entry.
+    pub end: usize,
+}
+
+impl Block {
+    /// Concatenate `blocks` in order, adjusting the paths of their entries as needed.
+    pub fn merge(blocks: Vec<Block>) -> Block {
+        let num_entries = blocks.iter().map(|b| b.entries.len()).sum();
+        let mut entries = Vec::with_capacity(num_entries);
+        let mut path_backing: Option<Vec<u8>> = None;
+        let mut end = 0;
+        for block in blocks {
+            let path_offset = match (path_backing.as_mut(), block.path_backing) {
+                (Some(paths), Some(block_paths)) => {
+                    let offset = paths.len();
+                    paths.extend_from_slice(&block_paths);
+                    offset
+                }
+                (None, Some(block_paths)) => {
+                    path_backing = Some(block_paths);
+                    0
+                }
+                (_, None) => 0,
+            };
+            entries.extend(block.entries.into_iter().map(|mut e| {
+                e.path = e.path.start + path_offset..e.path.end + path_offset;
+                e
+            }));
+            end = block.end;
+        }
+        Block {
+            entries,
+            path_backing,
+            end,
+        }
+    }
+}
+
+/// Decode `num_entries` entries from `data` starting at `offset`, where `first_index` is the index of the first entry
+/// within the whole index and only used for error messages.
+///
+/// With version 4, the first path is never compressed, as it is the first entry of the index or of a block listed in the
+/// index entry offset table.
+pub(crate) fn decode(
+    data: &[u8],
+    mut offset: usize,
+    num_entries: u32,
+    version: Version,
+    first_index: usize,
+) -> Result<Block, Error> {
+    let mut entries = Vec::with_capacity(num_entries as usize);
+    let mut path_backing = match version {
+        Version::V4 => Some(Vec::new()),
+        Version::V2 | Version::V3 => None,
+    };
+    let mut previous_path = None;
+    for index in first_index..first_index + num_entries as usize {
+        let corrupt = |message: &str| Error::Corrupt(format!("Entry {} is corrupt: {}", index, message));
+        let entry_start = offset;
+        if data.len() < offset + ENTRY_LEN {
+            return Err(corrupt("the index ends prematurely"));
+        }
+        let d = &data[offset..];
+        let stat = entry::Stat {
+            ctime: entry::Time {
+                secs: BigEndian::read_u32(d),
+                nsecs: BigEndian::read_u32(&d[4..]),
+            },
+            mtime: entry::Time {
+                secs: BigEndian::read_u32(&d[8..]),
+                nsecs: BigEndian::read_u32(&d[12..]),
+            },
+            dev: BigEndian::read_u32(&d[16..]),
+            ino: BigEndian::read_u32(&d[20..]),
+            uid: BigEndian::read_u32(&d[28..]),
+            gid: BigEndian::read_u32(&d[32..]),
+            size: BigEndian::read_u32(&d[36..]),
+        };
+        let raw_mode = BigEndian::read_u32(&d[24..]);
+        let mode = mode_from_u32(raw_mode).ok_or(Error::InvalidMode { index, mode: raw_mode })?;
+        let id = ObjectId::from_20_bytes(&d[40..60]);
+        let mut flags = BigEndian::read_u16(&d[60..]) as u32 & !PATH_LEN_MASK;
+        offset += ENTRY_LEN;
+        if flags & Flags::EXTENDED != 0 {
+            if version == Version::V2 {
+                return Err(corrupt("extended flags are not supported in version 2"));
+            }
+            if data.len() < offset + 2 {
+                return Err(corrupt("the index ends prematurely"));
+            }
+            flags |= (BigEndian::read_u16(&data[offset..]) as u32) << 16;
+            offset += 2;
+        }
+
+        let path = match path_backing.as_mut() {
+            None => {
+                let path_len = data[offset..]
+                    .find_byte(0)
+                    .ok_or_else(|| corrupt("the path is not null-terminated"))?;
+                let path = offset..offset + path_len;
+                let entry_len = (offset - entry_start + path_len + 8) & !7;
+                offset = entry_start + entry_len;
+                if data.len() < offset {
+                    return Err(corrupt("the padding after the path is missing"));
+                }
+                path
+            }
+            Some(paths) => {
+                let (strip_len, consumed) =
+                    var_int(&data[offset..]).ok_or_else(|| corrupt("the path prefix length is invalid"))?;
+                offset += consumed;
+                let suffix_len = data[offset..]
+                    .find_byte(0)
+                    .ok_or_else(|| corrupt("the path is not null-terminated"))?;
+                let start = paths.len();
+                // Like git, ignore the prefix to strip for the first entry of a block.
+                if let Some(previous_path) = previous_path {
+                    let previous_path: Range<usize> = previous_path;
+                    if strip_len > previous_path.len() {
+                        return Err(corrupt("the path prefix to strip is longer than the previous path"));
+                    }
+                    paths.extend_from_within(previous_path.start..previous_path.end - strip_len);
+                }
+                paths.extend_from_slice(&data[offset..offset + suffix_len]);
+                offset += suffix_len + 1;
+                start..paths.len()
+            }
+        };
+        previous_path = Some(path.clone());
+        entries.push(Entry {
+            stat,
+            id,
+            flags: Flags(flags),
+            mode,
+            path,
+        });
+    }
+    Ok(Block {
+        entries,
+        path_backing,
+        end: offset,
+    })
+}
@@ -0,0 +1,349 @@
+use crate::{entry, extension, Backing, State, Version};
+use bstr::ByteSlice;
+use byteorder::{BigEndian, ByteOrder};
+use git_features::parallel::in_parallel_if;
+use git_hash::{ObjectId, SIZE_OF_SHA1_DIGEST as SHA1_SIZE};
+use git_object::tree::EntryMode;
+
+mod entries;
+use entries::Block;
+
+/// The error returned by [`State::from_bytes()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("{0}")]
+    Corrupt(String),
+    #[error("Unsupported index version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("Entry {index} has an invalid mode: {mode:o}")]
+    InvalidMode { index: usize, mode: u32 },
+    #[error("The {:?} extension is corrupt", .signature.as_bstr())]
+    Extension { signature: extension::Signature },
+    #[error("The index checksum {actual} does not match the one stored in the file, {expected}")]
+    ChecksumMismatch { actual: ObjectId, expected: ObjectId },
+}
+
+/// Options to control how an index is decoded.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Options {
+    /// The amount of threads to use for decoding entries if the index contains an index entry offset table,
+    /// or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+}
+
+const SIGNATURE: &[u8] = b"DIRC";
+const HEADER_LEN: usize = 12;
+const TRAILER_LEN: usize = SHA1_SIZE;
+pub(crate) const EXTENSION_HEADER_LEN: usize = 8;
+
+struct Decoded {
+    version: Version,
+    block: Block,
+    extensions: extension::Decoded,
+    checksum: ObjectId,
+}
+
+impl State {
+    /// Decode an index from `data` according to `options`, verify its trailing checksum and return it along with
+    /// that checksum.
+    ///
+    /// Verification is skipped if the checksum is null, as written by git if `index.skipHash` is set.
+    pub fn from_bytes(data: Vec<u8>, options: Options) -> Result<(Self, ObjectId), Error> {
+        Self::from_backing(Backing::InMemory(data), options)
+    }
+
+    pub(crate) fn from_backing(data: Backing, options: Options) -> Result<(Self, ObjectId), Error> {
+        let Decoded {
+            version,
+            block,
+            extensions,
+            checksum,
+        } = {
+            let bytes = match &data {
+                Backing::InMemory(data) => data.as_slice(),
+                Backing::Mapped(data) => data,
+            };
+            decode(bytes, options)?
+        };
+        Ok((
+            State {
+                version,
+                entries: block.entries,
+                data,
+                path_backing: block.path_backing,
+                tree: extensions.tree,
+                resolve_undo: extensions.resolve_undo,
+                untracked: extensions.untracked,
+                link: extensions.link,
+                unknown_extensions: extensions.unknown,
+            },
+            checksum,
+        ))
+    }
+}
+
+fn decode(data: &[u8], options: Options) -> Result<Decoded, Error> {
+    if data.len() < HEADER_LEN + TRAILER_LEN {
+        return Err(Error::Corrupt(format!(
+            "An index file must be at least {} bytes large, but was {} bytes",
+            HEADER_LEN + TRAILER_LEN,
+            data.len()
+        )));
+    }
+    let checksum = verify_checksum(data)?;
+    if &data[..SIGNATURE.len()] != SIGNATURE {
+        return Err(Error::Corrupt(format!(
+            "Index file signature must be {:?}, but was {:?}",
+            SIGNATURE.as_bstr(),
+            data[..SIGNATURE.len()].as_bstr()
+        )));
+    }
+    let version = match BigEndian::read_u32(&data[4..]) {
+        2 => Version::V2,
+        3 => Version::V3,
+        4 => Version::V4,
+        unknown => return Err(Error::UnsupportedVersion(unknown)),
+    };
+    let num_entries = BigEndian::read_u32(&data[8..]);
+    let data = &data[..data.len() - TRAILER_LEN];
+
+    let (block, extensions) = match extension::end_of_index_entry::decode(data) {
+        Some(extensions_start) => {
+            let extensions = extensions(data, extensions_start)?;
+            let block = match extensions.offset_table.as_ref() {
+                Some(offsets) => entries_in_parallel(data, offsets, num_entries, version, options)?,
+                None => entries::decode(data, HEADER_LEN, num_entries, version, 0)?,
+            };
+            if block.end != extensions_start {
+                return Err(Error::Corrupt(format!(
+                    "Entries end at offset {}, but extensions were supposed to start at {}",
+                    block.end, extensions_start
+                )));
+            }
+            (block, extensions)
+        }
+        None => {
+            let block = entries::decode(data, HEADER_LEN, num_entries, version, 0)?;
+            let extensions = extensions(data, block.end)?;
+            (block, extensions)
+        }
+    };
+    Ok(Decoded {
+        version,
+        block,
+        extensions,
+        checksum,
+    })
+}
+
+fn verify_checksum(data: &[u8]) -> Result<ObjectId, Error> {
+    let data_len_without_trailer = data.len() - TRAILER_LEN;
+    let expected = ObjectId::from_20_bytes(&data[data_len_without_trailer..]);
+    if expected == ObjectId::null_sha1() {
+        return Ok(expected);
+    }
+    let mut hasher = git_features::hash::Sha1::default();
+    hasher.update(&data[..data_len_without_trailer]);
+    let actual = ObjectId::new_sha1(hasher.digest());
+    if actual != expected {
+        return Err(Error::ChecksumMismatch { actual, expected });
+    }
+    Ok(expected)
+}
+
+/// Decode each block of entries listed in `offsets` on its own thread, and merge them in order.
+fn entries_in_parallel(
+    data: &[u8],
+    offsets: &[extension::index_entry_offset_table::Offset],
+    num_entries: u32,
+    version: Version,
+    options: Options,
+) -> Result<Block, Error> {
+    let num_entries_in_blocks: u64 = offsets.iter().map(|o| o.num_entries as u64).sum();
+    if num_entries_in_blocks != num_entries as u64 {
+        return Err(Error::Corrupt(format!(
+            "The index entry offset table lists {} entries, but the index has {}",
+            num_entries_in_blocks, num_entries
+        )));
+    }
+    let input = offsets
+        .iter()
+        .enumerate()
+        .scan(0, |first_index, (block_index, offset)| {
+            let item = (block_index, *offset, *first_index);
+            *first_index += offset.num_entries as usize;
+            Some(item)
+        });
+    let blocks = in_parallel_if(
+        || offsets.len() > 1,
+        input,
+        options.thread_limit,
+        |_| (),
+        |(block_index, offset, first_index), _| {
+            (
+                block_index,
+                entries::decode(
+                    data,
+                    offset.from_beginning_of_file as usize,
+                    offset.num_entries,
+                    version,
+                    first_index,
+                ),
+            )
+        },
+        reduce::Blocks::with_capacity(offsets.len()),
+    )?;
+    Ok(Block::merge(blocks))
+}
+
+mod reduce {
+    use crate::decode::{entries::Block, Error};
+
+    /// Collect blocks of entries in the order in which they appear in the index.
+    pub(crate) struct Blocks(Vec<(usize, Block)>);
+
+    impl Blocks {
+        pub fn with_capacity(capacity: usize) -> Self {
+            Blocks(Vec::with_capacity(capacity))
+        }
+    }
+
+    impl git_features::parallel::Reduce for Blocks {
+        type Input = (usize, Result<Block, Error>);
+        type FeedProduce = ();
+        type Output = Vec<Block>;
+        type Error = Error;
+
+        fn feed(&mut self, (block_index, block): Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+            self.0.push((block_index, block?));
+            Ok(())
+        }
+
+        fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+            self.0.sort_by_key(|(block_index, _)| *block_index);
+            Ok(self.0.into_iter().map(|(_, block)| block).collect())
+        }
+    }
+}
+
+/// Decode all extensions starting at `offset` until the end of `data`.
+fn extensions(data: &[u8], mut offset: usize) -> Result<extension::Decoded, Error> {
+    use extension::{
+        end_of_index_entry, index_entry_offset_table, link, resolve_undo, tree, untracked_cache, Signature,
+    };
+    let mut out = extension::Decoded::default();
+    while offset < data.len() {
+        if data.len() < offset + EXTENSION_HEADER_LEN {
+            return Err(Error::Corrupt(format!(
+                "The extension header at offset {} is truncated",
+                offset
+            )));
+        }
+        let mut signature = Signature::default();
+        signature.copy_from_slice(&data[offset..offset + 4]);
+        let size = BigEndian::read_u32(&data[offset + 4..]) as usize;
+        let start = offset + EXTENSION_HEADER_LEN;
+        let end = start + size;
+        if data.len() < end {
+            return Err(Error::Corrupt(format!(
+                "The {:?} extension claims to be {} bytes large, but only {} bytes are left",
+                signature.as_bstr(),
+                size,
+                data.len() - start
+            )));
+        }
+        let ext_data = &data[start..end];
+        let corrupt = || Error::Extension { signature };
+        match signature {
+            tree::SIGNATURE => out.tree = Some(tree::decode(ext_data).ok_or_else(corrupt)?),
+            resolve_undo::SIGNATURE => out.resolve_undo = Some(resolve_undo::decode(ext_data).ok_or_else(corrupt)?),
+            untracked_cache::SIGNATURE => out.untracked = Some(untracked_cache::decode(ext_data).ok_or_else(corrupt)?),
+            link::SIGNATURE => out.link = Some(link::decode(ext_data).ok_or_else(corrupt)?),
+            index_entry_offset_table::SIGNATURE => out.offset_table = index_entry_offset_table::decode(ext_data),
+            end_of_index_entry::SIGNATURE => {}
+            _ => out.unknown.push((signature, start..end)),
+        }
+        offset = end;
+    }
+    Ok(out)
+}
+
+pub(crate) fn mode_from_u32(mode: u32) -> Option<EntryMode> {
+    Some(match mode & 0o170000 {
+        0o100000 if mode & 0o111 != 0 => EntryMode::BlobExecutable,
+        0o100000 => EntryMode::Blob,
+        0o120000 => EntryMode::Link,
+        0o160000 => EntryMode::Commit,
+        0o040000 => EntryMode::Tree,
+        _ => return None,
+    })
+}
+
+/// Decode the variable length integer used to encode the amount of bytes to strip from the previous path,
+/// returning the value and the amount of bytes consumed.
+pub(crate) fn var_int(data: &[u8]) -> Option<(usize, usize)> {
+    let mut bytes = data.iter();
+    let mut c = *bytes.next()?;
+    let mut value = (c & 0x7f) as usize;
+    let mut consumed = 1;
+    while c & 0x80 != 0 {
+        c = *bytes.next()?;
+        consumed += 1;
+        value = value.checked_add(1)?.checked_mul(1 << 7)? + (c & 0x7f) as usize;
+    }
+    Some((value, consumed))
+}
+
+pub(crate) fn split_u32(data: &[u8]) -> Option<(u32, &[u8])> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (value, rest) = data.split_at(4);
+    Some((BigEndian::read_u32(value), rest))
+}
+
+pub(crate) fn split_id(data: &[u8]) -> Option<(ObjectId, &[u8])> {
+    if data.len() < SHA1_SIZE {
+        return None;
+    }
+    let (id, rest) = data.split_at(SHA1_SIZE);
+    Some((ObjectId::from_20_bytes(id), rest))
+}
+
+/// Split at the first occurrence of `delimiter`, which is part of neither returned slice.
+pub(crate) fn split_at_byte_exclusive(data: &[u8], delimiter: u8) -> Option<(&[u8], &[u8])> {
+    let pos = data.find_byte(delimiter)?;
+    Some((&data[..pos], &data[pos + 1..]))
+}
+
+/// Decode stat information as stored outside of entries, that is without the file mode.
+pub(crate) fn split_stat(data: &[u8]) -> Option<(entry::Stat, &[u8])> {
+    let (ctime_secs, data) = split_u32(data)?;
+    let (ctime_nsecs, data) = split_u32(data)?;
+    let (mtime_secs, data) = split_u32(data)?;
+    let (mtime_nsecs, data) = split_u32(data)?;
+    let (dev, data) = split_u32(data)?;
+    let (ino, data) = split_u32(data)?;
+    let (uid, data) = split_u32(data)?;
+    let (gid, data) = split_u32(data)?;
+    let (size, data) = split_u32(data)?;
+    Some((
+        entry::Stat {
+            mtime: entry::Time {
+                secs: mtime_secs,
+                nsecs: mtime_nsecs,
+            },
+            ctime: entry::Time {
+                secs: ctime_secs,
+                nsecs: ctime_nsecs,
+            },
+            dev,
+            ino,
+            uid,
+            gid,
+            size,
+        },
+        data,
+    ))
+}
@@ -0,0 +1,139 @@
+use crate::State;
+use bstr::{BStr, ByteSlice};
+use git_object::tree::EntryMode;
+use std::ops::Range;
+
+/// A point in time as stored in an index entry.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Time {
+    /// The seconds since the unix epoch.
+    pub secs: u32,
+    /// The nanoseconds elapsed within the second, or 0 if git was compiled without support for it.
+    pub nsecs: u32,
+}
+
+/// The cached stat information of a file in the work tree.
+///
+/// All values are truncated to 32 bits, and are only used to detect changes to a file without reading it.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Stat {
+    /// The time the file was last modified.
+    pub mtime: Time,
+    /// The time the metadata of the file was last changed.
+    pub ctime: Time,
+    /// The device the file resides on.
+    pub dev: u32,
+    /// The inode number of the file.
+    pub ino: u32,
+    /// The id of the user owning the file.
+    pub uid: u32,
+    /// The id of the group owning the file.
+    pub gid: u32,
+    /// The size of the file in bytes.
+    pub size: u32,
+}
+
+impl Stat {
+    /// Obtain stat information from the `metadata` of a file, truncating all values to 32 bits like git does.
+    ///
+    /// Only the modification time and the size are available on platforms other than unix.
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Stat {
+                mtime: Time {
+                    secs: metadata.mtime() as u32,
+                    nsecs: metadata.mtime_nsec() as u32,
+                },
+                ctime: Time {
+                    secs: metadata.ctime() as u32,
+                    nsecs: metadata.ctime_nsec() as u32,
+                },
+                dev: metadata.dev() as u32,
+                ino: metadata.ino() as u32,
+                uid: metadata.uid(),
+                gid: metadata.gid(),
+                size: metadata.size() as u32,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|since_epoch| Time {
+                    secs: since_epoch.as_secs() as u32,
+                    nsecs: since_epoch.subsec_nanos(),
+                })
+                .unwrap_or_default();
+            Stat {
+                mtime,
+                ctime: mtime,
+                size: metadata.len() as u32,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// The flags of an entry, combining the 16 bit flags of each entry in the lower half with the extended flags of
+/// version 3 and later in the upper half.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Flags(pub u32);
+
+impl Flags {
+    /// The mask to obtain the stage of an entry, which is shifted by [`STAGE_SHIFT`][Flags::STAGE_SHIFT].
+    pub const STAGE_MASK: u32 = 0x3000;
+    /// The amount of bits by which the stage is shifted.
+    pub const STAGE_SHIFT: u32 = 12;
+    /// The entry is assumed to be unchanged in the work tree, as set by `git update-index --assume-unchanged`.
+    pub const ASSUME_VALID: u32 = 1 << 15;
+    /// The entry has extended flags, which are only available in version 3 and later.
+    pub const EXTENDED: u32 = 1 << 14;
+    /// The entry was added with `git add --intent-to-add` and has no content yet.
+    pub const INTENT_TO_ADD: u32 = 1 << 29;
+    /// The entry is not checked out in the work tree, as used by sparse checkouts.
+    pub const SKIP_WORKTREE: u32 = 1 << 30;
+    /// All flags stored in the extended flags field.
+    pub const EXTENDED_MASK: u32 = Self::INTENT_TO_ADD | Self::SKIP_WORKTREE;
+
+    /// Return the stage of the entry, which is `0` for regular entries and `1` to `3` for the base, ours and theirs
+    /// side of a conflict respectively.
+    pub fn stage(&self) -> u32 {
+        (self.0 & Self::STAGE_MASK) >> Self::STAGE_SHIFT
+    }
+
+    /// Return true if all bits of `flags` are set.
+    pub fn contains(&self, flags: u32) -> bool {
+        self.0 & flags == flags
+    }
+}
+
+/// An entry of the index, describing a single file at a particular stage.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Entry {
+    /// The cached stat information of the file in the work tree.
+    pub stat: Stat,
+    /// The id of the blob, or of the commit for submodules.
+    pub id: git_hash::ObjectId,
+    /// Additional information like the stage of the entry.
+    pub flags: Flags,
+    /// The kind of file.
+    pub mode: EntryMode,
+    /// The range of the path in the [path backing][State::path_backing()] of the owning state.
+    pub(crate) path: Range<usize>,
+}
+
+impl Entry {
+    /// Return the path of this entry relative to the root of the work tree, using the `state` it belongs to.
+    pub fn path<'a>(&self, state: &'a State) -> &'a BStr {
+        state.path_backing()[self.path.clone()].as_bstr()
+    }
+
+    /// Return the stage of the entry, see [`Flags::stage()`].
+    pub fn stage(&self) -> u32 {
+        self.flags.stage()
+    }
+}
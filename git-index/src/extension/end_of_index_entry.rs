@@ -0,0 +1,39 @@
+use crate::{decode::EXTENSION_HEADER_LEN, extension::Signature};
+use byteorder::{BigEndian, ByteOrder};
+use git_hash::{ObjectId, SIZE_OF_SHA1_DIGEST as SHA1_SIZE};
+
+/// The signature of the end of index entry extension.
+pub const SIGNATURE: Signature = *b"EOIE";
+/// The size of the extension's data, an offset followed by a hash over all extension headers.
+pub const SIZE: usize = 4 + SHA1_SIZE;
+/// The size of the extension including its header.
+pub const SIZE_WITH_HEADER: usize = EXTENSION_HEADER_LEN + SIZE;
+
+/// Return the offset at which the extensions start if `data`, which doesn't include the trailing checksum, ends with
+/// a valid end of index entry extension.
+///
+/// The extension is ignored if it doesn't match the headers of the extensions it claims to describe.
+pub(crate) fn decode(data: &[u8]) -> Option<usize> {
+    let start = data.len().checked_sub(SIZE_WITH_HEADER)?;
+    let extension = &data[start..];
+    if extension[..4] != SIGNATURE || BigEndian::read_u32(&extension[4..]) as usize != SIZE {
+        return None;
+    }
+    let offset = BigEndian::read_u32(&extension[8..]) as usize;
+    let expected = ObjectId::from_20_bytes(&extension[12..]);
+    if offset > start {
+        return None;
+    }
+
+    let mut hasher = git_features::hash::Sha1::default();
+    let mut cursor = offset;
+    while cursor < start {
+        let header = data.get(cursor..cursor + EXTENSION_HEADER_LEN)?;
+        hasher.update(header);
+        cursor += EXTENSION_HEADER_LEN + BigEndian::read_u32(&header[4..]) as usize;
+    }
+    if cursor != start || ObjectId::new_sha1(hasher.digest()) != expected {
+        return None;
+    }
+    Some(offset)
+}
@@ -0,0 +1,33 @@
+use crate::{decode::split_u32, extension::Signature};
+
+/// The signature of the index entry offset table extension.
+pub const SIGNATURE: Signature = *b"IEOT";
+
+/// A block of consecutive entries which can be decoded independently of all other blocks.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Offset {
+    /// The offset of the first entry of the block from the beginning of the file.
+    pub from_beginning_of_file: u32,
+    /// The amount of entries in the block.
+    pub num_entries: u32,
+}
+
+/// Decode the index entry offset table extension from `data`, returning `None` if it is malformed or of an
+/// unsupported version.
+pub(crate) fn decode(data: &[u8]) -> Option<Vec<Offset>> {
+    let (version, mut data) = split_u32(data)?;
+    if version != 1 || data.len() % 8 != 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len() / 8);
+    while !data.is_empty() {
+        let (from_beginning_of_file, rest) = split_u32(data)?;
+        let (num_entries, rest) = split_u32(rest)?;
+        out.push(Offset {
+            from_beginning_of_file,
+            num_entries,
+        });
+        data = rest;
+    }
+    Some(out)
+}
@@ -0,0 +1,45 @@
+use crate::{
+    decode::split_id,
+    extension::{ewah, Link},
+};
+
+/// The signature of the split-index extension.
+pub const SIGNATURE: super::Signature = *b"link";
+
+/// The bitmaps describing how the entries of a split index apply to the shared index.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Bitmaps {
+    /// A set bit marks an entry of the shared index as deleted.
+    pub delete: ewah::Vec,
+    /// A set bit marks an entry of the shared index to be replaced by the next entry of the split index.
+    pub replace: ewah::Vec,
+}
+
+/// Decode the split-index extension from `data`, returning `None` if it is malformed.
+pub(crate) fn decode(data: &[u8]) -> Option<Link> {
+    let (shared_index_checksum, data) = split_id(data)?;
+    let bitmaps = if data.is_empty() {
+        None
+    } else {
+        let (delete, data) = ewah::Vec::from_bytes(data).ok()?;
+        let (replace, data) = ewah::Vec::from_bytes(data).ok()?;
+        if !data.is_empty() {
+            return None;
+        }
+        Some(Bitmaps { delete, replace })
+    };
+    Some(Link {
+        shared_index_checksum,
+        bitmaps,
+    })
+}
+
+/// Serialize `link` to `out`.
+pub(crate) fn write_to(link: &Link, out: &mut impl std::io::Write) -> std::io::Result<()> {
+    out.write_all(link.shared_index_checksum.as_slice())?;
+    if let Some(Bitmaps { delete, replace }) = &link.bitmaps {
+        delete.write_to(&mut *out)?;
+        replace.write_to(&mut *out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use bstr::BString;
+use git_hash::ObjectId;
+use std::ops::Range;
+
+/// The signature identifying an extension, like `TREE`.
+///
+/// Extensions whose signature starts with an uppercase letter are optional and may be ignored, whereas all others
+/// must be understood to interpret the index correctly.
+pub type Signature = [u8; 4];
+
+/// Return true if the extension with `signature` may be ignored if it is not understood.
+pub fn is_optional(signature: Signature) -> bool {
+    signature[0].is_ascii_uppercase()
+}
+
+/// A node of the cached tree extension, `TREE`, which stores the ids of trees representing portions of the index
+/// to speed up writing trees.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Tree {
+    /// The name of the directory this tree represents, which is empty for the root tree.
+    pub name: BString,
+    /// The id of the tree, or `None` if it was invalidated by a change to an entry it contains.
+    pub id: Option<ObjectId>,
+    /// The amount of index entries this tree and all of its sub-trees contain, which is only valid if `id` is set.
+    pub num_entries: u32,
+    /// The sub-trees of this tree, in the order they were stored.
+    pub children: Vec<Tree>,
+}
+
+/// A path that had conflicts before they were resolved, as stored in the resolve-undo extension, `REUC`.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct ResolvePath {
+    /// The path relative to the root of the work tree.
+    pub path: BString,
+    /// The base, ours and theirs side of the conflict, or `None` if the respective stage was missing.
+    pub stages: [Option<resolve_undo::Stage>; 3],
+}
+
+/// The link to a shared index, as stored in the split-index extension, `link`.
+///
+/// Note that entries of the shared index are not merged into the state, which only contains the entries of the
+/// split index itself.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Link {
+    /// The checksum of the shared index, which is stored in `$GIT_DIR/sharedindex.<checksum>`.
+    pub shared_index_checksum: ObjectId,
+    /// Information about how to apply the entries of this index to the shared one, or `None` if this is the shared
+    /// index itself.
+    pub bitmaps: Option<link::Bitmaps>,
+}
+
+/// The untracked cache extension, `UNTR`, which caches the untracked files of directories whose stat information
+/// didn't change.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct UntrackedCache {
+    /// A description of the environment the cache was created in, which is only valid if it is the same environment.
+    pub identifier: BString,
+    /// Information about `$GIT_DIR/info/exclude`.
+    pub info_exclude: Option<untracked_cache::OidStat>,
+    /// Information about the file configured in `core.excludesFile`.
+    pub excludes_file: Option<untracked_cache::OidStat>,
+    /// The name of the exclude file used in each directory, usually `.gitignore`.
+    pub exclude_filename_per_directory: BString,
+    /// The flags of the directory traversal the cache was created with.
+    pub directory_flags: u32,
+    /// All cached directories in pre-order, starting with the root directory, or empty if there is no root.
+    pub directories: Vec<untracked_cache::Directory>,
+}
+
+///
+pub mod end_of_index_entry;
+
+///
+pub mod index_entry_offset_table;
+
+///
+pub mod link;
+
+///
+pub mod resolve_undo;
+
+///
+pub mod tree;
+
+///
+pub mod untracked_cache;
+
+pub use git_bitmap::ewah;
+
+/// All extensions of an index, as decoded while reading it.
+#[derive(Default)]
+pub(crate) struct Decoded {
+    pub tree: Option<Tree>,
+    pub resolve_undo: Option<Vec<ResolvePath>>,
+    pub untracked: Option<UntrackedCache>,
+    pub link: Option<Link>,
+    pub offset_table: Option<Vec<index_entry_offset_table::Offset>>,
+    pub unknown: Vec<(Signature, Range<usize>)>,
+}
@@ -0,0 +1,68 @@
+use crate::{
+    decode::{mode_from_u32, split_at_byte_exclusive, split_id},
+    extension::ResolvePath,
+};
+use bstr::ByteSlice;
+use git_hash::ObjectId;
+use git_object::tree::EntryMode;
+
+/// The signature of the resolve-undo extension.
+pub const SIGNATURE: super::Signature = *b"REUC";
+
+/// One side of a conflict that was resolved.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Stage {
+    /// The kind of file.
+    pub mode: EntryMode,
+    /// The id of the blob.
+    pub id: ObjectId,
+}
+
+/// Decode the resolve-undo extension from `data`, returning `None` if it is malformed.
+pub(crate) fn decode(mut data: &[u8]) -> Option<Vec<ResolvePath>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let (path, rest) = split_at_byte_exclusive(data, 0)?;
+        data = rest;
+
+        let mut modes = [0u32; 3];
+        for mode in modes.iter_mut() {
+            let (octal, rest) = split_at_byte_exclusive(data, 0)?;
+            *mode = u32::from_str_radix(octal.to_str().ok()?, 8).ok()?;
+            data = rest;
+        }
+
+        let mut stages = [None, None, None];
+        for (stage, mode) in stages.iter_mut().zip(modes.iter()) {
+            if *mode == 0 {
+                continue;
+            }
+            let (id, rest) = split_id(data)?;
+            *stage = Some(Stage {
+                mode: mode_from_u32(*mode)?,
+                id,
+            });
+            data = rest;
+        }
+        out.push(ResolvePath {
+            path: path.into(),
+            stages,
+        });
+    }
+    Some(out)
+}
+
+/// Serialize all `paths` to `out`.
+pub(crate) fn write_to(paths: &[ResolvePath], out: &mut impl std::io::Write) -> std::io::Result<()> {
+    for path in paths {
+        out.write_all(&path.path)?;
+        out.write_all(b"\0")?;
+        for stage in &path.stages {
+            write!(out, "{:o}\0", stage.map_or(0, |s| s.mode as u32))?;
+        }
+        for stage in path.stages.iter().flatten() {
+            out.write_all(stage.id.as_slice())?;
+        }
+    }
+    Ok(())
+}
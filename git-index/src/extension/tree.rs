@@ -0,0 +1,66 @@
+use crate::{
+    decode::{split_at_byte_exclusive, split_id},
+    extension::Tree,
+};
+use bstr::ByteSlice;
+
+/// The signature of the cached tree extension.
+pub const SIGNATURE: super::Signature = *b"TREE";
+
+/// Decode the cached tree extension from `data`, returning `None` if it is malformed.
+pub(crate) fn decode(data: &[u8]) -> Option<Tree> {
+    let (tree, data) = one_recursive(data)?;
+    if !data.is_empty() {
+        return None;
+    }
+    Some(tree)
+}
+
+fn one_recursive(data: &[u8]) -> Option<(Tree, &[u8])> {
+    let (name, data) = split_at_byte_exclusive(data, 0)?;
+    let (num_entries, data) = split_at_byte_exclusive(data, b' ')?;
+    let num_entries: i32 = num_entries.to_str().ok()?.parse().ok()?;
+    let (num_children, mut data) = split_at_byte_exclusive(data, b'\n')?;
+    let num_children: usize = num_children.to_str().ok()?.parse().ok()?;
+
+    let id = if num_entries >= 0 {
+        let (id, rest) = split_id(data)?;
+        data = rest;
+        Some(id)
+    } else {
+        None
+    };
+
+    let mut children = Vec::with_capacity(num_children);
+    for _ in 0..num_children {
+        let (child, rest) = one_recursive(data)?;
+        children.push(child);
+        data = rest;
+    }
+    Some((
+        Tree {
+            name: name.into(),
+            id,
+            num_entries: num_entries.max(0) as u32,
+            children,
+        },
+        data,
+    ))
+}
+
+/// Serialize `tree` and all of its children to `out`.
+pub(crate) fn write_to(tree: &Tree, out: &mut impl std::io::Write) -> std::io::Result<()> {
+    out.write_all(&tree.name)?;
+    out.write_all(b"\0")?;
+    match tree.id {
+        Some(id) => {
+            writeln!(out, "{} {}", tree.num_entries, tree.children.len())?;
+            out.write_all(id.as_slice())?;
+        }
+        None => writeln!(out, "-1 {}", tree.children.len())?,
+    }
+    for child in &tree.children {
+        write_to(child, out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,213 @@
+use crate::{
+    decode::{split_at_byte_exclusive, split_id, split_stat, split_u32, var_int},
+    entry,
+    extension::{ewah, UntrackedCache},
+    write::{write_stat, write_var_int},
+};
+use bstr::BString;
+use git_hash::ObjectId;
+
+/// The signature of the untracked cache extension.
+pub const SIGNATURE: super::Signature = *b"UNTR";
+
+/// The stat information and id of a file that affects which files are considered untracked.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct OidStat {
+    /// The stat information of the file at the time it was hashed.
+    pub stat: entry::Stat,
+    /// The id of the file's content.
+    pub id: ObjectId,
+}
+
+/// A directory whose untracked files are cached.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Directory {
+    /// The name of the directory, which is empty for the root directory.
+    pub name: BString,
+    /// The untracked files and directories within this directory, with directories having a trailing slash.
+    pub untracked_entries: Vec<BString>,
+    /// The indices of sub-directories within [`UntrackedCache::directories`].
+    pub sub_directories: Vec<usize>,
+    /// The stat information of the directory if the cached data is valid.
+    pub stat: Option<entry::Stat>,
+    /// The id of the exclude file within this directory, if it exists.
+    pub exclude_file_oid: Option<ObjectId>,
+    /// If true, only the presence of untracked files in this directory is cached, but not which ones.
+    pub check_only: bool,
+}
+
+/// Decode the untracked cache extension from `data`, returning `None` if it is malformed.
+pub(crate) fn decode(data: &[u8]) -> Option<UntrackedCache> {
+    if data.last() != Some(&0) {
+        return None;
+    }
+    let data = &data[..data.len() - 1];
+    let (identifier_len, consumed) = var_int(data)?;
+    let data = &data[consumed..];
+    if data.len() < identifier_len {
+        return None;
+    }
+    let (identifier, data) = data.split_at(identifier_len);
+
+    let (info_exclude_stat, data) = split_stat(data)?;
+    let (excludes_file_stat, data) = split_stat(data)?;
+    let (directory_flags, data) = split_u32(data)?;
+    let (info_exclude_id, data) = split_id(data)?;
+    let (excludes_file_id, data) = split_id(data)?;
+    let oid_stat = |stat, id: ObjectId| {
+        if id == ObjectId::null_sha1() {
+            None
+        } else {
+            Some(OidStat { stat, id })
+        }
+    };
+    let (exclude_filename_per_directory, data) = split_at_byte_exclusive(data, 0)?;
+
+    let (num_directories, consumed) = var_int(data)?;
+    let mut data = &data[consumed..];
+    let mut directories = Vec::with_capacity(num_directories);
+    if num_directories != 0 {
+        data = decode_directory_block(data, &mut directories)?;
+        if directories.len() != num_directories {
+            return None;
+        }
+        let (valid, rest) = ewah::Vec::from_bytes(data).ok()?;
+        let (check_only, rest) = ewah::Vec::from_bytes(rest).ok()?;
+        let (hash_valid, mut rest) = ewah::Vec::from_bytes(rest).ok()?;
+
+        let mut stat_and_ids_ok = true;
+        valid.for_each_set_bit(|index| match (split_stat(rest), directories.get_mut(index)) {
+            (Some((stat, remaining)), Some(directory)) => {
+                directory.stat = Some(stat);
+                rest = remaining;
+            }
+            _ => stat_and_ids_ok = false,
+        });
+        check_only.for_each_set_bit(|index| match directories.get_mut(index) {
+            Some(directory) => directory.check_only = true,
+            None => stat_and_ids_ok = false,
+        });
+        hash_valid.for_each_set_bit(|index| match (split_id(rest), directories.get_mut(index)) {
+            (Some((id, remaining)), Some(directory)) => {
+                directory.exclude_file_oid = Some(id);
+                rest = remaining;
+            }
+            _ => stat_and_ids_ok = false,
+        });
+        if !stat_and_ids_ok {
+            return None;
+        }
+        data = rest;
+    }
+    if !data.is_empty() {
+        return None;
+    }
+
+    Some(UntrackedCache {
+        identifier: identifier.into(),
+        info_exclude: oid_stat(info_exclude_stat, info_exclude_id),
+        excludes_file: oid_stat(excludes_file_stat, excludes_file_id),
+        exclude_filename_per_directory: exclude_filename_per_directory.into(),
+        directory_flags,
+        directories,
+    })
+}
+
+fn decode_directory_block<'a>(data: &'a [u8], directories: &mut Vec<Directory>) -> Option<&'a [u8]> {
+    let (num_untracked, consumed) = var_int(data)?;
+    let data = &data[consumed..];
+    let (num_sub_directories, consumed) = var_int(data)?;
+    let data = &data[consumed..];
+    let (name, mut data) = split_at_byte_exclusive(data, 0)?;
+
+    let mut untracked_entries = Vec::with_capacity(num_untracked.min(data.len()));
+    for _ in 0..num_untracked {
+        let (entry, rest) = split_at_byte_exclusive(data, 0)?;
+        untracked_entries.push(entry.into());
+        data = rest;
+    }
+
+    let index = directories.len();
+    directories.push(Directory {
+        name: name.into(),
+        untracked_entries,
+        sub_directories: Vec::with_capacity(num_sub_directories.min(data.len())),
+        stat: None,
+        exclude_file_oid: None,
+        check_only: false,
+    });
+    for _ in 0..num_sub_directories {
+        let sub_directory_index = directories.len();
+        data = decode_directory_block(data, directories)?;
+        directories[index].sub_directories.push(sub_directory_index);
+    }
+    Some(data)
+}
+
+/// Serialize `cache` to `out`.
+pub(crate) fn write_to(cache: &UntrackedCache, out: &mut impl std::io::Write) -> std::io::Result<()> {
+    write_var_int(cache.identifier.len(), &mut *out)?;
+    out.write_all(&cache.identifier)?;
+    let null = ObjectId::null_sha1();
+    let (info_exclude_stat, info_exclude_id) = cache
+        .info_exclude
+        .map_or((Default::default(), null), |s| (s.stat, s.id));
+    let (excludes_file_stat, excludes_file_id) = cache
+        .excludes_file
+        .map_or((Default::default(), null), |s| (s.stat, s.id));
+    write_stat(&info_exclude_stat, &mut *out)?;
+    write_stat(&excludes_file_stat, &mut *out)?;
+    out.write_all(&cache.directory_flags.to_be_bytes())?;
+    out.write_all(info_exclude_id.as_slice())?;
+    out.write_all(excludes_file_id.as_slice())?;
+    out.write_all(&cache.exclude_filename_per_directory)?;
+    out.write_all(b"\0")?;
+
+    write_var_int(cache.directories.len(), &mut *out)?;
+    if !cache.directories.is_empty() {
+        let mut order = Vec::with_capacity(cache.directories.len());
+        write_directory_block(&cache.directories, 0, &mut order, &mut *out)?;
+
+        let bitmap = |is_set: &dyn Fn(&Directory) -> bool| {
+            let bits: Vec<_> = order
+                .iter()
+                .enumerate()
+                .filter(|(_, dir)| is_set(dir))
+                .map(|(bit, _)| bit)
+                .collect();
+            ewah::Vec::from_set_bits(&bits)
+        };
+        bitmap(&|dir| dir.stat.is_some()).write_to(&mut *out)?;
+        bitmap(&|dir| dir.check_only).write_to(&mut *out)?;
+        bitmap(&|dir| dir.exclude_file_oid.is_some()).write_to(&mut *out)?;
+        for stat in order.iter().filter_map(|dir| dir.stat.as_ref()) {
+            write_stat(stat, &mut *out)?;
+        }
+        for id in order.iter().filter_map(|dir| dir.exclude_file_oid.as_ref()) {
+            out.write_all(id.as_slice())?;
+        }
+    }
+    out.write_all(b"\0")
+}
+
+fn write_directory_block<'a>(
+    directories: &'a [Directory],
+    index: usize,
+    order: &mut Vec<&'a Directory>,
+    out: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    let directory = &directories[index];
+    order.push(directory);
+    write_var_int(directory.untracked_entries.len(), &mut *out)?;
+    write_var_int(directory.sub_directories.len(), &mut *out)?;
+    out.write_all(&directory.name)?;
+    out.write_all(b"\0")?;
+    for entry in &directory.untracked_entries {
+        out.write_all(entry)?;
+        out.write_all(b"\0")?;
+    }
+    for sub_directory in &directory.sub_directories {
+        write_directory_block(directories, *sub_directory, order, out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crate::{File, State};
+use std::path::Path;
+
+///
+pub mod init {
+    use crate::{decode, Backing, File, State};
+    use filebuffer::FileBuffer;
+    use std::path::Path;
+
+    /// The error returned by [`File::at()`].
+    #[derive(thiserror::Error, Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not open index file at '{}'", .path.display())]
+        Io {
+            #[source]
+            err: std::io::Error,
+            path: std::path::PathBuf,
+        },
+        #[error(transparent)]
+        Decode(#[from] decode::Error),
+    }
+
+    impl File {
+        /// Open the index file at `path`, decode it according to `options` and verify its checksum.
+        pub fn at(path: impl AsRef<Path>, options: decode::Options) -> Result<Self, Error> {
+            let path = path.as_ref();
+            let data = FileBuffer::open(path).map_err(|err| Error::Io {
+                err,
+                path: path.to_owned(),
+            })?;
+            let (state, checksum) = State::from_backing(Backing::Mapped(data), options)?;
+            Ok(File {
+                state,
+                path: path.to_owned(),
+                checksum,
+            })
+        }
+    }
+}
+
+/// Access
+impl File {
+    /// Return the path from which this index was read.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the checksum stored at the end of the index file, which may be null if it was not computed.
+    pub fn checksum(&self) -> &git_hash::oid {
+        &self.checksum
+    }
+
+    /// Return the decoded state of the index.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+}
+
+impl std::ops::Deref for File {
+    type Target = State;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
+impl std::ops::DerefMut for File {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.state
+    }
+}
@@ -0,0 +1,58 @@
+use bstr::BString;
+use std::path::PathBuf;
+
+///
+pub mod wildmatch;
+pub use wildmatch::wildmatch;
+
+///
+pub mod pattern;
+
+mod search;
+
+/// A single pattern of an ignore file, like `*.o`, `/target/` or `!important.log`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Pattern {
+    /// The pattern without a leading `!` and a trailing `/`, whose meaning is stored in `mode`.
+    pub text: BString,
+    /// Additional information about how to match the pattern.
+    pub mode: pattern::Mode,
+}
+
+/// All patterns of a single ignore file, along with information about where they were read from.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct PatternList {
+    /// The patterns in the order in which they appear in the file, along with their line number.
+    pub patterns: Vec<(Pattern, usize)>,
+    /// The directory relative to the work tree which contains the ignore file, with a trailing slash, or empty for
+    /// patterns that apply to the whole work tree.
+    pub base: BString,
+    /// The file the patterns were read from, if any.
+    pub source: Option<PathBuf>,
+}
+
+/// A collection of [pattern lists][PatternList] ordered by increasing precedence, to determine whether a path is
+/// ignored.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Default)]
+pub struct Search {
+    /// The lists of patterns, where later lists take precedence over earlier ones.
+    pub lists: Vec<PatternList>,
+}
+
+/// The pattern that matched a path, as returned by [`Search::pattern_matching_relative_path()`].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Match<'a> {
+    /// The matching pattern.
+    pub pattern: &'a Pattern,
+    /// The line number of the pattern in its source, starting at 1.
+    pub line_number: usize,
+    /// The file the pattern was read from, if any.
+    pub source: Option<&'a std::path::Path>,
+}
+
+impl<'a> Match<'a> {
+    /// Return true if the path is ignored, or false if it was explicitly included by a negative pattern.
+    pub fn is_excluded(&self) -> bool {
+        !self.pattern.mode.contains(pattern::Mode::NEGATIVE)
+    }
+}
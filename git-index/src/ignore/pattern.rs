@@ -0,0 +1,97 @@
+use crate::ignore::{wildmatch, Pattern};
+use bstr::{BStr, ByteSlice};
+
+/// Information about how a [`Pattern`] is matched.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Mode(pub u32);
+
+impl Mode {
+    /// The pattern doesn't contain a slash and is matched against the file name only, at any depth.
+    pub const NO_SUB_DIR: u32 = 1 << 0;
+    /// The pattern had a trailing slash and only matches directories.
+    pub const MUST_BE_DIR: u32 = 1 << 1;
+    /// The pattern started with `!` and re-includes paths excluded by previous patterns.
+    pub const NEGATIVE: u32 = 1 << 2;
+
+    /// Return true if all bits of `flags` are set.
+    pub fn contains(&self, flags: u32) -> bool {
+        self.0 & flags == flags
+    }
+}
+
+impl Pattern {
+    /// Parse a single `line` of an ignore file, or return `None` if it is empty or a comment.
+    ///
+    /// Trailing spaces are removed unless they are escaped with a backslash.
+    pub fn from_bytes(line: &[u8]) -> Option<Self> {
+        if line.first() == Some(&b'#') {
+            return None;
+        }
+        let mut line = trim_trailing_spaces(line);
+        let mut mode = 0;
+        if line.first() == Some(&b'!') {
+            mode |= Mode::NEGATIVE;
+            line = &line[1..];
+        }
+        if line.last() == Some(&b'/') {
+            mode |= Mode::MUST_BE_DIR;
+            line = &line[..line.len() - 1];
+        }
+        if line.is_empty() {
+            return None;
+        }
+        if !line.contains(&b'/') {
+            mode |= Mode::NO_SUB_DIR;
+        }
+        Some(Pattern {
+            text: line.into(),
+            mode: Mode(mode),
+        })
+    }
+
+    /// Return true if this pattern matches `relative_path`, which is relative to the directory containing the
+    /// pattern's ignore file.
+    ///
+    /// `is_dir` is true if the path is a directory, and `ignore_case` makes the comparison case-insensitive.
+    /// Negation is not taken into consideration.
+    pub fn matches(&self, relative_path: &BStr, is_dir: bool, ignore_case: bool) -> bool {
+        if self.mode.contains(Mode::MUST_BE_DIR) && !is_dir {
+            return false;
+        }
+        let case = if ignore_case { wildmatch::Mode::IGNORE_CASE } else { 0 };
+        if self.mode.contains(Mode::NO_SUB_DIR) {
+            let basename = relative_path
+                .rfind_byte(b'/')
+                .map_or(relative_path, |pos| relative_path[pos + 1..].as_bstr());
+            wildmatch(self.text.as_bstr(), basename, wildmatch::Mode(case))
+        } else {
+            // Patterns with a slash are always anchored to the directory of their ignore file.
+            let pattern = self.text.strip_prefix(b"/").unwrap_or(&self.text);
+            wildmatch(
+                pattern.as_bstr(),
+                relative_path,
+                wildmatch::Mode(wildmatch::Mode::SLASH_IS_LITERAL | case),
+            )
+        }
+    }
+}
+
+fn trim_trailing_spaces(line: &[u8]) -> &[u8] {
+    let mut first_trailing_space = None;
+    let mut idx = 0;
+    while idx < line.len() {
+        match line[idx] {
+            b' ' => {
+                first_trailing_space.get_or_insert(idx);
+            }
+            b'\\' => {
+                // The escaped character is never trimmed.
+                idx += 1;
+                first_trailing_space = None;
+            }
+            _ => first_trailing_space = None,
+        }
+        idx += 1;
+    }
+    &line[..first_trailing_space.unwrap_or(line.len())]
+}
@@ -0,0 +1,102 @@
+use crate::ignore::{Match, Pattern, PatternList, Search};
+use bstr::{BStr, BString, ByteSlice};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+impl PatternList {
+    /// Parse all patterns in `bytes`, the content of an ignore file at `source` if known, which applies to paths
+    /// inside the `base` directory relative to the work tree.
+    pub fn from_bytes(bytes: &[u8], base: impl Into<BString>, source: Option<PathBuf>) -> Self {
+        let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+        let patterns = bytes
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| Pattern::from_bytes(line).map(|pattern| (pattern, idx + 1)))
+            .collect();
+        PatternList {
+            patterns,
+            base: base.into(),
+            source,
+        }
+    }
+
+    /// Read the ignore file at `path` and parse its patterns, which apply to paths inside the `base` directory, or
+    /// return `None` if it doesn't exist.
+    pub fn from_file(path: impl Into<PathBuf>, base: impl Into<BString>) -> io::Result<Option<Self>> {
+        let path = path.into();
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(Self::from_bytes(&bytes, base, Some(path)))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Return the last pattern matching `relative_path`, which is relative to the work tree, or `None` if the path
+    /// is outside of our `base` directory or no pattern matches.
+    pub fn pattern_matching_relative_path(
+        &self,
+        relative_path: &BStr,
+        is_dir: bool,
+        ignore_case: bool,
+    ) -> Option<Match<'_>> {
+        let path_in_base = relative_path.strip_prefix(self.base.as_slice())?.as_bstr();
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(path_in_base, is_dir, ignore_case))
+            .map(|(pattern, line_number)| Match {
+                pattern,
+                line_number: *line_number,
+                source: self.source.as_deref(),
+            })
+    }
+}
+
+impl Search {
+    /// Create a search with the patterns of the `excludes_file` configured in `core.excludesFile`, if set, and of
+    /// `info/exclude` in `git_dir`. Both apply to the whole work tree and are allowed to be missing.
+    pub fn from_git_dir(git_dir: impl AsRef<Path>, excludes_file: Option<PathBuf>) -> io::Result<Self> {
+        let mut search = Search::default();
+        if let Some(excludes_file) = excludes_file {
+            search.add_patterns_file(excludes_file, "")?;
+        }
+        search.add_patterns_file(git_dir.as_ref().join("info").join("exclude"), "")?;
+        Ok(search)
+    }
+
+    /// Add the patterns of the ignore file at `path` which apply to paths inside the `base` directory with the highest
+    /// precedence, and return true if the file existed.
+    pub fn add_patterns_file(&mut self, path: impl Into<PathBuf>, base: impl Into<BString>) -> io::Result<bool> {
+        Ok(match PatternList::from_file(path, base)? {
+            Some(list) => {
+                self.lists.push(list);
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Add the patterns in `bytes` which apply to paths inside the `base` directory with the highest precedence.
+    pub fn add_patterns_buffer(&mut self, bytes: &[u8], base: impl Into<BString>) {
+        self.lists.push(PatternList::from_bytes(bytes, base, None));
+    }
+
+    /// Return the pattern matching `relative_path` with the highest precedence, or `None` if no pattern matches.
+    ///
+    /// `is_dir` is true if the path is a directory, and `ignore_case` makes the comparison case-insensitive like
+    /// `core.ignoreCase` does. Note that a path is also ignored if one of its leading directories is, which isn't
+    /// checked here.
+    pub fn pattern_matching_relative_path(
+        &self,
+        relative_path: &BStr,
+        is_dir: bool,
+        ignore_case: bool,
+    ) -> Option<Match<'_>> {
+        self.lists
+            .iter()
+            .rev()
+            .find_map(|list| list.pattern_matching_relative_path(relative_path, is_dir, ignore_case))
+    }
+}
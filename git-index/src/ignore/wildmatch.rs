@@ -0,0 +1,270 @@
+//! Match paths against glob patterns exactly like git's `wildmatch()` does.
+use bstr::BStr;
+
+/// Flags to control how a pattern is matched against a value.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Mode(pub u32);
+
+impl Mode {
+    /// Slashes in the value can only be matched by a slash in the pattern or by `**`, but never by `*`, `?` or
+    /// character classes.
+    pub const SLASH_IS_LITERAL: u32 = 1 << 0;
+    /// Compare ASCII characters without regard to their case.
+    pub const IGNORE_CASE: u32 = 1 << 1;
+
+    /// Return true if all bits of `flags` are set.
+    pub fn contains(&self, flags: u32) -> bool {
+        self.0 & flags == flags
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Result {
+    Match,
+    NoMatch,
+    /// The value was exhausted, so no other way of matching it can succeed.
+    AbortAll,
+    /// A `*` couldn't match across a slash, which can only be recovered by an enclosing `**`.
+    AbortToStarStar,
+}
+
+/// Return true if `value` matches `pattern` according to `mode`.
+///
+/// Supported are `*`, `?`, character classes like `[a-z]`, `[!a]` or `[[:digit:]]` and escaping with backslashes.
+/// With [`SLASH_IS_LITERAL`][Mode::SLASH_IS_LITERAL], `**/` matches zero or more directories, `/**` matches everything
+/// inside a directory and `/**/` matches zero or more directories in between.
+pub fn wildmatch(pattern: &BStr, value: &BStr, mode: Mode) -> bool {
+    match_recursive(pattern, value, mode) == Result::Match
+}
+
+/// Return the byte at `idx` or 0 if it is past the end, which can't be contained in paths or patterns.
+fn at(bytes: &[u8], idx: usize) -> u8 {
+    bytes.get(idx).copied().unwrap_or(0)
+}
+
+fn is_glob_special(b: u8) -> bool {
+    matches!(b, b'*' | b'?' | b'[' | b'\\')
+}
+
+fn match_recursive(pattern: &[u8], text: &[u8], mode: Mode) -> Result {
+    let ignore_case = mode.contains(Mode::IGNORE_CASE);
+    let slash_is_literal = mode.contains(Mode::SLASH_IS_LITERAL);
+    let fold = |b: u8| if ignore_case { b.to_ascii_lowercase() } else { b };
+
+    let (mut p, mut t) = (0, 0);
+    while p < pattern.len() {
+        let mut p_ch = fold(pattern[p]);
+        let mut t_ch = at(text, t);
+        if t_ch == 0 && p_ch != b'*' {
+            return Result::AbortAll;
+        }
+        t_ch = fold(t_ch);
+        match p_ch {
+            b'?' => {
+                if slash_is_literal && t_ch == b'/' {
+                    return Result::NoMatch;
+                }
+            }
+            b'*' => {
+                let match_slash;
+                p += 1;
+                if at(pattern, p) == b'*' {
+                    let previous_p = p.checked_sub(2);
+                    while at(pattern, p + 1) == b'*' {
+                        p += 1;
+                    }
+                    p += 1;
+                    if !slash_is_literal {
+                        match_slash = true;
+                    } else if previous_p.filter(|idx| pattern[*idx] != b'/').is_none()
+                        && (at(pattern, p) == 0
+                            || at(pattern, p) == b'/'
+                            || (at(pattern, p) == b'\\' && at(pattern, p + 1) == b'/'))
+                    {
+                        // Assume `**/` matches nothing and try to match the rest of the pattern, which makes
+                        // `foo/**/bar` match `foo/bar` as well.
+                        if at(pattern, p) == b'/'
+                            && match_recursive(&pattern[p + 1..], &text[t..], mode) == Result::Match
+                        {
+                            return Result::Match;
+                        }
+                        match_slash = true;
+                    } else {
+                        match_slash = false;
+                    }
+                } else {
+                    match_slash = !slash_is_literal;
+                }
+
+                if p == pattern.len() {
+                    // A trailing `**` matches everything, but a trailing `*` only matches if there are no more slashes.
+                    if !match_slash && text[t..].contains(&b'/') {
+                        return Result::NoMatch;
+                    }
+                    return Result::Match;
+                } else if !match_slash && pattern[p] == b'/' {
+                    // A single `*` followed by a slash matches the next directory.
+                    match text[t..].iter().position(|b| *b == b'/') {
+                        Some(pos) => t += pos,
+                        None => return Result::NoMatch,
+                    }
+                } else {
+                    loop {
+                        if t_ch == 0 {
+                            break;
+                        }
+                        // Skip ahead to the next occurrence of a literal following the `*`, without looking past
+                        // slashes it can't match.
+                        if !is_glob_special(pattern[p]) {
+                            p_ch = fold(pattern[p]);
+                            loop {
+                                t_ch = at(text, t);
+                                if t_ch == 0 || (!match_slash && t_ch == b'/') {
+                                    break;
+                                }
+                                t_ch = fold(t_ch);
+                                if t_ch == p_ch {
+                                    break;
+                                }
+                                t += 1;
+                            }
+                            if t_ch != p_ch {
+                                return Result::NoMatch;
+                            }
+                        }
+                        let res = match_recursive(&pattern[p..], &text[t..], mode);
+                        if res != Result::NoMatch {
+                            if !match_slash || res != Result::AbortToStarStar {
+                                return res;
+                            }
+                        } else if !match_slash && t_ch == b'/' {
+                            return Result::AbortToStarStar;
+                        }
+                        t += 1;
+                        t_ch = at(text, t);
+                    }
+                    return Result::AbortAll;
+                }
+            }
+            b'[' => {
+                p += 1;
+                p_ch = at(pattern, p);
+                if p_ch == b'^' {
+                    p_ch = b'!';
+                }
+                let negated = p_ch == b'!';
+                if negated {
+                    p += 1;
+                    p_ch = at(pattern, p);
+                }
+                let mut previous_ch = 0;
+                let mut matched = false;
+                loop {
+                    if p_ch == 0 {
+                        return Result::AbortAll;
+                    }
+                    if p_ch == b'\\' {
+                        p += 1;
+                        p_ch = at(pattern, p);
+                        if p_ch == 0 {
+                            return Result::AbortAll;
+                        }
+                        if t_ch == p_ch {
+                            matched = true;
+                        }
+                    } else if p_ch == b'-' && previous_ch != 0 && at(pattern, p + 1) != 0 && at(pattern, p + 1) != b']'
+                    {
+                        p += 1;
+                        p_ch = at(pattern, p);
+                        if p_ch == b'\\' {
+                            p += 1;
+                            p_ch = at(pattern, p);
+                            if p_ch == 0 {
+                                return Result::AbortAll;
+                            }
+                        }
+                        if t_ch <= p_ch && t_ch >= previous_ch {
+                            matched = true;
+                        } else if ignore_case && t_ch.is_ascii_lowercase() {
+                            let t_ch_upper = t_ch.to_ascii_uppercase();
+                            if t_ch_upper <= p_ch && t_ch_upper >= previous_ch {
+                                matched = true;
+                            }
+                        }
+                        p_ch = 0;
+                    } else if p_ch == b'[' && at(pattern, p + 1) == b':' {
+                        let class_start = p + 2;
+                        p = class_start;
+                        loop {
+                            p_ch = at(pattern, p);
+                            if p_ch == 0 || p_ch == b']' {
+                                break;
+                            }
+                            p += 1;
+                        }
+                        if p_ch == 0 {
+                            return Result::AbortAll;
+                        }
+                        if p == class_start || pattern[p - 1] != b':' {
+                            // Without a closing `:]` this is a normal class containing `[`.
+                            p = class_start - 2;
+                            p_ch = b'[';
+                            if t_ch == p_ch {
+                                matched = true;
+                            }
+                        } else {
+                            let is_match = match &pattern[class_start..p - 1] {
+                                b"alnum" => t_ch.is_ascii_alphanumeric(),
+                                b"alpha" => t_ch.is_ascii_alphabetic(),
+                                b"blank" => t_ch == b' ' || t_ch == b'\t',
+                                b"cntrl" => t_ch.is_ascii_control(),
+                                b"digit" => t_ch.is_ascii_digit(),
+                                b"graph" => t_ch.is_ascii_graphic(),
+                                b"lower" => t_ch.is_ascii_lowercase(),
+                                b"print" => t_ch.is_ascii_graphic() || t_ch == b' ',
+                                b"punct" => t_ch.is_ascii_punctuation(),
+                                b"space" => matches!(t_ch, b' ' | b'\t' | b'\n' | b'\r'),
+                                b"upper" => t_ch.is_ascii_uppercase() || (ignore_case && t_ch.is_ascii_lowercase()),
+                                b"xdigit" => t_ch.is_ascii_hexdigit(),
+                                _ => return Result::AbortAll,
+                            };
+                            if is_match {
+                                matched = true;
+                            }
+                            p_ch = 0;
+                        }
+                    } else if t_ch == p_ch {
+                        matched = true;
+                    }
+
+                    previous_ch = p_ch;
+                    p += 1;
+                    p_ch = at(pattern, p);
+                    if p_ch == b']' {
+                        break;
+                    }
+                }
+                if matched == negated || (slash_is_literal && t_ch == b'/') {
+                    return Result::NoMatch;
+                }
+            }
+            _ => {
+                if p_ch == b'\\' {
+                    p += 1;
+                    p_ch = at(pattern, p);
+                }
+                if t_ch != p_ch {
+                    return Result::NoMatch;
+                }
+            }
+        }
+        p += 1;
+        t += 1;
+    }
+
+    if t < text.len() {
+        Result::NoMatch
+    } else {
+        Result::Match
+    }
+}
@@ -1 +1,90 @@
-#![forbid(unsafe_code, rust_2018_idioms)]
+//! Read and write git index files, also known as the staging area or the dircache.
+//!
+//! An index [file][File] lists all files known to git along with a cached copy of their stat information, which is used
+//! to quickly determine which files in the work tree have changed. Its entries are sorted by path and stage.
+//!
+//! Versions 2, 3 and 4 of the format are supported. Paths of entries are not copied when reading versions 2 and 3, but
+//! have to be reconstructed into a separate buffer for version 4 due to its path prefix compression.
+//!
+//! The cached tree, resolve-undo, untracked cache and split-index [extensions][extension] are decoded as well, and
+//! entries are decoded in parallel if the index contains an index entry offset table.
+#![forbid(unsafe_code)]
+#![deny(rust_2018_idioms, missing_docs)]
+
+use filebuffer::FileBuffer;
+use std::{ops::Range, path::PathBuf};
+
+///
+pub mod entry;
+pub use entry::Entry;
+
+///
+pub mod extension;
+
+///
+pub mod decode;
+
+///
+pub mod file;
+
+///
+pub mod write;
+
+///
+pub mod write_tree;
+
+///
+pub mod status;
+
+///
+pub mod ignore;
+
+///
+pub mod untracked;
+
+///
+pub mod checkout;
+
+mod access;
+
+/// The version of an index file.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[allow(missing_docs)]
+pub enum Version {
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+}
+
+enum Backing {
+    /// The data is held entirely in memory.
+    InMemory(Vec<u8>),
+    /// The data is mapping the file on disk.
+    Mapped(FileBuffer),
+}
+
+/// The in-memory representation of an index, holding its entries and extensions.
+///
+/// The paths of all entries are stored in a single buffer, which is the data of the index itself unless the
+/// paths had to be decompressed.
+pub struct State {
+    version: Version,
+    entries: Vec<Entry>,
+    /// The data the index was decoded from.
+    data: Backing,
+    /// Decompressed paths of all entries if the data uses path prefix compression.
+    path_backing: Option<Vec<u8>>,
+    tree: Option<extension::Tree>,
+    resolve_undo: Option<Vec<extension::ResolvePath>>,
+    untracked: Option<extension::UntrackedCache>,
+    link: Option<extension::Link>,
+    /// Extensions that are not understood, stored as signature and the range of their data.
+    unknown_extensions: Vec<(extension::Signature, Range<usize>)>,
+}
+
+/// An index file on disk, along with its decoded [state][State].
+pub struct File {
+    state: State,
+    path: PathBuf,
+    checksum: git_hash::ObjectId,
+}
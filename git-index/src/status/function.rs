@@ -0,0 +1,224 @@
+use crate::{
+    entry::{self, Flags},
+    status::{Error, Options, Outcome, Status, Visit},
+    Entry, State,
+};
+use bstr::{BStr, ByteSlice, ByteVec};
+use git_features::parallel::in_parallel_if;
+use git_object::tree::EntryMode;
+use git_odb::Write;
+use std::{fs, io, path::Path};
+
+/// The amount of entries checked by a thread at a time.
+const CHUNK_SIZE: usize = 256;
+
+impl State {
+    /// Compare each entry with the file it describes in the `worktree` directory according to `options`,
+    /// and pass its [status][crate::status::Status] to `delegate` in the order of entries.
+    ///
+    /// All files are checked in parallel, but only those whose stat information doesn't match the one of their
+    /// entry are read and hashed, along with those that are racily clean. Entries that are part of a conflict are not
+    /// checked, just like those that are assumed to be unchanged or not checked out in a sparse checkout.
+    ///
+    /// Note that content filters like line ending conversions aren't applied before hashing.
+    pub fn status(
+        &self,
+        worktree: impl AsRef<Path>,
+        options: Options,
+        delegate: &mut impl Visit,
+    ) -> Result<Outcome, Error> {
+        let worktree = worktree.as_ref();
+        let chunks = self.entries.chunks(CHUNK_SIZE);
+        let num_chunks = chunks.len();
+        let checks = in_parallel_if(
+            || num_chunks > 1,
+            chunks.enumerate(),
+            options.thread_limit,
+            |_| (),
+            |(chunk_index, entries), _| {
+                let checks = entries
+                    .iter()
+                    .map(|entry| check(entry, entry.path(self), worktree, &options))
+                    .collect();
+                (chunk_index, checks)
+            },
+            reduce::Chunks::with_capacity(num_chunks),
+        )?;
+
+        let mut outcome = Outcome::default();
+        for (entry, check) in self.entries.iter().zip(checks.into_iter().flatten()) {
+            let check = match check {
+                Some(check) => check,
+                None => continue,
+            };
+            outcome.entries += 1;
+            outcome.racy += check.racy as usize;
+            outcome.hashed += check.hashed as usize;
+            if delegate.visit(entry, entry.path(self), check.status).cancelled() {
+                return Err(Error::Cancelled);
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+pub(crate) struct Check {
+    status: Status,
+    racy: bool,
+    hashed: bool,
+}
+
+impl From<Status> for Check {
+    fn from(status: Status) -> Self {
+        Check {
+            status,
+            racy: false,
+            hashed: false,
+        }
+    }
+}
+
+/// Determine the status of `entry` at `path`, or return `None` if it is part of a conflict.
+fn check(entry: &Entry, path: &BStr, worktree: &Path, options: &Options) -> Result<Option<Check>, Error> {
+    if entry.stage() != 0 {
+        return Ok(None);
+    }
+    if entry.flags.contains(Flags::ASSUME_VALID) || entry.flags.contains(Flags::SKIP_WORKTREE) {
+        return Ok(Some(Status::Unchanged.into()));
+    }
+    let file_path = worktree.join(path.to_path_lossy());
+    let io_err = |err| Error::Io {
+        err,
+        path: file_path.clone(),
+    };
+    let metadata = match fs::symlink_metadata(&file_path) {
+        Ok(metadata) => metadata,
+        Err(err) if is_missing(&err) => return Ok(Some(Status::Deleted.into())),
+        Err(err) => return Err(io_err(err)),
+    };
+    let file_type = metadata.file_type();
+    let has_expected_type = match entry.mode {
+        EntryMode::Blob | EntryMode::BlobExecutable => file_type.is_file(),
+        EntryMode::Link => file_type.is_symlink(),
+        EntryMode::Commit | EntryMode::Tree => {
+            // The content of submodules and sparse directories is not inspected.
+            return Ok(Some(
+                if file_type.is_dir() {
+                    Status::Unchanged
+                } else {
+                    Status::TypeChanged
+                }
+                .into(),
+            ));
+        }
+    };
+    if !has_expected_type {
+        return Ok(Some(Status::TypeChanged.into()));
+    }
+    if options.check_executable_bit
+        && entry.mode != EntryMode::Link
+        && is_executable(&metadata) != (entry.mode == EntryMode::BlobExecutable)
+    {
+        return Ok(Some(Status::Modified.into()));
+    }
+
+    let stat = entry::Stat::from_metadata(&metadata);
+    let mut racy = false;
+    if stat_matches(&entry.stat, &stat, options) {
+        match options.index_mtime {
+            Some(index_mtime) if entry.stat.mtime >= index_mtime => racy = true,
+            _ => return Ok(Some(Status::Unchanged.into())),
+        }
+    } else if entry.stat.size != stat.size && entry.stat.size != 0 {
+        // A size of 0 may have been written on purpose for racily clean entries, so the content has to be compared.
+        return Ok(Some(Status::Modified.into()));
+    }
+
+    let id = if file_type.is_symlink() {
+        let target = match fs::read_link(&file_path) {
+            Ok(target) => target,
+            Err(err) if is_missing(&err) => return Ok(Some(Status::Deleted.into())),
+            Err(err) => return Err(io_err(err)),
+        };
+        let target = Vec::from_path_lossy(&target);
+        git_odb::sink().write_buf(git_object::Kind::Blob, &target, git_hash::Kind::Sha1)
+    } else {
+        match fs::File::open(&file_path) {
+            Ok(file) => {
+                git_odb::sink().write_stream(git_object::Kind::Blob, metadata.len(), file, git_hash::Kind::Sha1)
+            }
+            Err(err) if is_missing(&err) => return Ok(Some(Status::Deleted.into())),
+            Err(err) => return Err(io_err(err)),
+        }
+    }
+    .map_err(io_err)?;
+    Ok(Some(Check {
+        status: if id == entry.id {
+            Status::Unchanged
+        } else {
+            Status::Modified
+        },
+        racy,
+        hashed: true,
+    }))
+}
+
+fn stat_matches(cached: &entry::Stat, actual: &entry::Stat, options: &Options) -> bool {
+    // Like git, the device is ignored as it isn't necessarily stable, for instance on network filesystems.
+    cached.mtime == actual.mtime
+        && (!options.check_ctime || cached.ctime == actual.ctime)
+        && cached.ino == actual.ino
+        && cached.uid == actual.uid
+        && cached.gid == actual.gid
+        && cached.size == actual.size
+}
+
+/// Return true if `err` indicates that the file doesn't exist, which includes one of its leading directories being
+/// a file.
+fn is_missing(err: &io::Error) -> bool {
+    /// `ENOTDIR` on linux and MacOS.
+    const NOT_A_DIRECTORY: i32 = 20;
+    err.kind() == io::ErrorKind::NotFound || (cfg!(unix) && err.raw_os_error() == Some(NOT_A_DIRECTORY))
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o100 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+mod reduce {
+    use super::Check;
+    use crate::status::Error;
+
+    /// Collect the checks of chunks of entries in the order of the entries.
+    pub(crate) struct Chunks(Vec<(usize, Vec<Option<Check>>)>);
+
+    impl Chunks {
+        pub fn with_capacity(capacity: usize) -> Self {
+            Chunks(Vec::with_capacity(capacity))
+        }
+    }
+
+    impl git_features::parallel::Reduce for Chunks {
+        type Input = (usize, Result<Vec<Option<Check>>, Error>);
+        type FeedProduce = ();
+        type Output = Vec<Vec<Option<Check>>>;
+        type Error = Error;
+
+        fn feed(&mut self, (chunk_index, checks): Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+            self.0.push((chunk_index, checks?));
+            Ok(())
+        }
+
+        fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+            self.0.sort_by_key(|(chunk_index, _)| *chunk_index);
+            Ok(self.0.into_iter().map(|(_, checks)| checks).collect())
+        }
+    }
+}
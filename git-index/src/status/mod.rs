@@ -0,0 +1,100 @@
+use crate::{entry, Entry};
+use bstr::BStr;
+use std::path::PathBuf;
+
+/// The status of a tracked file in the work tree compared to its entry in the index.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Status {
+    /// The file has the same content and mode as the entry.
+    Unchanged,
+    /// The content of the file differs from the one of the entry, or its executable bit changed.
+    Modified,
+    /// The file does not exist in the work tree anymore.
+    Deleted,
+    /// The file exists, but is of another kind, like a symbolic link in place of a file, or a directory in place of
+    /// either.
+    TypeChanged,
+}
+
+/// What to do after the [status][Status] of an entry was [observed][Visit::visit()].
+#[derive(Clone, Copy, PartialOrd, PartialEq, Ord, Eq, Hash)]
+pub enum Action {
+    /// Continue visiting entries.
+    Continue,
+    /// Stop visiting entries, making this the last call to [visit(…)][Visit::visit()].
+    Cancel,
+}
+
+impl Action {
+    /// Returns true if this action means to stop visiting entries.
+    pub fn cancelled(&self) -> bool {
+        matches!(self, Action::Cancel)
+    }
+}
+
+/// A trait to receive the [status][Status] of each entry of the index, in the order of entries.
+pub trait Visit {
+    /// Record the `status` of `entry` located at `path` and return an instruction whether to continue or not.
+    fn visit(&mut self, entry: &Entry, path: &BStr, status: Status) -> Action;
+}
+
+/// Options to control how the status of the work tree is obtained.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Options {
+    /// The amount of threads to use for checking files, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// The modification time of the index file, as obtained with [`entry::Stat::from_metadata()`].
+    ///
+    /// Entries modified at the same time or after the index was written are _racy_, as changes to the file may have
+    /// happened within the granularity of the timestamp, so their content is always compared.
+    /// If `None`, no entry is considered racy.
+    pub index_mtime: Option<entry::Time>,
+    /// If true, changes to the executable bit are considered modifications, like git does with `core.fileMode`.
+    pub check_executable_bit: bool,
+    /// If true, changes to the time the metadata of a file changed invalidate the cached stat information, like git
+    /// does with `core.trustCTime`.
+    pub check_ctime: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            thread_limit: None,
+            index_mtime: None,
+            check_executable_bit: true,
+            check_ctime: true,
+        }
+    }
+}
+
+/// Information about how the status of entries was obtained, returned by [`State::status()`][crate::State::status()].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Outcome {
+    /// The amount of entries that were checked.
+    pub entries: usize,
+    /// The amount of entries whose stat information matched, but whose modification time didn't allow to trust it.
+    pub racy: usize,
+    /// The amount of files whose content was hashed to compare it with the one of their entry.
+    pub hashed: usize,
+}
+
+/// The error returned by [`State::status()`][crate::State::status()].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not access '{}' in the work tree", .path.display())]
+    Io {
+        #[source]
+        err: std::io::Error,
+        path: PathBuf,
+    },
+    #[error("The delegate cancelled the operation")]
+    Cancelled,
+}
+
+mod function;
+
+///
+pub mod recorder;
+#[doc(inline)]
+pub use recorder::Recorder;
@@ -0,0 +1,21 @@
+use crate::{
+    status::{Action, Status, Visit},
+    Entry,
+};
+use bstr::{BStr, BString};
+
+/// A [Visit] implementation to record the path and status of every entry that isn't [unchanged][Status::Unchanged].
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    /// The observed changes along with the path of their entry, in the order of entries.
+    pub records: Vec<(BString, Status)>,
+}
+
+impl Visit for Recorder {
+    fn visit(&mut self, _entry: &Entry, path: &BStr, status: Status) -> Action {
+        if status != Status::Unchanged {
+            self.records.push((path.to_owned(), status));
+        }
+        Action::Continue
+    }
+}
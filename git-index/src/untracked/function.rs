@@ -0,0 +1,167 @@
+use crate::{
+    ignore,
+    untracked::{Entry, Error, Kind, Options},
+    State,
+};
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// The name of the files containing ignore patterns for their directory.
+const IGNORE_FILE_NAME: &str = ".gitignore";
+
+impl State {
+    /// Find all paths in the `worktree` directory that aren't tracked by this index, sorted by path.
+    ///
+    /// Paths are ignored if they match patterns in `excludes`, like those of `info/exclude`, or in the `.gitignore`
+    /// files of their leading directories, and are only returned if [`Options::emit_ignored`] is set.
+    /// Ignored directories without tracked files are returned as a whole, just like nested repositories, while all
+    /// other untracked files are listed one by one. The `.git` directory is never traversed, and neither are directories
+    /// which are returned as a whole or which are tracked as submodules.
+    pub fn untracked_paths(
+        &self,
+        worktree: impl AsRef<Path>,
+        excludes: &ignore::Search,
+        options: Options,
+    ) -> Result<Vec<Entry>, Error> {
+        let worktree = worktree.as_ref();
+        let mut search = DirectorySearch {
+            excludes,
+            per_directory: HashMap::new(),
+            ignore_case: options.ignore_case,
+        };
+        search.load_ignore_file(worktree, BString::default())?;
+
+        let mut out = Vec::new();
+        // Directories which are ignored but contain tracked files, which makes all untracked files inside ignored.
+        let mut ignored = HashSet::<PathBuf>::new();
+        let mut entries = git_features::fs::sequential::walkdir_new(worktree)
+            .follow_links(false)
+            .into_iter();
+        while let Some(entry) = entries.next() {
+            let entry = entry?;
+            if entry.depth() == 0 {
+                continue;
+            }
+            let path = entry.path().to_owned();
+            let is_dir = entry.file_type().is_dir();
+            let parent = path.parent().expect("entries below the work tree have a parent");
+            if path.file_name() == Some(".git".as_ref()) {
+                if is_dir {
+                    entries.skip_current_dir();
+                }
+                continue;
+            }
+
+            let relative_path = relative_path(&path, worktree);
+            let is_ignored = ignored.contains(parent) || search.is_excluded(relative_path.as_ref(), is_dir);
+            if is_dir {
+                let mut directory = relative_path.clone();
+                directory.push(b'/');
+                if self.is_tracked(relative_path.as_ref()) {
+                    // Submodules are tracked as a whole.
+                    entries.skip_current_dir();
+                } else if self.has_tracked_entries_in(directory.as_ref()) {
+                    if is_ignored {
+                        // Like git, ignore files in ignored directories are not read.
+                        ignored.insert(path);
+                    } else {
+                        search.load_ignore_file(&path, directory)?;
+                    }
+                } else if is_ignored {
+                    if options.emit_ignored {
+                        out.push(Entry {
+                            path: relative_path,
+                            kind: Kind::Ignored,
+                            is_dir: true,
+                        });
+                    }
+                    entries.skip_current_dir();
+                } else if path.join(".git").exists() {
+                    out.push(Entry {
+                        path: relative_path,
+                        kind: Kind::Untracked,
+                        is_dir: true,
+                    });
+                    entries.skip_current_dir();
+                } else {
+                    search.load_ignore_file(&path, directory)?;
+                }
+            } else if !self.is_tracked(relative_path.as_ref()) && (!is_ignored || options.emit_ignored) {
+                out.push(Entry {
+                    path: relative_path,
+                    kind: if is_ignored { Kind::Ignored } else { Kind::Untracked },
+                    is_dir: false,
+                });
+            }
+        }
+        out.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(out)
+    }
+
+    fn is_tracked(&self, path: &BStr) -> bool {
+        self.entries.binary_search_by(|e| e.path(self).cmp(path)).is_ok()
+    }
+
+    /// Return true if there is an entry inside of `directory`, which ends with a slash.
+    fn has_tracked_entries_in(&self, directory: &BStr) -> bool {
+        let idx = self.entries.partition_point(|e| e.path(self) < directory);
+        matches!(self.entries.get(idx), Some(e) if e.path(self).starts_with(directory))
+    }
+}
+
+/// The patterns of all ignore files found so far, along with those applying to the whole work tree.
+struct DirectorySearch<'a> {
+    excludes: &'a ignore::Search,
+    /// The patterns of the ignore file in each directory, keyed by the directory with a trailing slash.
+    per_directory: HashMap<BString, ignore::PatternList>,
+    ignore_case: bool,
+}
+
+impl<'a> DirectorySearch<'a> {
+    fn load_ignore_file(&mut self, directory_path: &Path, directory: BString) -> Result<(), Error> {
+        let path = directory_path.join(IGNORE_FILE_NAME);
+        match ignore::PatternList::from_file(&path, directory.clone()) {
+            Ok(Some(list)) => {
+                self.per_directory.insert(directory, list);
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(err) => Err(Error::Io { err, path }),
+        }
+    }
+
+    /// Return true if `relative_path` is excluded by the last matching pattern in the ignore files of its leading
+    /// directories, which take precedence over the global excludes the deeper they are.
+    fn is_excluded(&self, relative_path: &BStr, is_dir: bool) -> bool {
+        let leading_directories = relative_path
+            .rfind_iter("/")
+            .map(|pos| relative_path[..pos + 1].as_bstr())
+            .chain(std::iter::once("".into()));
+        let pattern = leading_directories
+            .filter_map(|directory| self.per_directory.get(directory))
+            .find_map(|list| list.pattern_matching_relative_path(relative_path, is_dir, self.ignore_case))
+            .or_else(|| {
+                self.excludes
+                    .pattern_matching_relative_path(relative_path, is_dir, self.ignore_case)
+            });
+        matches!(pattern, Some(m) if m.is_excluded())
+    }
+}
+
+fn relative_path(path: &Path, worktree: &Path) -> BString {
+    let mut out = BString::default();
+    for component in path
+        .strip_prefix(worktree)
+        .expect("traversal starts in the work tree")
+        .components()
+    {
+        if !out.is_empty() {
+            out.push(b'/');
+        }
+        out.push_str(Vec::from_os_str_lossy(component.as_os_str()));
+    }
+    out
+}
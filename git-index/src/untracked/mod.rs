@@ -0,0 +1,49 @@
+use bstr::BString;
+use std::path::PathBuf;
+
+/// Whether a path in the work tree is untracked or ignored.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Kind {
+    /// The path isn't tracked and not ignored, so it could be added to the index.
+    Untracked,
+    /// The path isn't tracked and matches an ignore pattern.
+    Ignored,
+}
+
+/// A path in the work tree which isn't tracked by the index.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Entry {
+    /// The path relative to the work tree, with slashes as separators.
+    pub path: BString,
+    /// Whether the path is untracked or ignored.
+    pub kind: Kind,
+    /// True if the path is a directory, whose content is not listed separately.
+    ///
+    /// This is the case for ignored directories without tracked files, and for untracked nested repositories.
+    pub is_dir: bool,
+}
+
+/// Options to control which paths are found by [`State::untracked_paths()`][crate::State::untracked_paths()].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Options {
+    /// If true, ignored paths are returned as well, as with `git status --ignored`.
+    pub emit_ignored: bool,
+    /// If true, ignore patterns are matched case-insensitively, like git does with `core.ignoreCase`.
+    pub ignore_case: bool,
+}
+
+/// The error returned by [`State::untracked_paths()`][crate::State::untracked_paths()].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The work tree could not be traversed")]
+    Traversal(#[from] git_features::fs::sequential::Error),
+    #[error("Could not read ignore file at '{}'", .path.display())]
+    Io {
+        #[source]
+        err: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+mod function;
@@ -0,0 +1,211 @@
+use crate::{
+    entry::{self, Flags},
+    extension, File, State, Version,
+};
+use git_hash::ObjectId;
+use std::io::{self, Write};
+
+/// The error returned by [`File::write()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A lock for the index file could not be obtained")]
+    LockAcquire(#[from] git_lock::acquire::Error),
+    #[error("The index file could not be written")]
+    Io(#[from] io::Error),
+}
+
+/// Options to control how an index is written.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Options {
+    /// The version of the index format to write.
+    ///
+    /// Like git, version 3 is only used if entries have extended flags, and version 2 is used otherwise, no matter which
+    /// of these two versions is requested. Version 4 uses path prefix compression.
+    pub version: Version,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { version: Version::V2 }
+    }
+}
+
+impl State {
+    /// Serialize this state to `out` according to `options`, and return the checksum written at the end of it.
+    ///
+    /// All extensions understood by this crate are written, while unknown extensions are dropped just like git does,
+    /// as they may not be valid anymore.
+    pub fn write_to(&self, out: impl io::Write, Options { version }: Options) -> io::Result<ObjectId> {
+        let version = match version {
+            Version::V4 => Version::V4,
+            Version::V2 | Version::V3 => {
+                if self.entries.iter().any(|e| e.flags.0 & Flags::EXTENDED_MASK != 0) {
+                    Version::V3
+                } else {
+                    Version::V2
+                }
+            }
+        };
+        let mut out = git_features::hash::Write::new(out, git_hash::Kind::Sha1);
+        out.write_all(b"DIRC")?;
+        out.write_all(&(version as u32).to_be_bytes())?;
+        out.write_all(&(self.entries.len() as u32).to_be_bytes())?;
+        self.write_entries(version, &mut out)?;
+        self.write_extensions(&mut out)?;
+
+        let git_features::hash::Write { hash, mut inner } = out;
+        let checksum = ObjectId::new_sha1(hash.digest());
+        inner.write_all(checksum.as_slice())?;
+        inner.flush()?;
+        Ok(checksum)
+    }
+
+    fn write_entries(&self, version: Version, out: &mut impl io::Write) -> io::Result<()> {
+        let mut previous_path: &[u8] = &[];
+        for entry in &self.entries {
+            let path = entry.path(self);
+            write_stat_with_mode(&entry.stat, entry.mode as u32, &mut *out)?;
+            out.write_all(entry.id.as_slice())?;
+
+            let extended_flags = (entry.flags.0 & Flags::EXTENDED_MASK) >> 16;
+            let mut flags = entry.flags.0 & (Flags::STAGE_MASK | Flags::ASSUME_VALID);
+            if extended_flags != 0 {
+                flags |= Flags::EXTENDED;
+            }
+            flags |= path.len().min(PATH_LEN_MASK) as u32;
+            out.write_all(&(flags as u16).to_be_bytes())?;
+            let mut entry_len = ENTRY_LEN;
+            if extended_flags != 0 {
+                out.write_all(&(extended_flags as u16).to_be_bytes())?;
+                entry_len += 2;
+            }
+
+            match version {
+                Version::V2 | Version::V3 => {
+                    out.write_all(path)?;
+                    let padded_len = (entry_len + path.len() + 8) & !7;
+                    out.write_all(&[0u8; 8][..padded_len - entry_len - path.len()])?;
+                }
+                Version::V4 => {
+                    let common_prefix_len = previous_path
+                        .iter()
+                        .zip(path.iter())
+                        .take_while(|(lhs, rhs)| lhs == rhs)
+                        .count();
+                    write_var_int(previous_path.len() - common_prefix_len, &mut *out)?;
+                    out.write_all(&path[common_prefix_len..])?;
+                    out.write_all(b"\0")?;
+                    previous_path = path;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_extensions(&self, out: &mut impl io::Write) -> io::Result<()> {
+        if let Some(tree) = &self.tree {
+            write_extension(extension::tree::SIGNATURE, out, |buf| {
+                extension::tree::write_to(tree, buf)
+            })?;
+        }
+        if let Some(paths) = &self.resolve_undo {
+            write_extension(extension::resolve_undo::SIGNATURE, out, |buf| {
+                extension::resolve_undo::write_to(paths, buf)
+            })?;
+        }
+        if let Some(cache) = &self.untracked {
+            write_extension(extension::untracked_cache::SIGNATURE, out, |buf| {
+                extension::untracked_cache::write_to(cache, buf)
+            })?;
+        }
+        if let Some(link) = &self.link {
+            write_extension(extension::link::SIGNATURE, out, |buf| {
+                extension::link::write_to(link, buf)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl File {
+    /// Write the state of this index back to the file it was read from according to `options`, while holding its lock
+    /// which is obtained according to `lock`.
+    ///
+    /// The file is replaced atomically once it was written completely, and the new checksum is stored.
+    pub fn write(&mut self, options: Options, lock: git_lock::acquire::Fail) -> Result<(), Error> {
+        let mut lock = git_lock::File::acquire_to_update_resource(&self.path, lock, None)?;
+        let checksum = lock.with_mut(|out| self.state.write_to(io::BufWriter::new(out), options))?;
+        lock.commit()?;
+        self.checksum = checksum;
+        Ok(())
+    }
+}
+
+/// The size of an entry without its path and without extended flags.
+const ENTRY_LEN: usize = 62;
+/// The largest path length that can be stored in the flags of an entry.
+const PATH_LEN_MASK: usize = 0x0fff;
+
+fn write_extension(
+    signature: extension::Signature,
+    out: &mut impl io::Write,
+    encode: impl FnOnce(&mut Vec<u8>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut buf = Vec::new();
+    encode(&mut buf)?;
+    out.write_all(&signature)?;
+    out.write_all(&(buf.len() as u32).to_be_bytes())?;
+    out.write_all(&buf)
+}
+
+fn write_stat_with_mode(stat: &entry::Stat, mode: u32, out: &mut impl io::Write) -> io::Result<()> {
+    for value in &[
+        stat.ctime.secs,
+        stat.ctime.nsecs,
+        stat.mtime.secs,
+        stat.mtime.nsecs,
+        stat.dev,
+        stat.ino,
+        mode,
+        stat.uid,
+        stat.gid,
+        stat.size,
+    ] {
+        out.write_all(&value.to_be_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write stat information as stored outside of entries, that is without the file mode.
+pub(crate) fn write_stat(stat: &entry::Stat, mut out: impl io::Write) -> io::Result<()> {
+    for value in &[
+        stat.ctime.secs,
+        stat.ctime.nsecs,
+        stat.mtime.secs,
+        stat.mtime.nsecs,
+        stat.dev,
+        stat.ino,
+        stat.uid,
+        stat.gid,
+        stat.size,
+    ] {
+        out.write_all(&value.to_be_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write `value` as variable length integer in the encoding understood by [`var_int()`][crate::decode::var_int()].
+pub(crate) fn write_var_int(mut value: usize, mut out: impl io::Write) -> io::Result<()> {
+    let mut buf = [0u8; 16];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+        value >>= 7;
+    }
+    out.write_all(&buf[pos..])
+}
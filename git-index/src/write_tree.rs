@@ -0,0 +1,131 @@
+use crate::{entry::Flags, extension, Entry, State};
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::ObjectId;
+use git_object::{mutable, tree::EntryMode};
+
+/// The error returned by [`State::write_tree()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + 'static> {
+    #[error("The entry at '{path}' is unmerged as it has stage {stage}")]
+    Unmerged { path: BString, stage: u32 },
+    #[error("A tree could not be written to the object database")]
+    Write(#[source] E),
+}
+
+impl State {
+    /// Write the entries of this index as a hierarchy of trees into `db` and return the id of the root tree.
+    ///
+    /// Trees of the [cached tree extension][State::tree()] that are still valid are reused without writing them
+    /// again, and the extension is updated to contain all trees that were written. Entries added with
+    /// `git add --intent-to-add` are not part of the trees.
+    ///
+    /// Fails if any entry is unmerged, as conflicts must be resolved first.
+    pub fn write_tree<W: git_odb::Write>(&mut self, db: &W) -> Result<ObjectId, Error<W::Error>> {
+        if let Some(entry) = self.entries.iter().find(|e| e.stage() != 0) {
+            return Err(Error::Unmerged {
+                path: entry.path(self).to_owned(),
+                stage: entry.stage(),
+            });
+        }
+        let writer = Writer { state: self, db };
+        let tree = writer.write_recursive(&self.entries, 0, "".into(), self.tree.as_ref())?;
+        let id = tree.id;
+        self.tree = Some(tree.cache);
+        Ok(id)
+    }
+}
+
+struct Writer<'a, W> {
+    state: &'a State,
+    db: &'a W,
+}
+
+struct WrittenTree {
+    id: ObjectId,
+    /// The cached tree to store in the extension, which is invalid if the tree doesn't contain all entries.
+    cache: extension::Tree,
+}
+
+impl<'a, W: git_odb::Write> Writer<'a, W> {
+    /// Write the tree with `name` containing all `entries`, whose paths all have a prefix of `base_len` bytes
+    /// naming the tree's directory.
+    fn write_recursive(
+        &self,
+        entries: &[Entry],
+        base_len: usize,
+        name: &BStr,
+        cached: Option<&extension::Tree>,
+    ) -> Result<WrittenTree, Error<W::Error>> {
+        if let Some(cached) = cached {
+            if let Some(id) = cached.id.filter(|_| cached.num_entries as usize == entries.len()) {
+                return Ok(WrittenTree {
+                    id,
+                    cache: cached.clone(),
+                });
+            }
+        }
+
+        let mut tree = mutable::Tree { entries: Vec::new() };
+        let mut children = Vec::new();
+        let mut is_complete = true;
+        let mut idx = 0;
+        while idx < entries.len() {
+            let entry = &entries[idx];
+            let path = entry.path(self.state);
+            let relative_path = &path[base_len..];
+            match relative_path.find_byte(b'/') {
+                // Directory entries of sparse indices have a trailing slash, but are trees already.
+                Some(slash) if !(entry.mode == EntryMode::Tree && slash + 1 == relative_path.len()) => {
+                    let directory = &path[..base_len + slash + 1];
+                    let num_entries = entries[idx..]
+                        .iter()
+                        .position(|e| !e.path(self.state).starts_with(directory))
+                        .unwrap_or(entries.len() - idx);
+                    let child_name = relative_path[..slash].as_bstr();
+                    let cached_child = cached.and_then(|c| c.children.iter().find(|c| c.name == child_name));
+                    let child = self.write_recursive(
+                        &entries[idx..idx + num_entries],
+                        directory.len(),
+                        child_name,
+                        cached_child,
+                    )?;
+                    is_complete &= child.cache.id.is_some();
+                    tree.entries.push(mutable::tree::Entry {
+                        mode: EntryMode::Tree,
+                        filename: child_name.to_owned(),
+                        oid: child.id,
+                    });
+                    children.push(child.cache);
+                    idx += num_entries;
+                }
+                _ => {
+                    if entry.flags.contains(Flags::INTENT_TO_ADD) {
+                        is_complete = false;
+                    } else {
+                        tree.entries.push(mutable::tree::Entry {
+                            mode: entry.mode,
+                            filename: relative_path.trim_end_with(|c| c == '/').into(),
+                            oid: entry.id,
+                        });
+                    }
+                    idx += 1;
+                }
+            }
+        }
+
+        let id = self
+            .db
+            .write(&mutable::Object::Tree(tree), git_hash::Kind::Sha1)
+            .map_err(Error::Write)?;
+        Ok(WrittenTree {
+            id,
+            cache: extension::Tree {
+                name: name.to_owned(),
+                id: if is_complete { Some(id) } else { None },
+                num_entries: entries.len() as u32,
+                children,
+            },
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use crate::file::{actual_entries, expected_entries};
+use git_hash::ObjectId;
+use git_index::{checkout, status};
+use std::fs;
+
+#[test]
+fn all_kinds_of_entries_are_written_along_with_a_matching_index() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_read_only("make_checkout_repo.sh")?;
+    let git_dir = repo.join(".git");
+    let tree_id = ObjectId::from_hex(&fs::read(git_dir.join("expected-tree"))?[..40])?;
+    let db = git_odb::linked::Store::at(git_dir.join("objects"))?;
+    let tmp = git_testtools::tempfile::tempdir()?;
+    let worktree = tmp.path().join("worktree");
+
+    let (mut state, outcome) = checkout::tree(
+        tree_id,
+        &worktree,
+        &db,
+        || git_odb::pack::cache::Never,
+        checkout::Options { thread_limit: Some(2) },
+    )?;
+    assert_eq!(outcome.files, 106);
+    assert_eq!(
+        outcome.directories, 5,
+        "dir, dir/sub, empty-file-dir, many and submodule"
+    );
+
+    assert_eq!(fs::read(worktree.join("a"))?, b"content\n");
+    assert_eq!(fs::read(worktree.join("many").join("file-42"))?, b"42\n");
+    assert!(fs::read(worktree.join("empty-file-dir").join("empty"))?.is_empty());
+    assert_eq!(fs::read_link(worktree.join("link"))?, std::path::Path::new("a"));
+    assert!(fs::symlink_metadata(worktree.join("link-to-dir"))?
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_dir(worktree.join("submodule"))?.count(), 0);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_ne!(
+            fs::metadata(worktree.join("dir").join("executable"))?
+                .permissions()
+                .mode()
+                & 0o100,
+            0
+        );
+        assert_eq!(fs::metadata(worktree.join("a"))?.permissions().mode() & 0o100, 0);
+    }
+
+    assert_eq!(actual_entries(&state), expected_entries("make_checkout_repo.sh")?);
+    let mut recorder = status::Recorder::default();
+    let status = state.status(&worktree, Default::default(), &mut recorder)?;
+    assert!(recorder.records.is_empty());
+    assert_eq!(status.hashed, 0, "the stat information of all entries is up to date");
+    assert_eq!(state.write_tree(&git_odb::sink())?, tree_id);
+
+    let index_path = tmp.path().join("index");
+    state.write_to(fs::File::create(&index_path)?, Default::default())?;
+    let output = std::process::Command::new("git")
+        .args(["status", "--porcelain"])
+        .env("GIT_DIR", &git_dir)
+        .env("GIT_WORK_TREE", &worktree)
+        .env("GIT_INDEX_FILE", &index_path)
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout.as_slice(), b"", "git agrees that nothing changed");
+    Ok(())
+}
+
+#[test]
+fn existing_files_are_not_overwritten() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_read_only("make_checkout_repo.sh")?;
+    let git_dir = repo.join(".git");
+    let tree_id = ObjectId::from_hex(&fs::read(git_dir.join("expected-tree"))?[..40])?;
+    let db = git_odb::linked::Store::at(git_dir.join("objects"))?;
+    let worktree = git_testtools::tempfile::tempdir()?;
+    fs::write(worktree.path().join("a"), b"precious")?;
+
+    assert!(matches!(
+        checkout::tree(
+            tree_id,
+            worktree.path(),
+            &db,
+            || git_odb::pack::cache::Never,
+            Default::default()
+        ),
+        Err(checkout::Error::Io { .. })
+    ));
+    assert_eq!(fs::read(worktree.path().join("a"))?, b"precious");
+    Ok(())
+}
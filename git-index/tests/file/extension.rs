@@ -0,0 +1,152 @@
+use super::{actual_entries, expected_entries, fixture_dir, index};
+use bstr::ByteSlice;
+use git_index::extension;
+
+fn expected_ids(script_name: &str, file_name: &str) -> crate::Result<Vec<git_hash::ObjectId>> {
+    Ok(std::fs::read(fixture_dir(script_name)?.join(file_name))?
+        .lines()
+        .map(|hex| git_hash::ObjectId::from_hex(hex).expect("valid hex"))
+        .collect())
+}
+
+#[test]
+fn tree() -> crate::Result {
+    let file = index("make_index_v2.sh")?;
+    let expected = expected_ids("make_index_v2.sh", "expected-tree-ids")?;
+    let root = file.tree().expect("present");
+    assert_eq!(root.name, "");
+    assert_eq!(root.id, Some(expected[0]));
+    assert_eq!(root.num_entries as usize, file.entries().len());
+    assert_eq!(
+        root.children.iter().map(|t| t.name.to_string()).collect::<Vec<_>>(),
+        vec!["dir", "dir-other"]
+    );
+
+    let dir = &root.children[0];
+    assert_eq!(dir.id, Some(expected[1]));
+    assert_eq!(dir.num_entries, 2);
+    assert_eq!(dir.children.len(), 1);
+    assert_eq!(dir.children[0].name, "sub");
+    assert_eq!(dir.children[0].id, Some(expected[2]));
+    Ok(())
+}
+
+#[test]
+fn resolve_undo() -> crate::Result {
+    let file = index("make_index_with_resolve_undo.sh")?;
+    assert_eq!(
+        actual_entries(&file),
+        expected_entries("make_index_with_resolve_undo.sh")?
+    );
+    let expected = expected_ids("make_index_with_resolve_undo.sh", "expected-resolve-undo-ids")?;
+    let paths = file.resolve_undo().expect("present");
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].path, "file");
+    for (stage, expected_id) in paths[0].stages.iter().zip(expected) {
+        let stage = stage.expect("all stages are present");
+        assert_eq!(stage.id, expected_id);
+        assert_eq!(stage.mode, git_object::tree::EntryMode::Blob);
+    }
+    Ok(())
+}
+
+#[test]
+fn untracked_cache() -> crate::Result {
+    let file = index("make_index_with_untracked_cache.sh")?;
+    assert_eq!(
+        actual_entries(&file),
+        expected_entries("make_index_with_untracked_cache.sh")?
+    );
+    let cache = file.untracked().expect("present");
+    assert!(!cache.identifier.is_empty());
+    assert_eq!(cache.exclude_filename_per_directory, ".gitignore");
+    assert!(cache.info_exclude.is_some(), "git init creates the info/exclude file");
+    assert!(cache.excludes_file.is_none());
+
+    let root = &cache.directories[0];
+    assert_eq!(root.name, "");
+    assert!(root.stat.is_some(), "the root directory was traversed");
+    assert!(
+        root.exclude_file_oid.is_some(),
+        "the root directory has a .gitignore file"
+    );
+    let mut untracked: Vec<_> = root.untracked_entries.iter().map(|e| e.to_string()).collect();
+    untracked.sort();
+    assert_eq!(untracked, vec![".gitignore", "untracked", "untracked-dir/"]);
+
+    let sub_directory_names: Vec<_> = root
+        .sub_directories
+        .iter()
+        .map(|idx| cache.directories[*idx].name.to_string())
+        .collect();
+    assert!(sub_directory_names.contains(&"dir".to_string()));
+    let dir = &cache.directories[root.sub_directories[sub_directory_names.iter().position(|n| n == "dir").unwrap()]];
+    assert_eq!(dir.untracked_entries, vec!["untracked"]);
+    Ok(())
+}
+
+#[test]
+fn link() -> crate::Result {
+    let file = index("make_index_split.sh")?;
+    assert_eq!(
+        file.entries().last().expect("present").path(&file),
+        "d",
+        "only added entries are stored with their path"
+    );
+    let link = file.link().expect("present");
+    assert!(fixture_dir("make_index_split.sh")?
+        .join(format!("sharedindex.{}", link.shared_index_checksum))
+        .is_file());
+
+    let extension::link::Bitmaps { delete, replace } = link.bitmaps.as_ref().expect("not a shared index");
+    let set_bits = |bitmap: &extension::ewah::Vec| {
+        let mut out = Vec::new();
+        bitmap.for_each_set_bit(|idx| out.push(idx));
+        out
+    };
+    assert_eq!(set_bits(delete), vec![2], "'c' was deleted");
+    let replaced = set_bits(replace);
+    assert!(replaced.contains(&1), "'b' was replaced");
+    assert_eq!(
+        file.entries().iter().filter(|e| e.path(&file).is_empty()).count(),
+        replaced.len(),
+        "replacing entries have no path, and 'a' may be replaced as well if its stat information was refreshed"
+    );
+    Ok(())
+}
+
+#[test]
+fn offset_table_allows_decoding_entries_in_parallel() -> crate::Result {
+    for version in &["2", "4"] {
+        let dir = git_testtools::scripted_fixture_repo_read_only_with_args(
+            "make_index_with_offset_table.sh",
+            Some(*version),
+        )?
+        .join(".git");
+        let expected: Vec<_> = std::fs::read(dir.join("expected-ls-files"))?
+            .lines()
+            .map(|line| line.split_str("\t").nth(1).expect("path").as_bstr().to_owned())
+            .collect();
+        for thread_limit in &[None, Some(1)] {
+            let file = git_index::File::at(
+                dir.join("index"),
+                git_index::decode::Options {
+                    thread_limit: *thread_limit,
+                },
+            )?;
+            assert_eq!(file.version() as u32, version.parse::<u32>()?);
+            assert_eq!(
+                file.entries()
+                    .iter()
+                    .map(|e| e.path(&file).to_owned())
+                    .collect::<Vec<_>>(),
+                expected,
+                "version {}, thread limit {:?}",
+                version,
+                thread_limit
+            );
+            assert_eq!(file.unknown_extensions().count(), 0, "EOIE and IEOT are understood");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use super::{actual_entries, expected_entries, fixture_dir, index};
+use git_index::{entry::Flags, Version};
+
+#[test]
+fn v2_entries_match_the_ones_listed_by_git() -> crate::Result {
+    let file = index("make_index_v2.sh")?;
+    assert_eq!(file.version(), Version::V2);
+    assert_eq!(actual_entries(&file), expected_entries("make_index_v2.sh")?);
+
+    let a = file.entry_by_path_and_stage("a".into(), 0).expect("present");
+    assert_eq!(a.stat.size, 8, "stat information is available");
+    assert_ne!(a.stat.mtime.secs, 0);
+    assert!(file.entry_by_path_and_stage("a".into(), 1).is_none());
+    assert!(file.entry_by_path_and_stage("dir".into(), 0).is_none());
+    assert!(
+        file.entries().iter().all(|e| e.flags.0 == 0),
+        "there are no conflicts and no extended flags"
+    );
+    assert!(file.tree().is_some(), "the tree cache is written by the commit");
+    assert_eq!(file.unknown_extensions().count(), 0);
+    let data = std::fs::read(file.path())?;
+    assert_eq!(file.checksum().as_bytes(), &data[data.len() - 20..]);
+    Ok(())
+}
+
+#[test]
+fn v3_entries_have_extended_flags() -> crate::Result {
+    let file = index("make_index_v3.sh")?;
+    assert_eq!(file.version(), Version::V3);
+    assert_eq!(actual_entries(&file), expected_entries("make_index_v3.sh")?);
+
+    let flags_of = |path: &str| file.entry_by_path_and_stage(path.into(), 0).expect("present").flags;
+    assert_eq!(flags_of("a"), Flags(0));
+    assert!(flags_of("b").contains(Flags::SKIP_WORKTREE | Flags::EXTENDED));
+    assert!(flags_of("c").contains(Flags::ASSUME_VALID));
+    assert!(!flags_of("c").contains(Flags::EXTENDED));
+    assert!(flags_of("intent-to-add").contains(Flags::INTENT_TO_ADD | Flags::EXTENDED));
+    Ok(())
+}
+
+#[test]
+fn v4_paths_are_decompressed() -> crate::Result {
+    let file = index("make_index_v4.sh")?;
+    assert_eq!(file.version(), Version::V4);
+    assert_eq!(actual_entries(&file), expected_entries("make_index_v4.sh")?);
+    assert_eq!(
+        file.entries().last().expect("entries").path(&file),
+        "link",
+        "paths are not compressed in the path backing"
+    );
+    Ok(())
+}
+
+#[test]
+fn conflicts_are_represented_as_entries_with_stages() -> crate::Result {
+    let file = index("make_index_with_conflicts.sh")?;
+    assert_eq!(actual_entries(&file), expected_entries("make_index_with_conflicts.sh")?);
+    let stages: Vec<_> = file
+        .entries()
+        .iter()
+        .filter(|e| e.path(&file) == "file")
+        .map(|e| e.stage())
+        .collect();
+    assert_eq!(stages, vec![1, 2, 3]);
+    assert!(file.entry_by_path_and_stage("file".into(), 0).is_none());
+    assert!(file.entry_by_path_and_stage("file".into(), 2).is_some());
+    Ok(())
+}
+
+#[test]
+fn checksum_mismatches_are_detected_unless_the_checksum_is_null() -> crate::Result {
+    let mut data = std::fs::read(fixture_dir("make_index_v2.sh")?.join("index"))?;
+    let (_, checksum) = git_index::State::from_bytes(data.clone(), Default::default())?;
+    assert_eq!(checksum.as_bytes(), &data[data.len() - 20..]);
+
+    let lowest_byte_of_first_entry_size = 12 + 39;
+    data[lowest_byte_of_first_entry_size] ^= 1;
+    assert!(matches!(
+        git_index::State::from_bytes(data.clone(), Default::default()),
+        Err(git_index::decode::Error::ChecksumMismatch { .. })
+    ));
+
+    let trailer_start = data.len() - 20;
+    data[trailer_start..].iter_mut().for_each(|b| *b = 0);
+    let (state, checksum) = git_index::State::from_bytes(data, Default::default())?;
+    assert_eq!(checksum, git_hash::ObjectId::null_sha1(), "verification is skipped");
+    assert_eq!(
+        state.entries()[0].stat.size,
+        8 ^ 1,
+        "the modified size of the first entry, 'a', is used"
+    );
+    Ok(())
+}
+
+#[test]
+fn corrupt_data_is_rejected() -> crate::Result {
+    let data = std::fs::read(fixture_dir("make_index_v2.sh")?.join("index"))?;
+    let without_checksum = |mut data: Vec<u8>| {
+        let trailer_start = data.len() - 20;
+        data[trailer_start..].iter_mut().for_each(|b| *b = 0);
+        data
+    };
+
+    let mut unsupported_version = data.clone();
+    unsupported_version[7] = 5;
+    assert!(matches!(
+        git_index::State::from_bytes(without_checksum(unsupported_version), Default::default()),
+        Err(git_index::decode::Error::UnsupportedVersion(5))
+    ));
+
+    let mut bad_signature = data.clone();
+    bad_signature[0] = b'X';
+    assert!(git_index::State::from_bytes(without_checksum(bad_signature), Default::default()).is_err());
+
+    let mut too_many_entries = data.clone();
+    too_many_entries[11] = 200;
+    assert!(git_index::State::from_bytes(without_checksum(too_many_entries), Default::default()).is_err());
+
+    assert!(git_index::State::from_bytes(data[..20].to_vec(), Default::default()).is_err());
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use bstr::{BString, ByteSlice};
+use std::path::PathBuf;
+
+fn fixture_dir(script_name: &str) -> crate::Result<PathBuf> {
+    Ok(git_testtools::scripted_fixture_repo_read_only(script_name)?.join(".git"))
+}
+
+fn index(script_name: &str) -> crate::Result<git_index::File> {
+    Ok(git_index::File::at(
+        fixture_dir(script_name)?.join("index"),
+        git_index::decode::Options::default(),
+    )?)
+}
+
+/// Entries as printed by `git ls-files --stage`, that is mode, id, stage and path.
+pub fn expected_entries(script_name: &str) -> crate::Result<Vec<(u32, git_hash::ObjectId, u32, BString)>> {
+    let listing = std::fs::read(fixture_dir(script_name)?.join("expected-ls-files"))?;
+    Ok(listing
+        .lines()
+        .map(|line| {
+            let (info, path) = line.split_at(line.find_byte(b'\t').expect("tab separates path"));
+            let mut tokens = info.split_str(" ");
+            let mode = u32::from_str_radix(tokens.next().expect("mode").to_str().expect("ascii"), 8).expect("octal");
+            let id = git_hash::ObjectId::from_hex(tokens.next().expect("id")).expect("valid hex");
+            let stage = tokens
+                .next()
+                .expect("stage")
+                .to_str()
+                .expect("ascii")
+                .parse()
+                .expect("decimal");
+            (mode, id, stage, path[1..].into())
+        })
+        .collect())
+}
+
+pub fn actual_entries(state: &git_index::State) -> Vec<(u32, git_hash::ObjectId, u32, BString)> {
+    state
+        .entries()
+        .iter()
+        .map(|e| (e.mode as u32, e.id, e.stage(), e.path(state).to_owned()))
+        .collect()
+}
+
+mod extension;
+mod init;
+mod status;
+mod untracked;
+mod write;
+mod write_tree;
@@ -0,0 +1,121 @@
+use super::index;
+use bstr::{BStr, BString};
+use git_index::{
+    entry,
+    status::{self, Action, Recorder, Status, Visit},
+    Entry,
+};
+use std::path::PathBuf;
+
+fn worktree(script_name: &str) -> crate::Result<PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only(script_name)
+}
+
+fn status(script_name: &str, options: status::Options) -> crate::Result<(status::Outcome, Vec<(BString, Status)>)> {
+    let file = index(script_name)?;
+    let mut recorder = Recorder::default();
+    let outcome = file.status(worktree(script_name)?, options, &mut recorder)?;
+    Ok((outcome, recorder.records))
+}
+
+#[test]
+fn changes_are_classified_and_only_files_with_changed_stat_are_hashed() -> crate::Result {
+    let (outcome, records) = status("make_worktree_status.sh", Default::default())?;
+    assert_eq!(
+        records,
+        vec![
+            ("deleted".into(), Status::Deleted),
+            ("dir-replaced-by-file/file".into(), Status::Deleted),
+            ("dir/modified".into(), Status::Modified),
+            ("executable-bit".into(), Status::Modified),
+            ("link-replaced-by-file".into(), Status::TypeChanged),
+            ("modified-same-size".into(), Status::Modified),
+            ("replaced-by-dir".into(), Status::TypeChanged),
+        ]
+    );
+    assert_eq!(
+        outcome,
+        status::Outcome {
+            entries: 10,
+            racy: 0,
+            hashed: 2,
+        },
+        "'touched' and 'modified-same-size' are hashed, while the size of 'dir/modified' differs"
+    );
+    Ok(())
+}
+
+#[test]
+fn racily_clean_entries_are_hashed() -> crate::Result {
+    let (outcome, records) = status(
+        "make_worktree_status.sh",
+        status::Options {
+            index_mtime: Some(entry::Time { secs: 0, nsecs: 0 }),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(records.len(), 7, "racy entries are still unchanged");
+    assert_eq!(outcome.racy, 2, "'unchanged' and 'link'");
+    assert_eq!(outcome.hashed, 4);
+    Ok(())
+}
+
+#[test]
+fn the_executable_bit_can_be_ignored() -> crate::Result {
+    let (_, records) = status(
+        "make_worktree_status.sh",
+        status::Options {
+            check_executable_bit: false,
+            ..Default::default()
+        },
+    )?;
+    assert!(records.iter().all(|(path, _)| path != "executable-bit"));
+    Ok(())
+}
+
+#[test]
+fn conflicting_entries_are_not_visited() -> crate::Result {
+    let (outcome, records) = status("make_index_with_conflicts.sh", Default::default())?;
+    assert_eq!(outcome.entries, 1, "only 'unchanged' is checked");
+    assert!(records.is_empty());
+    Ok(())
+}
+
+#[test]
+fn many_entries_are_checked_in_parallel() -> crate::Result {
+    let worktree =
+        git_testtools::scripted_fixture_repo_read_only_with_args("make_index_with_offset_table.sh", Some("2"))?;
+    let file = git_index::File::at(worktree.join(".git").join("index"), Default::default())?;
+    let mut recorder = Recorder::default();
+    let outcome = file.status(
+        &worktree,
+        status::Options {
+            thread_limit: Some(4),
+            ..Default::default()
+        },
+        &mut recorder,
+    )?;
+    assert_eq!(outcome.entries, file.entries().len());
+    assert!(recorder.records.is_empty());
+    Ok(())
+}
+
+#[test]
+fn the_delegate_can_cancel() -> crate::Result {
+    struct CancelImmediately(usize);
+    impl Visit for CancelImmediately {
+        fn visit(&mut self, _entry: &Entry, _path: &BStr, _status: Status) -> Action {
+            self.0 += 1;
+            Action::Cancel
+        }
+    }
+
+    let file = index("make_worktree_status.sh")?;
+    let mut delegate = CancelImmediately(0);
+    assert!(matches!(
+        file.status(worktree("make_worktree_status.sh")?, Default::default(), &mut delegate),
+        Err(status::Error::Cancelled)
+    ));
+    assert_eq!(delegate.0, 1);
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use bstr::{BString, ByteSlice};
+use git_index::{
+    ignore,
+    untracked::{Kind, Options},
+};
+
+fn expected_paths(git_dir: &std::path::Path, file_name: &str) -> crate::Result<Vec<BString>> {
+    Ok(std::fs::read(git_dir.join(file_name))?
+        .lines()
+        .map(|line| line.as_bstr().to_owned())
+        .collect())
+}
+
+#[test]
+fn untracked_and_ignored_paths_match_the_ones_listed_by_git() -> crate::Result {
+    let worktree = git_testtools::scripted_fixture_repo_read_only("make_ignore_and_untracked.sh")?;
+    let git_dir = worktree.join(".git");
+    let file = git_index::File::at(git_dir.join("index"), Default::default())?;
+    let excludes = ignore::Search::from_git_dir(&git_dir, Some(worktree.join("user-excludes")))?;
+    let entries = file.untracked_paths(
+        &worktree,
+        &excludes,
+        Options {
+            emit_ignored: true,
+            ..Default::default()
+        },
+    )?;
+
+    let paths_of_kind = |kind: Kind| {
+        entries
+            .iter()
+            .filter(|e| e.kind == kind)
+            .map(|e| {
+                let mut path = e.path.clone();
+                if e.is_dir {
+                    path.push(b'/');
+                }
+                path
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        paths_of_kind(Kind::Untracked),
+        expected_paths(&git_dir, "expected-untracked")?
+    );
+    assert_eq!(
+        paths_of_kind(Kind::Ignored),
+        expected_paths(&git_dir, "expected-ignored")?
+    );
+
+    let untracked_only = file.untracked_paths(&worktree, &excludes, Options::default())?;
+    assert!(untracked_only.iter().all(|e| e.kind == Kind::Untracked));
+    assert_eq!(untracked_only.len(), paths_of_kind(Kind::Untracked).len());
+    Ok(())
+}
+
+#[test]
+fn ignored_directories_are_not_read() -> crate::Result {
+    let worktree = git_testtools::scripted_fixture_repo_read_only("make_ignored_directory_that_cannot_be_read.sh")?;
+    assert!(
+        git_features::fs::sequential::walkdir_new(worktree.join("too-deep"))
+            .into_iter()
+            .any(|entry| entry.is_err()),
+        "reading the ignored directory fails"
+    );
+
+    let git_dir = worktree.join(".git");
+    let file = git_index::File::at(git_dir.join("index"), Default::default())?;
+    let entries = file.untracked_paths(
+        &worktree,
+        &ignore::Search::from_git_dir(&git_dir, None)?,
+        Options {
+            emit_ignored: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        entries,
+        vec![git_index::untracked::Entry {
+            path: "too-deep".into(),
+            kind: Kind::Ignored,
+            is_dir: true
+        }]
+    );
+    Ok(())
+}
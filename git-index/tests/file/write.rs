@@ -0,0 +1,92 @@
+use super::{actual_entries, expected_entries, fixture_dir, index};
+use git_index::{write, Version};
+
+fn write_to_vec(state: &git_index::State, version: Version) -> crate::Result<(Vec<u8>, git_hash::ObjectId)> {
+    let mut buf = Vec::new();
+    let checksum = state.write_to(&mut buf, write::Options { version })?;
+    Ok((buf, checksum))
+}
+
+#[test]
+fn roundtrips_are_lossless_for_all_supported_versions_and_extensions() -> crate::Result {
+    for script_name in &[
+        "make_index_v2.sh",
+        "make_index_v3.sh",
+        "make_index_v4.sh",
+        "make_index_with_conflicts.sh",
+        "make_index_with_resolve_undo.sh",
+        "make_index_with_untracked_cache.sh",
+        "make_index_split.sh",
+    ] {
+        let file = index(script_name)?;
+        let (buf, checksum) = write_to_vec(&file, file.version())?;
+        assert_eq!(
+            buf,
+            std::fs::read(file.path())?,
+            "{}: the file written by git is reproduced exactly",
+            script_name
+        );
+        assert_eq!(checksum, file.checksum());
+    }
+    Ok(())
+}
+
+#[test]
+fn versions_can_be_converted() -> crate::Result {
+    let v2 = index("make_index_v4.sh")?;
+    let (v4_buf, _) = write_to_vec(&v2, Version::V4)?;
+    let (v4, _) = git_index::State::from_bytes(v4_buf, Default::default())?;
+    assert_eq!(v4.version(), Version::V4);
+    assert_eq!(actual_entries(&v4), expected_entries("make_index_v4.sh")?);
+    assert_eq!(v4.tree(), v2.tree());
+
+    let (v2_buf, _) = write_to_vec(&v4, Version::V2)?;
+    let (v2_again, _) = git_index::State::from_bytes(v2_buf.clone(), Default::default())?;
+    assert_eq!(v2_again.version(), Version::V2);
+    assert_eq!(actual_entries(&v2_again), expected_entries("make_index_v4.sh")?);
+
+    let (v3_buf, _) = write_to_vec(&v4, Version::V3)?;
+    assert_eq!(v3_buf, v2_buf, "version 3 is only used if there are extended flags");
+    Ok(())
+}
+
+#[test]
+fn offset_table_and_end_of_index_entry_are_dropped() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_read_only_with_args("make_index_with_offset_table.sh", Some("4"))?;
+    let file = git_index::File::at(dir.join(".git").join("index"), Default::default())?;
+    let (buf, _) = write_to_vec(&file, Version::V4)?;
+    assert!(buf.len() < std::fs::metadata(file.path())?.len() as usize);
+    let (state, _) = git_index::State::from_bytes(buf, Default::default())?;
+    assert_eq!(actual_entries(&state), actual_entries(&file));
+    Ok(())
+}
+
+#[test]
+fn file_is_replaced_atomically_and_readable_by_git() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_index_v2.sh")?;
+    let index_path = dir.path().join(".git").join("index");
+    let mut file = git_index::File::at(&index_path, Default::default())?;
+    file.write(
+        write::Options { version: Version::V4 },
+        git_lock::acquire::Fail::Immediately,
+    )?;
+    assert!(!index_path.with_extension("lock").exists(), "the lock is released");
+
+    let data = std::fs::read(&index_path)?;
+    assert_eq!(file.checksum().as_bytes(), &data[data.len() - 20..]);
+    assert_eq!(
+        git_index::File::at(&index_path, Default::default())?.version(),
+        Version::V4
+    );
+
+    let output = std::process::Command::new("git")
+        .args(["ls-files", "--stage"])
+        .current_dir(dir.path())
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        std::fs::read(fixture_dir("make_index_v2.sh")?.join("expected-ls-files"))?
+    );
+    Ok(())
+}
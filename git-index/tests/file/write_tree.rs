@@ -0,0 +1,96 @@
+use super::{fixture_dir, index};
+use git_hash::ObjectId;
+use std::{cell::Cell, io};
+
+/// A sink which counts how many objects were written to it.
+#[derive(Default)]
+struct CountingSink {
+    count: Cell<usize>,
+}
+
+impl git_odb::Write for CountingSink {
+    type Error = io::Error;
+
+    fn write_stream(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        from: impl io::Read,
+        hash: git_hash::Kind,
+    ) -> Result<ObjectId, Self::Error> {
+        self.count.set(self.count.get() + 1);
+        git_odb::sink().write_stream(kind, size, from, hash)
+    }
+}
+
+fn expected_tree(script_name: &str) -> crate::Result<ObjectId> {
+    let hex = std::fs::read(fixture_dir(script_name)?.join("expected-tree"))?;
+    Ok(ObjectId::from_hex(&hex[..40])?)
+}
+
+#[test]
+fn a_valid_tree_cache_is_used_without_writing_anything() -> crate::Result {
+    let mut file = index("make_index_v2.sh")?;
+    let expected_tree_ids = std::fs::read(fixture_dir("make_index_v2.sh")?.join("expected-tree-ids"))?;
+    let db = CountingSink::default();
+    let id = file.write_tree(&db)?;
+    assert_eq!(id, ObjectId::from_hex(&expected_tree_ids[..40])?);
+    assert_eq!(db.count.get(), 0, "all trees are cached");
+    Ok(())
+}
+
+#[test]
+fn all_trees_are_written_without_tree_cache_and_intent_to_add_entries_are_skipped() -> crate::Result {
+    let mut file = index("make_index_without_tree.sh")?;
+    assert!(file.tree().is_none());
+    let db = CountingSink::default();
+    let id = file.write_tree(&db)?;
+    assert_eq!(id, expected_tree("make_index_without_tree.sh")?);
+    assert_eq!(db.count.get(), 4, "the root tree, 'a', 'dir' and 'dir/sub'");
+
+    let tree = file.tree().expect("tree cache was created");
+    assert_eq!(
+        tree.id, None,
+        "an intent-to-add entry keeps the root tree from being cached"
+    );
+    assert_eq!(tree.num_entries as usize, file.entries().len());
+    assert_eq!(
+        tree.children
+            .iter()
+            .map(|c| (c.name.as_slice(), c.id.is_some()))
+            .collect::<Vec<_>>(),
+        vec![(&b"a"[..], true), (&b"dir"[..], true)]
+    );
+
+    let db = CountingSink::default();
+    assert_eq!(file.write_tree(&db)?, id);
+    assert_eq!(db.count.get(), 1, "only the root tree needs to be written again");
+    Ok(())
+}
+
+#[test]
+fn only_invalidated_trees_are_written() -> crate::Result {
+    let mut file = index("make_index_with_invalid_tree.sh")?;
+    let db = CountingSink::default();
+    let id = file.write_tree(&db)?;
+    assert_eq!(id, expected_tree("make_index_with_invalid_tree.sh")?);
+    assert_eq!(db.count.get(), 2, "the root tree and 'dir-other'");
+
+    let tree = file.tree().expect("present");
+    assert_eq!(tree.id, Some(id));
+    assert!(tree.children.iter().all(|c| c.id.is_some()));
+    Ok(())
+}
+
+#[test]
+fn unmerged_entries_cause_an_error() -> crate::Result {
+    let mut file = index("make_index_with_conflicts.sh")?;
+    match file.write_tree(&git_odb::sink()) {
+        Err(git_index::write_tree::Error::Unmerged { path, stage }) => {
+            assert_eq!(path, "file");
+            assert_eq!(stage, 1);
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+    Ok(())
+}
@@ -0,0 +1,22 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+mkdir -p dir/sub empty-file-dir many
+echo content > a
+echo "#!/bin/sh" > dir/executable && chmod +x dir/executable
+echo nested > dir/sub/file
+touch empty-file-dir/empty
+ln -s a link
+ln -s dir/sub link-to-dir
+for i in $(seq 1 100); do
+  echo $i > many/file-$i
+done
+
+git add .
+git update-index --add --cacheinfo 160000,1111111111111111111111111111111111111111,submodule
+git commit -q -m c1
+git rev-parse HEAD^{tree} > .git/expected-tree
+git ls-files --stage > .git/expected-ls-files
@@ -0,0 +1,41 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.excludesFile "$PWD/user-excludes"
+
+echo "*.user" > user-excludes
+cat <<EOT >> .git/info/exclude
+*.info
+user-excludes
+EOT
+cat <<EOT > .gitignore
+# a comment
+*.log
+!important.log
+/anchored
+build/
+**/deep/*.tmp
+with-trailing-space\ 
+[abc]-class
+ignored-with-tracked-files/
+EOT
+mkdir -p dir ignored-with-tracked-files
+cat <<EOT > dir/.gitignore
+!*.log
+*.txt
+EOT
+touch a dir/b tracked.log ignored-with-tracked-files/tracked
+git add .
+git add -f tracked.log ignored-with-tracked-files/tracked
+git commit -q -m c1
+
+mkdir -p sub/build build untracked-dir/nested x/deep ignored-with-tracked-files/sub nested-repo
+touch new-file anchored sub/anchored build/out sub/build/out untracked-dir/nested/file \
+      x/deep/y.tmp x/deep/y.txt file.user file.info important.log debug.log dir/x.log dir/y.txt \
+      "with-trailing-space " b-class d-class ignored-with-tracked-files/untracked ignored-with-tracked-files/sub/file
+(cd nested-repo && git init -q)
+
+git ls-files --others --exclude-standard > .git/expected-untracked
+git ls-files --others --ignored --exclude-standard --directory > .git/expected-ignored
@@ -0,0 +1,20 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+echo "too-deep/" > .gitignore
+touch tracked
+git add .
+git commit -q -m c1
+
+# Paths within this directory are longer than the system supports, which makes reading it fail even with all permissions.
+mkdir too-deep
+cd too-deep
+name=$(printf 'd%.0s' {1..250})
+for _ in $(seq 20); do
+  mkdir "$name"
+  cd "$name"
+done
+touch file
@@ -0,0 +1,15 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config splitIndex.maxPercentChange 100
+
+touch a b c
+git add .
+git update-index --split-index
+
+echo changed > b
+touch d
+git rm -q --cached c
+git add b d
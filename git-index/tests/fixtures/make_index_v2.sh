@@ -0,0 +1,16 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+mkdir -p dir/sub dir-other
+touch a empty dir/b dir/sub/c dir-other/d
+echo content > a
+echo "#!/bin/sh" > executable && chmod +x executable
+ln -s a link
+
+git add .
+git commit -q -m c1
+git ls-files --stage > .git/expected-ls-files
+git rev-parse HEAD^{tree} HEAD:dir HEAD:dir/sub > .git/expected-tree-ids
@@ -0,0 +1,15 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+touch a b c
+git add .
+git commit -q -m c1
+
+touch intent-to-add
+git add --intent-to-add intent-to-add
+git update-index --skip-worktree b
+git update-index --assume-unchanged c
+git ls-files --stage > .git/expected-ls-files
@@ -0,0 +1,16 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+mkdir -p dir/sub/deeper/deepest dir-other
+touch a empty dir/b dir/sub/c dir/sub/deeper/deepest/d dir/sub/deeper/e dir-other/f
+echo content > a
+echo "#!/bin/sh" > executable && chmod +x executable
+ln -s a link
+
+git add .
+git commit -q -m c1
+git update-index --index-version 4
+git ls-files --stage > .git/expected-ls-files
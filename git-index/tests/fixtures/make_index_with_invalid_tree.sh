@@ -0,0 +1,17 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+mkdir -p dir/sub dir-other
+touch a dir/b dir/sub/c dir-other/d
+git add .
+git commit -q -m c1
+
+echo changed > dir-other/d
+git add dir-other/d
+
+cp .git/index .git/index.orig
+git write-tree > .git/expected-tree
+mv .git/index.orig .git/index
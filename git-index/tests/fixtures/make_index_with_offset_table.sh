@@ -0,0 +1,18 @@
+#!/bin/bash
+set -eu -o pipefail
+
+version=${1:?the index version to write}
+
+git init -q
+git config commit.gpgsign false
+
+mkdir -p dir/sub
+for i in $(seq 1 1200); do
+  echo $i > dir/file-$i
+done
+touch a dir/sub/b z
+
+git add .
+git -c index.threads=2 -c index.recordEndOfIndexEntries=true -c index.recordOffsetTable=true \
+  update-index --index-version $version
+git ls-files --stage > .git/expected-ls-files
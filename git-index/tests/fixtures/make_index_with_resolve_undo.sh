@@ -0,0 +1,24 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+echo base > file
+git add .
+git commit -q -m base
+
+git checkout -q -b other
+echo theirs > file
+git commit -q -am theirs
+
+git checkout -q main
+echo ours > file
+git commit -q -am ours
+
+git merge other -q >/dev/null 2>&1 || :
+echo resolved > file
+git add file
+git ls-files --stage > .git/expected-ls-files
+git rev-parse main^:file main:file other:file > .git/expected-resolve-undo-ids
@@ -0,0 +1,18 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.untrackedCache true
+
+mkdir -p dir/sub untracked-dir
+touch tracked dir/tracked dir/sub/tracked
+git add .
+git commit -q -m c1
+
+touch untracked dir/untracked untracked-dir/file
+echo "ignored" > .gitignore
+git update-index --untracked-cache
+git status --porcelain >/dev/null
+git status --porcelain >/dev/null
+git ls-files --stage > .git/expected-ls-files
@@ -0,0 +1,18 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+mkdir -p a dir/sub
+touch a.b a/x dir/sub/c empty
+echo content > dir/b
+echo "#!/bin/sh" > executable && chmod +x executable
+ln -s a.b link
+git add .
+echo "not yet added" > intent-to-add
+git add --intent-to-add intent-to-add
+
+cp .git/index .git/index.orig
+git write-tree > .git/expected-tree
+mv .git/index.orig .git/index
@@ -0,0 +1,31 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+mkdir dir dir-replaced-by-file
+echo unchanged > unchanged
+echo content > dir/modified
+echo content > modified-same-size
+echo content > touched
+echo content > deleted
+echo content > executable-bit
+echo content > dir-replaced-by-file/file
+echo content > replaced-by-dir
+ln -s unchanged link-replaced-by-file
+ln -s unchanged link
+
+# Files must be older than the index to not be racily clean.
+find . -path ./.git -prune -o -exec touch -h -d '2000-01-01 00:00:00' {} +
+git add .
+git commit -q -m c1
+
+echo more content >> dir/modified
+echo CONTENT > modified-same-size && touch -d '2000-01-01 00:00:00' modified-same-size
+touch touched
+rm deleted
+chmod +x executable-bit
+rm -r dir-replaced-by-file && echo content > dir-replaced-by-file
+rm replaced-by-dir && mkdir replaced-by-dir
+rm link-replaced-by-file && echo unchanged > link-replaced-by-file
@@ -0,0 +1,3 @@
+mod wildmatch;
+
+mod search;
@@ -0,0 +1,61 @@
+use bstr::ByteSlice;
+use git_index::ignore::{pattern::Mode, Pattern, Search};
+
+#[test]
+fn patterns_are_parsed_like_git_does() {
+    let pattern = |line: &str| Pattern::from_bytes(line.as_bytes());
+    assert_eq!(pattern("# comment"), None);
+    assert_eq!(pattern(""), None);
+    assert_eq!(pattern("   "), None);
+    assert_eq!(pattern("!"), None);
+    let p = pattern("!build/").expect("valid");
+    assert_eq!(p.text, "build");
+    assert_eq!(p.mode, Mode(Mode::NEGATIVE | Mode::MUST_BE_DIR | Mode::NO_SUB_DIR));
+    let p = pattern("/anchored  ").expect("valid");
+    assert_eq!(p.text, "/anchored", "trailing spaces are removed");
+    assert_eq!(p.mode, Mode::default());
+    assert_eq!(pattern(r"space\  ").expect("valid").text, r"space\ ");
+    assert_eq!(pattern(r"\#not-a-comment").expect("valid").text, r"\#not-a-comment");
+}
+
+#[test]
+fn later_patterns_and_lists_take_precedence() {
+    let mut search = Search::default();
+    search.add_patterns_buffer(b"*.log\n!important.log\nbuild/\n/anchored\n", "");
+    search.add_patterns_buffer(b"# comment\n*.txt\n!*.log\n", "dir/");
+
+    let is_excluded = |path: &str, is_dir: bool| {
+        search
+            .pattern_matching_relative_path(path.as_bytes().as_bstr(), is_dir, false)
+            .map(|m| (m.is_excluded(), m.line_number))
+    };
+    assert_eq!(is_excluded("a.log", false), Some((true, 1)));
+    assert_eq!(is_excluded("sub/a.log", false), Some((true, 1)));
+    assert_eq!(is_excluded("important.log", false), Some((false, 2)));
+    assert_eq!(
+        is_excluded("build", false),
+        None,
+        "directory patterns don't match files"
+    );
+    assert_eq!(is_excluded("sub/build", true), Some((true, 3)));
+    assert_eq!(is_excluded("anchored", false), Some((true, 4)));
+    assert_eq!(is_excluded("sub/anchored", false), None);
+    assert_eq!(
+        is_excluded("dir/a.log", false),
+        Some((false, 3)),
+        "deeper lists have precedence"
+    );
+    assert_eq!(is_excluded("dir/a.txt", false), Some((true, 2)));
+    assert_eq!(
+        is_excluded("a.txt", false),
+        None,
+        "patterns only apply inside their base directory"
+    );
+    assert_eq!(is_excluded("A.LOG", false), None);
+    assert_eq!(
+        search
+            .pattern_matching_relative_path("A.LOG".into(), false, true)
+            .map(|m| m.is_excluded()),
+        Some(true)
+    );
+}
@@ -0,0 +1,164 @@
+use bstr::ByteSlice;
+use git_index::ignore::{wildmatch, wildmatch::Mode};
+
+/// Cases taken from git's own test-suite, as `(is_match, is_match_ignoring_case, value, pattern)` with slashes being
+/// matched literally.
+const CASES: &[(bool, bool, &str, &str)] = &[
+    (true, true, "foo", "foo"),
+    (false, false, "foo", "bar"),
+    (true, true, "", ""),
+    (true, true, "foo", "???"),
+    (false, false, "foo", "??"),
+    (true, true, "foo", "*"),
+    (true, true, "foo", "f*"),
+    (false, false, "foo", "*f"),
+    (true, true, "foo", "*foo*"),
+    (true, true, "foobar", "*ob*a*r*"),
+    (true, true, "aaaaaaabababab", "*ab"),
+    (true, true, "foo*", r"foo\*"),
+    (false, false, "foobar", r"foo\*bar"),
+    (true, true, r"f\oo", r"f\\oo"),
+    (true, true, "ball", "*[al]?"),
+    (false, false, "ten", "[ten]"),
+    (true, true, "ten", "**[!te]"),
+    (false, false, "ten", "**[!ten]"),
+    (true, true, "ten", "t[a-g]n"),
+    (false, false, "ten", "t[!a-g]n"),
+    (true, true, "ton", "t[!a-g]n"),
+    (true, true, "ton", "t[^a-g]n"),
+    (true, true, "a]b", "a[]]b"),
+    (true, true, "a-b", "a[]-]b"),
+    (true, true, "a]b", "a[]-]b"),
+    (false, false, "aab", "a[]-]b"),
+    (true, true, "aab", "a[]a-]b"),
+    (true, true, "]", "]"),
+    (false, false, "foo/baz/bar", "foo*bar"),
+    (false, false, "foo/baz/bar", "foo**bar"),
+    (true, true, "foobazbar", "foo**bar"),
+    (true, true, "foo/baz/bar", "foo/**/bar"),
+    (true, true, "foo/baz/bar", "foo/**/**/bar"),
+    (true, true, "foo/b/a/z/bar", "foo/**/bar"),
+    (true, true, "foo/b/a/z/bar", "foo/**/**/bar"),
+    (true, true, "foo/bar", "foo/**/bar"),
+    (true, true, "foo/bar", "foo/**/**/bar"),
+    (false, false, "foo/bar", "foo?bar"),
+    (false, false, "foo/bar", "foo[/]bar"),
+    (false, false, "foo/bar", "foo[^a-z]bar"),
+    (false, false, "foo/bar", "f[^eiu][^eiu][^eiu][^eiu][^eiu]r"),
+    (true, true, "foo-bar", "f[^eiu][^eiu][^eiu][^eiu][^eiu]r"),
+    (true, true, "foo", "**/foo"),
+    (true, true, "XXX/foo", "**/foo"),
+    (true, true, "bar/baz/foo", "**/foo"),
+    (false, false, "bar/baz/foo", "*/foo"),
+    (false, false, "foo/bar/baz", "**/bar*"),
+    (true, true, "deep/foo/bar/baz", "**/bar/*"),
+    (false, false, "deep/foo/bar/baz/", "**/bar/*"),
+    (true, true, "deep/foo/bar/baz/", "**/bar/**"),
+    (false, false, "deep/foo/bar", "**/bar/*"),
+    (true, true, "deep/foo/bar/", "**/bar/**"),
+    (false, false, "foo/bar/baz", "**/bar**"),
+    (true, true, "foo/bar/baz/x", "*/bar/**"),
+    (false, false, "deep/foo/bar/baz/x", "*/bar/**"),
+    (true, true, "deep/foo/bar/baz/x", "**/bar/*/*"),
+    (true, true, "a1B", "[[:alpha:]][[:digit:]][[:upper:]]"),
+    (false, true, "a", "[[:digit:][:upper:][:space:]]"),
+    (true, true, "A", "[[:digit:][:upper:][:space:]]"),
+    (true, true, "1", "[[:digit:][:upper:][:space:]]"),
+    (false, false, "1", "[[:digit:][:upper:][:spaci:]]"),
+    (true, true, " ", "[[:digit:][:upper:][:space:]]"),
+    (false, false, ".", "[[:digit:][:upper:][:space:]]"),
+    (true, true, ".", "[[:digit:][:punct:][:space:]]"),
+    (true, true, "5", "[[:xdigit:]]"),
+    (true, true, "f", "[[:xdigit:]]"),
+    (true, true, "D", "[[:xdigit:]]"),
+    (true, true, "]", "[\\]]"),
+    (false, false, "\\]", "[\\]]"),
+    (false, false, "ab", "a[]b"),
+    (false, false, "a[]b", "a[]b"),
+    (false, false, "-", "[!]-]"),
+    (true, true, "a", "[!]-]"),
+    (
+        true,
+        true,
+        "-adobe-courier-bold-o-normal--12-120-75-75-m-70-iso8859-1",
+        "-*-*-*-*-*-*-12-*-*-*-m-*-*-*",
+    ),
+    (
+        false,
+        false,
+        "-adobe-courier-bold-o-normal--12-120-75-75-X-70-iso8859-1",
+        "-*-*-*-*-*-*-12-*-*-*-m-*-*-*",
+    ),
+    (
+        true,
+        true,
+        "XXX/adobe/courier/bold/o/normal//12/120/75/75/m/70/iso8859/1",
+        "XXX/*/*/*/*/*/*/12/*/*/*/m/*/*/*",
+    ),
+    (
+        true,
+        true,
+        "abcd/abcdefg/abcdefghijk/abcdefghijklmnop.txt",
+        "**/*a*b*g*n*t",
+    ),
+    (
+        false,
+        false,
+        "abcd/abcdefg/abcdefghijk/abcdefghijklmnop.txtz",
+        "**/*a*b*g*n*t",
+    ),
+    (false, true, "foo", "FOO"),
+    (false, true, "a", "[A-Z]"),
+    (true, true, "A", "[A-Z]"),
+    (false, true, "A", "[a-z]"),
+];
+
+#[test]
+fn compatibility_with_git() {
+    for (is_match, is_match_ignoring_case, value, pattern) in CASES {
+        assert_eq!(
+            wildmatch(
+                pattern.as_bytes().as_bstr(),
+                value.as_bytes().as_bstr(),
+                Mode(Mode::SLASH_IS_LITERAL)
+            ),
+            *is_match,
+            "{:?} against {:?}",
+            value,
+            pattern
+        );
+        assert_eq!(
+            wildmatch(
+                pattern.as_bytes().as_bstr(),
+                value.as_bytes().as_bstr(),
+                Mode(Mode::SLASH_IS_LITERAL | Mode::IGNORE_CASE)
+            ),
+            *is_match_ignoring_case,
+            "{:?} against {:?} ignoring case",
+            value,
+            pattern
+        );
+    }
+}
+
+#[test]
+fn without_literal_slashes_wildcards_match_slashes() {
+    for (value, pattern) in &[
+        ("foo/baz/bar", "foo*bar"),
+        ("foo/baz/bar", "foo**bar"),
+        ("foo/bar", "foo?bar"),
+        ("foo/bar", "foo[/]bar"),
+        ("bar/baz/foo", "*/foo"),
+    ] {
+        assert!(
+            wildmatch(
+                pattern.as_bytes().as_bstr(),
+                value.as_bytes().as_bstr(),
+                Mode::default()
+            ),
+            "{:?} against {:?}",
+            value,
+            pattern
+        );
+    }
+}
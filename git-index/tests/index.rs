@@ -0,0 +1,5 @@
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+mod checkout;
+mod file;
+mod ignore;
@@ -137,7 +137,7 @@ fn lock_with_mode<T>(
 
 fn add_lock_suffix(resource_path: &Path) -> PathBuf {
     resource_path.with_extension(resource_path.extension().map_or_else(
-        || DOT_SUFFIX.to_string(),
+        || DOT_SUFFIX[1..].to_string(),
         |ext| format!("{}{}", ext.to_string_lossy(), DOT_SUFFIX),
     ))
 }
@@ -23,6 +23,21 @@ fn lock_create_dir_write_commit() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn lock_write_commit_resource_without_extension() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let resource = dir.path().join("HEAD");
+    let mut file = git_lock::File::acquire_to_update_resource(&resource, fail_immediately(), None)?;
+    assert!(
+        dir.path().join("HEAD.lock").is_file(),
+        "the lock is named like git would"
+    );
+    file.with_mut(|out| out.write_all(b"hello world"))?;
+    file.commit()?;
+    assert_eq!(std::fs::read(resource)?, &b"hello world"[..]);
+    Ok(())
+}
+
 #[test]
 fn lock_write_drop() -> crate::Result {
     let dir = tempfile::tempdir()?;
@@ -12,12 +12,16 @@ pub enum Error {
     Loose(#[from] loose::find::Error),
     #[error("An error occurred while obtaining an object from the packed object store")]
     Pack(#[from] pack::data::decode_entry::Error),
+    #[error("The multi-pack index refers to the pack at index {pack_index}, but it only covers {num_packs} packs")]
+    MultiIndexPackOutOfBounds { pack_index: u32, num_packs: usize },
 }
 
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 pub(crate) struct PackLocation {
     pub bundle_index: usize,
-    pub entry_index: u32,
+    /// The index of the object in the pack index, unknown if it was found using the multi-pack index.
+    pub entry_index: Option<u32>,
+    pub pack_offset: u64,
 }
 
 impl compound::Store {
@@ -31,11 +35,9 @@ impl compound::Store {
         pack_cache: &mut impl pack::cache::DecodeEntry,
     ) -> Result<Option<data::Object<'a>>, Error> {
         let id = id.as_ref();
-        for bundle in &self.bundles {
-            if let Some(idx) = find_pack_index(bundle, id) {
-                let object = bundle.get_object_by_index(idx, buffer, pack_cache)?;
-                return Ok(Some(object));
-            }
+        if let Some(location) = self.internal_find_packed(id)? {
+            let object = self.internal_get_packed_object(location, buffer, pack_cache)?;
+            return Ok(Some(object));
         }
         if self.loose.contains(id) {
             return self.loose.find(id, buffer).map_err(Into::into);
@@ -48,27 +50,57 @@ impl compound::Store {
     /// (The polonius borrow-checker would support this via the locate
     /// function, so this can be [simplified](https://github.com/Byron/gitoxide/blob/0c5f4043da4615820cb180804a81c2d4fe75fe5e/git-odb/src/compound/locate.rs#L47)
     /// once polonius is stable.)
-    pub(crate) fn internal_find_packed(&self, id: impl AsRef<git_hash::oid>) -> Option<PackLocation> {
+    pub(crate) fn internal_find_packed(&self, id: impl AsRef<git_hash::oid>) -> Result<Option<PackLocation>, Error> {
         let id = id.as_ref();
+        let mut skip_covered_bundles = false;
+        if let Some(multi_index) = &self.multi_index {
+            match multi_index.lookup(id) {
+                Some(idx) => {
+                    // The multi-pack index tells us where the object is, which saves probing the index of each pack in turn.
+                    let (pack_index, pack_offset) = multi_index.pack_id_and_pack_offset_at_index(idx);
+                    let bundle_index =
+                        *self
+                            .multi_index_bundles
+                            .get(pack_index as usize)
+                            .ok_or(Error::MultiIndexPackOutOfBounds {
+                                pack_index,
+                                num_packs: self.multi_index_bundles.len(),
+                            })?;
+                    return Ok(Some(PackLocation {
+                        bundle_index,
+                        entry_index: None,
+                        pack_offset,
+                    }));
+                }
+                None => skip_covered_bundles = true,
+            }
+        }
         for (bundle_index, bundle) in self.bundles.iter().enumerate() {
+            if skip_covered_bundles && self.multi_index_bundles.contains(&bundle_index) {
+                continue;
+            }
             if let Some(idx) = find_pack_index(bundle, id) {
-                return Some(PackLocation {
+                return Ok(Some(PackLocation {
                     bundle_index,
-                    entry_index: idx,
-                });
+                    entry_index: Some(idx),
+                    pack_offset: bundle.index.pack_offset_at_index(idx),
+                }));
             }
         }
-        None
+        Ok(None)
     }
 
-    pub(crate) fn internal_get_packed_object_by_index<'a>(
+    pub(crate) fn internal_get_packed_object<'a>(
         &self,
-        pack_index: usize,
-        object_index: u32,
+        location: PackLocation,
         buffer: &'a mut Vec<u8>,
         pack_cache: &mut impl pack::cache::DecodeEntry,
     ) -> Result<data::Object<'a>, pack::data::decode_entry::Error> {
-        self.bundles[pack_index].get_object_by_index(object_index, buffer, pack_cache)
+        let bundle = &self.bundles[location.bundle_index];
+        match location.entry_index {
+            Some(entry_index) => bundle.get_object_by_index(entry_index, buffer, pack_cache),
+            None => bundle.get_object_at_offset(location.pack_offset, buffer, pack_cache),
+        }
     }
 }
 
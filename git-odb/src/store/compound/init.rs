@@ -17,12 +17,29 @@ pub enum Error {
     Alternate(#[from] Box<crate::alternate::Error>),
 }
 
+/// The reason for a multi-pack index not to be used by a [`compound::Store`], see [`compound::Store::multi_index_ignored`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum MultiIndexIgnored {
+    #[error("The multi-pack index at '{path}' couldn't be read")]
+    Unreadable {
+        path: PathBuf,
+        source: pack::multi_index::init::Error,
+    },
+    #[error("The multi-pack index refers to the pack index '{name}' which isn't present in the pack directory")]
+    MissingPack { name: PathBuf },
+}
+
 /// Instantiation
 impl compound::Store {
     /// Returns a compound database as initialized from the given git `objects_directory`, commonly `.git/objects`.
     ///
     /// Only loose and packed objects will be considered. See the [linked Db][crate::store::linked::Store] for a database with
     /// support for _git alternates_, i.e. linking to other repositories.
+    ///
+    /// If there is a `multi-pack-index` file in the pack directory, it will be used for all lookups of objects in the packs
+    /// it covers. It's ignored if one of its packs doesn't exist anymore, or if it can't be read, in which case the reason
+    /// is available in [`multi_index_ignored`][compound::Store::multi_index_ignored].
     pub fn at(objects_directory: impl Into<PathBuf>) -> Result<compound::Store, Error> {
         let loose_objects = objects_directory.into();
         if !loose_objects.is_dir() {
@@ -46,9 +63,46 @@ impl compound::Store {
             Err(_) => Vec::new(),
         };
 
+        let multi_index_path = loose_objects.join("pack").join("multi-pack-index");
+        let (multi_index, multi_index_bundles, multi_index_ignored) = if multi_index_path.is_file() {
+            match pack::multi_index::File::at(&multi_index_path) {
+                Ok(multi_index) => match bundle_indices_of(&multi_index, &packs) {
+                    Ok(bundle_indices) => (Some(multi_index), bundle_indices, None),
+                    Err(name) => (None, Vec::new(), Some(MultiIndexIgnored::MissingPack { name })),
+                },
+                Err(source) => (
+                    None,
+                    Vec::new(),
+                    Some(MultiIndexIgnored::Unreadable {
+                        path: multi_index_path,
+                        source,
+                    }),
+                ),
+            }
+        } else {
+            (None, Vec::new(), None)
+        };
+
         Ok(compound::Store {
             loose: loose::Store::at(loose_objects),
             bundles: packs,
+            multi_index,
+            multi_index_bundles,
+            multi_index_ignored,
         })
     }
 }
+
+/// Return the index into `bundles` for each pack of `multi_index`, or the name of the first pack index that isn't among them.
+fn bundle_indices_of(multi_index: &pack::multi_index::File, bundles: &[pack::Bundle]) -> Result<Vec<usize>, PathBuf> {
+    multi_index
+        .index_names()
+        .iter()
+        .map(|name| {
+            bundles
+                .iter()
+                .position(|bundle| bundle.index.path().file_name() == name.file_name())
+                .ok_or_else(|| name.to_owned())
+        })
+        .collect()
+}
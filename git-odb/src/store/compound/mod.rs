@@ -14,4 +14,12 @@ pub struct Store {
     pub loose: loose::Store,
     /// All packs in the `objects/packs` directory
     pub bundles: Vec<pack::Bundle>,
+    /// The multi-pack index in the `objects/packs` directory, if present and if all of its packs were among our `bundles`.
+    ///
+    /// Its packs are mapped to `bundles` when opening the store, which thus must not be changed while it is used.
+    pub multi_index: Option<pack::multi_index::File>,
+    /// The index into `bundles` for each pack of the `multi_index`, in the order of its pack ids.
+    pub(crate) multi_index_bundles: Vec<usize>,
+    /// The reason for a `multi-pack-index` file in the `objects/packs` directory not to be used as `multi_index`.
+    pub multi_index_ignored: Option<init::MultiIndexIgnored>,
 }
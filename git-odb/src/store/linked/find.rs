@@ -13,7 +13,7 @@ impl linked::Store {
     pub fn contains(&self, id: impl AsRef<oid>) -> bool {
         let id = id.as_ref();
         for db in self.dbs.iter() {
-            if db.internal_find_packed(id).is_some() || db.loose.contains(id) {
+            if matches!(db.internal_find_packed(id), Ok(Some(_))) || db.loose.contains(id) {
                 return true;
             }
         }
@@ -32,13 +32,10 @@ impl crate::Find for linked::Store {
     ) -> Result<Option<Object<'a>>, Self::Error> {
         let id = id.as_ref();
         for db in self.dbs.iter() {
-            match db.internal_find_packed(id) {
-                Some(compound::find::PackLocation {
-                    bundle_index: pack_id,
-                    entry_index,
-                }) => {
+            match db.internal_find_packed(id)? {
+                Some(location) => {
                     return db
-                        .internal_get_packed_object_by_index(pack_id, entry_index, buffer, pack_cache)
+                        .internal_get_packed_object(location, buffer, pack_cache)
                         .map(Some)
                         .map_err(Into::into)
                 }
@@ -58,10 +55,10 @@ impl crate::Find for linked::Store {
             if let Some(compound::find::PackLocation {
                 bundle_index,
                 entry_index,
-            }) = db.internal_find_packed(id)
+                pack_offset,
+            }) = db.internal_find_packed(id).ok().flatten()
             {
                 let bundle = &db.bundles[bundle_index];
-                let pack_offset = bundle.index.pack_offset_at_index(entry_index);
                 let entry = bundle.pack.entry(pack_offset);
 
                 buf.resize(entry.decompressed_size.try_into().expect("representable szie"), 0);
@@ -72,6 +69,7 @@ impl crate::Find for linked::Store {
                     .map(|entry_size_past_header| pack::bundle::Location {
                         pack_id: bundle.pack.id,
                         index_file_id: entry_index,
+                        pack_offset,
                         entry_size: entry.header_size() + entry_size_past_header,
                     });
             }
@@ -85,9 +83,8 @@ impl crate::Find for linked::Store {
             .find_map(|db| db.bundles.iter().find(|p| p.pack.id == location.pack_id))
             .map(|b| (b, location))
             .and_then(|(bundle, l)| {
-                let crc32 = bundle.index.crc32_at_index(l.index_file_id);
-                let pack_offset = bundle.index.pack_offset_at_index(l.index_file_id);
-                bundle.pack.entry_slice(l.entry_range(pack_offset)).map(|data| Entry {
+                let crc32 = l.index_file_id.and_then(|idx| bundle.index.crc32_at_index(idx));
+                bundle.pack.entry_slice(l.entry_range()).map(|data| Entry {
                     data,
                     crc32,
                     version: bundle.pack.version(),
@@ -1,4 +1,4 @@
-pub use git_testtools::{fixture_path, hex_to_id, scripted_fixture_repo_read_only};
+pub use git_testtools::{fixture_path, hex_to_id, scripted_fixture_repo_read_only, scripted_fixture_repo_writable};
 
 pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
 
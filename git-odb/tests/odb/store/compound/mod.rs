@@ -41,3 +41,106 @@ mod locate {
         can_locate(&db(), "dd25c539efbb0ab018caa4cda2d133285634e9b5"); // pack c043
     }
 }
+
+mod multi_index {
+    use crate::scripted_fixture_repo_read_only;
+    use git_odb::compound::{init::MultiIndexIgnored, Store};
+
+    /// The script is shared with the multi-pack index tests of `git-pack`.
+    const SCRIPT: &str = "../../../git-pack/tests/fixtures/make_repo_multi_index.sh";
+
+    fn db() -> crate::Result<Store> {
+        Ok(Store::at(
+            scripted_fixture_repo_read_only(SCRIPT)?.join(".git").join("objects"),
+        )?)
+    }
+
+    #[test]
+    fn is_loaded_alongside_all_packs() -> crate::Result {
+        let db = db()?;
+        assert_eq!(db.bundles.len(), 4);
+        assert!(db.multi_index_ignored.is_none());
+        let multi_index = db.multi_index.as_ref().expect("multi-pack index present");
+        assert_eq!(
+            multi_index.num_packs(),
+            3,
+            "the last pack was written after the multi-pack index"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn objects_are_found_in_covered_and_uncovered_packs() -> crate::Result {
+        let db = db()?;
+        let mut buf = Vec::new();
+        let mut num_objects = 0;
+        for bundle in &db.bundles {
+            for entry in bundle.index.iter() {
+                let obj = db
+                    .find(entry.oid, &mut buf, &mut git_pack::cache::Never)?
+                    .expect("object present");
+                let location = obj.pack_location.expect("packed object");
+                assert_eq!(
+                    location.pack_id, bundle.pack.id,
+                    "each object is found in the only pack it is contained in"
+                );
+                assert_eq!(location.pack_offset, entry.pack_offset);
+                num_objects += 1;
+            }
+        }
+        assert_eq!(num_objects, 4 * 12, "4 commits with a tree and 10 blobs each");
+        Ok(())
+    }
+
+    #[test]
+    fn a_multi_index_with_a_missing_pack_is_ignored() -> crate::Result {
+        let repo = crate::scripted_fixture_repo_writable(SCRIPT)?;
+        let objects_dir = repo.path().join(".git").join("objects");
+        let removed = db()?.bundles.remove(0);
+        let removed_index_name = removed.index.path().file_name().expect("file name").to_owned();
+        let pack_dir = objects_dir.join("pack");
+        std::fs::remove_file(pack_dir.join(&removed_index_name))?;
+        std::fs::remove_file(pack_dir.join(removed.pack.path().file_name().expect("file name")))?;
+
+        let db = Store::at(objects_dir)?;
+        assert!(db.multi_index.is_none());
+        assert!(
+            matches!(&db.multi_index_ignored, Some(MultiIndexIgnored::MissingPack { name }) if name.as_os_str() == removed_index_name),
+            "the largest pack is covered by the multi-pack index"
+        );
+        let mut buf = Vec::new();
+        for entry in removed.index.iter() {
+            assert!(db.find(entry.oid, &mut buf, &mut git_pack::cache::Never)?.is_none());
+        }
+        for bundle in &db.bundles {
+            for entry in bundle.index.iter() {
+                let obj = db
+                    .find(entry.oid, &mut buf, &mut git_pack::cache::Never)?
+                    .expect("object present");
+                assert_eq!(obj.pack_location.expect("packed object").pack_id, bundle.pack.id);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn a_multi_index_that_cannot_be_read_is_ignored() -> crate::Result {
+        let repo = crate::scripted_fixture_repo_writable(SCRIPT)?;
+        let objects_dir = repo.path().join(".git").join("objects");
+        std::fs::write(objects_dir.join("pack").join("multi-pack-index"), b"MIDX")?;
+
+        let db = Store::at(objects_dir)?;
+        assert!(db.multi_index.is_none());
+        assert!(matches!(
+            db.multi_index_ignored,
+            Some(MultiIndexIgnored::Unreadable { .. })
+        ));
+        let mut buf = Vec::new();
+        for bundle in &db.bundles {
+            for entry in bundle.index.iter() {
+                assert!(db.find(entry.oid, &mut buf, &mut git_pack::cache::Never)?.is_some());
+            }
+        }
+        Ok(())
+    }
+}
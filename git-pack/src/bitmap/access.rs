@@ -0,0 +1,70 @@
+use crate::bitmap::{Bits, File, Version};
+use std::path::Path;
+
+/// Access methods
+impl File {
+    /// The version of the bitmap file
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// The path of the opened bitmap file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// The checksum of the pack this bitmap file belongs to, as found in its trailer.
+    pub fn pack_checksum(&self) -> git_hash::ObjectId {
+        self.pack_checksum
+    }
+    /// The amount of commits which have a reachability bitmap.
+    pub fn num_commits(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    /// Return an iterator over the positions of all commits with a reachability bitmap in the pack index.
+    pub fn commit_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.entries.iter().map(|e| e.index)
+    }
+
+    /// Return the reachability bitmap of the commit at `index` in the pack index, or `None` if there is none.
+    ///
+    /// Its set bits are the pack positions of all objects reachable from the commit, including the commit itself.
+    pub fn bitmap_at_index(&self, index: u32) -> Option<Bits> {
+        let mut entry_index = *self.entry_by_index.get(&index)?;
+        let mut chain = vec![entry_index];
+        loop {
+            let xor_offset = self.entries[entry_index].xor_offset as usize;
+            if xor_offset == 0 {
+                break;
+            }
+            entry_index -= xor_offset;
+            chain.push(entry_index);
+        }
+        let mut bits = Bits::default();
+        for entry_index in chain.into_iter().rev() {
+            bits.xor(&Bits::from_words(self.entries[entry_index].bitmap.decompress()));
+        }
+        Some(bits)
+    }
+
+    /// Return a bitmap with the pack positions of all objects of the given `kind` set.
+    pub fn objects_of_kind(&self, kind: git_object::Kind) -> Bits {
+        use git_object::Kind::*;
+        let bitmap = match kind {
+            Commit => &self.commits,
+            Tree => &self.trees,
+            Blob => &self.blobs,
+            Tag => &self.tags,
+        };
+        Bits::from_words(bitmap.decompress())
+    }
+
+    /// Return the hash of the path at which the object at `index` in the pack index was seen when the pack was written,
+    /// or `None` if the bitmap file doesn't cache these.
+    ///
+    /// See [`name_hash()`][crate::data::output::count::name_hash()] for how it's computed.
+    pub fn name_hash_at_index(&self, index: u32) -> Option<u32> {
+        self.name_hashes
+            .as_ref()
+            .and_then(|hashes| hashes.get(index as usize).copied())
+    }
+}
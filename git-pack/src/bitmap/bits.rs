@@ -0,0 +1,83 @@
+/// An uncompressed bitmap, with each bit typically representing an object by its position in a pack.
+#[derive(Default, PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Bits {
+    words: Vec<u64>,
+}
+
+const WORD_BITS: usize = 64;
+
+impl Bits {
+    /// Create a new instance from `words`, with the first bit being the least significant bit of the first word.
+    pub fn from_words(words: Vec<u64>) -> Self {
+        Bits { words }
+    }
+
+    /// The words backing this bitmap, with the first bit being the least significant bit of the first word.
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Return true if the bit at `position` is set.
+    pub fn is_set(&self, position: usize) -> bool {
+        self.words
+            .get(position / WORD_BITS)
+            .map(|word| word & (1 << (position % WORD_BITS)) != 0)
+            .unwrap_or(false)
+    }
+
+    /// Set the bit at `position`, growing the bitmap as needed.
+    pub fn set(&mut self, position: usize) {
+        let word = position / WORD_BITS;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (position % WORD_BITS);
+    }
+
+    /// Set all bits that are set in `other`.
+    pub fn or(&mut self, other: &Bits) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            *word |= other;
+        }
+    }
+
+    /// Clear all bits that are set in `other`.
+    pub fn and_not(&mut self, other: &Bits) {
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            *word &= !other;
+        }
+    }
+
+    /// Flip all bits that are set in `other`.
+    pub fn xor(&mut self, other: &Bits) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            *word ^= other;
+        }
+    }
+
+    /// The amount of bits which are set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Return an iterator over the positions of all set bits in ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_index, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(word_index * WORD_BITS + bit)
+            })
+        })
+    }
+}
@@ -0,0 +1,149 @@
+use crate::bitmap::{ewah, Entry, File, Version, SIGNATURE};
+use byteorder::{BigEndian, ByteOrder};
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+use std::{collections::HashMap, convert::TryFrom, path::Path};
+
+/// Returned by [`File::at()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open bitmap file at '{}'", .path.display())]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported bitmap version: {version}")]
+    UnsupportedVersion { version: u16 },
+    #[error("Bitmaps need to be computed for the full commit graph, but the file doesn't claim that")]
+    UnsupportedPartialGraph,
+    #[error("Could not decode the {kind} bitmap")]
+    Ewah { source: ewah::Error, kind: &'static str },
+}
+
+const HEADER_LEN: usize = 4 + 2 + 2 + 4 + SHA1_SIZE;
+const TRAILER_LEN: usize = SHA1_SIZE;
+const ENTRY_HEADER_LEN: usize = 4 + 1 + 1;
+const LOOKUP_TABLE_ENTRY_LEN: usize = 4 + 8 + 4;
+const NAME_HASH_LEN: usize = 4;
+
+pub(crate) const FLAG_FULL_DAG: u16 = 0x1;
+pub(crate) const FLAG_HASH_CACHE: u16 = 0x4;
+const FLAG_LOOKUP_TABLE: u16 = 0x10;
+
+/// Instantiation
+impl File {
+    /// Open the bitmap file at the given `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<File, Error> {
+        Self::try_from(path.as_ref())
+    }
+}
+
+impl TryFrom<&Path> for File {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let data = std::fs::read(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        if data.len() < HEADER_LEN + TRAILER_LEN {
+            return Err(corrupt("Bitmap file is too small to hold even the header"));
+        }
+        if &data[..SIGNATURE.len()] != SIGNATURE {
+            return Err(corrupt("Bitmap file does not start with expected signature"));
+        }
+        let mut ofs = SIGNATURE.len();
+        let version = match BigEndian::read_u16(&data[ofs..]) {
+            1 => Version::V1,
+            version => return Err(Error::UnsupportedVersion { version }),
+        };
+        ofs += 2;
+        let flags = BigEndian::read_u16(&data[ofs..]);
+        ofs += 2;
+        if flags & FLAG_FULL_DAG == 0 {
+            return Err(Error::UnsupportedPartialGraph);
+        }
+        let num_entries = BigEndian::read_u32(&data[ofs..]) as usize;
+        ofs += 4;
+        let pack_checksum = git_hash::ObjectId::from_20_bytes(&data[ofs..ofs + SHA1_SIZE]);
+        ofs += SHA1_SIZE;
+
+        let body = &data[ofs..data.len() - TRAILER_LEN];
+        let decode =
+            |kind: &'static str, data| ewah::Vec::from_bytes(data).map_err(|source| Error::Ewah { source, kind });
+        let (commits, body) = decode("commit type", body)?;
+        let (trees, body) = decode("tree type", body)?;
+        let (blobs, body) = decode("blob type", body)?;
+        let (tags, mut body) = decode("tag type", body)?;
+
+        let mut entries = Vec::with_capacity(num_entries);
+        let mut entry_by_index = HashMap::with_capacity(num_entries);
+        for entry_index in 0..num_entries {
+            if body.len() < ENTRY_HEADER_LEN {
+                return Err(corrupt(format!(
+                    "Bitmap file is truncated at commit bitmap {} of {}",
+                    entry_index, num_entries
+                )));
+            }
+            let index = BigEndian::read_u32(body);
+            let xor_offset = body[4];
+            if xor_offset as usize > entry_index {
+                return Err(corrupt(format!(
+                    "Commit bitmap {} refers to a bitmap {} entries before it",
+                    entry_index, xor_offset
+                )));
+            }
+            let (bitmap, rest) = decode("commit", &body[ENTRY_HEADER_LEN..])?;
+            body = rest;
+            if entry_by_index.insert(index, entry_index).is_some() {
+                return Err(corrupt(format!(
+                    "Commit at index position {} has more than one bitmap",
+                    index
+                )));
+            }
+            entries.push(Entry {
+                index,
+                xor_offset,
+                bitmap,
+            });
+        }
+
+        if flags & FLAG_LOOKUP_TABLE != 0 {
+            let lookup_table_len = num_entries * LOOKUP_TABLE_ENTRY_LEN;
+            if body.len() < lookup_table_len {
+                return Err(corrupt("Bitmap file is too small to hold the commit lookup table"));
+            }
+            body = &body[lookup_table_len..];
+        }
+
+        let name_hashes = if flags & FLAG_HASH_CACHE != 0 {
+            if body.len() % NAME_HASH_LEN != 0 {
+                return Err(corrupt("The name-hash cache of the bitmap file has an invalid size"));
+            }
+            Some(body.chunks(NAME_HASH_LEN).map(BigEndian::read_u32).collect())
+        } else {
+            None
+        };
+
+        Ok(File {
+            path: path.to_owned(),
+            version,
+            pack_checksum,
+            commits,
+            trees,
+            blobs,
+            tags,
+            entries,
+            entry_by_index,
+            name_hashes,
+        })
+    }
+}
+
+fn corrupt(message: impl Into<String>) -> Error {
+    Error::Corrupt {
+        message: message.into(),
+    }
+}
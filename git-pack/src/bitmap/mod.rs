@@ -0,0 +1,55 @@
+//! Reachability bitmaps as stored in `pack-*.bitmap` files next to a pack, associating selected commits with the set of
+//! all objects reachable from them.
+//!
+//! Bits refer to objects by their position in the pack, that is the order of objects when sorted by their pack offset.
+use std::{collections::HashMap, path::PathBuf};
+
+/// The version of a bitmap file
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    V1 = 1,
+}
+
+/// A commit with a reachability bitmap, which may be stored as difference to the bitmap of a previous commit.
+struct Entry {
+    /// The position of the commit in the pack index.
+    index: u32,
+    /// If not 0, `bitmap` has to be xor'ed with the bitmap of the entry this amount of entries before us.
+    xor_offset: u8,
+    bitmap: ewah::Vec,
+}
+
+/// A representation of a `.bitmap` file, providing the reachability bitmaps of selected commits along with bitmaps
+/// indicating the kind of each object in the pack.
+pub struct File {
+    path: PathBuf,
+    version: Version,
+    pack_checksum: git_hash::ObjectId,
+    commits: ewah::Vec,
+    trees: ewah::Vec,
+    blobs: ewah::Vec,
+    tags: ewah::Vec,
+    entries: Vec<Entry>,
+    entry_by_index: HashMap<u32, usize>,
+    name_hashes: Option<Vec<u32>>,
+}
+
+const SIGNATURE: &[u8] = b"BITM";
+
+mod bits;
+pub use bits::Bits;
+
+mod pack_order;
+pub(crate) use pack_order::PackOrder;
+
+pub use git_bitmap::ewah;
+
+///
+pub mod init;
+
+mod access;
+
+///
+pub mod write;
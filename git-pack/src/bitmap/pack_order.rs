@@ -0,0 +1,37 @@
+use git_features::progress::{self, Progress};
+
+/// The objects of a pack sorted by their offset, which is the order in which objects are referred to by bitmaps.
+pub(crate) struct PackOrder {
+    pub index_by_position: Vec<u32>,
+    pub position_by_index: Vec<u32>,
+    pub offsets: Vec<u64>,
+    pack_end: u64,
+}
+
+impl PackOrder {
+    pub fn new(bundle: &crate::Bundle, mut progress: impl Progress) -> Self {
+        let num_objects = bundle.index.num_objects();
+        progress.init(Some(num_objects as usize), progress::count("entries"));
+        let mut index_and_offset: Vec<_> = (0..num_objects)
+            .map(|index| (index, bundle.index.pack_offset_at_index(index)))
+            .collect();
+        index_and_offset.sort_by_key(|(_, offset)| *offset);
+        progress.inc_by(num_objects as usize);
+
+        let mut position_by_index = vec![0; num_objects as usize];
+        for (position, (index, _)) in index_and_offset.iter().enumerate() {
+            position_by_index[*index as usize] = position as u32;
+        }
+        PackOrder {
+            index_by_position: index_and_offset.iter().map(|(index, _)| *index).collect(),
+            offsets: index_and_offset.into_iter().map(|(_, offset)| offset).collect(),
+            position_by_index,
+            pack_end: bundle.pack.pack_end() as u64,
+        }
+    }
+
+    pub fn entry_size(&self, position: usize) -> usize {
+        let end = self.offsets.get(position + 1).copied().unwrap_or(self.pack_end);
+        (end - self.offsets[position]) as usize
+    }
+}
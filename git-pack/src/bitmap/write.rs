@@ -0,0 +1,439 @@
+use crate::{
+    bitmap::{
+        ewah,
+        init::{FLAG_FULL_DAG, FLAG_HASH_CACHE},
+        Bits, File, PackOrder, Version, SIGNATURE,
+    },
+    data::output::count::name_hash,
+    index,
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::{
+    hash,
+    progress::{self, Progress},
+};
+use git_hash::ObjectId;
+use git_object::immutable;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Returned by [`File::write_from_bundle()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Traverse(#[from] index::traverse::Error<immutable::object::decode::Error>),
+    #[error(
+        "Object {id} is reachable from {referenced_by} but isn't contained in the pack, which is required for bitmaps"
+    )]
+    MissingObject { id: ObjectId, referenced_by: ObjectId },
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+/// Configuration for [`File::write_from_bundle()`].
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// The amount of threads to use at most when decoding the objects of the pack. If `None`, all logical cores are used.
+    pub thread_limit: Option<usize>,
+    /// If true, store the hash of the name under which each object was found in a tree, which helps to find good
+    /// delta bases when creating packs from the objects selected by bitmaps.
+    pub name_hash_cache: bool,
+}
+
+impl Default for Options {
+    /// Options which use all cores and write the name-hash cache, like git does by default.
+    fn default() -> Self {
+        Options {
+            thread_limit: None,
+            name_hash_cache: true,
+        }
+    }
+}
+
+/// Returned by [`File::write_from_bundle()`].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The checksum of the written bitmap file, also stored in its trailer.
+    pub bitmap_checksum: ObjectId,
+    /// The amount of commits which received a reachability bitmap.
+    pub num_commits: u32,
+}
+
+/// An object of the pack along with the objects it refers to.
+struct Node {
+    id: ObjectId,
+    pack_offset: u64,
+    kind: git_object::Kind,
+    /// The committer time, if this is a commit.
+    commit_time: u32,
+    num_parents: usize,
+    /// The ids of referred-to objects along with the hash of the name under which they are stored in a tree.
+    edges: Vec<(ObjectId, u32)>,
+}
+
+impl Node {
+    fn from_object(
+        kind: git_object::Kind,
+        data: &[u8],
+        entry: &index::Entry,
+    ) -> Result<Self, immutable::object::decode::Error> {
+        let mut node = Node {
+            id: entry.oid,
+            pack_offset: entry.pack_offset,
+            kind,
+            commit_time: 0,
+            num_parents: 0,
+            edges: Vec::new(),
+        };
+        match kind {
+            git_object::Kind::Commit => {
+                for token in immutable::CommitIter::from_bytes(data) {
+                    use immutable::commit::iter::Token;
+                    match token? {
+                        Token::Tree { id } => node.edges.push((id, 0)),
+                        Token::Parent { id } => {
+                            node.edges.push((id, 0));
+                            node.num_parents += 1;
+                        }
+                        Token::Committer { signature } => {
+                            node.commit_time = signature.time.time;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            git_object::Kind::Tree => {
+                for entry in immutable::TreeIter::from_bytes(data) {
+                    let entry = entry?;
+                    // submodules refer to commits in another repository
+                    if entry.mode != git_object::tree::EntryMode::Commit {
+                        node.edges.push((entry.oid.to_owned(), name_hash(entry.filename)));
+                    }
+                }
+            }
+            git_object::Kind::Tag => {
+                if let Some(token) = immutable::TagIter::from_bytes(data).next() {
+                    if let immutable::tag::iter::Token::Target { id } = token? {
+                        node.edges.push((id, 0));
+                    }
+                }
+            }
+            git_object::Kind::Blob => {}
+        }
+        Ok(node)
+    }
+}
+
+/// The objects of the pack in pack order, with all references between them.
+struct Graph {
+    kinds: Vec<git_object::Kind>,
+    edges: Vec<u32>,
+    /// The range of each object's edges, with the edges of object `n` at `edges[edge_start[n]..edge_start[n + 1]]`
+    edge_start: Vec<usize>,
+    name_hashes: Vec<u32>,
+    commits: Vec<Commit>,
+}
+
+impl Graph {
+    fn edges(&self, position: usize) -> &[u32] {
+        &self.edges[self.edge_start[position]..self.edge_start[position + 1]]
+    }
+}
+
+/// A commit eligible for receiving a bitmap.
+struct Commit {
+    position: u32,
+    time: u32,
+    is_merge: bool,
+}
+
+const MAX_XOR_OFFSET_SEARCH: usize = 10;
+
+/// Writing
+impl File {
+    /// Write a bitmap file for the pack in `bundle` into `out`, which typically is a file next to the pack with the
+    /// `.bitmap` extension.
+    ///
+    /// The pack must contain all objects reachable from the commits it contains, as it's the case for packs created
+    /// by cloning or by `git repack -a`.
+    /// Commits receive a bitmap following git's heuristics, favoring recent commits and merges, and each bitmap is stored
+    /// as difference to one of the previous bitmaps if that saves space.
+    pub fn write_from_bundle(
+        bundle: &crate::Bundle,
+        out: impl io::Write,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+        Options {
+            thread_limit,
+            name_hash_cache,
+        }: Options,
+    ) -> Result<Outcome, Error> {
+        let order = PackOrder::new(bundle, progress.add_child("sort by offset"));
+        let graph = build_graph(bundle, &order, thread_limit, &mut progress, should_interrupt)?;
+
+        let selected = {
+            let mut commits: Vec<_> = graph.commits.iter().collect();
+            // newest first, like git
+            commits.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.position.cmp(&b.position)));
+            select_commits(&commits)
+        };
+
+        let bitmaps = {
+            let mut progress = progress.add_child("compute bitmaps");
+            progress.init(Some(selected.len()), progress::count("commits"));
+            let mut bitmaps = HashMap::<u32, ewah::Vec>::with_capacity(selected.len());
+            for &position in selected.iter().rev() {
+                let bits = reachable_from(&graph, position, &bitmaps);
+                bitmaps.insert(position, ewah::Vec::from_words(bits.as_words()));
+                progress.inc();
+                if should_interrupt.load(Ordering::Relaxed) {
+                    return Err(Error::Interrupted);
+                }
+            }
+            selected
+                .iter()
+                .map(|position| bitmaps.remove(position).expect("computed for each selected commit"))
+                .collect::<Vec<_>>()
+        };
+        let entries = xor_offsets(&bitmaps, progress.add_child("compress bitmaps"), should_interrupt)?;
+
+        let (mut commits, mut trees, mut blobs, mut tags) =
+            (Bits::default(), Bits::default(), Bits::default(), Bits::default());
+        for (position, kind) in graph.kinds.iter().enumerate() {
+            use git_object::Kind::*;
+            match kind {
+                Commit => &mut commits,
+                Tree => &mut trees,
+                Blob => &mut blobs,
+                Tag => &mut tags,
+            }
+            .set(position);
+        }
+
+        let start = std::time::Instant::now();
+        let mut out = hash::Write::new(io::BufWriter::with_capacity(8 * 4096, out), git_hash::Kind::Sha1);
+        out.write_all(SIGNATURE)?;
+        out.write_u16::<BigEndian>(Version::V1 as u16)?;
+        out.write_u16::<BigEndian>(FLAG_FULL_DAG | if name_hash_cache { FLAG_HASH_CACHE } else { 0 })?;
+        out.write_u32::<BigEndian>(selected.len() as u32)?;
+        out.write_all(bundle.index.pack_checksum().as_slice())?;
+
+        for bits in &[commits, trees, blobs, tags] {
+            ewah::Vec::from_words(bits.as_words()).write_to(&mut out)?;
+        }
+        for (position, (xor_offset, bitmap)) in selected.iter().zip(entries) {
+            out.write_u32::<BigEndian>(order.index_by_position[*position as usize])?;
+            out.write_u8(xor_offset)?;
+            out.write_u8(0 /* flags */)?;
+            bitmap.write_to(&mut out)?;
+        }
+        if name_hash_cache {
+            for position in order.position_by_index.iter() {
+                out.write_u32::<BigEndian>(graph.name_hashes[*position as usize])?;
+            }
+        }
+
+        let hash::Write { hash, inner } = out;
+        let checksum = ObjectId::from(hash.digest());
+        let mut out = inner.into_inner().map_err(|err| err.into_error())?;
+        out.write_all(checksum.as_slice())?;
+        out.flush()?;
+        progress.show_throughput(start);
+
+        Ok(Outcome {
+            bitmap_checksum: checksum,
+            num_commits: selected.len() as u32,
+        })
+    }
+}
+
+fn build_graph(
+    bundle: &crate::Bundle,
+    order: &PackOrder,
+    thread_limit: Option<usize>,
+    progress: &mut impl Progress,
+    should_interrupt: &AtomicBool,
+) -> Result<Graph, Error> {
+    let nodes = parking_lot::Mutex::new(Vec::with_capacity(bundle.index.num_objects() as usize));
+    let interrupt_traversal = Arc::new(AtomicBool::new(false));
+    bundle
+        .index
+        .traverse(
+            &bundle.pack,
+            Some(progress.add_child("decode objects")),
+            || {
+                |kind, data: &[u8], entry: &index::Entry, _progress: &mut _| {
+                    if should_interrupt.load(Ordering::Relaxed) {
+                        interrupt_traversal.store(true, Ordering::SeqCst);
+                    }
+                    let node = Node::from_object(kind, data, entry)?;
+                    nodes.lock().push(node);
+                    Ok(())
+                }
+            },
+            || crate::cache::Never,
+            index::traverse::Options {
+                algorithm: index::traverse::Algorithm::DeltaTreeLookup,
+                thread_limit,
+                check: index::traverse::SafetyCheck::SkipFileAndObjectChecksumVerification,
+                should_interrupt: Arc::clone(&interrupt_traversal),
+            },
+        )
+        .map_err(|err| match err {
+            index::traverse::Error::Interrupted => Error::Interrupted,
+            err => err.into(),
+        })?;
+
+    let mut nodes = nodes.into_inner();
+    nodes.sort_by_key(|node| node.pack_offset);
+    let mut graph = Graph {
+        kinds: Vec::with_capacity(nodes.len()),
+        edges: Vec::new(),
+        edge_start: Vec::with_capacity(nodes.len() + 1),
+        name_hashes: vec![0; nodes.len()],
+        commits: Vec::new(),
+    };
+    let mut has_name = vec![false; nodes.len()];
+    for (position, node) in nodes.into_iter().enumerate() {
+        if node.kind == git_object::Kind::Commit {
+            graph.commits.push(Commit {
+                position: position as u32,
+                time: node.commit_time,
+                is_merge: node.num_parents > 1,
+            });
+        }
+        graph.kinds.push(node.kind);
+        graph.edge_start.push(graph.edges.len());
+        for (id, name_hash) in node.edges {
+            let position = bundle
+                .index
+                .lookup(id)
+                .map(|index| order.position_by_index[index as usize])
+                .ok_or(Error::MissingObject {
+                    id,
+                    referenced_by: node.id,
+                })?;
+            if node.kind == git_object::Kind::Tree && !has_name[position as usize] {
+                has_name[position as usize] = true;
+                graph.name_hashes[position as usize] = name_hash;
+            }
+            graph.edges.push(position);
+        }
+    }
+    graph.edge_start.push(graph.edges.len());
+    Ok(graph)
+}
+
+const MIN_COMMITS: usize = 100;
+const MAX_COMMITS: usize = 5000;
+const MUST_REGION: usize = 100;
+const MIN_REGION: usize = 20000;
+
+/// The distance to the next commit to select after the one at `idx`, which grows the older the commits get.
+fn next_commit_index(idx: usize) -> usize {
+    if idx <= MUST_REGION {
+        return 0;
+    }
+    if idx <= MIN_REGION {
+        let offset = idx - MUST_REGION;
+        return offset.min(MIN_COMMITS);
+    }
+    let offset = idx - MIN_REGION;
+    let next = if offset < MAX_COMMITS { offset } else { MAX_COMMITS };
+    next.max(MIN_COMMITS)
+}
+
+/// Select the pack positions of `commits` sorted from newest to oldest which should receive a bitmap, like git does.
+///
+/// All recent commits are selected, with the distance between selected commits growing with their age. Merges are
+/// preferred as they tend to make traversals more expensive.
+fn select_commits(commits: &[&Commit]) -> Vec<u32> {
+    if commits.len() < MIN_COMMITS {
+        return commits.iter().map(|c| c.position).collect();
+    }
+    let mut out = Vec::new();
+    let mut idx = 0;
+    loop {
+        let next = next_commit_index(idx);
+        if idx + next >= commits.len() {
+            break;
+        }
+        let chosen = if next == 0 {
+            commits[idx]
+        } else {
+            commits[idx..=idx + next]
+                .iter()
+                .rev()
+                .find(|c| c.is_merge)
+                .copied()
+                .unwrap_or(commits[idx + next])
+        };
+        out.push(chosen.position);
+        idx += next + 1;
+    }
+    out
+}
+
+/// Compute all objects reachable from the object at `position`, reusing the `bitmaps` of commits seen on the way.
+fn reachable_from(graph: &Graph, position: u32, bitmaps: &HashMap<u32, ewah::Vec>) -> Bits {
+    let mut out = Bits::default();
+    let mut stack = vec![position];
+    while let Some(position) = stack.pop() {
+        if out.is_set(position as usize) {
+            continue;
+        }
+        if let Some(bitmap) = bitmaps.get(&position) {
+            out.or(&Bits::from_words(bitmap.decompress()));
+            continue;
+        }
+        out.set(position as usize);
+        stack.extend(
+            graph
+                .edges(position as usize)
+                .iter()
+                .filter(|p| !out.is_set(**p as usize)),
+        );
+    }
+    out
+}
+
+/// For each bitmap, find the bitmap among the previous ones which yields the smallest result when XORed with it.
+///
+/// Returns the distance to the chosen bitmap, or 0 if none was chosen, along with the bitmap to store.
+fn xor_offsets(
+    bitmaps: &[ewah::Vec],
+    mut progress: impl Progress,
+    should_interrupt: &AtomicBool,
+) -> Result<Vec<(u8, ewah::Vec)>, Error> {
+    progress.init(Some(bitmaps.len()), progress::count("bitmaps"));
+    let decompressed: Vec<_> = bitmaps.iter().map(|b| Bits::from_words(b.decompress())).collect();
+    let mut out = Vec::with_capacity(bitmaps.len());
+    for (idx, bits) in decompressed.iter().enumerate() {
+        let mut best = (0u8, bitmaps[idx].clone());
+        for offset in 1..=MAX_XOR_OFFSET_SEARCH.min(idx) {
+            let mut xored = bits.clone();
+            xored.xor(&decompressed[idx - offset]);
+            let candidate = ewah::Vec::from_words(xored.as_words());
+            if candidate.num_words() < best.1.num_words() {
+                best = (offset as u8, candidate);
+            }
+        }
+        out.push(best);
+        progress.inc();
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+    }
+    Ok(out)
+}
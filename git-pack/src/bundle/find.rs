@@ -32,8 +32,31 @@ impl crate::Bundle {
         out: &'a mut Vec<u8>,
         cache: &mut impl crate::cache::DecodeEntry,
     ) -> Result<data::Object<'a>, crate::data::decode_entry::Error> {
-        let ofs = self.index.pack_offset_at_index(idx);
-        let pack_entry = self.pack.entry(ofs);
+        self.decode_object_at(self.index.pack_offset_at_index(idx), Some(idx), out, cache)
+    }
+
+    /// Get the object whose entry begins at `pack_offset` in our pack, as obtained from our index or a multi-pack index.
+    ///
+    /// # Panics
+    ///
+    /// If `pack_offset` doesn't point to the beginning of an entry in our pack.
+    pub fn get_object_at_offset<'a>(
+        &self,
+        pack_offset: u64,
+        out: &'a mut Vec<u8>,
+        cache: &mut impl crate::cache::DecodeEntry,
+    ) -> Result<data::Object<'a>, crate::data::decode_entry::Error> {
+        self.decode_object_at(pack_offset, None, out, cache)
+    }
+
+    fn decode_object_at<'a>(
+        &self,
+        pack_offset: u64,
+        index_file_id: Option<u32>,
+        out: &'a mut Vec<u8>,
+        cache: &mut impl crate::cache::DecodeEntry,
+    ) -> Result<data::Object<'a>, crate::data::decode_entry::Error> {
+        let pack_entry = self.pack.entry(pack_offset);
         let header_size = pack_entry.header_size();
         self.pack
             .decode_entry(
@@ -51,7 +74,8 @@ impl crate::Bundle {
                 data: out.as_slice(),
                 pack_location: Some(crate::bundle::Location {
                     pack_id: self.pack.id,
-                    index_file_id: idx,
+                    index_file_id,
+                    pack_offset,
                     entry_size: r.compressed_size + header_size,
                 }),
             })
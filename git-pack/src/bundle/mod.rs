@@ -58,16 +58,20 @@ pub enum Error {
 pub struct Location {
     /// The id of the pack containing the object
     pub pack_id: u32,
-    /// The index at which the object can be fonud in the index file
-    pub index_file_id: u32,
+    /// The index at which the object can be found in the index file, if known.
+    ///
+    /// It's unknown if the object was found using a multi-pack index.
+    pub index_file_id: Option<u32>,
+    /// The offset at which the object's entry begins in the pack
+    pub pack_offset: u64,
     /// The size of the entry of disk
     pub entry_size: usize,
 }
 
 impl Location {
     /// Compute a range suitable for lookup in pack data using the [`entry_slice()`][crate::data::File::entry_slice()] method.
-    pub fn entry_range(&self, pack_offset: u64) -> crate::data::EntryRange {
-        pack_offset..pack_offset + self.entry_size as u64
+    pub fn entry_range(&self) -> crate::data::EntryRange {
+        self.pack_offset..self.pack_offset + self.entry_size as u64
     }
 }
 
@@ -10,4 +10,8 @@ pub enum Error {
     PeristError(#[from] tempfile::PersistError),
     #[error(transparent)]
     IndexWrite(#[from] crate::index::write::Error),
+    #[error("Could not open the newly written pack to create its bitmap")]
+    BundleInit(#[from] crate::bundle::Error),
+    #[error(transparent)]
+    BitmapWrite(#[from] crate::bitmap::write::Error),
 }
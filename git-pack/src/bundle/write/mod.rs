@@ -22,11 +22,49 @@ impl crate::Bundle {
     ///
     /// `progress` provides detailed progress information which can be discarded with [`git_features::progress::Discard`].
     /// `options` further configure how the task is performed.
+    ///
+    /// Thin packs can't be written this way, use [`write_to_directory_completing_thin_pack()`][crate::Bundle::write_to_directory_completing_thin_pack()] instead.
     pub fn write_to_directory(
+        pack: impl io::BufRead,
+        directory: Option<impl AsRef<Path>>,
+        progress: impl Progress,
+        should_interrupt: &AtomicBool,
+        options: Options,
+    ) -> Result<Outcome, Error> {
+        Self::write_to_directory_inner(pack, directory, progress, should_interrupt, None, options)
+    }
+
+    /// Like [`write_to_directory()`][crate::Bundle::write_to_directory()], but uses `thin_pack_base_object_lookup` to complete
+    /// thin packs, typically the object database the pack is written into.
+    ///
+    /// The bases of deltas which aren't contained in the pack are looked up and appended to it as full objects after the pack
+    /// was received, along with a new object count and trailer, making it self-contained like `git index-pack --fix-thin` does.
+    pub fn write_to_directory_completing_thin_pack<Find>(
+        pack: impl io::BufRead,
+        directory: Option<impl AsRef<Path>>,
+        progress: impl Progress,
+        should_interrupt: &AtomicBool,
+        thin_pack_base_object_lookup: Find,
+        options: Options,
+    ) -> Result<Outcome, Error>
+    where
+        Find: crate::Find,
+        Find::Error: Send + Sync,
+    {
+        let find: &mut crate::index::write::FindFn<'_> = &mut |id, buf| {
+            thin_pack_base_object_lookup
+                .find(id, buf, &mut crate::cache::Never)
+                .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)
+        };
+        Self::write_to_directory_inner(pack, directory, progress, should_interrupt, Some(find), options)
+    }
+
+    fn write_to_directory_inner(
         pack: impl io::BufRead,
         directory: Option<impl AsRef<Path>>,
         mut progress: impl Progress,
         should_interrupt: &AtomicBool,
+        find_thin_pack_base: Option<&mut crate::index::write::FindFn<'_>>,
         options: Options,
     ) -> Result<Outcome, Error> {
         let mut read_progress = progress.add_child("read pack");
@@ -59,7 +97,7 @@ impl crate::Bundle {
             crate::data::input::EntryDataMode::Crc32,
         )?;
         let pack_kind = pack_entries_iter.kind();
-        let (outcome, data_path, index_path) = crate::Bundle::inner_write(
+        let (outcome, data_path, index_path, bitmap_path) = crate::Bundle::inner_write(
             directory,
             progress,
             options,
@@ -67,6 +105,7 @@ impl crate::Bundle {
             data_path,
             pack_entries_iter,
             should_interrupt,
+            find_thin_pack_base,
         )?;
 
         Ok(Outcome {
@@ -74,6 +113,7 @@ impl crate::Bundle {
             pack_kind,
             data_path,
             index_path,
+            bitmap_path,
         })
     }
 
@@ -123,7 +163,7 @@ impl crate::Bundle {
         let pack_entries_iter =
             git_features::parallel::EagerIterIf::new(move || num_objects > 25_000, pack_entries_iter, 5_000, 5);
 
-        let (outcome, data_path, index_path) = crate::Bundle::inner_write(
+        let (outcome, data_path, index_path, bitmap_path) = crate::Bundle::inner_write(
             directory,
             progress,
             options,
@@ -131,6 +171,7 @@ impl crate::Bundle {
             data_path,
             pack_entries_iter,
             should_interrupt,
+            None,
         )?;
 
         Ok(Outcome {
@@ -138,9 +179,11 @@ impl crate::Bundle {
             pack_kind,
             data_path,
             index_path,
+            bitmap_path,
         })
     }
 
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
     fn inner_write(
         directory: Option<impl AsRef<Path>>,
         mut progress: impl Progress,
@@ -148,19 +191,36 @@ impl crate::Bundle {
             thread_limit,
             iteration_mode: _,
             index_kind,
+            bitmap,
         }: Options,
         data_file: Arc<parking_lot::Mutex<NamedTempFile>>,
         data_path: PathBuf,
         pack_entries_iter: impl Iterator<Item = Result<crate::data::input::Entry, crate::data::input::Error>>,
         should_interrupt: &AtomicBool,
-    ) -> Result<(crate::index::write::Outcome, Option<PathBuf>, Option<PathBuf>), Error> {
+        find_thin_pack_base: Option<&mut crate::index::write::FindFn<'_>>,
+    ) -> Result<
+        (
+            crate::index::write::Outcome,
+            Option<PathBuf>,
+            Option<PathBuf>,
+            Option<PathBuf>,
+        ),
+        Error,
+    > {
         let indexing_progress = progress.add_child("create index file");
+        let thin_pack = match find_thin_pack_base {
+            Some(find) => Some(crate::index::write::ThinPackCompletion {
+                find,
+                pack: data_file.lock().reopen()?,
+            }),
+            None => None,
+        };
         Ok(match directory {
             Some(directory) => {
                 let directory = directory.as_ref();
                 let mut index_file = NamedTempFile::new_in(directory)?;
 
-                let outcome = crate::index::File::write_data_iter_to_stream(
+                let outcome = crate::index::File::write_data_iter_to_stream_completing_thin_pack(
                     index_kind,
                     move || new_pack_file_resolver(data_path),
                     pack_entries_iter,
@@ -168,6 +228,7 @@ impl crate::Bundle {
                     indexing_progress,
                     &mut index_file,
                     should_interrupt,
+                    thin_pack,
                 )?;
 
                 let data_path = directory.join(format!("{}.pack", outcome.data_hash.to_sha1_hex_string()));
@@ -186,10 +247,36 @@ impl crate::Bundle {
                         ));
                         err
                     })?;
-                (outcome, Some(data_path), Some(index_path))
+
+                let bitmap_path = match bitmap {
+                    Some(options) => {
+                        let bitmap_path = data_path.with_extension("bitmap");
+                        match write_bitmap(
+                            directory,
+                            &index_path,
+                            &bitmap_path,
+                            progress.add_child("create bitmap file"),
+                            should_interrupt,
+                            options,
+                        ) {
+                            Ok(()) => Some(bitmap_path),
+                            Err(err @ Error::BitmapWrite(crate::bitmap::write::Error::Interrupted)) => return Err(err),
+                            Err(err) => {
+                                progress.info(format!(
+                                    "pack at {} is usable without its bitmap, which couldn't be written: {}",
+                                    data_path.display(),
+                                    err
+                                ));
+                                None
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                (outcome, Some(data_path), Some(index_path), bitmap_path)
             }
             None => (
-                crate::index::File::write_data_iter_to_stream(
+                crate::index::File::write_data_iter_to_stream_completing_thin_pack(
                     index_kind,
                     move || new_pack_file_resolver(data_path),
                     pack_entries_iter,
@@ -197,14 +284,32 @@ impl crate::Bundle {
                     indexing_progress,
                     io::sink(),
                     should_interrupt,
+                    thin_pack,
                 )?,
                 None,
                 None,
+                None,
             ),
         })
     }
 }
 
+/// Write the reachability bitmap for the persisted pack bundle at `index_path` to `bitmap_path`.
+fn write_bitmap(
+    directory: &Path,
+    index_path: &Path,
+    bitmap_path: &Path,
+    progress: impl Progress,
+    should_interrupt: &AtomicBool,
+    options: crate::bitmap::write::Options,
+) -> Result<(), Error> {
+    let bundle = crate::Bundle::at(index_path)?;
+    let mut bitmap_file = NamedTempFile::new_in(directory)?;
+    crate::bitmap::File::write_from_bundle(&bundle, &mut bitmap_file, progress, should_interrupt, options)?;
+    bitmap_file.persist(bitmap_path)?;
+    Ok(())
+}
+
 fn new_pack_file_resolver(
     data_path: PathBuf,
 ) -> io::Result<impl Fn(crate::data::EntryRange, &mut Vec<u8>) -> Option<()> + Send + Sync> {
@@ -11,6 +11,12 @@ pub struct Options {
     pub iteration_mode: crate::data::input::Mode,
     /// The version of pack index to write, should be [`crate::index::Version::default()`]
     pub index_kind: crate::index::Version,
+    /// If `Some`, write a reachability bitmap for the pack as well, configured by the given options.
+    ///
+    /// It's only written if a directory is provided, and requires the pack to contain all objects reachable from its commits.
+    /// Failing to write it isn't fatal as the pack is usable without it, which is reported through `progress` instead.
+    /// Being interrupted while writing it aborts the operation though, leaving the pack and its index in place.
+    pub bitmap: Option<crate::bitmap::write::Options>,
 }
 
 impl Default for Options {
@@ -20,6 +26,7 @@ impl Default for Options {
             thread_limit: None,
             iteration_mode: crate::data::input::Mode::Verify,
             index_kind: Default::default(),
+            bitmap: None,
         }
     }
 }
@@ -38,6 +45,8 @@ pub struct Outcome {
     pub index_path: Option<PathBuf>,
     /// The path to the pack data file
     pub data_path: Option<PathBuf>,
+    /// The path to the reachability bitmap file, if one was requested and could be written
+    pub bitmap_path: Option<PathBuf>,
 }
 
 impl Outcome {
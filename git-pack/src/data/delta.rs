@@ -68,3 +68,217 @@ pub fn apply(base: &[u8], mut target: &mut [u8], data: &[u8]) {
     assert_eq!(i, data.len());
     assert_eq!(target.len(), 0);
 }
+
+/// The size of the blocks of the base object which are indexed to find copies of them in the target object.
+const BLOCK_SIZE: usize = 16;
+/// The maximum amount of base offsets to remember for blocks with the same hash, which keeps repetitive data cheap.
+const MAX_OFFSETS_PER_HASH: usize = 64;
+/// The maximum amount of bytes a single copy instruction can refer to.
+const MAX_COPY_SIZE: usize = 0x10000;
+/// The maximum amount of bytes a single insert instruction can hold.
+const MAX_INSERT_SIZE: usize = 0x7f;
+/// The factor used for hashing blocks in a way that allows rolling them byte by byte.
+const HASH_FACTOR: u32 = 0x0100_0193;
+
+/// Create a delta which turns `base` into `target` when [applied][apply()], or return `None` if it would be larger than
+/// `max_size` bytes.
+///
+/// Like git, all non-overlapping blocks of `base` are indexed by their hash, and a rolling hash over `target` is used
+/// to find copies of them. Matches are then extended in both directions, while everything else is inserted literally.
+pub fn encode(base: &[u8], target: &[u8], max_size: usize) -> Option<Vec<u8>> {
+    if base.len() > u32::MAX as usize {
+        return None;
+    }
+    let mut out = Vec::with_capacity(max_size.min(target.len() + 32));
+    encode_header_size(&mut out, base.len());
+    encode_header_size(&mut out, target.len());
+
+    let index = BlockIndex::new(base);
+    let outgoing_factor = HASH_FACTOR.wrapping_pow(BLOCK_SIZE as u32 - 1);
+    let mut insert_start = 0;
+    let mut pos = 0;
+    let mut rolling_hash = None;
+    while pos + BLOCK_SIZE <= target.len() {
+        let hash = rolling_hash.unwrap_or_else(|| block_hash(&target[pos..][..BLOCK_SIZE]));
+        let best_match = index
+            .offsets_of(hash)
+            .map(|base_ofs| (base_ofs, common_prefix_len(&base[base_ofs..], &target[pos..])))
+            .filter(|(_, len)| *len >= BLOCK_SIZE)
+            .max_by_key(|(_, len)| *len);
+        match best_match {
+            Some((mut base_ofs, len)) => {
+                let mut start = pos;
+                while start > insert_start && base_ofs > 0 && base[base_ofs - 1] == target[start - 1] {
+                    start -= 1;
+                    base_ofs -= 1;
+                }
+                push_inserts(&mut out, &target[insert_start..start]);
+                push_copies(&mut out, base_ofs, pos + len - start);
+                pos += len;
+                insert_start = pos;
+                rolling_hash = None;
+            }
+            None => {
+                rolling_hash = target.get(pos + BLOCK_SIZE).map(|incoming| {
+                    hash.wrapping_sub((target[pos] as u32).wrapping_mul(outgoing_factor))
+                        .wrapping_mul(HASH_FACTOR)
+                        .wrapping_add(*incoming as u32)
+                });
+                pos += 1;
+            }
+        }
+        if out.len() > max_size {
+            return None;
+        }
+    }
+    push_inserts(&mut out, &target[insert_start..]);
+    if out.len() > max_size {
+        None
+    } else {
+        Some(out)
+    }
+}
+
+/// The hashes of all non-overlapping blocks of a base object along with their offsets, sorted by hash.
+struct BlockIndex(Vec<(u32, usize)>);
+
+impl BlockIndex {
+    fn new(base: &[u8]) -> Self {
+        let mut blocks: Vec<_> = base
+            .chunks_exact(BLOCK_SIZE)
+            .enumerate()
+            .map(|(idx, block)| (block_hash(block), idx * BLOCK_SIZE))
+            .collect();
+        blocks.sort_unstable();
+        let mut offsets_with_same_hash = 0;
+        let mut previous_hash = None;
+        blocks.retain(|(hash, _)| {
+            if previous_hash == Some(*hash) {
+                offsets_with_same_hash += 1;
+            } else {
+                previous_hash = Some(*hash);
+                offsets_with_same_hash = 1;
+            }
+            offsets_with_same_hash <= MAX_OFFSETS_PER_HASH
+        });
+        BlockIndex(blocks)
+    }
+
+    fn offsets_of(&self, hash: u32) -> impl Iterator<Item = usize> + '_ {
+        let start = self.0.partition_point(|(h, _)| *h < hash);
+        self.0[start..]
+            .iter()
+            .take_while(move |(h, _)| *h == hash)
+            .map(|(_, ofs)| *ofs)
+    }
+}
+
+fn block_hash(block: &[u8]) -> u32 {
+    block
+        .iter()
+        .fold(0u32, |hash, b| hash.wrapping_mul(HASH_FACTOR).wrapping_add(*b as u32))
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn encode_header_size(out: &mut Vec<u8>, mut size: usize) {
+    while size >= 0x80 {
+        out.push(0b1000_0000 | (size as u8 & 0b0111_1111));
+        size >>= 7;
+    }
+    out.push(size as u8);
+}
+
+fn push_inserts(out: &mut Vec<u8>, data: &[u8]) {
+    for chunk in data.chunks(MAX_INSERT_SIZE) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+fn push_copies(out: &mut Vec<u8>, mut base_ofs: usize, mut len: usize) {
+    while len != 0 {
+        let size = len.min(MAX_COPY_SIZE);
+        let cmd_pos = out.len();
+        let mut cmd = 0b1000_0000;
+        out.push(cmd);
+        for (bit, byte) in (base_ofs as u32).to_le_bytes().iter().enumerate() {
+            if *byte != 0 {
+                cmd |= 1 << bit;
+                out.push(*byte);
+            }
+        }
+        // A size of zero is interpreted as the maximum size.
+        if size != MAX_COPY_SIZE {
+            for (bit, byte) in (size as u32).to_le_bytes()[..3].iter().enumerate() {
+                if *byte != 0 {
+                    cmd |= 0b0001_0000 << bit;
+                    out.push(*byte);
+                }
+            }
+        }
+        out[cmd_pos] = cmd;
+        base_ofs += size;
+        len -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(base: &[u8], target: &[u8]) -> Vec<u8> {
+        let delta = encode(base, target, usize::MAX).expect("no size limit");
+        let (base_size, consumed) = decode_header_size(&delta);
+        assert_eq!(base_size as usize, base.len());
+        let (target_size, consumed_target) = decode_header_size(&delta[consumed..]);
+        assert_eq!(target_size as usize, target.len());
+        let mut out = vec![0; target.len()];
+        apply(base, &mut out, &delta[consumed + consumed_target..]);
+        assert_eq!(out, target, "applying the delta reproduces the target");
+        delta
+    }
+
+    fn pseudo_random_bytes(len: usize, mut seed: u32) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn similar_objects_produce_small_deltas() {
+        let base = pseudo_random_bytes(200_000, 1);
+        let mut target = base.clone();
+        target[50_000..50_100].copy_from_slice(&pseudo_random_bytes(100, 2));
+        target.drain(100_000..100_050);
+        target.splice(150_000..150_000, b"inserted text".iter().copied());
+        let delta = round_trip(&base, &target);
+        assert!(
+            delta.len() < 200,
+            "only the changes are stored, got {} bytes",
+            delta.len()
+        );
+    }
+
+    #[test]
+    fn unrelated_and_tiny_objects_round_trip() {
+        round_trip(&pseudo_random_bytes(1000, 1), &pseudo_random_bytes(1000, 2));
+        round_trip(b"", b"target smaller than a block");
+        round_trip(b"base smaller than a block", b"");
+        round_trip(b"", b"");
+        let repetitive = vec![b'a'; 100_000];
+        assert!(round_trip(&repetitive, &repetitive[1..]).len() < 32);
+    }
+
+    #[test]
+    fn deltas_larger_than_the_maximum_size_are_not_produced() {
+        let base = pseudo_random_bytes(1000, 1);
+        assert!(encode(&base, &pseudo_random_bytes(1000, 2), 500).is_none());
+        assert!(encode(&base, &base, 20).is_some());
+    }
+}
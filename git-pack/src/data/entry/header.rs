@@ -77,6 +77,13 @@ impl Header {
 }
 
 impl Header {
+    /// Return the amount of bytes this header occupies in a pack along with `decompressed_size_in_bytes`, the full size
+    /// of the object it represents.
+    pub fn size(&self, decompressed_size_in_bytes: u64) -> usize {
+        self.write_to(decompressed_size_in_bytes, io::sink())
+            .expect("io::sink() to never fail")
+    }
+
     /// Encode this header along the given `decompressed_size_in_bytes` into the `out` write stream for use within a data pack.
     ///
     /// Returns the amount of bytes written to `out`.
@@ -31,3 +31,5 @@ pub use types::{EntryDataMode, Error, Mode};
 
 mod iter;
 pub use iter::BytesToEntriesIter;
+
+mod entry;
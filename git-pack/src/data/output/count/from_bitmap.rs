@@ -0,0 +1,214 @@
+use crate::{bitmap, data::output, find, FindExt};
+use git_features::progress::{self, Progress};
+use git_hash::{oid, ObjectId};
+use git_object::immutable;
+use std::collections::HashSet;
+
+pub use super::from_objects_iter::Outcome;
+
+/// Generate [`Count`][output::Count]s for all objects reachable from `tips` which aren't reachable from `haves`, using the
+/// reachability `bitmap` of `bundle` to avoid traversing commits and trees.
+///
+/// The resulting objects are the same as the ones obtained by feeding all commits reachable from `tips` into
+/// [`from_objects_iter()`][super::from_objects_iter()] with [`TreeContents`][super::ObjectExpansion::TreeContents] expansion.
+/// Only commits without a bitmap and objects outside of `bundle` are traversed, which typically are only a few recent commits.
+///
+/// * `db` - the object store to use for accessing objects which aren't covered by a bitmap.
+/// * `bundle` - the pack the `bitmap` was created for.
+/// * `bitmap` - the reachability bitmap of the pack in `bundle`.
+/// * `tips` - the ids of objects to start the traversal from, typically commits or tags.
+/// * `haves`
+///   * The ids of commits the receiver of the pack is known to have, or empty otherwise.
+///   * Objects reachable from these aren't counted. Ids of objects that aren't in `db` are ignored.
+/// * `cache` - a pack cache to accelerate object lookups.
+/// * `progress` - a way to obtain progress information.
+#[allow(clippy::type_complexity)]
+pub fn from_bitmap<Find, Cache>(
+    db: &Find,
+    bundle: &crate::Bundle,
+    bitmap: &bitmap::File,
+    tips: impl IntoIterator<Item = impl AsRef<oid>>,
+    haves: impl IntoIterator<Item = impl AsRef<oid>>,
+    cache: &mut Cache,
+    mut progress: impl Progress,
+) -> Result<(Vec<output::Count>, Outcome), Error<find::existing::Error<Find::Error>>>
+where
+    Find: crate::Find,
+    Cache: crate::cache::DecodeEntry,
+{
+    let actual = bundle.index.pack_checksum();
+    if bitmap.pack_checksum() != actual {
+        return Err(Error::PackMismatch {
+            expected: bitmap.pack_checksum(),
+            actual,
+        });
+    }
+
+    let order = bitmap::PackOrder::new(bundle, progress.add_child("sort by offset"));
+    let mut stats = Outcome::default();
+    let mut buf = Vec::new();
+    let mut walk = Walk {
+        db,
+        bundle,
+        bitmap,
+        order: &order,
+        cache,
+        buf: &mut buf,
+        stats: &mut stats,
+    };
+
+    let mut existing_haves = Vec::new();
+    for id in haves {
+        let id = id.as_ref();
+        if walk
+            .db
+            .find(id, walk.buf, walk.cache)
+            .map_err(find::existing::Error::Find)?
+            .is_some()
+        {
+            existing_haves.push(id.to_owned());
+        }
+    }
+    let haves = walk.reachable(existing_haves)?;
+    let tips: Vec<_> = tips.into_iter().map(|id| id.as_ref().to_owned()).collect();
+    walk.stats.input_objects = tips.len();
+    let mut wants = walk.reachable(tips)?;
+    wants.bits.and_not(&haves.bits);
+
+    progress.init(
+        Some(wants.bits.count_ones() + wants.extra.len()),
+        progress::count("objects"),
+    );
+    let mut out = Vec::with_capacity(wants.bits.count_ones() + wants.extra.len());
+    for pack_position in wants.bits.iter_ones() {
+        let index = order.index_by_position[pack_position];
+        out.push(output::Count {
+            id: bundle.index.oid_at_index(index).to_owned(),
+            entry_pack_location: Some(crate::bundle::Location {
+                pack_id: bundle.pack.id,
+                index_file_id: Some(index),
+                pack_offset: bundle.index.pack_offset_at_index(index),
+                entry_size: order.entry_size(pack_position),
+            }),
+            name_hash: bitmap.name_hash_at_index(index).unwrap_or(0),
+            is_external_base: false,
+        });
+        progress.inc();
+    }
+    let mut extra: Vec<_> = wants.extra.difference(&haves.extra).copied().collect();
+    extra.sort();
+    for id in extra {
+        out.push(output::Count {
+            entry_pack_location: db.location_by_id(id, &mut buf),
+            id,
+            name_hash: 0,
+            is_external_base: false,
+        });
+        progress.inc();
+    }
+
+    stats.total_objects = out.len();
+    stats.expanded_objects = stats.total_objects.saturating_sub(stats.input_objects);
+    Ok((out, stats))
+}
+
+/// All objects reachable from a set of tips.
+#[derive(Default)]
+struct Reachable {
+    /// The pack positions of reachable objects in the pack.
+    bits: bitmap::Bits,
+    /// Reachable objects which aren't in the pack.
+    extra: HashSet<ObjectId>,
+}
+
+struct Walk<'a, Find, Cache> {
+    db: &'a Find,
+    bundle: &'a crate::Bundle,
+    bitmap: &'a bitmap::File,
+    order: &'a bitmap::PackOrder,
+    cache: &'a mut Cache,
+    buf: &'a mut Vec<u8>,
+    stats: &'a mut Outcome,
+}
+
+impl<'a, Find, Cache> Walk<'a, Find, Cache>
+where
+    Find: crate::Find,
+    Cache: crate::cache::DecodeEntry,
+{
+    /// Traverse all objects reachable from `tips`, but use the bitmaps of commits instead of traversing them if possible.
+    fn reachable(&mut self, tips: Vec<ObjectId>) -> Result<Reachable, Error<find::existing::Error<Find::Error>>> {
+        let mut out = Reachable::default();
+        let mut stack = tips;
+        while let Some(id) = stack.pop() {
+            match self.bundle.index.lookup(id) {
+                Some(index) => {
+                    let position = self.order.position_by_index[index as usize] as usize;
+                    if out.bits.is_set(position) {
+                        continue;
+                    }
+                    if let Some(bits) = self.bitmap.bitmap_at_index(index) {
+                        out.bits.or(&bits);
+                        continue;
+                    }
+                    out.bits.set(position);
+                }
+                None => {
+                    if !out.extra.insert(id) {
+                        continue;
+                    }
+                }
+            }
+
+            let obj = self.db.find_existing(id, self.buf, self.cache)?;
+            self.stats.decoded_objects += 1;
+            match obj.kind {
+                git_object::Kind::Commit => {
+                    for token in immutable::CommitIter::from_bytes(obj.data) {
+                        match token {
+                            Ok(immutable::commit::iter::Token::Tree { id })
+                            | Ok(immutable::commit::iter::Token::Parent { id }) => stack.push(id),
+                            Ok(_) => break,
+                            Err(err) => return Err(Error::Decode(err)),
+                        }
+                    }
+                }
+                git_object::Kind::Tree => {
+                    for entry in immutable::TreeIter::from_bytes(obj.data) {
+                        let entry = entry.map_err(Error::Decode)?;
+                        // submodules refer to commits in another repository
+                        if entry.mode != git_object::tree::EntryMode::Commit {
+                            stack.push(entry.oid.to_owned());
+                        }
+                    }
+                }
+                git_object::Kind::Tag => {
+                    if let Some(token) = immutable::TagIter::from_bytes(obj.data).next() {
+                        match token {
+                            Ok(immutable::tag::iter::Token::Target { id }) => stack.push(id),
+                            Ok(_) => {}
+                            Err(err) => return Err(Error::Decode(err)),
+                        }
+                    }
+                }
+                git_object::Kind::Blob => {}
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// The error returned by [`from_bitmap()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error<FindErr>
+where
+    FindErr: std::error::Error + 'static,
+{
+    #[error("The bitmap was created for pack {expected}, but was used with pack {actual}")]
+    PackMismatch { expected: ObjectId, actual: ObjectId },
+    #[error(transparent)]
+    Decode(git_object::immutable::object::decode::Error),
+    #[error(transparent)]
+    FindExisting(#[from] FindErr),
+}
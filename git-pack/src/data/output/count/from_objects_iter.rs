@@ -3,7 +3,7 @@ use dashmap::DashSet;
 use git_features::{parallel, progress::Progress};
 use git_hash::{oid, ObjectId};
 use git_object::immutable;
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 /// Generate [`Count`][output::Count] from input `objects` with object expansion based on [`options`][Options]
 /// to learn which objects would be part of a pack.
@@ -24,6 +24,33 @@ pub fn from_objects_iter<Find, Iter, Oid, Cache>(
     make_cache: impl Fn() -> Cache + Send + Clone + Sync + 'static,
     objects_ids: Iter,
     progress: impl Progress,
+    options: Options,
+) -> impl Iterator<Item = Result<Vec<output::Count>, Error<find::existing::Error<Find::Error>>>>
+       + parallel::reduce::Finalize<Reduce = reduce::Statistics<Error<find::existing::Error<Find::Error>>>>
+where
+    Find: crate::Find + Clone + Send + Sync + 'static,
+    <Find as crate::Find>::Error: Send,
+    Iter: Iterator<Item = Oid> + Send + 'static,
+    Oid: AsRef<oid> + Send + 'static,
+    Cache: crate::cache::DecodeEntry,
+{
+    from_objects_iter_with_haves(db, make_cache, objects_ids, HashSet::new(), progress, options)
+}
+
+/// Like [`from_objects_iter()`], but for producing a thin pack for a receiver which is known to have all `haves`.
+///
+/// * `haves`
+///   * The ids of commits the receiver of the pack is known to have.
+///   * Only used with [`ObjectExpansion::TreeAdditionsComparedToAncestor`]. Parents of input commits which are among the
+///     `haves` aren't added along with their tree. Instead, the objects their trees contain at the paths of changed objects
+///     are added as [external bases][output::Count::is_external_base], to allow deltas against objects that won't be
+///     part of the pack.
+pub fn from_objects_iter_with_haves<Find, Iter, Oid, Cache>(
+    db: Find,
+    make_cache: impl Fn() -> Cache + Send + Clone + Sync + 'static,
+    objects_ids: Iter,
+    haves: HashSet<ObjectId>,
+    progress: impl Progress,
     Options {
         thread_limit,
         input_object_expansion,
@@ -50,6 +77,7 @@ where
         size: chunk_size,
     };
     let seen_objs = Arc::new(dashmap::DashSet::<ObjectId>::new());
+    let haves = Arc::new(haves);
     let progress = Arc::new(parking_lot::Mutex::new(progress));
 
     parallel::reduce::Stepwise::new(
@@ -72,6 +100,7 @@ where
         },
         {
             let seen_objs = Arc::clone(&seen_objs);
+            let haves = Arc::clone(&haves);
             move |oids: Vec<Oid>, (buf1, buf2, cache, progress)| {
                 use ObjectExpansion::*;
                 let mut out = Vec::new();
@@ -142,18 +171,21 @@ where
                                             &traverse_delegate.objects
                                         } else {
                                             for commit_id in &parent_commit_ids {
+                                                let is_have = haves.contains(commit_id);
                                                 let parent_tree_id = {
                                                     let parent_commit_obj = db.find_existing(commit_id, buf2, cache)?;
 
-                                                    push_obj_count_unique(
-                                                        &mut out,
-                                                        seen_objs,
-                                                        &commit_id,
-                                                        &parent_commit_obj,
-                                                        progress,
-                                                        stats,
-                                                        true,
-                                                    );
+                                                    if !is_have {
+                                                        push_obj_count_unique(
+                                                            &mut out,
+                                                            seen_objs,
+                                                            commit_id,
+                                                            &parent_commit_obj,
+                                                            progress,
+                                                            stats,
+                                                            true,
+                                                        );
+                                                    }
                                                     immutable::CommitIter::from_bytes(parent_commit_obj.data)
                                                         .tree_id()
                                                         .expect("every commit has a tree")
@@ -161,19 +193,22 @@ where
                                                 let parent_tree = {
                                                     let parent_tree_obj =
                                                         db.find_existing(parent_tree_id, buf2, cache)?;
-                                                    push_obj_count_unique(
-                                                        &mut out,
-                                                        seen_objs,
-                                                        &parent_tree_id,
-                                                        &parent_tree_obj,
-                                                        progress,
-                                                        stats,
-                                                        true,
-                                                    );
+                                                    if !is_have {
+                                                        push_obj_count_unique(
+                                                            &mut out,
+                                                            seen_objs,
+                                                            &parent_tree_id,
+                                                            &parent_tree_obj,
+                                                            progress,
+                                                            stats,
+                                                            true,
+                                                        );
+                                                    }
                                                     immutable::TreeIter::from_bytes(parent_tree_obj.data)
                                                 };
 
                                                 changes_delegate.clear();
+                                                changes_delegate.record_external_bases = is_have;
                                                 git_diff::tree::Changes::from(Some(parent_tree))
                                                     .needed_to_obtain(
                                                         current_tree_iter.clone(),
@@ -186,10 +221,18 @@ where
                                                     )
                                                     .map_err(Error::TreeChanges)?;
                                             }
+                                            for (id, name_hash) in changes_delegate.external_bases.iter() {
+                                                out.push(output::Count {
+                                                    id: *id,
+                                                    entry_pack_location: db.location_by_id(id, buf2),
+                                                    name_hash: *name_hash,
+                                                    is_external_base: true,
+                                                });
+                                            }
                                             &changes_delegate.objects
                                         };
-                                        for id in objects.iter() {
-                                            out.push(id_to_count(&db, buf2, id, progress, stats));
+                                        for (id, name_hash) in objects.iter() {
+                                            out.push(id_to_count(&db, buf2, id, *name_hash, progress, stats));
                                         }
                                         break;
                                     }
@@ -215,8 +258,8 @@ where
                                             &mut traverse_delegate,
                                         )
                                         .map_err(Error::TreeTraverse)?;
-                                        for id in traverse_delegate.objects.iter() {
-                                            out.push(id_to_count(&db, buf1, id, progress, stats));
+                                        for (id, name_hash) in traverse_delegate.objects.iter() {
+                                            out.push(id_to_count(&db, buf1, id, *name_hash, progress, stats));
                                         }
                                         break;
                                     }
@@ -252,6 +295,7 @@ where
 
 mod tree {
     pub mod changes {
+        use crate::data::output::count::name_hash;
         use dashmap::DashSet;
         use git_diff::tree::{
             visit::{Action, Change},
@@ -261,37 +305,61 @@ mod tree {
         use git_object::bstr::BStr;
 
         pub struct AllNew<'a> {
-            pub objects: Vec<ObjectId>,
+            /// The ids of new objects along with the hash of their name.
+            pub objects: Vec<(ObjectId, u32)>,
+            /// The ids of previous versions of modified objects along with the hash of their name, if
+            /// `record_external_bases` is set.
+            pub external_bases: Vec<(ObjectId, u32)>,
+            /// If true, the objects of the previous tree are known to the receiver and can be used as delta bases.
+            pub record_external_bases: bool,
             all_seen: &'a DashSet<ObjectId>,
+            /// The name hash of the last path component, which is the name of the next visited entry.
+            name_hash: u32,
         }
 
         impl<'a> AllNew<'a> {
             pub fn new(all_seen: &'a DashSet<ObjectId>) -> Self {
                 AllNew {
                     objects: Default::default(),
+                    external_bases: Default::default(),
+                    record_external_bases: false,
                     all_seen,
+                    name_hash: 0,
                 }
             }
             pub fn clear(&mut self) {
                 self.objects.clear();
+                self.external_bases.clear();
             }
         }
 
         impl<'a> Visit for AllNew<'a> {
             fn pop_front_tracked_path_and_set_current(&mut self) {}
 
-            fn push_back_tracked_path_component(&mut self, _component: &BStr) {}
+            fn push_back_tracked_path_component(&mut self, component: &BStr) {
+                self.name_hash = name_hash(component);
+            }
 
-            fn push_path_component(&mut self, _component: &BStr) {}
+            fn push_path_component(&mut self, component: &BStr) {
+                self.name_hash = name_hash(component);
+            }
 
             fn pop_path_component(&mut self) {}
 
             fn visit(&mut self, change: Change) -> Action {
                 match change {
-                    Change::Addition { oid, .. } | Change::Modification { oid, .. } => {
+                    Change::Modification { previous_oid, oid, .. } => {
+                        if self.all_seen.insert(oid) {
+                            self.objects.push((oid, self.name_hash));
+                        }
+                        if self.record_external_bases && self.all_seen.insert(previous_oid) {
+                            self.external_bases.push((previous_oid, self.name_hash));
+                        }
+                    }
+                    Change::Addition { oid, .. } => {
                         let inserted = self.all_seen.insert(oid);
                         if inserted {
-                            self.objects.push(oid);
+                            self.objects.push((oid, self.name_hash));
                         }
                     }
                     Change::Deletion { .. } => {}
@@ -302,13 +370,15 @@ mod tree {
     }
 
     pub mod traverse {
+        use crate::data::output::count::name_hash;
         use dashmap::DashSet;
         use git_hash::ObjectId;
         use git_object::{bstr::BStr, immutable::tree::Entry};
         use git_traverse::tree::visit::{Action, Visit};
 
         pub struct AllUnseen<'a> {
-            pub objects: Vec<ObjectId>,
+            /// The ids of unseen objects along with the hash of their name.
+            pub objects: Vec<(ObjectId, u32)>,
             all_seen: &'a DashSet<ObjectId>,
         }
 
@@ -336,7 +406,7 @@ mod tree {
             fn visit_tree(&mut self, entry: &Entry<'_>) -> Action {
                 let inserted = self.all_seen.insert(entry.oid.to_owned());
                 if inserted {
-                    self.objects.push(entry.oid.to_owned());
+                    self.objects.push((entry.oid.to_owned(), name_hash(entry.filename)));
                     Action::Continue
                 } else {
                     Action::Skip
@@ -346,7 +416,7 @@ mod tree {
             fn visit_nontree(&mut self, entry: &Entry<'_>) -> Action {
                 let inserted = self.all_seen.insert(entry.oid.to_owned());
                 if inserted {
-                    self.objects.push(entry.oid.to_owned());
+                    self.objects.push((entry.oid.to_owned(), name_hash(entry.filename)));
                 }
                 Action::Continue
             }
@@ -378,6 +448,7 @@ fn id_to_count<Find: crate::Find>(
     db: &Find,
     buf: &mut Vec<u8>,
     id: &oid,
+    name_hash: u32,
     progress: &mut impl Progress,
     statistics: &mut Outcome,
 ) -> output::Count {
@@ -386,6 +457,8 @@ fn id_to_count<Find: crate::Find>(
     output::Count {
         id: id.to_owned(),
         entry_pack_location: db.location_by_id(id, buf),
+        name_hash,
+        is_external_base: false,
     }
 }
 
@@ -434,6 +507,7 @@ mod types {
         /// The amount of fully decoded objects. These are the most expensive as they are fully decoded
         pub decoded_objects: usize,
         /// The total amount of objects seed. Should be `expanded_objects + input_objects`.
+        /// External bases for thin packs aren't included.
         pub total_objects: usize,
     }
 
@@ -551,7 +625,7 @@ mod reduce {
 
         fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
             item.map(|(counts, mut stats)| {
-                stats.total_objects = counts.len();
+                stats.total_objects = counts.iter().filter(|count| !count.is_external_base).count();
                 self.total.aggregate(stats);
                 counts
             })
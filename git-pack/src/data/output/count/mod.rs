@@ -12,6 +12,14 @@ pub struct Count {
     pub id: ObjectId,
     /// A way to locate a pack entry in the object database, only available if the object is in a pack.
     pub entry_pack_location: Option<crate::bundle::Location>,
+    /// A hash of the name under which the object was found in a tree, or 0 if it was not found in a tree.
+    ///
+    /// It's used to place objects of similar name next to each other when searching for delta bases.
+    /// See [`name_hash()`] for how it is computed.
+    pub name_hash: u32,
+    /// If true, the object isn't to be written into the pack as the receiver is known to have it, but it can serve as base
+    /// for deltas of other objects, which makes the pack 'thin'.
+    pub is_external_base: bool,
 }
 
 impl Count {
@@ -20,10 +28,26 @@ impl Count {
         Count {
             id: oid.into(),
             entry_pack_location: obj.pack_location.clone(),
+            name_hash: 0,
+            is_external_base: false,
         }
     }
 }
 
+/// Compute a hash of the `name` of a tree entry like git does, which is most influenced by its last characters.
+///
+/// That way objects of files with the same name or extension end up with similar hashes, which makes them good candidates for
+/// delta compression once sorted by it.
+pub fn name_hash(name: &[u8]) -> u32 {
+    name.iter()
+        .filter(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r'))
+        .fold(0u32, |hash, b| (hash >> 2).wrapping_add((*b as u32) << 24))
+}
+
 ///
 pub mod from_objects_iter;
-pub use from_objects_iter::from_objects_iter;
+pub use from_objects_iter::{from_objects_iter, from_objects_iter_with_haves};
+
+///
+pub mod from_bitmap;
+pub use from_bitmap::from_bitmap;
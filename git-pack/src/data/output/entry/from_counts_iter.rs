@@ -1,4 +1,5 @@
 use crate::{
+    data,
     data::{output, output::ChunkId},
     find, FindExt,
 };
@@ -19,6 +20,16 @@ use std::sync::Arc;
 /// * `options`
 ///   * more configuration
 ///
+/// If [`Options::window_size`] isn't 0, objects are delta-compressed against each other. To that end, all objects of a chunk
+/// are decoded and sorted like git does, by kind, [name hash][output::Count::name_hash] and size. Each object is then compared
+/// to the `window_size` objects before it in that order, and written as delta against the one yielding the smallest delta, as
+/// long as delta chains don't get longer than [`Options::max_delta_depth`]. As chunks are processed in parallel, deltas are
+/// only found within a chunk. Entries keep the order of their counts, but bases are written before the deltas using them.
+///
+/// Counts marked as [external bases][output::Count::is_external_base] aren't written, but may serve as bases for
+/// [`DeltaOid`][output::entry::Kind::DeltaOid] entries, producing a thin pack. Note that the amount of entries written is the
+/// amount of counts which aren't external bases.
+///
 /// _Returns_ the checksum of the pack
 ///
 /// ## Discussion
@@ -30,7 +41,6 @@ use std::sync::Arc;
 ///
 /// ### Disadvantages
 ///
-/// * **does not yet support thin packs** as we don't have a way to determine which objects are supposed to be thin.
 /// * ~~currently there is no way to easily write the pack index, even though the state here is uniquely positioned to do
 ///   so with minimal overhead (especially compared to `gixp index-from-pack`)~~ Probably works now by chaining Iterators
 ///  or keeping enough state to write a pack and then generate an index with recorded data.
@@ -44,6 +54,8 @@ pub fn from_counts_iter<Find, Cache>(
         version,
         thread_limit,
         chunk_size,
+        window_size,
+        max_delta_depth,
     }: Options,
 ) -> impl Iterator<Item = Result<(ChunkId, Vec<output::Entry>), Error<find::existing::Error<Find::Error>>>>
        + parallel::reduce::Finalize<Reduce = reduce::Statistics<Error<find::existing::Error<Find::Error>>>>
@@ -83,28 +95,128 @@ where
                 let mut stats = Outcome::default();
                 progress.init(Some(chunk.len()), git_features::progress::count("objects"));
 
-                for count in chunk {
-                    out.push(
-                        match count.entry_pack_location.as_ref().and_then(|l| db.entry_by_location(l)) {
-                            Some(pack_entry) => match output::Entry::from_pack_entry(pack_entry, count, version) {
-                                Some(entry) => {
-                                    stats.objects_copied_from_pack += 1;
-                                    entry
-                                }
-                                None => {
-                                    let obj = db.find_existing(count.id, buf, cache).map_err(Error::FindExisting)?;
-                                    stats.decoded_and_recompressed_objects += 1;
-                                    output::Entry::from_data(count, &obj)
-                                }
-                            },
+                if window_size == 0 {
+                    for count in chunk.iter().filter(|count| !count.is_external_base) {
+                        out.push(match copy_from_pack(count, &db, version) {
+                            Some(entry) => {
+                                stats.objects_copied_from_pack += 1;
+                                entry?
+                            }
                             None => {
                                 let obj = db.find_existing(count.id, buf, cache).map_err(Error::FindExisting)?;
                                 stats.decoded_and_recompressed_objects += 1;
-                                output::Entry::from_data(count, &obj)
+                                output::Entry::from_data(count, &obj)?
+                            }
+                        });
+                        progress.inc();
+                    }
+                } else {
+                    let mut objects = Vec::with_capacity(chunk.len());
+                    for count in chunk {
+                        let obj = db.find_existing(count.id, buf, cache).map_err(Error::FindExisting)?;
+                        objects.push((count, obj.kind, obj.data.to_owned()));
+                    }
+                    // Like git, search for deltas among objects of the same kind and name, and put larger ones first as
+                    // these tend to be more recent, to make them the base of deltas which then mostly remove data. External
+                    // bases go first so all objects of the same name can use them.
+                    let mut search_order: Vec<_> = (0..objects.len()).collect();
+                    search_order.sort_by(|a, b| {
+                        let ((a, a_kind, a_data), (b, b_kind, b_data)) = (&objects[*a], &objects[*b]);
+                        (b_kind, b.name_hash, b.is_external_base, b_data.len()).cmp(&(
+                            a_kind,
+                            a.name_hash,
+                            a.is_external_base,
+                            a_data.len(),
+                        ))
+                    });
+
+                    let mut depths = vec![0; objects.len()];
+                    // The index of the object each object is a delta of, along with the delta.
+                    let mut deltas: Vec<Option<(usize, Vec<u8>)>> = vec![None; objects.len()];
+                    for (search_idx, &idx) in search_order.iter().enumerate() {
+                        let (count, kind, data) = &objects[idx];
+                        if count.is_external_base {
+                            continue;
+                        }
+                        let mut best: Option<(usize, Vec<u8>)> = None;
+                        for &base_idx in search_order[search_idx.saturating_sub(window_size)..search_idx]
+                            .iter()
+                            .rev()
+                        {
+                            let (_, base_kind, base_data) = &objects[base_idx];
+                            if base_kind != kind {
+                                break;
+                            }
+                            let base_depth = depths[base_idx];
+                            if base_depth >= max_delta_depth {
+                                continue;
+                            }
+                            let best_delta = best
+                                .as_ref()
+                                .map(|(best_idx, delta)| (delta.len(), depths[*best_idx] + 1));
+                            let max_size =
+                                max_delta_size(data.len(), base_data.len(), base_depth, max_delta_depth, best_delta);
+                            if max_size == 0 {
+                                continue;
+                            }
+                            if let Some(delta) = data::delta::encode(base_data, data, max_size) {
+                                match best_delta {
+                                    Some((size, depth)) if delta.len() == size && base_depth + 1 >= depth => {}
+                                    _ => best = Some((base_idx, delta)),
+                                }
+                            }
+                        }
+                        if let Some((base_idx, _)) = best {
+                            depths[idx] = depths[base_idx] + 1;
+                        }
+                        deltas[idx] = best;
+                    }
+
+                    // Objects are written in the order they were counted in, except for bases of deltas which are
+                    // written right before the first delta needing them, as git does.
+                    // The index of the entry written for each object, if it was written already.
+                    let mut entry_indices = vec![None; objects.len()];
+                    let mut chain = Vec::new();
+                    for idx in 0..objects.len() {
+                        let mut next = Some(idx);
+                        while let Some(idx) = next {
+                            if entry_indices[idx].is_some() || objects[idx].0.is_external_base {
+                                break;
                             }
-                        }?,
-                    );
-                    progress.inc();
+                            chain.push(idx);
+                            next = deltas[idx].as_ref().map(|(base_idx, _)| *base_idx);
+                        }
+                        while let Some(idx) = chain.pop() {
+                            let (count, kind, data) = &objects[idx];
+                            let entry = match deltas[idx].take() {
+                                Some((base_idx, delta)) => {
+                                    stats.deltified_objects += 1;
+                                    let entry_kind = match entry_indices[base_idx] {
+                                        Some(base_entry_idx) => output::entry::Kind::DeltaRef {
+                                            nth_before: out.len() - base_entry_idx,
+                                        },
+                                        None => output::entry::Kind::DeltaOid {
+                                            id: objects[base_idx].0.id,
+                                        },
+                                    };
+                                    output::Entry::from_delta(count, *kind, entry_kind, &delta)?
+                                }
+                                None => match copy_from_pack(count, &db, version) {
+                                    Some(entry) => {
+                                        stats.objects_copied_from_pack += 1;
+                                        entry?
+                                    }
+                                    None => {
+                                        stats.decoded_and_recompressed_objects += 1;
+                                        output::Entry::from_data(count, &data::Object::new(*kind, data))?
+                                    }
+                                },
+                            };
+                            entry_indices[idx] = Some(out.len());
+                            out.push(entry);
+                        }
+                    }
+                    progress.inc_by(objects.len());
                 }
                 Ok((chunk_id, out, stats))
             }
@@ -113,6 +225,41 @@ where
     )
 }
 
+/// Return an entry with the data of `count` as found in its pack if it's stored there as base object of the desired `version`.
+fn copy_from_pack<Find: crate::Find>(
+    count: &output::Count,
+    db: &Find,
+    version: crate::data::Version,
+) -> Option<Result<output::Entry, output::entry::Error>> {
+    count
+        .entry_pack_location
+        .as_ref()
+        .and_then(|l| db.entry_by_location(l))
+        .and_then(|pack_entry| output::Entry::from_pack_entry(pack_entry, count, version))
+}
+
+/// Return the maximum size of a delta of an object of `target_size` against a base of `base_size` at `base_depth`, or 0
+/// if no delta should be attempted, given the size and depth of the `best_delta` found so far.
+///
+/// This mirrors git, which wants a delta to save at least half of the object's size minus the size of an object id,
+/// makes deltas against deep bases less attractive and avoids trying bases of very different size.
+fn max_delta_size(
+    target_size: usize,
+    base_size: usize,
+    base_depth: usize,
+    max_depth: usize,
+    best_delta: Option<(usize, usize)>,
+) -> usize {
+    let (max_size, ref_depth) = best_delta.unwrap_or(((target_size / 2).saturating_sub(20), 1));
+    let max_size = (max_size as u64 * (max_depth - base_depth) as u64 / (max_depth - ref_depth + 1) as u64) as usize;
+    let size_difference = target_size.saturating_sub(base_size);
+    if size_difference >= max_size || target_size < base_size / 32 {
+        0
+    } else {
+        max_size
+    }
+}
+
 mod util {
     pub struct Chunks {
         cursor: usize,
@@ -197,6 +344,9 @@ mod types {
         /// The amount of objects that could be copied directly from the pack. These are cheapest as they
         /// only cost a memory copy for the most part.
         pub objects_copied_from_pack: usize,
+        /// The amount of objects written as delta against another object in the pack. These are the most expensive as
+        /// they are fully decoded and compared to multiple other objects.
+        pub deltified_objects: usize,
     }
 
     impl Outcome {
@@ -205,10 +355,12 @@ mod types {
             Outcome {
                 decoded_and_recompressed_objects: decoded_objects,
                 objects_copied_from_pack,
+                deltified_objects,
             }: Self,
         ) {
             self.decoded_and_recompressed_objects += decoded_objects;
             self.objects_copied_from_pack += objects_copied_from_pack;
+            self.deltified_objects += deltified_objects;
         }
     }
 
@@ -218,11 +370,18 @@ mod types {
     pub struct Options {
         /// The amount of threads to use at most when resolving the pack. If `None`, all logical cores are used.
         pub thread_limit: Option<usize>,
-        /// The amount of objects per chunk or unit of work to be sent to threads for processing
-        /// TODO: could this become the window size?
+        /// The amount of objects per chunk or unit of work to be sent to threads for processing.
+        ///
+        /// Deltas are only searched within a chunk, so larger chunks allow for better compression at the cost of
+        /// holding all of their objects in memory.
         pub chunk_size: usize,
         /// The pack data version to produce
         pub version: crate::data::Version,
+        /// The amount of objects preceding an object in sort order to try as base for a delta, like `pack.window` in git.
+        /// If 0, no deltas are searched and objects are written as base objects, copying them from their pack if possible.
+        pub window_size: usize,
+        /// The maximum amount of deltas between an object and its base object, like `pack.depth` in git.
+        pub max_delta_depth: usize,
     }
 
     impl Default for Options {
@@ -231,6 +390,8 @@ mod types {
                 thread_limit: None,
                 chunk_size: 10,
                 version: Default::default(),
+                window_size: 0,
+                max_delta_depth: 50,
             }
         }
     }
@@ -97,28 +97,37 @@ impl output::Entry {
             object_kind: obj.kind,
             kind: Kind::Base,
             decompressed_size: obj.data.len(),
-            compressed_data: {
-                let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
-                if let Err(err) = std::io::copy(&mut &*obj.data, &mut out) {
-                    match err.kind() {
-                        std::io::ErrorKind::Other => return Err(Error::ZlibDeflate(err)),
-                        err => unreachable!("Should never see other errors than zlib, but got {:?}", err,),
-                    }
-                };
-                out.flush()?;
-                out.into_inner()
-            },
+            compressed_data: deflate(obj.data)?,
+        })
+    }
+
+    /// Create a new instance from the given `count` of an object of `object_kind`, which is represented by `delta` data
+    /// to be applied to the base object identified by `kind`.
+    pub(crate) fn from_delta(
+        count: &output::Count,
+        object_kind: git_object::Kind,
+        kind: Kind,
+        delta: &[u8],
+    ) -> Result<Self, Error> {
+        debug_assert!(kind != Kind::Base, "delta entries need a base object");
+        Ok(output::Entry {
+            id: count.id.to_owned(),
+            object_kind,
+            kind,
+            decompressed_size: delta.len(),
+            compressed_data: deflate(delta)?,
         })
     }
 
     /// Transform ourselves into pack entry header of `version` which can be written into a pack.
     ///
-    /// `index_to_pack(nth_before) -> pack_offset` is a function to convert the base object's offset as index into an
-    /// array to an offset into the pack. This information is known to the one calling the method.
+    /// `index_to_base_distance(nth_before) -> base_distance` is a function to convert the base object's offset as index
+    /// into an array to the distance in bytes between the base object's pack offset and the one of this entry.
+    /// This information is known to the one calling the method.
     pub fn to_entry_header(
         &self,
         version: crate::data::Version,
-        index_to_pack: impl FnOnce(usize) -> u64,
+        index_to_base_distance: impl FnOnce(usize) -> u64,
     ) -> crate::data::entry::Header {
         assert!(
             matches!(version, data::Version::V2),
@@ -138,8 +147,21 @@ impl output::Entry {
             }
             DeltaOid { id } => data::entry::Header::RefDelta { base_id: id.to_owned() },
             DeltaRef { nth_before } => data::entry::Header::OfsDelta {
-                base_distance: index_to_pack(nth_before),
+                base_distance: index_to_base_distance(nth_before),
             },
         }
     }
 }
+
+/// Compress `data` with zlib, the way pack entries are stored.
+pub(crate) fn deflate(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
+    if let Err(err) = std::io::copy(&mut &*data, &mut out) {
+        match err.kind() {
+            std::io::ErrorKind::Other => return Err(err),
+            err => unreachable!("Should never see other errors than zlib, but got {:?}", err,),
+        }
+    };
+    out.flush()?;
+    Ok(out.into_inner())
+}
@@ -10,8 +10,17 @@ pub enum Error {
     PackEntryDecode(#[from] crate::data::input::Error),
     #[error("Indices of type {} cannot be written, only {} are supported", *.0 as usize, crate::index::Version::default() as usize)]
     Unsupported(crate::index::Version),
-    #[error("Ref delta objects are not supported as there is no way to look them up. Resolve them beforehand.")]
-    IteratorInvariantNoRefDelta,
+    #[error("The base object {id} of a ref delta isn't contained in the pack and couldn't be added to complete the thin pack")]
+    RefDeltaBaseNotFound { id: git_hash::ObjectId },
+    #[error("The base object {id} needed to complete the thin pack could not be looked up")]
+    ThinPackBaseLookup {
+        id: git_hash::ObjectId,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    #[error("The pack entry at offset {pack_offset} could not be read")]
+    ResolveFailed { pack_offset: u64 },
+    #[error("A pack entry could not be decompressed")]
+    ZlibInflate(#[from] git_features::zlib::inflate::Error),
     #[error("The iterator failed to set a trailing hash over all prior pack entries in the last provided entry")]
     IteratorInvariantTrailer,
     #[error("Did not encounter a single base")]
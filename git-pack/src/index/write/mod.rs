@@ -7,6 +7,7 @@ use std::{convert::TryInto, io, sync::atomic::AtomicBool};
 
 mod encode;
 mod error;
+mod ref_delta;
 pub use error::Error;
 
 pub(crate) struct TreeEntry {
@@ -38,6 +39,22 @@ pub struct Outcome {
     pub num_objects: u32,
 }
 
+/// A function to find the object with the given id using the buffer as backing store, returning `None` if it doesn't exist.
+pub(crate) type FindFn<'a> = dyn for<'b> FnMut(
+        &git_hash::oid,
+        &'b mut Vec<u8>,
+    ) -> Result<Option<crate::data::Object<'b>>, Box<dyn std::error::Error + Send + Sync + 'static>>
+    + 'a;
+
+/// The means to complete a thin pack while writing its index, by appending the bases of ref deltas which aren't
+/// contained in it as full objects, like `git index-pack --fix-thin` does.
+pub(crate) struct ThinPackCompletion<'a> {
+    /// Find the bases which aren't contained in the pack.
+    pub find: &'a mut FindFn<'a>,
+    /// The pack data file the entries were read from, for appending the missing bases and rewriting its trailer.
+    pub pack: std::fs::File,
+}
+
 /// Various ways of writing an index file from pack entries
 impl crate::index::File {
     /// Write information about `entries` as obtained from a pack data file into a pack index file via the `out` stream.
@@ -51,12 +68,41 @@ impl crate::index::File {
     ///
     /// # Remarks
     ///
-    /// * neither in-pack nor out-of-pack Ref Deltas are supported here, these must have been resolved beforehand.
+    /// * Ref deltas are resolved against the objects in the pack, out-of-pack Ref Deltas of thin packs cause an error.
     /// * `make_resolver()` will only be called after the iterator stopped returning elements and produces a function that
     /// provides all bytes belonging to a pack entry writing them to the given mutable output `Vec`.
     /// It should return `None` if the entry cannot be resolved from the pack that produced the `entries` iterator, causing
     /// the write operation to fail.
     pub fn write_data_iter_to_stream<F, F2>(
+        kind: crate::index::Version,
+        make_resolver: F,
+        entries: impl Iterator<Item = Result<crate::data::input::Entry, crate::data::input::Error>>,
+        thread_limit: Option<usize>,
+        root_progress: impl Progress,
+        out: impl io::Write,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error>
+    where
+        F: FnOnce() -> io::Result<F2>,
+        F2: for<'r> Fn(crate::data::EntryRange, &'r mut Vec<u8>) -> Option<()> + Send + Sync,
+    {
+        Self::write_data_iter_to_stream_completing_thin_pack(
+            kind,
+            make_resolver,
+            entries,
+            thread_limit,
+            root_progress,
+            out,
+            should_interrupt,
+            None,
+        )
+    }
+
+    /// Like [`write_data_iter_to_stream()`][crate::index::File::write_data_iter_to_stream()], but if `thin_pack` is set,
+    /// the bases of ref deltas which aren't contained in the pack are looked up and appended to the pack data file, which
+    /// then receives a new object count and trailer.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn write_data_iter_to_stream_completing_thin_pack<F, F2>(
         kind: crate::index::Version,
         make_resolver: F,
         entries: impl Iterator<Item = Result<crate::data::input::Entry, crate::data::input::Error>>,
@@ -64,6 +110,7 @@ impl crate::index::File {
         mut root_progress: impl Progress,
         out: impl io::Write,
         should_interrupt: &AtomicBool,
+        thin_pack: Option<ThinPackCompletion<'_>>,
     ) -> Result<Outcome, Error>
     where
         F: FnOnce() -> io::Result<F2>,
@@ -86,6 +133,7 @@ impl crate::index::File {
         let mut decompressed_progress = root_progress.add_child("decompressing");
         decompressed_progress.init(None, progress::bytes());
         let mut pack_entries_end: u64 = 0;
+        let mut deferred = Vec::new();
 
         for (eid, entry) in entries.enumerate() {
             let crate::data::input::Entry {
@@ -119,7 +167,15 @@ impl crate::index::File {
                         },
                     )?;
                 }
-                RefDelta { .. } => return Err(Error::IteratorInvariantNoRefDelta),
+                RefDelta { base_id } => {
+                    tree.set_pack_entries_end(pack_offset);
+                    deferred.push(ref_delta::Deferred {
+                        pack_offset,
+                        entry_end: pack_entries_end,
+                        crc32,
+                        base: ref_delta::Base::Id(base_id),
+                    });
+                }
                 OfsDelta { base_distance } => {
                     let base_pack_offset =
                         crate::data::entry::Header::verified_base_pack_offset(pack_offset, base_distance).ok_or(
@@ -128,14 +184,27 @@ impl crate::index::File {
                                 distance: base_distance,
                             },
                         )?;
-                    tree.add_child(
-                        base_pack_offset,
-                        pack_offset,
-                        TreeEntry {
-                            id: git_hash::ObjectId::null_sha1(),
+                    if deferred
+                        .binary_search_by_key(&base_pack_offset, |d: &ref_delta::Deferred| d.pack_offset)
+                        .is_ok()
+                    {
+                        tree.set_pack_entries_end(pack_offset);
+                        deferred.push(ref_delta::Deferred {
+                            pack_offset,
+                            entry_end: pack_entries_end,
                             crc32,
-                        },
-                    )?;
+                            base: ref_delta::Base::Offset(base_pack_offset),
+                        });
+                    } else {
+                        tree.add_child(
+                            base_pack_offset,
+                            pack_offset,
+                            TreeEntry {
+                                id: git_hash::ObjectId::null_sha1(),
+                                crc32,
+                            },
+                        )?;
+                    }
                 }
             };
             last_seen_trailer = trailer;
@@ -148,10 +217,12 @@ impl crate::index::File {
                 anticipated_num_objects, num_objects
             ));
         }
-        let num_objects: u32 = num_objects
+        let mut num_objects: u32 = num_objects
             .try_into()
             .map_err(|_| Error::IteratorInvariantTooManyObjects(num_objects))?;
-        last_base_index.ok_or(Error::IteratorInvariantBasesPresent)?;
+        if deferred.is_empty() {
+            last_base_index.ok_or(Error::IteratorInvariantBasesPresent)?;
+        }
 
         objects_progress.show_throughput(indexing_start);
         decompressed_progress.show_throughput(indexing_start);
@@ -165,7 +236,7 @@ impl crate::index::File {
             let in_parallel_if_pack_is_big_enough = || bytes_to_process > 5_000_000;
             let mut items = tree.traverse(
                 in_parallel_if_pack_is_big_enough,
-                resolver,
+                &resolver,
                 root_progress.add_child("Resolving"),
                 root_progress.add_child("Decoding"),
                 thread_limit,
@@ -185,6 +256,23 @@ impl crate::index::File {
             )?;
             root_progress.inc();
 
+            if !deferred.is_empty() {
+                let completed = ref_delta::resolve(
+                    deferred,
+                    &items,
+                    &resolver,
+                    pack_entries_end,
+                    num_objects,
+                    kind.hash(),
+                    thin_pack,
+                )?;
+                items.extend(completed.items);
+                if let Some((num_objects_with_bases, pack_hash)) = completed.thin_pack {
+                    num_objects = num_objects_with_bases;
+                    last_seen_trailer = Some(pack_hash);
+                }
+            }
+
             {
                 let _progress = root_progress.add_child("sorting by id");
                 items.make_contiguous().sort_by_key(|e| e.data.id);
@@ -216,20 +304,19 @@ impl crate::index::File {
     }
 }
 
+fn compute_hash(kind: git_object::Kind, bytes: &[u8], hash_kind: git_hash::Kind) -> git_hash::ObjectId {
+    let mut write = git_features::hash::Write::new(io::sink(), hash_kind);
+    loose::object::header::encode(kind, bytes.len() as u64, &mut write).expect("write to sink and hash cannot fail");
+    write.hash.update(bytes);
+    git_hash::ObjectId::from(write.hash.digest())
+}
+
 fn modify_base(
     entry: &mut crate::index::write::TreeEntry,
     pack_entry: &crate::data::Entry,
     decompressed: &[u8],
     hash: git_hash::Kind,
 ) {
-    fn compute_hash(kind: git_object::Kind, bytes: &[u8], hash_kind: git_hash::Kind) -> git_hash::ObjectId {
-        let mut write = git_features::hash::Write::new(io::sink(), hash_kind);
-        loose::object::header::encode(kind, bytes.len() as u64, &mut write)
-            .expect("write to sink and hash cannot fail");
-        write.hash.update(bytes);
-        git_hash::ObjectId::from(write.hash.digest())
-    }
-
     let object_kind = pack_entry.header.as_kind().expect("base object as source of iteration");
     let id = compute_hash(object_kind, &decompressed, hash);
     entry.id = id;
@@ -0,0 +1,282 @@
+use crate::{
+    data::{self, EntryRange},
+    index::write::{compute_hash, Error, ThinPackCompletion, TreeEntry},
+    tree::Item,
+};
+use git_features::zlib;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+/// A pack entry which can only be resolved once the ids of the objects in the pack are known.
+pub(crate) struct Deferred {
+    pub pack_offset: u64,
+    pub entry_end: u64,
+    pub crc32: u32,
+    pub base: Base,
+}
+
+pub(crate) enum Base {
+    /// The base of a ref delta.
+    Id(git_hash::ObjectId),
+    /// The base of an ofs delta, which is a deferred entry itself.
+    Offset(u64),
+}
+
+pub(crate) struct Outcome {
+    /// The resolved deferred entries, along with the bases added to complete a thin pack.
+    pub items: Vec<Item<TreeEntry>>,
+    /// The amount of objects in the completed thin pack along with its new trailer, if bases were added.
+    pub thin_pack: Option<(u32, git_hash::ObjectId)>,
+}
+
+/// Resolve all `deferred` pack entries against the already resolved `items` of the pack, which ends at `pack_entries_end`
+/// and contains `num_objects`.
+///
+/// Ref deltas whose base isn't contained in the pack are resolved by appending their base to the pack if `thin_pack`
+/// is set. The pack trailer is rewritten afterwards.
+pub(crate) fn resolve<F>(
+    deferred: Vec<Deferred>,
+    items: &VecDeque<Item<TreeEntry>>,
+    resolve: &F,
+    pack_entries_end: u64,
+    num_objects: u32,
+    hash_kind: git_hash::Kind,
+    thin_pack: Option<ThinPackCompletion<'_>>,
+) -> Result<Outcome, Error>
+where
+    F: for<'r> Fn(EntryRange, &'r mut Vec<u8>) -> Option<()>,
+{
+    let mut waiting_on_offset = HashMap::<_, Vec<_>>::new();
+    let mut waiting_on_id = HashMap::<_, Vec<_>>::new();
+    for (idx, entry) in deferred.iter().enumerate() {
+        match entry.base {
+            Base::Offset(offset) => waiting_on_offset.entry(offset).or_default().push(idx),
+            Base::Id(id) => waiting_on_id.entry(id).or_default().push(idx),
+        }
+    }
+
+    let mut resolver = Resolver {
+        deferred: &deferred,
+        waiting_on_offset,
+        waiting_on_id,
+        resolve,
+        hash_kind,
+        buf: Vec::new(),
+        items: Vec::with_capacity(deferred.len()),
+    };
+
+    let mut entry_ends: Vec<_> = items.iter().map(|item| (item.offset, item.next_offset)).collect();
+    entry_ends.sort_unstable();
+    let bases_in_pack: Vec<_> = items
+        .iter()
+        .filter(|item| resolver.waiting_on_id.contains_key(&item.data.id))
+        .map(|item| (item.offset, item.data.id))
+        .collect();
+    for (offset, id) in bases_in_pack {
+        let (kind, data) = resolver.decode_in_pack(offset, &entry_ends)?;
+        resolver.resolve_deltas_of(offset, id, kind, data)?;
+    }
+
+    let thin_pack = match (resolver.waiting_on_id.is_empty(), thin_pack) {
+        (false, Some(ThinPackCompletion { find, mut pack })) => {
+            let mut missing_bases: Vec<_> = resolver
+                .waiting_on_id
+                .iter()
+                .map(|(id, waiting)| (waiting[0], *id))
+                .collect();
+            missing_bases.sort_unstable();
+
+            let mut buf = Vec::new();
+            let mut pack_offset = pack_entries_end;
+            let mut num_added_bases = 0u32;
+            for (_, id) in missing_bases {
+                if !resolver.waiting_on_id.contains_key(&id) {
+                    continue;
+                }
+                let obj = match find(&id, &mut buf).map_err(|source| Error::ThinPackBaseLookup { id, source })? {
+                    Some(obj) => obj,
+                    None => continue,
+                };
+                let entry = data::input::Entry::from_data_obj(&obj, pack_offset)?;
+                pack.seek(SeekFrom::Start(pack_offset))?;
+                entry.header.write_to(entry.decompressed_size, &mut pack)?;
+                pack.write_all(entry.compressed.as_ref().expect("compressed data is kept"))?;
+
+                let entry_end = pack_offset + entry.bytes_in_pack();
+                resolver.items.push(Item {
+                    offset: pack_offset,
+                    next_offset: entry_end,
+                    data: TreeEntry {
+                        id,
+                        crc32: entry.crc32.expect("crc32 is computed"),
+                    },
+                    children: Vec::new(),
+                });
+                let (kind, data) = (obj.kind, obj.data.to_owned());
+                resolver.resolve_deltas_of(pack_offset, id, kind, data)?;
+                pack_offset = entry_end;
+                num_added_bases += 1;
+            }
+
+            match num_added_bases {
+                0 => None,
+                _ => {
+                    let num_objects =
+                        num_objects
+                            .checked_add(num_added_bases)
+                            .ok_or(Error::IteratorInvariantTooManyObjects(
+                                num_objects as usize + num_added_bases as usize,
+                            ))?;
+                    let pack_hash = rewrite_header_and_trailer(&mut pack, num_objects, pack_offset, hash_kind)?;
+                    Some((num_objects, pack_hash))
+                }
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(id) = resolver.waiting_on_id.keys().min() {
+        return Err(Error::RefDeltaBaseNotFound { id: *id });
+    }
+    Ok(Outcome {
+        items: resolver.items,
+        thin_pack,
+    })
+}
+
+struct Resolver<'a, F> {
+    deferred: &'a [Deferred],
+    waiting_on_offset: HashMap<u64, Vec<usize>>,
+    waiting_on_id: HashMap<git_hash::ObjectId, Vec<usize>>,
+    resolve: &'a F,
+    hash_kind: git_hash::Kind,
+    buf: Vec<u8>,
+    items: Vec<Item<TreeEntry>>,
+}
+
+impl<'a, F> Resolver<'a, F>
+where
+    F: for<'r> Fn(EntryRange, &'r mut Vec<u8>) -> Option<()>,
+{
+    /// Resolve all deferred entries depending on the object at `pack_offset` with `id`, `kind` and `data`, as well as
+    /// the ones depending on them in turn.
+    fn resolve_deltas_of(
+        &mut self,
+        pack_offset: u64,
+        id: git_hash::ObjectId,
+        kind: git_object::Kind,
+        data: Vec<u8>,
+    ) -> Result<(), Error> {
+        let deferred = self.deferred;
+        let mut bases = vec![(pack_offset, id, data)];
+        while let Some((pack_offset, id, base)) = bases.pop() {
+            let deltas = self
+                .waiting_on_offset
+                .remove(&pack_offset)
+                .into_iter()
+                .chain(self.waiting_on_id.remove(&id))
+                .flatten();
+            for idx in deltas {
+                let entry = &deferred[idx];
+                let (_, delta) = self.decompress(entry.pack_offset..entry.entry_end)?;
+                let object = apply_delta(&base, &delta);
+                let object_id = compute_hash(kind, &object, self.hash_kind);
+                self.items.push(Item {
+                    offset: entry.pack_offset,
+                    next_offset: entry.entry_end,
+                    data: TreeEntry {
+                        id: object_id,
+                        crc32: entry.crc32,
+                    },
+                    children: Vec::new(),
+                });
+                bases.push((entry.pack_offset, object_id, object));
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode the object at `pack_offset` which is a base or an ofs delta whose bases are part of the pack as well.
+    /// `entry_ends` maps the pack offset of each of these entries to their end, sorted by pack offset.
+    fn decode_in_pack(
+        &mut self,
+        pack_offset: u64,
+        entry_ends: &[(u64, u64)],
+    ) -> Result<(git_object::Kind, Vec<u8>), Error> {
+        let entry_end = |pack_offset: u64| {
+            entry_ends
+                .binary_search_by_key(&pack_offset, |(offset, _)| *offset)
+                .map(|idx| entry_ends[idx].1)
+                .map_err(|_| Error::ResolveFailed { pack_offset })
+        };
+        let mut deltas = Vec::new();
+        let mut pack_offset = pack_offset;
+        let (kind, mut data) = loop {
+            let (entry, decompressed) = self.decompress(pack_offset..entry_end(pack_offset)?)?;
+            match entry.header {
+                data::entry::Header::OfsDelta { base_distance } => {
+                    pack_offset = entry.base_pack_offset(base_distance);
+                    deltas.push(decompressed);
+                }
+                header => {
+                    break (
+                        header
+                            .as_kind()
+                            .expect("ref deltas are deferred and not part of the tree"),
+                        decompressed,
+                    )
+                }
+            }
+        };
+        for delta in deltas.iter().rev() {
+            data = apply_delta(&data, delta);
+        }
+        Ok((kind, data))
+    }
+
+    fn decompress(&mut self, range: EntryRange) -> Result<(data::Entry, Vec<u8>), Error> {
+        self.buf.resize((range.end - range.start) as usize, 0);
+        (self.resolve)(range.clone(), &mut self.buf).ok_or(Error::ResolveFailed {
+            pack_offset: range.start,
+        })?;
+        let entry = data::Entry::from_bytes(&self.buf, range.start);
+        let mut out = vec![0; entry.decompressed_size as usize];
+        zlib::Inflate::default().once(&self.buf[entry.header_size()..], &mut out)?;
+        Ok((entry, out))
+    }
+}
+
+fn apply_delta(base: &[u8], delta: &[u8]) -> Vec<u8> {
+    let (_base_size, consumed) = data::delta::decode_header_size(delta);
+    let mut header_ofs = consumed;
+    let (result_size, consumed) = data::delta::decode_header_size(&delta[consumed..]);
+    header_ofs += consumed;
+    let mut out = vec![0; result_size as usize];
+    data::delta::apply(base, &mut out, &delta[header_ofs..]);
+    out
+}
+
+/// Set the object count in the header of `pack` to `num_objects` and write a new trailer after the last entry which ends
+/// at `pack_entries_end`, returning the new pack hash.
+fn rewrite_header_and_trailer(
+    pack: &mut std::fs::File,
+    num_objects: u32,
+    pack_entries_end: u64,
+    hash_kind: git_hash::Kind,
+) -> io::Result<git_hash::ObjectId> {
+    let object_count_offset = 8;
+    pack.seek(SeekFrom::Start(object_count_offset))?;
+    pack.write_all(&num_objects.to_be_bytes())?;
+
+    pack.seek(SeekFrom::Start(0))?;
+    let mut hash = git_features::hash::Write::new(io::sink(), hash_kind);
+    io::copy(&mut (&*pack).take(pack_entries_end), &mut hash)?;
+    let pack_hash = git_hash::ObjectId::from(hash.hash.digest());
+
+    pack.seek(SeekFrom::Start(pack_entries_end))?;
+    pack.write_all(pack_hash.as_slice())?;
+    pack.set_len(pack_entries_end + pack_hash.as_slice().len() as u64)?;
+    Ok(pack_hash)
+}
@@ -13,6 +13,8 @@
 //! When traversing all objects in a pack, a [Tree acceleration structure][tree::Tree] can be built from pack data or an index
 //! in order to decompress packs in parallel and without any waste.
 
+///
+pub mod bitmap;
 ///
 pub mod bundle;
 pub use crate::bundle::Bundle;
@@ -29,6 +31,8 @@ pub mod data;
 ///
 pub mod index;
 ///
+pub mod multi_index;
+///
 pub mod tree;
 
 ///
@@ -0,0 +1,124 @@
+use crate::multi_index::{
+    init::{LARGE_OFFSET_ENTRY_SIZE, OBJECT_OFFSET_ENTRY_SIZE},
+    File, PackIndex, Version, FAN_LEN,
+};
+use byteorder::{BigEndian, ByteOrder};
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+use std::path::Path;
+
+const HIGH_BIT: u32 = 1 << 31;
+
+/// Represents an entry within a multi-pack index file, effectively mapping object [`IDs`][git_hash::ObjectId] to
+/// pack data file locations.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The ID of the object
+    pub oid: git_hash::ObjectId,
+    /// The offset to the object's header in the pack data file
+    pub pack_offset: u64,
+    /// The index of the pack matching our [`File::index_names()`] slice.
+    pub pack_index: PackIndex,
+}
+
+/// Access methods
+impl File {
+    /// The version of the multi-pack index file
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// The path of the opened multi-pack index file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// The amount of packs contained in this multi-pack index
+    pub fn num_packs(&self) -> PackIndex {
+        self.num_packs
+    }
+    /// The amount of objects contained in all packs of this multi-pack index
+    pub fn num_objects(&self) -> u32 {
+        self.num_objects
+    }
+    /// The names of the pack index files of all packs covered by this multi-pack index, sorted lexicographically.
+    ///
+    /// They are relative to the directory containing this file.
+    pub fn index_names(&self) -> &[std::path::PathBuf] {
+        &self.index_names
+    }
+
+    /// Returns the object id at the given `index` in our list of (sorted) object ids.
+    /// The index ranges from 0 to [`num_objects()`][File::num_objects()].
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn oid_at_index(&self, index: u32) -> &git_hash::oid {
+        let start = self.lookup_ofs + index as usize * SHA1_SIZE;
+        git_hash::oid::try_from(&self.data[start..start + SHA1_SIZE]).expect("20 bytes SHA1 to be alright")
+    }
+
+    /// Returns the `index` of the given object `id`, for use with [`oid_at_index()`][File::oid_at_index()] or
+    /// [`pack_id_and_pack_offset_at_index()`][File::pack_id_and_pack_offset_at_index()].
+    pub fn lookup(&self, id: impl AsRef<git_hash::oid>) -> Option<u32> {
+        let id = id.as_ref();
+        let first_byte = id.first_byte() as usize;
+        let mut upper_bound = self.fan[first_byte];
+        let mut lower_bound = if first_byte != 0 { self.fan[first_byte - 1] } else { 0 };
+
+        while lower_bound < upper_bound {
+            let mid = (lower_bound + upper_bound) / 2;
+            let mid_sha = self.oid_at_index(mid);
+
+            use std::cmp::Ordering::*;
+            match id.cmp(mid_sha) {
+                Less => upper_bound = mid,
+                Equal => return Some(mid),
+                Greater => lower_bound = mid + 1,
+            }
+        }
+        None
+    }
+
+    /// Returns the index of the pack containing the object at `index` along with its offset into that pack's data file.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn pack_id_and_pack_offset_at_index(&self, index: u32) -> (PackIndex, u64) {
+        let start = self.offsets_ofs + index as usize * OBJECT_OFFSET_ENTRY_SIZE;
+        let pack_index = BigEndian::read_u32(&self.data[start..start + 4]);
+        let offset = BigEndian::read_u32(&self.data[start + 4..start + 8]);
+        let pack_offset = match self.large_offsets_ofs {
+            Some(large_offsets_ofs) if offset & HIGH_BIT == HIGH_BIT => {
+                let start = large_offsets_ofs + (offset ^ HIGH_BIT) as usize * LARGE_OFFSET_ENTRY_SIZE;
+                BigEndian::read_u64(&self.data[start..start + LARGE_OFFSET_ENTRY_SIZE])
+            }
+            _ => offset as u64,
+        };
+        (pack_index, pack_offset)
+    }
+
+    /// An iterator over all [`Entries`][Entry] of this multi-pack index file.
+    pub fn iter(&self) -> impl Iterator<Item = Entry> + '_ {
+        (0..self.num_objects).map(move |idx| {
+            let (pack_index, pack_offset) = self.pack_id_and_pack_offset_at_index(idx);
+            Entry {
+                oid: self.oid_at_index(idx).to_owned(),
+                pack_offset,
+                pack_index,
+            }
+        })
+    }
+
+    pub(crate) fn fan(&self) -> &[u32; FAN_LEN] {
+        &self.fan
+    }
+
+    pub(crate) fn checksum_offset(&self) -> usize {
+        self.data.len() - SHA1_SIZE
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
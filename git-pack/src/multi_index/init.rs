@@ -0,0 +1,273 @@
+use crate::multi_index::{File, Version, FAN_LEN, SIGNATURE};
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+use git_object::bstr::ByteSlice;
+use std::{
+    convert::{TryFrom, TryInto},
+    path::{Path, PathBuf},
+};
+
+/// The identifier of a chunk in a multi-pack index file.
+pub type ChunkId = [u8; 4];
+
+/// Returned by [`File::at()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open multi-pack index file at '{}'", .path.display())]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported multi-pack index version: {version}")]
+    UnsupportedVersion { version: u8 },
+    #[error("Unsupported hash kind: {kind}")]
+    UnsupportedHashKind { kind: u8 },
+    #[error("Multi-pack index files based on other multi-pack indices aren't supported")]
+    UnsupportedBaseFiles,
+    #[error("Multi-pack index file contains multiple {:?} chunks", .0.as_bstr())]
+    DuplicateChunk(ChunkId),
+    #[error("Multi-pack index chunk {:?} has invalid size: {message}", .id.as_bstr())]
+    InvalidChunkSize { id: ChunkId, message: String },
+    #[error("Missing required chunk {:?}", .0.as_bstr())]
+    MissingChunk(ChunkId),
+}
+
+pub(crate) const HEADER_LEN: usize = 12;
+pub(crate) const CHUNK_LOOKUP_SIZE: usize = 12;
+pub(crate) const TRAILER_LEN: usize = SHA1_SIZE;
+// Required chunks: PNAM, OIDF, OIDL, OOFF
+const MIN_CHUNKS: usize = 4;
+const MIN_FILE_SIZE: usize = HEADER_LEN + (MIN_CHUNKS + 1) * CHUNK_LOOKUP_SIZE + TRAILER_LEN;
+
+pub(crate) const PACK_NAMES_CHUNK_ID: ChunkId = *b"PNAM";
+pub(crate) const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
+pub(crate) const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
+pub(crate) const OBJECT_OFFSETS_CHUNK_ID: ChunkId = *b"OOFF";
+pub(crate) const LARGE_OFFSETS_CHUNK_ID: ChunkId = *b"LOFF";
+pub(crate) const SENTINEL_CHUNK_ID: ChunkId = [0u8; 4];
+
+pub(crate) const OBJECT_OFFSET_ENTRY_SIZE: usize = 8;
+pub(crate) const LARGE_OFFSET_ENTRY_SIZE: usize = 8;
+
+/// Instantiation
+impl File {
+    /// Open the multi-pack index file at the given `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<File, Error> {
+        Self::try_from(path.as_ref())
+    }
+}
+
+impl TryFrom<&Path> for File {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let data = FileBuffer::open(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        let data_size = data.len();
+        if data_size < MIN_FILE_SIZE {
+            return Err(corrupt("Multi-pack index file is too small even for an empty index"));
+        }
+
+        let mut ofs = 0;
+        if &data[ofs..ofs + SIGNATURE.len()] != SIGNATURE {
+            return Err(corrupt("Multi-pack index file does not start with expected signature"));
+        }
+        ofs += SIGNATURE.len();
+
+        let version = match data[ofs] {
+            1 => Version::V1,
+            version => return Err(Error::UnsupportedVersion { version }),
+        };
+        ofs += 1;
+
+        match data[ofs] {
+            1 => {}
+            kind => return Err(Error::UnsupportedHashKind { kind }),
+        }
+        ofs += 1;
+
+        let chunk_count = data[ofs] as usize;
+        ofs += 1;
+
+        if data[ofs] != 0 {
+            return Err(Error::UnsupportedBaseFiles);
+        }
+        ofs += 1;
+
+        let num_packs = BigEndian::read_u32(&data[ofs..ofs + 4]);
+        ofs += 4;
+
+        let chunk_lookup_end = ofs + (chunk_count + 1) * CHUNK_LOOKUP_SIZE;
+        if chunk_lookup_end > data_size {
+            return Err(corrupt(format!(
+                "Multi-pack index file is too small to hold {} chunks",
+                chunk_count
+            )));
+        }
+
+        let mut pack_names_range = None;
+        let mut fan_ofs = None;
+        let mut lookup = None;
+        let mut offsets = None;
+        let mut large_offsets_ofs = None;
+
+        let read_chunk_entry = |ofs: usize| -> (ChunkId, usize) {
+            let id: ChunkId = data[ofs..ofs + 4].try_into().expect("ChunkId to accept 4 bytes");
+            let offset = BigEndian::read_u64(&data[ofs + 4..ofs + CHUNK_LOOKUP_SIZE])
+                .try_into()
+                .expect("an offset small enough to fit a usize");
+            (id, offset)
+        };
+        let (mut chunk_id, mut chunk_offset) = read_chunk_entry(ofs);
+        ofs += CHUNK_LOOKUP_SIZE;
+        if chunk_offset < chunk_lookup_end {
+            return Err(corrupt(format!(
+                "Multi-pack index chunk 0 has invalid offset {} (must be at least {})",
+                chunk_offset, chunk_lookup_end
+            )));
+        }
+
+        for _ in 0..chunk_count {
+            let (next_chunk_id, next_chunk_offset) = read_chunk_entry(ofs);
+            ofs += CHUNK_LOOKUP_SIZE;
+
+            let chunk_size = next_chunk_offset
+                .checked_sub(chunk_offset)
+                .ok_or_else(|| Error::InvalidChunkSize {
+                    id: chunk_id,
+                    message: "size is negative".into(),
+                })?;
+            if next_chunk_offset >= data_size {
+                return Err(Error::InvalidChunkSize {
+                    id: chunk_id,
+                    message: "chunk extends beyond end of file".into(),
+                });
+            }
+
+            let set_once = |slot: &mut Option<(usize, usize)>| -> Result<(), Error> {
+                if slot.replace((chunk_offset, chunk_size)).is_some() {
+                    return Err(Error::DuplicateChunk(chunk_id));
+                }
+                Ok(())
+            };
+            match chunk_id {
+                PACK_NAMES_CHUNK_ID => set_once(&mut pack_names_range)?,
+                OID_FAN_CHUNK_ID => set_once(&mut fan_ofs)?,
+                OID_LOOKUP_CHUNK_ID => set_once(&mut lookup)?,
+                OBJECT_OFFSETS_CHUNK_ID => set_once(&mut offsets)?,
+                LARGE_OFFSETS_CHUNK_ID => set_once(&mut large_offsets_ofs)?,
+                _unknown_chunks_are_ignored => {}
+            }
+
+            chunk_id = next_chunk_id;
+            chunk_offset = next_chunk_offset;
+        }
+        if chunk_id != SENTINEL_CHUNK_ID {
+            return Err(corrupt(format!(
+                "Multi-pack index file has invalid last chunk ID: {:?}",
+                chunk_id.as_bstr()
+            )));
+        }
+        if data_size.saturating_sub(chunk_offset) != TRAILER_LEN {
+            return Err(corrupt(format!(
+                "Expected multi-pack index trailer to contain {} bytes, got {}",
+                TRAILER_LEN,
+                data_size.saturating_sub(chunk_offset)
+            )));
+        }
+
+        let (fan_ofs, fan_size) = fan_ofs.ok_or(Error::MissingChunk(OID_FAN_CHUNK_ID))?;
+        if fan_size != FAN_LEN * 4 {
+            return Err(Error::InvalidChunkSize {
+                id: OID_FAN_CHUNK_ID,
+                message: format!("expected chunk length {}, got {}", FAN_LEN * 4, fan_size),
+            });
+        }
+        let fan = read_fan(&data[fan_ofs..]);
+        let num_objects = fan[FAN_LEN - 1];
+
+        let (lookup_ofs, lookup_size) = lookup.ok_or(Error::MissingChunk(OID_LOOKUP_CHUNK_ID))?;
+        if lookup_size != num_objects as usize * SHA1_SIZE {
+            return Err(Error::InvalidChunkSize {
+                id: OID_LOOKUP_CHUNK_ID,
+                message: format!("expected {} object ids, got {} bytes", num_objects, lookup_size),
+            });
+        }
+        let (offsets_ofs, offsets_size) = offsets.ok_or(Error::MissingChunk(OBJECT_OFFSETS_CHUNK_ID))?;
+        if offsets_size != num_objects as usize * OBJECT_OFFSET_ENTRY_SIZE {
+            return Err(Error::InvalidChunkSize {
+                id: OBJECT_OFFSETS_CHUNK_ID,
+                message: format!("expected {} object offsets, got {} bytes", num_objects, offsets_size),
+            });
+        }
+        let large_offsets_ofs = match large_offsets_ofs {
+            Some((ofs, size)) if size % LARGE_OFFSET_ENTRY_SIZE == 0 => Some(ofs),
+            Some((_, size)) => {
+                return Err(Error::InvalidChunkSize {
+                    id: LARGE_OFFSETS_CHUNK_ID,
+                    message: format!("chunk size {} is not a multiple of {}", size, LARGE_OFFSET_ENTRY_SIZE),
+                })
+            }
+            None => None,
+        };
+
+        let (pack_names_ofs, pack_names_size) = pack_names_range.ok_or(Error::MissingChunk(PACK_NAMES_CHUNK_ID))?;
+        let index_names = read_index_names(&data[pack_names_ofs..pack_names_ofs + pack_names_size], num_packs)?;
+
+        Ok(File {
+            data,
+            path: path.to_owned(),
+            version,
+            num_objects,
+            num_packs,
+            fan,
+            index_names,
+            lookup_ofs,
+            offsets_ofs,
+            large_offsets_ofs,
+        })
+    }
+}
+
+fn corrupt(message: impl Into<String>) -> Error {
+    Error::Corrupt {
+        message: message.into(),
+    }
+}
+
+fn read_index_names(chunk: &[u8], num_packs: u32) -> Result<Vec<PathBuf>, Error> {
+    // Names are null-terminated, with the chunk possibly being padded with null bytes to a multiple of 4 bytes.
+    let names: Vec<_> = chunk
+        .split(|b| *b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            name.to_str()
+                .map(PathBuf::from)
+                .map_err(|_| corrupt(format!("Pack index name {:?} is not valid UTF-8", name.as_bstr())))
+        })
+        .collect::<Result<_, _>>()?;
+    if names.len() != num_packs as usize {
+        return Err(Error::InvalidChunkSize {
+            id: PACK_NAMES_CHUNK_ID,
+            message: format!("expected {} pack index names, got {}", num_packs, names.len()),
+        });
+    }
+    if names.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(corrupt("Pack index names are not sorted"));
+    }
+    Ok(names)
+}
+
+fn read_fan(d: &[u8]) -> [u32; FAN_LEN] {
+    let mut fan = [0; FAN_LEN];
+    for (c, f) in d.chunks(4).zip(fan.iter_mut()) {
+        *f = BigEndian::read_u32(c);
+    }
+    fan
+}
@@ -0,0 +1,45 @@
+//! A multi-pack index, mapping object ids to packs and their offsets within these, to avoid probing each pack index in turn.
+use filebuffer::FileBuffer;
+use std::path::PathBuf;
+
+/// The version of a multi-pack index file
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    V1 = 1,
+}
+
+/// An index into the list of packs of a multi-pack index, referring to the pack index file at the same position
+/// in [`index_names()`][File::index_names()].
+pub type PackIndex = u32;
+
+/// A representation of an index file for multiple packs at the same time, typically stored in a file
+/// named `multi-pack-index`.
+pub struct File {
+    data: FileBuffer,
+    path: PathBuf,
+    version: Version,
+    num_objects: u32,
+    num_packs: u32,
+    fan: [u32; FAN_LEN],
+    index_names: Vec<PathBuf>,
+    lookup_ofs: usize,
+    offsets_ofs: usize,
+    large_offsets_ofs: Option<usize>,
+}
+
+const FAN_LEN: usize = 256;
+const SIGNATURE: &[u8] = b"MIDX";
+
+///
+pub mod init;
+
+mod access;
+pub use access::Entry;
+
+///
+pub mod verify;
+
+///
+pub mod write;
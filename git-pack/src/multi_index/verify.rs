@@ -0,0 +1,244 @@
+use crate::{index, multi_index::File};
+use git_features::progress::Progress;
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+///
+pub mod integrity {
+    use crate::multi_index::PackIndex;
+    use std::path::PathBuf;
+
+    /// Returned by [`multi_index::File::verify_integrity()`][crate::multi_index::File::verify_integrity()].
+    #[derive(thiserror::Error, Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Checksum(#[from] super::checksum::Error),
+        #[error("The fan at index {index} is out of order as it's larger then the following value.")]
+        Fan { index: usize },
+        #[error("The object {id} at index {index} is out of order relative to its predecessor {predecessor_id}")]
+        OutOfOrder {
+            id: git_hash::ObjectId,
+            index: u32,
+            predecessor_id: git_hash::ObjectId,
+        },
+        #[error("The object {id} refers to pack {pack_index} which doesn't exist as there are only {num_packs} packs")]
+        PackIndexOutOfBounds {
+            id: git_hash::ObjectId,
+            pack_index: PackIndex,
+            num_packs: PackIndex,
+        },
+        #[error("Could not open the pack index file at '{}'", .path.display())]
+        IndexOpen {
+            source: crate::index::init::Error,
+            path: PathBuf,
+        },
+        #[error("The object {id} should be in pack index '{}', but wasn't found there", .index_path.display())]
+        MissingFromPackIndex {
+            id: git_hash::ObjectId,
+            index_path: PathBuf,
+        },
+        #[error("The object {id} is recorded at pack offset {expected}, but pack index '{}' has it at {actual}", .index_path.display())]
+        PackOffsetMismatch {
+            id: git_hash::ObjectId,
+            index_path: PathBuf,
+            expected: u64,
+            actual: u64,
+        },
+        #[error("The object {id} of pack index '{}' is not contained in the multi-pack index", .index_path.display())]
+        MissingFromMultiIndex {
+            id: git_hash::ObjectId,
+            index_path: PathBuf,
+        },
+        #[error("Interrupted")]
+        Interrupted,
+    }
+
+    /// Returned by [`multi_index::File::verify_integrity()`][crate::multi_index::File::verify_integrity()].
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Outcome {
+        /// The checksum of the multi-pack index file.
+        pub actual_index_checksum: git_hash::ObjectId,
+        /// The amount of objects verified in the multi-pack index and the pack indices it refers to.
+        pub num_objects: u32,
+        /// The paths to all pack indices that were checked against the multi-pack index.
+        pub index_paths: Vec<PathBuf>,
+    }
+}
+
+///
+pub mod checksum {
+    /// Returned by [`multi_index::File::verify_checksum()`][crate::multi_index::File::verify_checksum()].
+    #[derive(thiserror::Error, Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("multi-pack index checksum mismatch: expected {expected}, got {actual}")]
+        Mismatch {
+            expected: git_hash::ObjectId,
+            actual: git_hash::ObjectId,
+        },
+    }
+}
+
+/// Verify and validate the content of the multi-pack index file
+impl File {
+    /// Returns the trailing hash stored at the end of this multi-pack index file.
+    ///
+    /// It's a hash over all bytes of the file.
+    pub fn checksum(&self) -> git_hash::ObjectId {
+        git_hash::ObjectId::from_20_bytes(&self.data()[self.checksum_offset()..])
+    }
+
+    /// Validate that our [`checksum()`][File::checksum()] matches the actual contents of this file, and return
+    /// it if it does.
+    pub fn verify_checksum(
+        &self,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<git_hash::ObjectId, checksum::Error> {
+        let data_len_without_trailer = self.checksum_offset();
+        let actual = match git_features::hash::bytes_of_file(
+            self.path(),
+            data_len_without_trailer,
+            git_hash::Kind::Sha1,
+            &mut progress,
+            should_interrupt,
+        ) {
+            Ok(id) => id,
+            Err(_io_err) => {
+                let start = std::time::Instant::now();
+                let mut hasher = git_features::hash::Sha1::default();
+                hasher.update(&self.data()[..data_len_without_trailer]);
+                progress.inc_by(data_len_without_trailer);
+                progress.show_throughput(start);
+                git_hash::ObjectId::new_sha1(hasher.digest())
+            }
+        };
+
+        let expected = self.checksum();
+        if actual == expected {
+            Ok(actual)
+        } else {
+            Err(checksum::Error::Mismatch { actual, expected })
+        }
+    }
+
+    /// Similar to [`index::File::verify_integrity()`][crate::index::File::verify_integrity()], validate the checksum
+    /// of this file, the order of all object ids along with the fan-out table, and that each object is located
+    /// exactly where the respective pack index says it is.
+    ///
+    /// All pack indices referred to by this file are expected to be in the same directory as this file. Each of
+    /// their objects must be contained in this file, but objects contained in multiple packs are only required to
+    /// be recorded in one of them.
+    pub fn verify_integrity(
+        &self,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<integrity::Outcome, integrity::Error> {
+        let actual_index_checksum =
+            self.verify_checksum(progress.add_child("Sha1 of multi-pack index"), should_interrupt)?;
+
+        let fan = self.fan();
+        if let Some(index) = fan.windows(2).position(|w| w[0] > w[1]) {
+            return Err(integrity::Error::Fan { index });
+        }
+
+        let parent = self.path().parent().expect("file in a directory");
+        let index_paths: Vec<PathBuf> = self.index_names().iter().map(|name| parent.join(name)).collect();
+        let indices = index_paths
+            .iter()
+            .map(|path| {
+                index::File::at(path).map_err(|source| integrity::Error::IndexOpen {
+                    source,
+                    path: path.to_owned(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut objects_progress = progress.add_child("verify object locations");
+        objects_progress.init(
+            Some(self.num_objects() as usize),
+            git_features::progress::count("objects"),
+        );
+        let mut fan_index = 0;
+        for idx in 0..self.num_objects() {
+            let id = self.oid_at_index(idx);
+            if idx > 0 {
+                let predecessor = self.oid_at_index(idx - 1);
+                if predecessor >= id {
+                    return Err(integrity::Error::OutOfOrder {
+                        id: id.to_owned(),
+                        index: idx,
+                        predecessor_id: predecessor.to_owned(),
+                    });
+                }
+            }
+            while fan_index < id.first_byte() as usize {
+                if fan[fan_index] != idx {
+                    return Err(integrity::Error::Fan { index: fan_index });
+                }
+                fan_index += 1;
+            }
+
+            let (pack_index, expected) = self.pack_id_and_pack_offset_at_index(idx);
+            let index = indices
+                .get(pack_index as usize)
+                .ok_or_else(|| integrity::Error::PackIndexOutOfBounds {
+                    id: id.to_owned(),
+                    pack_index,
+                    num_packs: self.num_packs(),
+                })?;
+            let index_path = &index_paths[pack_index as usize];
+            let actual = index
+                .lookup(id)
+                .map(|entry_index| index.pack_offset_at_index(entry_index))
+                .ok_or_else(|| integrity::Error::MissingFromPackIndex {
+                    id: id.to_owned(),
+                    index_path: index_path.to_owned(),
+                })?;
+            if actual != expected {
+                return Err(integrity::Error::PackOffsetMismatch {
+                    id: id.to_owned(),
+                    index_path: index_path.to_owned(),
+                    expected,
+                    actual,
+                });
+            }
+            objects_progress.inc();
+            if should_interrupt.load(std::sync::atomic::Ordering::Relaxed) {
+                return Err(integrity::Error::Interrupted);
+            }
+        }
+        if let Some(index) = fan[fan_index..].iter().position(|count| *count != self.num_objects()) {
+            return Err(integrity::Error::Fan {
+                index: fan_index + index,
+            });
+        }
+
+        let mut pack_objects_progress = progress.add_child("verify pack index objects are covered");
+        pack_objects_progress.init(
+            Some(indices.iter().map(|index| index.num_objects() as usize).sum()),
+            git_features::progress::count("objects"),
+        );
+        for (index, index_path) in indices.iter().zip(index_paths.iter()) {
+            for entry in index.iter() {
+                if self.lookup(entry.oid).is_none() {
+                    return Err(integrity::Error::MissingFromMultiIndex {
+                        id: entry.oid,
+                        index_path: index_path.to_owned(),
+                    });
+                }
+                pack_objects_progress.inc();
+            }
+            if should_interrupt.load(std::sync::atomic::Ordering::Relaxed) {
+                return Err(integrity::Error::Interrupted);
+            }
+        }
+
+        Ok(integrity::Outcome {
+            actual_index_checksum,
+            num_objects: self.num_objects(),
+            index_paths,
+        })
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{
+    index,
+    multi_index::{
+        init::{
+            ChunkId, CHUNK_LOOKUP_SIZE, HEADER_LEN, LARGE_OFFSETS_CHUNK_ID, LARGE_OFFSET_ENTRY_SIZE,
+            OBJECT_OFFSETS_CHUNK_ID, OBJECT_OFFSET_ENTRY_SIZE, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID,
+            PACK_NAMES_CHUNK_ID, SENTINEL_CHUNK_ID,
+        },
+        File, PackIndex, Version, FAN_LEN, SIGNATURE,
+    },
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::{hash, progress, progress::Progress};
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    sync::atomic::AtomicBool,
+    time::SystemTime,
+};
+
+/// Returned by [`File::write_from_index_paths()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Could not open the pack index file at '{}'", .path.display())]
+    IndexOpen { source: index::init::Error, path: PathBuf },
+    #[error("The index path '{}' doesn't have a file name", .0.display())]
+    InvalidIndexPath(PathBuf),
+    #[error("The preferred pack '{}' is not among the packs to write", .0.display())]
+    PreferredPackNotFound(PathBuf),
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+/// Configuration for [`File::write_from_index_paths()`].
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// The name or path of the pack, or its index, from which to take objects that are contained in multiple packs.
+    ///
+    /// If `None`, duplicate objects are taken from the most recently modified pack, like git does.
+    pub preferred_pack: Option<PathBuf>,
+}
+
+/// Returned by [`File::write_from_index_paths()`].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The checksum of the written multi-pack index, also stored in its trailer.
+    pub multi_index_checksum: git_hash::ObjectId,
+    /// The amount of unique objects in the written multi-pack index.
+    pub num_objects: u32,
+    /// The amount of packs in the written multi-pack index.
+    pub num_packs: PackIndex,
+}
+
+struct Entry {
+    id: git_hash::ObjectId,
+    pack_index: PackIndex,
+    pack_offset: u64,
+}
+
+/// Writing
+impl File {
+    /// Write a multi-pack index covering all pack indices at `index_paths` into `out`, which typically is a file named
+    /// `multi-pack-index` in the same directory as the pack indices.
+    ///
+    /// Objects contained in more than one pack are recorded only once, taken from the preferred pack as configured
+    /// in `options`, or from the pack with the most recent modification time.
+    /// Note that only the file names of the pack indices are stored, which is why they are expected to be located in
+    /// the directory that will contain the multi-pack index.
+    pub fn write_from_index_paths(
+        mut index_paths: Vec<PathBuf>,
+        out: impl io::Write,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+        Options { preferred_pack }: Options,
+    ) -> Result<Outcome, Error> {
+        let index_names = index_paths
+            .iter()
+            .map(|path| {
+                path.file_name()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| Error::InvalidIndexPath(path.to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut paths_and_names: Vec<_> = index_paths.drain(..).zip(index_names).collect();
+        paths_and_names.sort_by(|a, b| a.1.cmp(&b.1));
+        paths_and_names.dedup_by(|a, b| a.1 == b.1);
+
+        let preferred_pack_index = match preferred_pack {
+            Some(preferred_pack) => Some(
+                paths_and_names
+                    .iter()
+                    .position(|(_, name)| {
+                        preferred_pack
+                            .file_name()
+                            .map(|preferred| PathBuf::from(preferred).with_extension(""))
+                            == Some(name.with_extension(""))
+                    })
+                    .ok_or(Error::PreferredPackNotFound(preferred_pack))?,
+            ),
+            None => None,
+        };
+
+        let mut entries = Vec::new();
+        let mut pack_mtimes = Vec::with_capacity(paths_and_names.len());
+        {
+            let mut read_progress = progress.add_child("read pack indices");
+            read_progress.init(Some(paths_and_names.len()), progress::count("indices"));
+            for (pack_index, (path, _)) in paths_and_names.iter().enumerate() {
+                let index = index::File::at(path).map_err(|source| Error::IndexOpen {
+                    source,
+                    path: path.to_owned(),
+                })?;
+                pack_mtimes.push(
+                    std::fs::metadata(path.with_extension("pack"))
+                        .or_else(|_| std::fs::metadata(path))?
+                        .modified()
+                        .unwrap_or(SystemTime::UNIX_EPOCH),
+                );
+                entries.reserve(index.num_objects() as usize);
+                entries.extend(index.iter().map(|entry| Entry {
+                    id: entry.oid,
+                    pack_index: pack_index as PackIndex,
+                    pack_offset: entry.pack_offset,
+                }));
+                read_progress.inc();
+                if should_interrupt.load(std::sync::atomic::Ordering::Relaxed) {
+                    return Err(Error::Interrupted);
+                }
+            }
+        }
+
+        // Like git, among duplicates we prefer the preferred pack, then the most recently modified one, then the first one.
+        let is_preferred = |e: &Entry| Some(e.pack_index as usize) == preferred_pack_index;
+        entries.sort_by(|a, b| {
+            a.id.cmp(&b.id)
+                .then_with(|| is_preferred(b).cmp(&is_preferred(a)))
+                .then_with(|| pack_mtimes[b.pack_index as usize].cmp(&pack_mtimes[a.pack_index as usize]))
+                .then_with(|| a.pack_index.cmp(&b.pack_index))
+        });
+        entries.dedup_by_key(|e| e.id);
+        if should_interrupt.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+
+        let names: Vec<_> = paths_and_names.into_iter().map(|(_, name)| name).collect();
+        let checksum = write_to(out, &names, &entries, progress.add_child("write multi-pack index"))?;
+        Ok(Outcome {
+            multi_index_checksum: checksum,
+            num_objects: entries.len() as u32,
+            num_packs: names.len() as PackIndex,
+        })
+    }
+}
+
+const LARGE_OFFSET_THRESHOLD: u64 = 0x7fff_ffff;
+const HIGH_BIT: u32 = 0x8000_0000;
+// Chunks are aligned to multiples of 4 bytes.
+const CHUNK_ALIGNMENT: usize = 4;
+
+fn write_to(
+    out: impl io::Write,
+    index_names: &[PathBuf],
+    entries_sorted_by_oid: &[Entry],
+    mut progress: impl Progress,
+) -> Result<git_hash::ObjectId, Error> {
+    assert!(
+        entries_sorted_by_oid.len() <= u32::MAX as usize,
+        "a multi-pack index cannot have more than u32::MAX objects"
+    );
+    let index_names = index_names
+        .iter()
+        .map(|name| {
+            name.to_str()
+                .ok_or_else(|| Error::InvalidIndexPath(name.to_owned()))
+                .map(str::as_bytes)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let num_large_offsets = entries_sorted_by_oid
+        .iter()
+        .filter(|e| e.pack_offset > LARGE_OFFSET_THRESHOLD)
+        .count();
+
+    let pack_names_size = {
+        let size: usize = index_names.iter().map(|name| name.len() + 1).sum();
+        size + (CHUNK_ALIGNMENT - size % CHUNK_ALIGNMENT) % CHUNK_ALIGNMENT
+    };
+    let mut chunks: Vec<(ChunkId, usize)> = vec![
+        (PACK_NAMES_CHUNK_ID, pack_names_size),
+        (OID_FAN_CHUNK_ID, FAN_LEN * 4),
+        (OID_LOOKUP_CHUNK_ID, entries_sorted_by_oid.len() * SHA1_SIZE),
+        (
+            OBJECT_OFFSETS_CHUNK_ID,
+            entries_sorted_by_oid.len() * OBJECT_OFFSET_ENTRY_SIZE,
+        ),
+    ];
+    if num_large_offsets > 0 {
+        chunks.push((LARGE_OFFSETS_CHUNK_ID, num_large_offsets * LARGE_OFFSET_ENTRY_SIZE));
+    }
+
+    let start = std::time::Instant::now();
+    progress.init(Some(chunks.len()), progress::steps());
+    let mut out = hash::Write::new(io::BufWriter::with_capacity(8 * 4096, out), git_hash::Kind::Sha1);
+
+    out.write_all(SIGNATURE)?;
+    out.write_u8(Version::V1 as u8)?;
+    out.write_u8(1 /* SHA1 */)?;
+    out.write_u8(chunks.len() as u8)?;
+    out.write_u8(0 /* base multi-pack index files */)?;
+    out.write_u32::<BigEndian>(index_names.len() as u32)?;
+
+    let mut chunk_offset = HEADER_LEN + (chunks.len() + 1) * CHUNK_LOOKUP_SIZE;
+    for (id, size) in chunks.iter() {
+        out.write_all(id)?;
+        out.write_u64::<BigEndian>(chunk_offset as u64)?;
+        chunk_offset += size;
+    }
+    out.write_all(&SENTINEL_CHUNK_ID)?;
+    out.write_u64::<BigEndian>(chunk_offset as u64)?;
+
+    let mut written = 0;
+    for name in &index_names {
+        out.write_all(name)?;
+        out.write_u8(0)?;
+        written += name.len() + 1;
+    }
+    out.write_all(&[0u8; CHUNK_ALIGNMENT][..pack_names_size - written])?;
+    progress.inc();
+
+    let mut fan = [0u32; FAN_LEN];
+    for entry in entries_sorted_by_oid {
+        fan[entry.id.first_byte() as usize] += 1;
+    }
+    let mut total = 0;
+    for count in fan.iter_mut() {
+        total += *count;
+        *count = total;
+    }
+    for count in fan.iter() {
+        out.write_u32::<BigEndian>(*count)?;
+    }
+    progress.inc();
+
+    for entry in entries_sorted_by_oid {
+        out.write_all(entry.id.as_slice())?;
+    }
+    progress.inc();
+
+    let mut large_offsets = Vec::with_capacity(num_large_offsets);
+    for entry in entries_sorted_by_oid {
+        out.write_u32::<BigEndian>(entry.pack_index)?;
+        out.write_u32::<BigEndian>(if entry.pack_offset > LARGE_OFFSET_THRESHOLD {
+            large_offsets.push(entry.pack_offset);
+            (large_offsets.len() - 1) as u32 | HIGH_BIT
+        } else {
+            entry.pack_offset as u32
+        })?;
+    }
+    progress.inc();
+
+    for offset in large_offsets {
+        out.write_u64::<BigEndian>(offset)?;
+    }
+    if num_large_offsets > 0 {
+        progress.inc();
+    }
+
+    let hash::Write { hash, inner } = out;
+    let checksum = git_hash::ObjectId::from(hash.digest());
+    let mut out = inner.into_inner().map_err(|err| err.into_error())?;
+    out.write_all(checksum.as_slice())?;
+    out.flush()?;
+    progress.show_throughput_with(
+        start,
+        chunk_offset + SHA1_SIZE,
+        progress::bytes().expect("unit always set"),
+    );
+
+    Ok(checksum)
+}
@@ -113,6 +113,7 @@ impl<T> Tree<T> {
             }
         }
 
+        tree.assert_bases_of_children_present()?;
         progress.show_throughput(then);
         Ok(tree)
     }
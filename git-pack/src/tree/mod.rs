@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 /// Returned when using various methods on a [`Tree`]
 #[derive(thiserror::Error, Debug)]
@@ -38,7 +38,7 @@ pub struct Item<T> {
     pub next_offset: u64,
     /// Data to store with each Item, effectively data associated with each entry in a pack.
     pub data: T,
-    children: Vec<usize>,
+    pub(crate) children: Vec<usize>,
 }
 /// A tree that allows one-time iteration over all nodes and their children, consuming it in the process,
 /// while being shareable among threads without a lock.
@@ -48,6 +48,8 @@ pub struct Tree<T> {
     items: VecDeque<Item<T>>,
     roots: usize,
     last_index: usize,
+    /// Children by the pack offset of their base, which follows them in the pack.
+    children_of_later_bases: HashMap<u64, Vec<usize>>,
 }
 
 impl<T> Tree<T> {
@@ -60,6 +62,7 @@ impl<T> Tree<T> {
             items: VecDeque::with_capacity(num_objects),
             roots: 0,
             last_index: 0,
+            children_of_later_bases: HashMap::new(),
         })
     }
 
@@ -74,13 +77,17 @@ impl<T> Tree<T> {
                 pack_offset: offset,
             });
         }
-        self.items[self.last_index].next_offset = offset;
+        self.set_pack_entries_end(offset);
         Ok(())
     }
 
-    fn set_pack_entries_end(&mut self, pack_entries_end: u64) {
-        if !self.items.is_empty() {
-            self.items[self.last_index].next_offset = pack_entries_end;
+    /// Set the end of the last item added to `pack_entries_end` unless it is already known, which is the case if it was
+    /// followed by pack entries which aren't part of this tree.
+    pub(crate) fn set_pack_entries_end(&mut self, pack_entries_end: u64) {
+        if let Some(item) = self.items.get_mut(self.last_index) {
+            if item.next_offset == 0 {
+                item.next_offset = pack_entries_end;
+            }
         }
     }
 
@@ -93,13 +100,16 @@ impl<T> Tree<T> {
             offset,
             next_offset: 0,
             data,
-            children: Vec::new(),
+            children: self.children_of_later_bases.remove(&offset).unwrap_or_default(),
         });
         self.roots += 1;
         Ok(())
     }
 
     /// Add a child of the item at `base_offset` which itself resides at pack `offset` and associate custom `data` with it.
+    ///
+    /// The base may follow its child in the pack, which is the case for ref deltas of thin packs that were completed by
+    /// appending their bases. It must be added before the tree is used though.
     pub fn add_child(&mut self, base_offset: u64, offset: u64, data: T) -> Result<(), Error> {
         self.assert_is_incrementing(offset)?;
         let (roots, children) = self.items.as_mut_slices();
@@ -108,7 +118,12 @@ impl<T> Tree<T> {
             self.roots,
             "item deque has been resized, maybe we added more nodes than we declared in the constructor?"
         );
-        if let Ok(i) = children.binary_search_by_key(&base_offset, |i| i.offset) {
+        if base_offset > offset {
+            self.children_of_later_bases
+                .entry(base_offset)
+                .or_default()
+                .push(children.len());
+        } else if let Ok(i) = children.binary_search_by_key(&base_offset, |i| i.offset) {
             children[i].children.push(children.len());
         } else if let Ok(i) = roots.binary_search_by(|i| base_offset.cmp(&i.offset)) {
             roots[i].children.push(children.len());
@@ -123,11 +138,22 @@ impl<T> Tree<T> {
             offset,
             next_offset: 0,
             data,
-            children: Vec::new(),
+            children: self.children_of_later_bases.remove(&offset).unwrap_or_default(),
         });
         Ok(())
     }
 
+    /// Fail if a child was added whose base follows it in the pack but wasn't added yet.
+    pub(crate) fn assert_bases_of_children_present(&self) -> Result<(), Error> {
+        match self.children_of_later_bases.iter().next() {
+            Some((base_offset, children)) => Err(Error::InvariantBasesBeforeDeltasNeedThem {
+                delta_pack_offset: self.items.as_slices().1[children[0]].offset,
+                base_pack_offset: *base_offset,
+            }),
+            None => Ok(()),
+        }
+    }
+
     /// Transform this `Tree` into its items.
     pub fn into_items(self) -> VecDeque<Item<T>> {
         self.items
@@ -0,0 +1,29 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config gc.auto 0
+
+function commit_files() {
+  local round=${1:?the round to make contents unique}
+  for file_id in $(seq 10); do
+    seq "$file_id" > "$file_id"
+    echo "$round" >> "$file_id"
+  done
+  git add .
+  git commit -qm "$round"
+}
+
+git checkout -q -b main
+for round in $(seq 3); do
+  commit_files "$round"
+  # each repack without -a adds a pack with the new objects only
+  git repack -q -d
+done
+
+git multi-pack-index write
+
+# a pack that isn't covered by the multi-pack index
+commit_files 4
+git repack -q -d
@@ -0,0 +1,38 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config gc.auto 0
+
+function commit_files() {
+  local round=${1:?the round to make contents unique}
+  for file_id in $(seq 10); do
+    seq "$file_id" > "$file_id"
+    echo "$round" >> "$file_id"
+  done
+  git add .
+  git commit -qm "$round"
+}
+
+git checkout -q -b main
+for round in $(seq 3); do
+  commit_files "$round"
+  git repack -q -d
+done
+
+# a pack with all objects, duplicating the ones of all other packs
+git repack -q -a
+
+largest_pack=
+largest_count=0
+for index in .git/objects/pack/*.idx; do
+  count=$(git show-index < "$index" | wc -l)
+  if [ "$count" -gt "$largest_count" ]; then
+    largest_count=$count
+    largest_pack=$(basename "${index%.idx}.pack")
+  fi
+done
+
+git multi-pack-index write --preferred-pack="$largest_pack"
+echo "$largest_pack" > preferred-pack
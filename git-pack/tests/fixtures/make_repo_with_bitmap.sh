@@ -0,0 +1,39 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config gc.auto 0
+
+function commit_files() {
+  local round=${1:?the round to make contents unique}
+  mkdir -p dir
+  for file_id in $(seq 5); do
+    seq "$file_id" > "$file_id"
+    echo "$round" >> "$file_id"
+    echo "$round" > "dir/$file_id.txt"
+  done
+  git add .
+  git commit -qm "$round"
+}
+
+git checkout -q -b main
+for round in $(seq 20); do
+  commit_files "$round"
+  if [ "$round" = 10 ]; then
+    git tag -m "annotated" v1
+  fi
+done
+
+# a single pack with a bitmap, with name-hashes and the lookup table of newer versions
+git -c pack.writeBitmapHashCache=true -c pack.writeBitmapLookupTable=true repack -q -a -d -b
+
+# objects that are only reachable from commits without a bitmap, stored as loose objects
+for round in $(seq 21 22); do
+  commit_files "$round"
+done
+
+git rev-list --objects main v1 | cut -d' ' -f1 > .git/all-objects
+git rev-list --objects main | cut -d' ' -f1 > .git/objects-of-main
+git rev-list --objects main~3 | cut -d' ' -f1 > .git/objects-of-main~3
+git rev-list --objects main~15 | cut -d' ' -f1 > .git/objects-of-main~15
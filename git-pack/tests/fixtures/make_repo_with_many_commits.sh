@@ -0,0 +1,42 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config gc.auto 0
+
+time=1600000000
+function commit() {
+  local message=${1:?the message to make contents unique}
+  time=$((time + 60))
+  echo "$message" > file
+  git add file
+  GIT_AUTHOR_DATE="$time +0000" GIT_COMMITTER_DATE="$time +0000" git commit -qm "$message"
+}
+
+function merge() {
+  local branch=${1:?the branch to merge}
+  time=$((time + 60))
+  GIT_AUTHOR_DATE="$time +0000" GIT_COMMITTER_DATE="$time +0000" git merge -q --no-ff -s ours -m "merge $branch" "$branch"
+}
+
+git checkout -q -b main
+for round in $(seq 80); do
+  if [ $((round % 2)) = 0 ]; then
+    git checkout -q -b "side-$round"
+    commit "side $round"
+    git checkout -q main
+    commit "$round"
+    merge "side-$round"
+  else
+    commit "$round"
+  fi
+done
+
+git repack -q -a -d
+
+# all commits from newest to oldest, the order in which they are selected to receive a bitmap
+git rev-list --date-order main > .git/commits-newest-first
+# The commits at index 115 to 130 form a single region in which one commit is selected, preferring merges.
+sed -n '116,131p' .git/commits-newest-first > .git/commits-of-region
+git rev-list --merges --no-walk $(cat .git/commits-of-region) > .git/merges-of-region
@@ -0,0 +1,282 @@
+use crate::scripted_fixture_repo_read_only;
+use git_pack::{bitmap, cache, Bundle};
+use std::path::PathBuf;
+
+pub fn pack_dir() -> crate::Result<PathBuf> {
+    Ok(scripted_fixture_repo_read_only("make_repo_with_bitmap.sh")?
+        .join(".git")
+        .join("objects")
+        .join("pack"))
+}
+
+pub fn fixture() -> crate::Result<(Bundle, bitmap::File)> {
+    let dir = pack_dir()?;
+    let index_path = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .find(|path| path.extension().unwrap_or_default() == "idx")
+        .expect("a single pack");
+    Ok((
+        Bundle::at(&index_path)?,
+        bitmap::File::at(index_path.with_extension("bitmap"))?,
+    ))
+}
+
+/// The position of each object in pack order, indexed by the position of the object in the pack index.
+fn pack_positions(bundle: &Bundle) -> Vec<usize> {
+    let mut indices: Vec<_> = (0..bundle.index.num_objects()).collect();
+    indices.sort_by_key(|index| bundle.index.pack_offset_at_index(*index));
+    let mut positions = vec![0; indices.len()];
+    for (position, index) in indices.into_iter().enumerate() {
+        positions[index as usize] = position;
+    }
+    positions
+}
+
+#[test]
+fn access() -> crate::Result {
+    let (bundle, file) = fixture()?;
+    assert_eq!(file.version(), bitmap::Version::V1);
+    assert_eq!(file.pack_checksum(), bundle.index.pack_checksum());
+    assert!(file.num_commits() > 0, "some commits are selected to receive a bitmap");
+    assert_eq!(file.commit_indices().count(), file.num_commits() as usize);
+
+    let positions = pack_positions(&bundle);
+    let kinds = [
+        git_object::Kind::Commit,
+        git_object::Kind::Tree,
+        git_object::Kind::Blob,
+        git_object::Kind::Tag,
+    ];
+    let bitmaps_by_kind: Vec<_> = kinds.iter().map(|kind| file.objects_of_kind(*kind)).collect();
+    assert_eq!(
+        bitmaps_by_kind.iter().map(|bits| bits.count_ones()).sum::<usize>(),
+        bundle.index.num_objects() as usize,
+        "each object has exactly one kind"
+    );
+
+    let mut buf = Vec::new();
+    for index in 0..bundle.index.num_objects() {
+        let kind = bundle.get_object_by_index(index, &mut buf, &mut cache::Never)?.kind;
+        let bits = &bitmaps_by_kind[kinds.iter().position(|k| *k == kind).expect("known kind")];
+        assert!(bits.is_set(positions[index as usize]));
+        assert!(
+            file.name_hash_at_index(index).is_some(),
+            "the fixture writes the name-hash cache"
+        );
+        if kind == git_object::Kind::Commit {
+            assert_eq!(file.name_hash_at_index(index), Some(0), "commits have no name");
+        }
+    }
+    assert_eq!(bitmaps_by_kind[3].count_ones(), 1, "there is a single annotated tag");
+
+    for index in file.commit_indices() {
+        let bits = file.bitmap_at_index(index).expect("selected commits have a bitmap");
+        assert!(
+            bits.is_set(positions[index as usize]),
+            "a commit is reachable from itself"
+        );
+        assert!(bits.count_ones() > 1, "the tree and its blobs are reachable as well");
+    }
+    assert!(
+        file.bitmap_at_index(bundle.index.num_objects()).is_none(),
+        "unknown positions have no bitmap"
+    );
+    Ok(())
+}
+
+#[test]
+fn init_fails_on_other_files() -> crate::Result {
+    let (bundle, _) = fixture()?;
+    assert!(matches!(
+        bitmap::File::at(bundle.index.path()),
+        Err(bitmap::init::Error::Corrupt { .. })
+    ));
+    Ok(())
+}
+
+mod write {
+    use super::fixture;
+    use git_features::progress;
+    use git_pack::{bitmap, Bundle};
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn yields_the_same_bitmaps_as_git() -> crate::Result {
+        let (bundle, expected) = fixture()?;
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("pack.bitmap");
+        let outcome = bitmap::File::write_from_bundle(
+            &bundle,
+            std::fs::File::create(&path)?,
+            progress::Discard,
+            &AtomicBool::new(false),
+            Default::default(),
+        )?;
+        let actual = bitmap::File::at(&path)?;
+        assert_eq!(outcome.num_commits, actual.num_commits());
+        assert_eq!(
+            outcome.num_commits,
+            expected.num_commits(),
+            "with less than 100 commits, git selects all of them"
+        );
+        assert_eq!(actual.pack_checksum(), bundle.index.pack_checksum());
+
+        for kind in &[
+            git_object::Kind::Commit,
+            git_object::Kind::Tree,
+            git_object::Kind::Blob,
+            git_object::Kind::Tag,
+        ] {
+            assert_eq!(actual.objects_of_kind(*kind), expected.objects_of_kind(*kind));
+        }
+        for index in expected.commit_indices() {
+            assert_eq!(
+                actual.bitmap_at_index(index),
+                expected.bitmap_at_index(index),
+                "the bitmap of the commit at index {} is the same",
+                index
+            );
+        }
+        for index in 0..bundle.index.num_objects() {
+            assert!(actual.name_hash_at_index(index).is_some());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn without_name_hash_cache() -> crate::Result {
+        let (bundle, _) = fixture()?;
+        let mut buf = Vec::new();
+        bitmap::File::write_from_bundle(
+            &bundle,
+            &mut buf,
+            progress::Discard,
+            &AtomicBool::new(false),
+            bitmap::write::Options {
+                thread_limit: Some(1),
+                name_hash_cache: false,
+            },
+        )?;
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("pack.bitmap");
+        std::fs::write(&path, buf)?;
+        let file = bitmap::File::at(&path)?;
+        assert_eq!(file.name_hash_at_index(0), None);
+        Ok(())
+    }
+
+    #[test]
+    fn is_accepted_by_git() -> crate::Result {
+        let repo = git_testtools::scripted_fixture_repo_writable("make_repo_with_bitmap.sh")?;
+        let pack_dir = repo.path().join(".git").join("objects").join("pack");
+        let index_path = std::fs::read_dir(&pack_dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .find(|path| path.extension().unwrap_or_default() == "idx")
+            .expect("a single pack");
+        let bitmap_path = index_path.with_extension("bitmap");
+        std::fs::remove_file(&bitmap_path)?;
+
+        let bundle = Bundle::at(&index_path)?;
+        bitmap::File::write_from_bundle(
+            &bundle,
+            std::fs::File::create(&bitmap_path)?,
+            progress::Discard,
+            &AtomicBool::new(false),
+            Default::default(),
+        )?;
+
+        for tip in &["main~2", "main~15"] {
+            let output = std::process::Command::new("git")
+                .args(&["rev-list", "--test-bitmap", tip])
+                .current_dir(repo.path())
+                .output()?;
+            assert!(
+                output.status.success(),
+                "git validates the bitmap reachable from {}: {}",
+                tip,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn selects_the_oldest_merge_of_a_region_like_git() -> crate::Result {
+        let repo = crate::scripted_fixture_repo_read_only("make_repo_with_many_commits.sh")?;
+        let index_path = std::fs::read_dir(repo.join(".git").join("objects").join("pack"))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .find(|path| path.extension().unwrap_or_default() == "idx")
+            .expect("a single pack");
+        let bundle = Bundle::at(&index_path)?;
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("pack.bitmap");
+        bitmap::File::write_from_bundle(
+            &bundle,
+            std::fs::File::create(&path)?,
+            progress::Discard,
+            &AtomicBool::new(false),
+            Default::default(),
+        )?;
+        let file = bitmap::File::at(&path)?;
+        let selected: Vec<_> = file
+            .commit_indices()
+            .map(|index| bundle.index.oid_at_index(index).to_owned())
+            .collect();
+
+        let ids = |name: &str| -> crate::Result<Vec<git_hash::ObjectId>> {
+            std::fs::read_to_string(repo.join(".git").join(name))?
+                .lines()
+                .map(|hex| git_hash::ObjectId::from_hex(hex.as_bytes()).map_err(Into::into))
+                .collect()
+        };
+        assert_eq!(
+            selected.len(),
+            101 + 4,
+            "the 101 newest commits are selected, and one commit in each of the following regions"
+        );
+        let merges = ids("merges-of-region")?;
+        assert!(merges.len() > 1, "the region contains multiple merges");
+        let selected_in_region: Vec<_> = ids("commits-of-region")?
+            .into_iter()
+            .filter(|id| selected.contains(id))
+            .collect();
+        assert_eq!(
+            selected_in_region,
+            merges[merges.len() - 1..],
+            "the last merge of the region is chosen, which is the oldest one"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn as_part_of_writing_a_bundle() -> crate::Result {
+        let (bundle, _) = fixture()?;
+        let dir = tempfile::TempDir::new()?;
+        let outcome = Bundle::write_to_directory(
+            std::io::BufReader::new(std::fs::File::open(bundle.pack.path())?),
+            Some(dir.path()),
+            progress::Discard,
+            &AtomicBool::new(false),
+            git_pack::bundle::write::Options {
+                bitmap: Some(Default::default()),
+                ..Default::default()
+            },
+        )?;
+        let bitmap_path = outcome.bitmap_path.expect("a bitmap was requested");
+        assert_eq!(
+            Some(bitmap_path.with_extension("pack")),
+            outcome.data_path,
+            "the bitmap is placed next to the pack"
+        );
+        let file = bitmap::File::at(bitmap_path)?;
+        assert_eq!(file.pack_checksum(), bundle.index.pack_checksum());
+        assert!(file.num_commits() > 0);
+        Ok(())
+    }
+}
@@ -91,6 +91,7 @@ mod write_to_directory {
             pack_kind: pack::data::Version::V2,
             index_path: None,
             data_path: None,
+            bitmap_path: None,
         })
     }
 
@@ -148,6 +149,7 @@ mod write_to_directory {
                 thread_limit: None,
                 iteration_mode: pack::data::input::Mode::Verify,
                 index_kind: pack::index::Version::V2,
+                bitmap: None,
             },
         )
         .map_err(Into::into)
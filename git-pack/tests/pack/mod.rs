@@ -30,8 +30,10 @@ pub fn fixup(v: Vec<u8>) -> Vec<u8> {
     v.replace(b"\r\n", "\n")
 }
 
+mod bitmap;
 mod bundle;
 mod data;
 mod index;
 mod iter;
+mod multi_index;
 mod tree;
@@ -0,0 +1,183 @@
+use crate::scripted_fixture_repo_read_only;
+use git_features::progress;
+use git_pack::{index, multi_index};
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+fn pack_dir() -> crate::Result<PathBuf> {
+    pack_dir_of("make_repo_multi_index.sh")
+}
+
+fn pack_dir_of(script_name: &str) -> crate::Result<PathBuf> {
+    Ok(scripted_fixture_repo_read_only(script_name)?
+        .join(".git")
+        .join("objects")
+        .join("pack"))
+}
+
+fn fixture() -> crate::Result<(multi_index::File, PathBuf)> {
+    let dir = pack_dir()?;
+    Ok((multi_index::File::at(dir.join("multi-pack-index"))?, dir))
+}
+
+#[test]
+fn access() -> crate::Result {
+    let (file, dir) = fixture()?;
+    assert_eq!(file.version(), multi_index::Version::V1);
+    assert_eq!(
+        file.num_packs(),
+        3,
+        "the last pack was added after the multi-pack index was written"
+    );
+    assert_eq!(file.index_names().len(), 3);
+    assert!(file
+        .index_names()
+        .iter()
+        .all(|name| name.extension().unwrap_or_default() == "idx" && dir.join(name).is_file()));
+    assert_eq!(file.num_objects(), 3 * 12, "3 commits with a tree and 10 blobs each");
+
+    let indices = file
+        .index_names()
+        .iter()
+        .map(|name| index::File::at(dir.join(name)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut num_entries = 0;
+    for entry in file.iter() {
+        let idx = file.lookup(&entry.oid).expect("all contained objects can be found");
+        assert_eq!(file.oid_at_index(idx), entry.oid);
+        assert_eq!(
+            file.pack_id_and_pack_offset_at_index(idx),
+            (entry.pack_index, entry.pack_offset)
+        );
+        let index = &indices[entry.pack_index as usize];
+        assert_eq!(
+            index.lookup(&entry.oid).map(|idx| index.pack_offset_at_index(idx)),
+            Some(entry.pack_offset),
+            "the pack offset is the one of the respective pack"
+        );
+        num_entries += 1;
+    }
+    assert_eq!(num_entries, file.num_objects());
+    assert_eq!(
+        file.lookup(git_hash::ObjectId::from_hex(
+            b"ffffffffffffffffffffffffffffffffffffffff"
+        )?),
+        None
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_integrity() -> crate::Result {
+    let (file, dir) = fixture()?;
+    let should_interrupt = AtomicBool::new(false);
+    let outcome = file.verify_integrity(progress::Discard, &should_interrupt)?;
+    assert_eq!(outcome.actual_index_checksum, file.checksum());
+    assert_eq!(outcome.num_objects, file.num_objects());
+    assert_eq!(
+        outcome.index_paths,
+        file.index_names().iter().map(|name| dir.join(name)).collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[test]
+fn init_fails_on_files_that_are_not_multi_pack_indices() -> crate::Result {
+    let (file, dir) = fixture()?;
+    let err = match multi_index::File::at(dir.join(&file.index_names()[0])) {
+        Ok(_) => panic!("a pack index is no multi-pack index"),
+        Err(err) => err,
+    };
+    assert!(matches!(err, multi_index::init::Error::Corrupt { .. }));
+    Ok(())
+}
+
+mod write {
+    use crate::pack::multi_index::{fixture, pack_dir_of};
+    use git_features::progress;
+    use git_pack::multi_index;
+    use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+    #[test]
+    fn from_index_paths_like_git() -> crate::Result {
+        let (file, dir) = fixture()?;
+        let index_paths: Vec<_> = file.index_names().iter().rev().map(|name| dir.join(name)).collect();
+        let mut buf = Vec::new();
+        let outcome = multi_index::File::write_from_index_paths(
+            index_paths,
+            &mut buf,
+            progress::Discard,
+            &AtomicBool::new(false),
+            Default::default(),
+        )?;
+        assert_eq!(outcome.num_packs, 3);
+        assert_eq!(outcome.num_objects, file.num_objects());
+        assert_eq!(outcome.multi_index_checksum, file.checksum());
+        assert_eq!(
+            buf,
+            std::fs::read(file.path())?,
+            "the written file is exactly the same as the one git wrote"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn with_duplicates_from_preferred_pack_like_git() -> crate::Result {
+        let dir = pack_dir_of("make_repo_multi_index_with_duplicates.sh")?;
+        let expected = multi_index::File::at(dir.join("multi-pack-index"))?;
+        let preferred_pack = std::fs::read_to_string(dir.join("..").join("..").join("..").join("preferred-pack"))?;
+        let index_paths: Vec<_> = std::fs::read_dir(&dir)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|p| p.extension().unwrap_or_default() == "idx")
+            .collect();
+        assert_eq!(index_paths.len(), 4);
+
+        let tmp = tempfile::TempDir::new()?;
+        let path = tmp.path().join("multi-pack-index");
+        let outcome = multi_index::File::write_from_index_paths(
+            index_paths,
+            std::fs::File::create(&path)?,
+            progress::Discard,
+            &AtomicBool::new(false),
+            multi_index::write::Options {
+                preferred_pack: Some(PathBuf::from(preferred_pack.trim())),
+            },
+        )?;
+        assert_eq!(outcome.num_objects, 3 * 12, "duplicates are recorded only once");
+        assert_eq!(
+            std::fs::read(&path)?,
+            std::fs::read(expected.path())?,
+            "the written file is exactly the same as the one git wrote"
+        );
+
+        let file = multi_index::File::at(&path)?;
+        let preferred_pack_index = file
+            .index_names()
+            .iter()
+            .position(|name| name.with_extension("pack").to_str() == Some(preferred_pack.trim()))
+            .expect("preferred pack present") as u32;
+        assert!(
+            file.iter().all(|e| e.pack_index == preferred_pack_index),
+            "all objects are taken from the preferred pack"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_preferred_pack_is_an_error() -> crate::Result {
+        let (file, dir) = fixture()?;
+        let err = multi_index::File::write_from_index_paths(
+            file.index_names().iter().map(|name| dir.join(name)).collect(),
+            std::io::sink(),
+            progress::Discard,
+            &AtomicBool::new(false),
+            multi_index::write::Options {
+                preferred_pack: Some("pack-does-not-exist.pack".into()),
+            },
+        )
+        .expect_err("preferred pack must be present");
+        assert!(matches!(err, multi_index::write::Error::PreferredPackNotFound(_)));
+        Ok(())
+    }
+}
@@ -12,7 +12,7 @@
 //! * **files**
 //!   * **[loose][file::Store]**
 //!     * one reference maps to a file on disk
-//!   * **packed**
+//!   * **[packed][packed::Buffer]**
 //!     * references are stored in a single human-readable file, along with their targets if they are symbolic.
 //! * **ref-table**
 //!   * supersedes all of the above to allow handling hundreds of thousands of references.
@@ -25,7 +25,6 @@ mod store;
 pub use store::*;
 
 pub mod transaction {
-    #![allow(missing_docs, dead_code)]
     //! Research
     //!
     //!   * `RefLogOnly`
@@ -56,48 +55,81 @@ pub mod transaction {
     //! |refs/tags/0.1.0          |CreateOrUpdate|peeled  |oid        |force-reflog|     |✔         |✔     |        |               |
 
     use crate::{mutable, SafeRefPath};
-    use std::marker::PhantomData;
+    use bstr::BString;
 
+    /// An update to a reference, creating it if it doesn't exist yet.
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
     pub struct Update {
+        /// How to treat the reference log.
         pub mode: Reflog,
+        /// If not `None`, the reference must exist and its value must agree with `previous`, turning this into a
+        /// compare-and-swap operation.
+        /// Otherwise it functions as `create-or-update`.
         pub previous: Option<mutable::Target>,
+        /// The new state of the reference.
         pub new: mutable::Target,
-        /// Set if this update is coming from a symbolic reference and used to make it appear like it is the one that is handled,
-        /// instead of the referent reference.
-        parent_index: Option<usize>,
+        /// The message to write into the reference log, if one is written.
+        pub message: BString,
     }
 
     /// A description of an edit to perform on a reference
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
     pub enum Edit {
-        /// If previous is not `None`, the ref must exist and its `oid` must agree with the `previous`, and
-        /// we function like `update`.
-        /// Otherwise it functions as `create-or-update`.
+        /// Create or update the reference.
         Update(Update),
+        /// Delete the reference along with its reference log.
         Delete {
+            /// If not `None`, the reference must exist and its value must agree with `previous`.
             previous: Option<mutable::Target>,
+            /// How to treat the reference and its referent if it is symbolic.
+            ///
+            /// Only [`Reflog::OnlyAndDeref`] is meaningful and causes the referent of a symbolic reference to be deleted instead.
+            mode: Reflog,
         },
     }
 
+    impl Edit {
+        /// Return the reflog mode of this edit.
+        pub fn mode(&self) -> Reflog {
+            match self {
+                Edit::Update(Update { mode, .. }) | Edit::Delete { mode, .. } => *mode,
+            }
+        }
+
+        /// Return the expected previous value of the reference, if set.
+        pub fn previous(&self) -> Option<&mutable::Target> {
+            match self {
+                Edit::Update(Update { previous, .. }) | Edit::Delete { previous, .. } => previous.as_ref(),
+            }
+        }
+    }
+
+    /// A reference that is to be changed along with the [edit][Edit] to perform.
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
     pub struct RefEdit {
-        edit: Edit,
-        refpath: SafeRefPath,
+        /// The edit to perform.
+        pub edit: Edit,
+        /// The full name of the reference to change, like `HEAD` or `refs/heads/main`.
+        pub name: SafeRefPath,
     }
 
+    /// The way to deal with the reference log and with symbolic references.
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
     pub enum Reflog {
         /// As symbolic references only ever see this when you want to detach them, we won't try to dereference them
         /// in this case and apply the change to it directly.
+        ///
+        /// A reflog is written if it already exists, or if the reference is `HEAD` or in `refs/heads/`, `refs/remotes/`
+        /// or `refs/notes/`.
         AutoAndNoDeref,
         /// Only update the reflog but require this to be a symbolic ref so the actual update can be performed on the
         /// referent.
+        ///
+        /// If the reference is not symbolic, the change is applied to it directly as if [`AutoAndNoDeref`][Reflog::AutoAndNoDeref] was used.
         OnlyAndDeref,
         /// Create a reflog even if it otherwise wouldn't be created, as is the case for tags. Otherwise it acts like `AutoNoDeref`.
         CreateUnconditionally,
     }
-
-    pub struct Transaction<T> {
-        updates: Vec<RefEdit>,
-        _state: PhantomData<T>,
-    }
 }
 
 /// Indicate that the given BString is a validate reference name that can be used as path on disk or written as target
@@ -107,7 +139,6 @@ pub struct SafeRefPath(bstr::BString);
 
 pub mod mutable {
     //!
-    #![allow(dead_code)]
     use crate::SafeRefPath;
     use git_hash::ObjectId;
 
@@ -1,10 +1,10 @@
-use crate::SafePartialName;
+use crate::{SafePartialName, SafeRefPath};
 use bstr::{BStr, BString, ByteSlice};
 use quick_error::quick_error;
 use std::{borrow::Cow, convert::TryFrom, path::Path};
 
 quick_error! {
-    /// The error used in the [`SafePartialName`]::try_from(…) implementations.
+    /// The error used in the [`SafePartialName`]::try_from(…) and [`SafeRefPath`]::try_from(…) implementations.
     #[derive(Debug)]
     #[allow(missing_docs)]
     pub enum Error {
@@ -53,3 +53,57 @@ impl<'a> TryFrom<&'a String> for SafePartialName<'a> {
         ))
     }
 }
+
+impl SafeRefPath {
+    /// Return the full name of the reference, like `refs/heads/main`.
+    pub fn as_bstr(&self) -> &BStr {
+        self.0.as_bstr()
+    }
+
+    /// Convert this name into the relative path identifying the reference location.
+    pub fn to_path(&self) -> Cow<'_, Path> {
+        self.0.to_path_lossy()
+    }
+}
+
+impl<'a> From<&'a SafeRefPath> for SafePartialName<'a> {
+    fn from(v: &'a SafeRefPath) -> Self {
+        SafePartialName(v.0.as_bstr())
+    }
+}
+
+impl TryFrom<&str> for SafeRefPath {
+    type Error = Error;
+
+    fn try_from(v: &str) -> Result<Self, Self::Error> {
+        let v = v.as_bytes().as_bstr();
+        Ok(SafeRefPath(
+            git_validate::refname(v)
+                .map_err(|err| Error::RefnameValidation { err, path: v.into() })?
+                .into(),
+        ))
+    }
+}
+
+impl TryFrom<&BStr> for SafeRefPath {
+    type Error = Error;
+
+    fn try_from(v: &BStr) -> Result<Self, Self::Error> {
+        Ok(SafeRefPath(
+            git_validate::refname(v)
+                .map_err(|err| Error::RefnameValidation { err, path: v.into() })?
+                .into(),
+        ))
+    }
+}
+
+impl TryFrom<BString> for SafeRefPath {
+    type Error = Error;
+
+    fn try_from(v: BString) -> Result<Self, Self::Error> {
+        match git_validate::refname(v.as_bstr()) {
+            Ok(_) => Ok(SafeRefPath(v)),
+            Err(err) => Err(Error::RefnameValidation { err, path: v }),
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    file::{self, path_to_name, reference::State, Reference},
+    packed,
+};
+use bstr::{BStr, BString, ByteSlice};
+use git_features::fs::walkdir::DirEntryIter;
+use quick_error::quick_error;
+use std::{cmp::Ordering, iter::Peekable};
+
+/// An iterator over all loose references of a [`Store`][file::Store] in `refs/`, ordered by their full name.
+pub struct Loose<'a> {
+    parent: &'a file::Store,
+    traversal: Option<DirEntryIter>,
+    prefix: Option<BString>,
+}
+
+impl<'a> Loose<'a> {
+    fn new(parent: &'a file::Store, prefix: Option<BString>) -> Self {
+        let directory_of_prefix = prefix
+            .as_ref()
+            .and_then(|prefix| prefix.rfind_byte(b'/').map(|pos| prefix[..pos].as_bstr()))
+            .filter(|dir| dir.starts_with(b"refs/"));
+        let root = match directory_of_prefix {
+            Some(dir) => parent.base.join(dir.to_path_lossy()),
+            None => parent.base.join("refs"),
+        };
+        let traversal = if root.is_dir() {
+            Some(git_features::fs::walkdir_sorted_new(root).into_iter())
+        } else {
+            None
+        };
+        Loose {
+            parent,
+            traversal,
+            prefix,
+        }
+    }
+}
+
+impl<'a> Iterator for Loose<'a> {
+    type Item = Result<Reference<'a>, loose::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let traversal = self.traversal.as_mut()?;
+        for entry in traversal {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(loose::Error::Traversal(err))),
+            };
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            let full_path = entry.path().to_owned();
+            if full_path.extension() == Some(std::ffi::OsStr::new("lock")) {
+                continue;
+            }
+            let relative_path = full_path
+                .strip_prefix(&self.parent.base)
+                .expect("traversal starts within base")
+                .to_owned();
+            if let Some(prefix) = &self.prefix {
+                if !path_to_name(&relative_path).starts_with(prefix.as_slice()) {
+                    continue;
+                }
+            }
+            return Some(match std::fs::read(&full_path) {
+                Ok(contents) => Reference::try_from_path(self.parent, &relative_path, &contents)
+                    .map_err(|err| loose::Error::ReferenceCreation { err, relative_path }),
+                Err(err) => Err(loose::Error::ReadFileContents { err, relative_path }),
+            });
+        }
+        None
+    }
+}
+
+/// An iterator over all loose and packed references of a [`Store`][file::Store], ordered by their full name.
+///
+/// If a reference exists both as loose file and in the packed-refs buffer, the loose one takes precedence.
+pub struct LooseThenPacked<'p, 's> {
+    parent: &'s file::Store,
+    loose: Peekable<Loose<'s>>,
+    packed: Option<Peekable<packed::Iter<'p>>>,
+}
+
+impl<'p, 's> LooseThenPacked<'p, 's> {
+    fn convert_packed(
+        &self,
+        packed: Result<packed::Reference<'p>, packed::iter::Error>,
+    ) -> Result<Reference<'s>, Error> {
+        packed.map_err(Error::Packed).map(|packed| Reference {
+            parent: self.parent,
+            relative_path: packed.full_name.to_path_lossy().into_owned(),
+            state: State::Id(packed.target()),
+        })
+    }
+
+    fn next_loose(&mut self) -> Option<Result<Reference<'s>, Error>> {
+        self.loose.next().map(|r| r.map_err(Error::Loose))
+    }
+}
+
+impl<'p, 's> Iterator for LooseThenPacked<'p, 's> {
+    type Item = Result<Reference<'s>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        fn loose_name(item: &Result<Reference<'_>, loose::Error>) -> Option<BString> {
+            match item {
+                Ok(r) => Some(path_to_name(&r.relative_path)),
+                Err(loose::Error::ReadFileContents { relative_path, .. })
+                | Err(loose::Error::ReferenceCreation { relative_path, .. }) => Some(path_to_name(relative_path)),
+                Err(loose::Error::Traversal(_)) => None,
+            }
+        }
+        fn packed_name<'p>(item: &Result<packed::Reference<'p>, packed::iter::Error>) -> Option<&'p BStr> {
+            item.as_ref().ok().map(|r| r.full_name)
+        }
+
+        let packed = match self.packed.as_mut() {
+            Some(packed) => packed,
+            None => return self.next_loose(),
+        };
+        match (self.loose.peek(), packed.peek()) {
+            (None, None) => None,
+            (None, Some(_)) => {
+                let next = packed.next().expect("peeked");
+                Some(self.convert_packed(next))
+            }
+            (Some(_), None) => self.next_loose(),
+            (Some(loose), Some(packed_item)) => match (loose_name(loose), packed_name(packed_item)) {
+                (None, _) => self.next_loose(),
+                (_, None) => {
+                    let next = packed.next().expect("peeked");
+                    Some(self.convert_packed(next))
+                }
+                (Some(loose_name), Some(packed_name)) => match loose_name.as_bstr().cmp(packed_name) {
+                    Ordering::Less => self.next_loose(),
+                    Ordering::Equal => {
+                        packed.next();
+                        self.next_loose()
+                    }
+                    Ordering::Greater => {
+                        let next = packed.next().expect("peeked");
+                        Some(self.convert_packed(next))
+                    }
+                },
+            },
+        }
+    }
+}
+
+impl file::Store {
+    /// Return an iterator over all loose references, notably not including any packed ones, ordered by their full name.
+    ///
+    /// Errors are returned similarly to what would happen when loose references were found using [`find_one()`][file::Store::find_one()]
+    /// and do not abort the iteration.
+    pub fn loose_iter(&self) -> Loose<'_> {
+        Loose::new(self, None)
+    }
+
+    /// Return an iterator over all loose references whose full name starts with `prefix`, like `refs/tags/`.
+    pub fn loose_iter_prefixed(&self, prefix: impl Into<BString>) -> Loose<'_> {
+        Loose::new(self, Some(prefix.into()))
+    }
+
+    /// Return an iterator over all references, loose or `packed`, ordered by their full name.
+    ///
+    /// Loose references take precedence over packed ones of the same name, and errors are returned for
+    /// each reference that can't be read or decoded without stopping the iteration.
+    pub fn iter<'p, 's>(&'s self, packed: Option<&'p packed::Buffer>) -> LooseThenPacked<'p, 's> {
+        LooseThenPacked {
+            parent: self,
+            loose: self.loose_iter().peekable(),
+            packed: packed.map(|packed| packed.iter().peekable()),
+        }
+    }
+
+    /// As [`iter(…)`][file::Store::iter()], but only yields references whose full name starts with `prefix`,
+    /// like `refs/tags/`.
+    pub fn iter_prefixed<'p, 's>(
+        &'s self,
+        packed: Option<&'p packed::Buffer>,
+        prefix: impl Into<BString>,
+    ) -> LooseThenPacked<'p, 's> {
+        let prefix = prefix.into();
+        LooseThenPacked {
+            parent: self,
+            packed: packed.map(|packed| packed.iter_prefixed(prefix.clone()).peekable()),
+            loose: self.loose_iter_prefixed(prefix).peekable(),
+        }
+    }
+}
+
+quick_error! {
+    /// The error returned by the [`LooseThenPacked`] iterator.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Loose(err: loose::Error) {
+            display("A loose reference could not be read")
+            from()
+            source(err)
+        }
+        Packed(err: packed::iter::Error) {
+            display("A packed reference could not be decoded")
+            from()
+            source(err)
+        }
+    }
+}
+
+///
+pub mod loose {
+    use crate::file;
+    use quick_error::quick_error;
+    use std::{io, path::PathBuf};
+
+    quick_error! {
+        /// The error returned by the [`Loose`][super::Loose] iterator.
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Traversal(err: git_features::fs::walkdir::Error) {
+                display("The file system could not be traversed")
+                source(err)
+            }
+            ReadFileContents{err: io::Error, relative_path: PathBuf} {
+                display("The ref file '{}' could not be read in full", relative_path.display())
+                source(err)
+            }
+            ReferenceCreation{ err: file::reference::decode::Error, relative_path: PathBuf } {
+                display("The reference at '{}' could not be instantiated", relative_path.display())
+                source(err)
+            }
+        }
+    }
+}
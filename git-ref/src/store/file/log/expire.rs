@@ -0,0 +1,133 @@
+use crate::{
+    file::{
+        self,
+        log::{self, line},
+    },
+    SafeRefPath,
+};
+use bstr::ByteSlice;
+use git_hash::oid;
+use quick_error::quick_error;
+use std::{convert::TryInto, io::Write};
+
+quick_error! {
+    /// The error returned by [`file::Store::reflog_expire()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        RefnameValidation(err: crate::safe_name::Error) {
+            display("The reference name is invalid")
+            source(err)
+        }
+        LockAcquire(err: git_lock::acquire::Error) {
+            display("A lock for the reference log could not be obtained")
+            source(err)
+        }
+        Io(err: std::io::Error) {
+            display("The reference log could not be read or rewritten")
+            from()
+            source(err)
+        }
+        LineDecode(err: line::decode::Error) {
+            display("A line of the reference log could not be decoded")
+            from()
+            source(err)
+        }
+    }
+}
+
+/// Determines which entries of a reference log to remove.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Options {
+    /// Remove all entries that were recorded before this time, similar to `gc.reflogExpire`.
+    pub expire: Option<git_actor::Time>,
+    /// Remove entries that were recorded before this time if their new object isn't reachable from the tip of the
+    /// reference anymore, similar to `gc.reflogExpireUnreachable`.
+    pub expire_unreachable: Option<git_actor::Time>,
+    /// If true, remove all entries whose previous or new object doesn't exist in the object database anymore, similar to
+    /// `git reflog expire --stale-fix`.
+    pub remove_stale: bool,
+}
+
+/// Information about a completed [reflog expiry][file::Store::reflog_expire()].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Outcome {
+    /// The amount of entries that remain in the reference log.
+    pub kept: usize,
+    /// The amount of entries that were removed from the reference log.
+    pub removed: usize,
+}
+
+impl Options {
+    fn should_remove(
+        &self,
+        line: &log::Line<'_>,
+        exists: &mut impl FnMut(&oid) -> bool,
+        is_reachable: &mut impl FnMut(&oid) -> bool,
+    ) -> bool {
+        if self.remove_stale {
+            let null = oid::null_sha1();
+            let mut is_stale = |id: &oid| id != null && !exists(id);
+            if is_stale(&line.previous_oid) || is_stale(&line.new_oid) {
+                return true;
+            }
+        }
+        let time = line.signature.time.time;
+        if matches!(self.expire, Some(cutoff) if time < cutoff.time) {
+            return true;
+        }
+        matches!(self.expire_unreachable, Some(cutoff) if time < cutoff.time) && !is_reachable(&line.new_oid)
+    }
+}
+
+impl file::Store {
+    /// Remove entries from the reference log of the reference with the full `name` according to `options`, and return
+    /// how many entries were kept and removed, or `None` if there is no such log.
+    ///
+    /// `exists(id)` must return true if the object with `id` is present in the object database, which is only asked if
+    /// [`Options::remove_stale`] is set, and `is_reachable(id)`
+    /// must return true if the object with `id` can be reached from the current tip of the reference.
+    /// The log is rewritten atomically while holding its lock, which is obtained according to `lock`, and it is left
+    /// untouched if no entry has to be removed.
+    pub fn reflog_expire<Name>(
+        &self,
+        name: Name,
+        options: Options,
+        lock: git_lock::acquire::Fail,
+        mut exists: impl FnMut(&oid) -> bool,
+        mut is_reachable: impl FnMut(&oid) -> bool,
+    ) -> Result<Option<Outcome>, Error>
+    where
+        Name: TryInto<SafeRefPath, Error = crate::safe_name::Error>,
+    {
+        let name = name.try_into().map_err(Error::RefnameValidation)?;
+        let path = self.reflog_path(&name.to_path());
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let mut lock = git_lock::File::acquire_to_update_resource(&path, lock, None).map_err(Error::LockAcquire)?;
+        let log = match std::fs::read(&path) {
+            Ok(log) => log,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut kept = Vec::with_capacity(log.len());
+        let mut outcome = Outcome { kept: 0, removed: 0 };
+        for raw_line in log.lines_with_terminator() {
+            let line = log::Line::from_bytes(raw_line)?;
+            if options.should_remove(&line, &mut exists, &mut is_reachable) {
+                outcome.removed += 1;
+            } else {
+                outcome.kept += 1;
+                kept.extend_from_slice(raw_line);
+            }
+        }
+
+        if outcome.removed != 0 {
+            lock.with_mut(|out| out.write_all(&kept))?;
+            lock.commit()?;
+        }
+        Ok(Some(outcome))
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    file::{
+        self,
+        log::{self, line},
+    },
+    SafeRefPath,
+};
+use bstr::ByteSlice;
+use quick_error::quick_error;
+use std::{
+    convert::TryInto,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+/// An iterator over the lines of a reference log in a buffer, from the oldest entry to the newest one.
+pub struct Forward<'a> {
+    inner: bstr::Lines<'a>,
+}
+
+/// Return a [`Forward`] iterator over all lines of the reference log in `lines`.
+pub fn forward(lines: &[u8]) -> Forward<'_> {
+    Forward { inner: lines.lines() }
+}
+
+impl<'a> Iterator for Forward<'a> {
+    type Item = Result<log::Line<'a>, line::decode::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(log::Line::from_bytes)
+    }
+}
+
+/// An iterator over the lines of a reference log which reads it from the end, yielding the newest entry first.
+///
+/// Only a chunk of the log is held in memory at a time, and as the chunk buffer is reused, owned lines are returned.
+pub struct Reverse<'a, F> {
+    buf: &'a mut [u8],
+    /// The log to read along with the offset up to which it still has to be read.
+    read_and_pos: Option<(F, u64)>,
+    /// Data read from the log which wasn't returned as line yet, always ending at the line returned last.
+    pending: Vec<u8>,
+}
+
+/// Return a [`Reverse`] iterator over the lines of the reference `log`, using `buf` to read chunks of it from the end.
+///
+/// The size of `buf` determines how much is read at once, it must not be empty.
+pub fn reverse<F>(mut log: F, buf: &mut [u8]) -> io::Result<Reverse<'_, F>>
+where
+    F: Read + Seek,
+{
+    if buf.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Zero sized buffers are not allowed, use 256 bytes or more for typical logs",
+        ));
+    }
+    let pos = log.seek(SeekFrom::End(0))?;
+    Ok(Reverse {
+        buf,
+        read_and_pos: Some((log, pos)),
+        pending: Vec::new(),
+    })
+}
+
+impl<'a, F> Reverse<'a, F>
+where
+    F: Read + Seek,
+{
+    /// Read the chunk preceding all data read so far and return true, or false if the start of the log was reached.
+    fn read_previous_chunk(&mut self) -> io::Result<bool> {
+        let (log, pos) = match self.read_and_pos.as_mut() {
+            Some((log, pos)) if *pos > 0 => (log, pos),
+            _ => return Ok(false),
+        };
+        let n = (self.buf.len() as u64).min(*pos);
+        *pos -= n;
+        log.seek(SeekFrom::Start(*pos))?;
+        let chunk = &mut self.buf[..n as usize];
+        log.read_exact(chunk)?;
+        self.pending.splice(0..0, chunk.iter().copied());
+        Ok(true)
+    }
+}
+
+impl<'a, F> Iterator for Reverse<'a, F>
+where
+    F: Read + Seek,
+{
+    type Item = Result<log::mutable::Line, reverse::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while matches!(self.pending.last(), Some(b'\n') | Some(b'\r')) {
+                self.pending.pop();
+            }
+            if let Some(pos) = self.pending.rfind_byte(b'\n') {
+                let res = log::Line::from_bytes(&self.pending[pos + 1..])
+                    .map(Into::into)
+                    .map_err(Into::into);
+                self.pending.truncate(pos);
+                return Some(res);
+            }
+            match self.read_previous_chunk() {
+                Ok(true) => continue,
+                Ok(false) if self.pending.is_empty() => return None,
+                Ok(false) => {
+                    let res = log::Line::from_bytes(&self.pending).map(Into::into).map_err(Into::into);
+                    self.pending.clear();
+                    return Some(res);
+                }
+                Err(err) => {
+                    self.read_and_pos = None;
+                    self.pending.clear();
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+    }
+}
+
+///
+pub mod reverse {
+    use crate::file::log::line;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by the [`Reverse`][super::Reverse] iterator.
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io(err: std::io::Error) {
+                display("The reference log could not be read")
+                from()
+                source(err)
+            }
+            LineDecode(err: line::decode::Error) {
+                display("A line of the reference log could not be decoded")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+
+quick_error! {
+    /// The error returned by [`file::Store::reflog_iter()`] and [`file::Store::reflog_iter_rev()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        RefnameValidation(err: crate::safe_name::Error) {
+            display("The reference name is invalid")
+            source(err)
+        }
+        Io(err: io::Error) {
+            display("The reference log could not be opened or read")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl file::Store {
+    /// Read the reference log of the reference with the full `name`, like `HEAD` or `refs/heads/main`, into `buf` and
+    /// return an iterator over its lines from oldest to newest, or `None` if there is no such log.
+    pub fn reflog_iter<'b, Name>(&self, name: Name, buf: &'b mut Vec<u8>) -> Result<Option<Forward<'b>>, Error>
+    where
+        Name: TryInto<SafeRefPath, Error = crate::safe_name::Error>,
+    {
+        let mut log = match self.open_reflog(name)? {
+            Some(log) => log,
+            None => return Ok(None),
+        };
+        buf.clear();
+        log.read_to_end(buf)?;
+        Ok(Some(forward(buf)))
+    }
+
+    /// Return an iterator over the reference log of the reference with the full `name` from newest to oldest entry,
+    /// reading it in chunks of the size of `buf`, or `None` if there is no such log.
+    pub fn reflog_iter_rev<'b, Name>(
+        &self,
+        name: Name,
+        buf: &'b mut [u8],
+    ) -> Result<Option<Reverse<'b, std::fs::File>>, Error>
+    where
+        Name: TryInto<SafeRefPath, Error = crate::safe_name::Error>,
+    {
+        match self.open_reflog(name)? {
+            Some(log) => Ok(Some(reverse(log, buf)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn open_reflog<Name>(&self, name: Name) -> Result<Option<std::fs::File>, Error>
+    where
+        Name: TryInto<SafeRefPath, Error = crate::safe_name::Error>,
+    {
+        let name = name.try_into().map_err(Error::RefnameValidation)?;
+        match std::fs::File::open(self.reflog_path(&name.to_path())) {
+            Ok(log) => Ok(Some(log)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
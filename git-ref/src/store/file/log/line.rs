@@ -0,0 +1,123 @@
+use crate::file::log::{mutable, Line};
+
+impl<'a> Line<'a> {
+    /// Decode a single line of a reference log from `input`, with or without its trailing newline.
+    pub fn from_bytes(input: &'a [u8]) -> Result<Self, decode::Error> {
+        decode::line(input)
+            .map(|(_, line)| line)
+            .map_err(|_| decode::Error::Line(input.into()))
+    }
+}
+
+impl<'a> From<Line<'a>> for mutable::Line {
+    fn from(v: Line<'a>) -> Self {
+        mutable::Line {
+            previous_oid: v.previous_oid,
+            new_oid: v.new_oid,
+            signature: v.signature.into(),
+            message: v.message.into(),
+        }
+    }
+}
+
+///
+pub mod decode {
+    use crate::{
+        file::log::Line,
+        parse::{hex_sha1, newline},
+    };
+    use bstr::{BString, ByteSlice};
+    use git_hash::ObjectId;
+    use nom::{
+        bytes::complete::{tag, take_while},
+        combinator::{map_res, opt},
+        sequence::{preceded, terminated, tuple},
+        IResult,
+    };
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Line::from_bytes()`].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Line(line: BString) {
+                display("Reflog line '{}' did not match '<old-hexsha> <new-hexsha> <name> <<email>> <timestamp> <tz>\\t<message>'", line)
+            }
+        }
+    }
+
+    fn hex_id(i: &[u8]) -> IResult<&[u8], ObjectId> {
+        let (i, hex) = hex_sha1(i)?;
+        Ok((i, ObjectId::from_hex(hex).expect("hex_sha1 only yields valid hex")))
+    }
+
+    pub(crate) fn line(input: &[u8]) -> IResult<&[u8], Line<'_>> {
+        let (rest, (previous_oid, new_oid, signature, message)) = tuple((
+            terminated(hex_id, tag(b" ")),
+            terminated(hex_id, tag(b" ")),
+            map_res(
+                take_while(|b| b != b'\t' && b != b'\r' && b != b'\n'),
+                git_actor::immutable::Signature::from_bytes,
+            ),
+            opt(preceded(tag(b"\t"), take_while(|b| b != b'\r' && b != b'\n'))),
+        ))(input)?;
+        let (rest, _) = opt(newline)(rest)?;
+        Ok((
+            rest,
+            Line {
+                previous_oid,
+                new_oid,
+                signature,
+                message: message.unwrap_or_default().as_bstr(),
+            },
+        ))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::line;
+        use bstr::ByteSlice;
+        use git_actor::{Sign, Time};
+
+        const NULL_SHA1: &[u8] = b"0000000000000000000000000000000000000000";
+
+        #[test]
+        fn with_message_and_newline() {
+            let input = b"0000000000000000000000000000000000000000 134385f6d781b7e97062102c6a483440bfda2a03 committer <committer@example.com> 946771200 +0100\tcommit (initial): c1\n";
+            let (rest, line) = line(input).expect("valid");
+            assert!(rest.is_empty(), "the newline is consumed");
+            assert_eq!(line.previous_oid.as_bytes(), &[0u8; 20][..]);
+            assert_eq!(line.new_oid.to_string(), "134385f6d781b7e97062102c6a483440bfda2a03");
+            assert_eq!(line.signature.name, "committer");
+            assert_eq!(line.signature.email, "committer@example.com");
+            assert_eq!(
+                line.signature.time,
+                Time {
+                    time: 946771200,
+                    offset: 3600,
+                    sign: Sign::Plus
+                }
+            );
+            assert_eq!(line.message, "commit (initial): c1");
+        }
+
+        #[test]
+        fn without_message() {
+            let mut input = NULL_SHA1.to_vec();
+            input.push(b' ');
+            input.extend_from_slice(NULL_SHA1);
+            input.extend_from_slice(b" name <email> 1234 -0000");
+            let (rest, line) = line(&input).expect("valid");
+            assert!(rest.is_empty());
+            assert_eq!(line.message, b"".as_bstr());
+            assert_eq!(line.signature.time.sign, Sign::Minus);
+        }
+
+        #[test]
+        fn invalid() {
+            assert!(line(b"0000 1111 name <email> 1234 +0000\tmsg").is_err());
+            assert!(line(b"").is_err());
+        }
+    }
+}
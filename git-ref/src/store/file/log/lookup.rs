@@ -0,0 +1,73 @@
+use crate::{
+    file::{
+        self,
+        log::{iter, mutable},
+    },
+    SafeRefPath,
+};
+use quick_error::quick_error;
+use std::convert::TryInto;
+
+quick_error! {
+    /// The error returned by [`file::Store::reflog_entry_at()`] and [`file::Store::reflog_entry_before()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Open(err: iter::Error) {
+            display("The reference log could not be opened")
+            from()
+            source(err)
+        }
+        Iter(err: iter::reverse::Error) {
+            display("The reference log could not be traversed")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl file::Store {
+    /// Return the entry at `index` in the reference log of the reference with the full `name`, where `0` is the newest
+    /// entry, as used in `HEAD@{2}`.
+    ///
+    /// Returns `None` if there is no reference log or if it has not enough entries.
+    pub fn reflog_entry_at<Name>(&self, name: Name, index: usize) -> Result<Option<mutable::Line>, Error>
+    where
+        Name: TryInto<SafeRefPath, Error = crate::safe_name::Error>,
+    {
+        let mut buf = [0u8; 512];
+        let iter = match self.reflog_iter_rev(name, &mut buf)? {
+            Some(iter) => iter,
+            None => return Ok(None),
+        };
+        for (line_index, line) in iter.enumerate() {
+            let line = line?;
+            if line_index == index {
+                return Ok(Some(line));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the newest entry in the reference log of the reference with the full `name` which was recorded at or
+    /// before `time`, as used in `main@{yesterday}`.
+    ///
+    /// Returns `None` if there is no reference log or if all of its entries are newer than `time`.
+    pub fn reflog_entry_before<Name>(&self, name: Name, time: git_actor::Time) -> Result<Option<mutable::Line>, Error>
+    where
+        Name: TryInto<SafeRefPath, Error = crate::safe_name::Error>,
+    {
+        let mut buf = [0u8; 512];
+        let iter = match self.reflog_iter_rev(name, &mut buf)? {
+            Some(iter) => iter,
+            None => return Ok(None),
+        };
+        for line in iter {
+            let line = line?;
+            if line.signature.time.time <= time.time {
+                return Ok(Some(line));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,75 @@
+use bstr::BStr;
+use git_hash::ObjectId;
+
+/// A line of a reference log, borrowing its data from the buffer it was decoded from.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Line<'a> {
+    /// The previous object id of the ref, or the null hash if it did not exist.
+    pub previous_oid: ObjectId,
+    /// The new object id of the ref, or the null hash if it was deleted.
+    pub new_oid: ObjectId,
+    /// The signature of the actor who performed the change.
+    pub signature: git_actor::immutable::Signature<'a>,
+    /// The message describing the change, which may be empty.
+    pub message: &'a BStr,
+}
+
+///
+pub mod line;
+
+///
+pub mod iter;
+
+///
+pub mod lookup;
+
+///
+pub mod expire;
+
+///
+pub mod mutable {
+    use bstr::{BString, ByteSlice};
+    use git_hash::ObjectId;
+    use std::io;
+
+    /// A line of a reference log with owned data, suitable for writing.
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+    pub struct Line {
+        /// The previous object id of the ref, or the null hash if it did not exist.
+        pub previous_oid: ObjectId,
+        /// The new object id of the ref, or the null hash if it was deleted.
+        pub new_oid: ObjectId,
+        /// The signature of the actor who performed the change.
+        pub signature: git_actor::Signature,
+        /// The message describing the change, which will be written as single line.
+        pub message: BString,
+    }
+
+    impl Line {
+        /// Serialize this instance to `out` in the git reflog format, including the trailing newline.
+        ///
+        /// Newlines in the message are replaced with spaces, and the message is omitted entirely if it is empty.
+        pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+            write!(out, "{} {} ", self.previous_oid, self.new_oid)?;
+            self.signature.write_to(&mut out)?;
+            let message = self.message.trim_with(|c| c.is_ascii_whitespace());
+            if !message.is_empty() {
+                out.write_all(b"\t")?;
+                out.write_all(&message.replace(b"\n", b" "))?;
+            }
+            out.write_all(b"\n")
+        }
+    }
+}
+
+mod path {
+    use crate::file;
+    use std::path::{Path, PathBuf};
+
+    impl file::Store {
+        /// Return the path to the reflog of the reference at `relative_path`, which may or may not exist.
+        pub(in crate::store::file) fn reflog_path(&self, relative_path: &Path) -> PathBuf {
+            self.base.join("logs").join(relative_path)
+        }
+    }
+}
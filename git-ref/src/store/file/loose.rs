@@ -1,6 +1,7 @@
 ///
 pub mod find_one {
-    use crate::{file, SafePartialName};
+    use crate::{file, packed, SafePartialName};
+    use bstr::ByteSlice;
     use quick_error::quick_error;
     use std::{convert::TryInto, io, io::Read, path::Path, path::PathBuf};
 
@@ -22,6 +23,16 @@ pub mod find_one {
                 display("The reference at '{}' could not be instantiated", relative_path.display())
                 source(err)
             }
+            PackedOpen(err: packed::buffer::open::Error) {
+                display("The packed-refs file could not be opened")
+                from()
+                source(err)
+            }
+            PackedRef(err: packed::find::Error) {
+                display("A packed ref lookup failed")
+                from()
+                source(err)
+            }
         }
     }
 
@@ -35,6 +46,9 @@ pub mod find_one {
         ///
         /// Returns `Ok(None)` if no such ref exists.
         ///
+        /// Loose references take precedence over the ones in the `packed-refs` file, which is only consulted if no loose
+        /// reference file exists for a given name.
+        ///
         /// ### Note
         ///
         /// The lookup algorithm follows the one in [the git documentation][git-lookup-docs].
@@ -57,26 +71,48 @@ pub mod find_one {
                 .as_ref()
                 .chars()
                 .all(|c| c.is_ascii_uppercase());
+            let packed = self.packed()?;
             if relative_path.components().count() == 1 && is_all_uppercase {
-                if let Some(r) = self.find_inner("", &relative_path, Transform::None)? {
+                if let Some(r) = self.find_inner("", &relative_path, packed.as_ref(), Transform::None)? {
                     return Ok(Some(r));
                 }
             }
 
             for inbetween in &["", "tags", "heads", "remotes"] {
-                match self.find_inner(*inbetween, &relative_path, Transform::EnforceRefsPrefix) {
+                match self.find_inner(
+                    *inbetween,
+                    &relative_path,
+                    packed.as_ref(),
+                    Transform::EnforceRefsPrefix,
+                ) {
                     Ok(Some(r)) => return Ok(Some(r)),
                     Ok(None) => continue,
                     Err(err) => return Err(err),
                 }
             }
-            self.find_inner("remotes", &relative_path.join("HEAD"), Transform::EnforceRefsPrefix)
+            self.find_inner(
+                "remotes",
+                &relative_path.join("HEAD"),
+                packed.as_ref(),
+                Transform::EnforceRefsPrefix,
+            )
+        }
+
+        /// Find the reference at `relative_path` verbatim, without applying any lookup rules, in loose references first
+        /// and in the `packed` buffer second.
+        pub(in crate::store::file) fn find_one_exact(
+            &self,
+            relative_path: &Path,
+            packed: Option<&packed::Buffer>,
+        ) -> Result<Option<file::Reference<'_>>, Error> {
+            self.find_inner("", relative_path, packed, Transform::None)
         }
 
         fn find_inner(
             &self,
             inbetween: &str,
             relative_path: &Path,
+            packed: Option<&packed::Buffer>,
             transform: Transform,
         ) -> Result<Option<file::Reference<'_>>, Error> {
             let relative_path = match transform {
@@ -99,7 +135,12 @@ pub mod find_one {
 
             let mut contents = Vec::new();
             match std::fs::File::open(ref_path) {
-                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    return match packed {
+                        Some(packed) => self.find_packed(packed, relative_path),
+                        None => Ok(None),
+                    }
+                }
                 Err(err) => return Err(err.into()),
                 Ok(mut file) => file.read_to_end(&mut contents)?,
             };
@@ -108,6 +149,20 @@ pub mod find_one {
                     .map_err(|err| Error::ReferenceCreation { err, relative_path })?,
             ))
         }
+
+        fn find_packed(
+            &self,
+            packed: &packed::Buffer,
+            relative_path: PathBuf,
+        ) -> Result<Option<file::Reference<'_>>, Error> {
+            Ok(packed
+                .find_full_name(file::path_to_name(&relative_path).as_bstr())?
+                .map(|packed| file::Reference {
+                    parent: self,
+                    relative_path,
+                    state: file::reference::State::Id(packed.target()),
+                }))
+        }
     }
 
     ///
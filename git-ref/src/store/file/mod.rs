@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use bstr::{BString, ByteSlice, ByteVec};
+use std::path::{Path, PathBuf};
 
 /// A git _ref_ which is stored in a file.
 #[derive(Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone)]
@@ -24,8 +25,31 @@ pub struct Store {
 mod loose;
 pub use loose::*;
 
+mod packed;
+
+///
+pub mod iter;
+
+/// Convert a path relative to a [`Store`] into a full reference name, using forward slashes as separators.
+fn path_to_name(relative_path: &Path) -> BString {
+    Vec::from_path_lossy(relative_path).replace(b"\\", b"/").into()
+}
+
+/// Return the directory up to which empty directories may be removed along with the reference at `relative_path`
+/// in the store at `base`, which spares `refs/` and its immediate subdirectories just like git does.
+fn removal_boundary(base: &Path, relative_path: &Path) -> PathBuf {
+    let parent = relative_path.parent().unwrap_or_else(|| Path::new(""));
+    base.join(parent.components().take(2).collect::<PathBuf>())
+}
+
 ///
 pub mod reference;
 
 ///
 pub mod log;
+
+///
+pub mod transaction;
+
+///
+pub mod pack_refs;
@@ -0,0 +1,181 @@
+use crate::{
+    file::{self, iter, removal_boundary},
+    packed::{self, Peeled},
+};
+use bstr::BStr;
+use git_hash::{oid, ObjectId};
+use quick_error::quick_error;
+use std::{io::Write, path::PathBuf};
+
+quick_error! {
+    /// The error returned by [`file::Store::pack_refs()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        LockAcquire(err: git_lock::acquire::Error) {
+            display("A lock for the packed-refs file could not be obtained")
+            source(err)
+        }
+        PackedOpen(err: packed::buffer::open::Error) {
+            display("The packed-refs file could not be opened")
+            from()
+            source(err)
+        }
+        Iter(err: iter::Error) {
+            display("A reference could not be read")
+            from()
+            source(err)
+        }
+        Io(err: std::io::Error) {
+            display("The packed-refs file could not be written")
+            from()
+            source(err)
+        }
+        DeleteReference { err: std::io::Error, relative_path: PathBuf } {
+            display("The packed loose reference at '{}' could not be deleted", relative_path.display())
+            source(err)
+        }
+    }
+}
+
+/// Determines which references to pack, similar to the flags of `git pack-refs`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Options {
+    /// If true, pack all loose references, not only tags, like `--all`.
+    ///
+    /// References that are already packed always remain packed.
+    pub all: bool,
+    /// If true, delete loose references once they are packed, like `--prune`.
+    pub prune: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            all: false,
+            prune: true,
+        }
+    }
+}
+
+/// Information about a completed [`file::Store::pack_refs()`] operation.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Outcome {
+    /// The amount of references written to the packed-refs file.
+    pub packed: usize,
+    /// The amount of loose references that were deleted after packing them.
+    pub pruned: usize,
+}
+
+impl file::Store {
+    /// Write all packed references along with the loose ones selected by `options` into a new, sorted packed-refs file,
+    /// which atomically replaces the existing one while holding its lock obtained according to `lock`.
+    ///
+    /// `peel(id)` is called for each reference to obtain the object its target ultimately points to if it is an
+    /// annotated tag, or `None` otherwise, which allows the file to claim it is fully peeled.
+    /// Symbolic references are never packed, and neither are loose references that can't be parsed, which are skipped
+    /// just like git does.
+    ///
+    /// If [pruning][Options::prune], packed loose references are deleted unless they changed in the mean time or
+    /// are locked by another writer.
+    pub fn pack_refs(
+        &self,
+        options: Options,
+        lock: git_lock::acquire::Fail,
+        mut peel: impl FnMut(&oid) -> Option<ObjectId>,
+    ) -> Result<Outcome, Error> {
+        let mut packed_lock = git_lock::File::acquire_to_update_resource(self.packed_refs_path(), lock, None)
+            .map_err(Error::LockAcquire)?;
+        let packed = self.packed()?;
+
+        let mut buf = Vec::new();
+        packed::write::header(Peeled::Fully, &mut buf)?;
+        let mut packed_count = 0;
+        let mut loose_to_prune = Vec::new();
+        for reference in self.iter(packed.as_ref()) {
+            let reference = match reference {
+                Ok(r) => r,
+                Err(iter::Error::Loose(iter::loose::Error::ReferenceCreation { .. })) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let target = match reference.target().as_id() {
+                Some(id) => id.to_owned(),
+                None => continue,
+            };
+            let full_name = file::path_to_name(&reference.relative_path);
+            let is_loose = self.base.join(&reference.relative_path).is_file();
+            if is_loose {
+                if !(options.all || full_name.starts_with(b"refs/tags/")) {
+                    // Keep the packed version of references that stay loose, just like git does.
+                    if let Some(packed_ref) = packed
+                        .as_ref()
+                        .and_then(|packed| packed.find_full_name(full_name.as_ref()).ok().flatten())
+                    {
+                        write_record(&mut buf, &packed_ref.target(), full_name.as_ref(), &mut peel)?;
+                        packed_count += 1;
+                    }
+                    continue;
+                }
+                loose_to_prune.push((reference.relative_path.clone(), target));
+            }
+
+            write_record(&mut buf, &target, full_name.as_ref(), &mut peel)?;
+            packed_count += 1;
+        }
+        packed_lock.with_mut(|out| out.write_all(&buf))?;
+        packed_lock.commit()?;
+
+        let mut pruned = 0;
+        if options.prune {
+            for (relative_path, target) in loose_to_prune {
+                if self.prune_loose_ref(relative_path, &target, lock)? {
+                    pruned += 1;
+                }
+            }
+        }
+        Ok(Outcome {
+            packed: packed_count,
+            pruned,
+        })
+    }
+
+    /// Delete the loose reference at `relative_path` if it still points to `target`, and return true if it was deleted.
+    fn prune_loose_ref(
+        &self,
+        relative_path: PathBuf,
+        target: &oid,
+        lock: git_lock::acquire::Fail,
+    ) -> Result<bool, Error> {
+        let path = self.base.join(&relative_path);
+        let _marker = match git_lock::Marker::acquire_to_hold_resource(
+            &path,
+            lock,
+            Some(removal_boundary(&self.base, &relative_path)),
+        ) {
+            Ok(marker) => marker,
+            Err(_locked_by_another_writer) => return Ok(false),
+        };
+        let is_unchanged = matches!(
+            self.find_one_exact(&relative_path, None),
+            Ok(Some(r)) if r.target().as_id() == Some(target)
+        );
+        if !is_unchanged {
+            return Ok(false);
+        }
+        std::fs::remove_file(&path).map_err(|err| Error::DeleteReference { err, relative_path })?;
+        Ok(true)
+    }
+}
+
+fn write_record(
+    out: &mut Vec<u8>,
+    target: &oid,
+    full_name: &BStr,
+    peel: &mut impl FnMut(&oid) -> Option<ObjectId>,
+) -> std::io::Result<()> {
+    writeln!(out, "{} {}", target, full_name)?;
+    if let Some(peeled) = peel(target) {
+        writeln!(out, "^{}", peeled)?;
+    }
+    Ok(())
+}
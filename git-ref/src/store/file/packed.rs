@@ -0,0 +1,20 @@
+use crate::{file, packed};
+use std::path::PathBuf;
+
+impl file::Store {
+    /// Return the path at which packed-refs would usually be stored
+    pub fn packed_refs_path(&self) -> PathBuf {
+        self.base.join("packed-refs")
+    }
+
+    /// Return a buffer of the packed refs file, or `None` if it does not exist.
+    ///
+    /// Files larger than a few kilobytes are memory mapped, smaller ones are read into memory entirely.
+    pub fn packed(&self) -> Result<Option<packed::Buffer>, packed::buffer::open::Error> {
+        match packed::Buffer::open(self.packed_refs_path(), 32 * 1024) {
+            Ok(buf) => Ok(Some(buf)),
+            Err(packed::buffer::open::Error::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
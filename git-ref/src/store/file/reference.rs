@@ -27,7 +27,7 @@ impl<'a> Reference<'a> {
         }
     }
     /// Return the target to which this instance is pointing.
-    pub fn target(&'a self) -> Target<'a> {
+    pub fn target(&self) -> Target<'_> {
         match self.state {
             State::ValidatedPath(ref path) => Target::Symbolic(path.as_ref()),
             State::Id(ref oid) => Target::Peeled(oid.as_ref()),
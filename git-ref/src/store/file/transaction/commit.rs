@@ -0,0 +1,160 @@
+use crate::{
+    file::{
+        self,
+        log::mutable::Line,
+        transaction::{needs_reflog, prepare, Change, Lock, State, Transaction},
+    },
+    mutable::Target,
+    packed,
+    transaction::{Edit, RefEdit},
+};
+use bstr::BString;
+use git_hash::ObjectId;
+use quick_error::quick_error;
+use std::io;
+
+quick_error! {
+    /// The error returned by [`Transaction::commit()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Prepare(err: prepare::Error) {
+            display("The transaction could not be prepared")
+            from()
+            source(err)
+        }
+        PackedOpen(err: packed::buffer::open::Error) {
+            display("The packed-refs file could not be opened to read the referents of symbolic references")
+            from()
+            source(err)
+        }
+        PackedCommit(err: io::Error) {
+            display("The packed-refs file could not be updated")
+            source(err)
+        }
+        LockCommit { err: io::Error, full_name: BString } {
+            display("The change for reference '{}' could not be committed", full_name)
+            source(err)
+        }
+        DeleteReference { err: io::Error, full_name: BString } {
+            display("The reference '{}' could not be deleted", full_name)
+            source(err)
+        }
+        DeleteReflog { err: io::Error, full_name: BString } {
+            display("The reflog of reference '{}' could not be deleted", full_name)
+            source(err)
+        }
+        ReflogWrite { err: io::Error, full_name: BString } {
+            display("The reflog of reference '{}' could not be created or appended to", full_name)
+            source(err)
+        }
+    }
+}
+
+impl<'s> Transaction<'s> {
+    /// Make all edits permanent, [preparing][Transaction::prepare()] the transaction first if needed, and return them.
+    ///
+    /// Reference logs are written using `committer` as signature, and reflog-only changes of symbolic references record
+    /// the change of their referent.
+    pub fn commit(self, committer: &git_actor::Signature) -> Result<Vec<RefEdit>, Error> {
+        let mut tx = match self.state {
+            State::Open => self.prepare()?,
+            State::Prepared => self,
+        };
+
+        let packed = tx.store.packed()?;
+        for (idx, change) in tx.updates.iter().enumerate() {
+            if let Edit::Update(update) = &change.update.edit {
+                if needs_reflog(tx.store, change) {
+                    let affected = tx.innermost_referent(idx);
+                    let new_oid = match &update.new {
+                        Target::Peeled(oid) => Some(*oid),
+                        Target::Symbolic(name) => tx
+                            .store
+                            .find_one_exact(&name.to_path(), packed.as_ref())
+                            .ok()
+                            .flatten()
+                            .and_then(|mut r| r.peel_to_id_in_place().ok().map(ToOwned::to_owned)),
+                    };
+                    let line = Line {
+                        previous_oid: affected.previous_id.unwrap_or_else(ObjectId::null_sha1),
+                        new_oid: new_oid.unwrap_or_else(ObjectId::null_sha1),
+                        signature: committer.clone(),
+                        message: update.message.clone(),
+                    };
+                    append_reflog_line(tx.store, change, &line).map_err(|err| Error::ReflogWrite {
+                        err,
+                        full_name: change.name().to_owned(),
+                    })?;
+                }
+            }
+        }
+
+        if let Some(packed_lock) = tx.packed_lock.take() {
+            packed_lock.commit().map_err(Error::PackedCommit)?;
+        }
+
+        for change in tx.updates.iter_mut() {
+            let lock = change.lock.take();
+            if change.reflog_only {
+                continue;
+            }
+            match (&change.update.edit, lock) {
+                (Edit::Update(_), Some(Lock::File(file))) => {
+                    file.commit().map_err(|err| Error::LockCommit {
+                        err,
+                        full_name: change.name().to_owned(),
+                    })?;
+                }
+                (Edit::Delete { .. }, Some(Lock::Marker(marker))) => {
+                    let relative_path = change.update.name.to_path();
+                    remove_if_present(&tx.store.reflog_path(&relative_path)).map_err(|err| Error::DeleteReflog {
+                        err,
+                        full_name: change.name().to_owned(),
+                    })?;
+                    remove_if_present(&tx.store.base.join(&relative_path)).map_err(|err| Error::DeleteReference {
+                        err,
+                        full_name: change.name().to_owned(),
+                    })?;
+                    // Releasing the lock only now also removes directories that became empty.
+                    drop(marker);
+                }
+                _ => unreachable!("prepare() always locks updates with a file and deletions with a marker"),
+            }
+        }
+        Ok(tx.updates.into_iter().map(|c| c.update).collect())
+    }
+
+    /// Follow the chain of changes created for symbolic references to the one at `idx`, and return the last of them
+    /// which is the one that is actually changed.
+    fn innermost_referent(&self, mut idx: usize) -> &Change {
+        while self.updates[idx].reflog_only {
+            idx = self
+                .updates
+                .iter()
+                .position(|c| c.parent_index == Some(idx))
+                .expect("reflog-only changes always have a referent");
+        }
+        &self.updates[idx]
+    }
+}
+
+fn append_reflog_line(store: &file::Store, change: &Change, line: &Line) -> io::Result<()> {
+    let path = store.reflog_path(&change.update.name.to_path());
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let mut buf = Vec::new();
+    line.write_to(&mut buf)?;
+    io::Write::write_all(
+        &mut std::fs::OpenOptions::new().append(true).create(true).open(path)?,
+        &buf,
+    )
+}
+
+fn remove_if_present(path: &std::path::Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    file,
+    mutable::Target,
+    transaction::{Edit, RefEdit, Reflog},
+};
+use bstr::BStr;
+use git_hash::ObjectId;
+
+/// A transaction on a file store, applying a set of [reference edits][RefEdit] atomically.
+///
+/// All references to change are locked and checked against their expected previous values when
+/// [preparing][Transaction::prepare()] the transaction.
+/// If any of these steps fails, all locks are released and no change is made.
+/// Dropping a prepared transaction without [committing][Transaction::commit()] it rolls it back as well.
+pub struct Transaction<'s> {
+    store: &'s file::Store,
+    updates: Vec<Change>,
+    packed_lock: Option<git_lock::File>,
+    state: State,
+    lock_fail_mode: git_lock::acquire::Fail,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum State {
+    Open,
+    Prepared,
+}
+
+enum Lock {
+    /// A lock to hold the reference while it's being changed, which doesn't allow writing.
+    Marker(git_lock::Marker),
+    /// A lock file containing the new value of the reference.
+    File(git_lock::File),
+}
+
+/// A reference edit along with all the state needed to apply it.
+struct Change {
+    update: RefEdit,
+    lock: Option<Lock>,
+    /// Set if this update is coming from a symbolic reference and used to make it appear like it is the one that is handled,
+    /// instead of the referent reference.
+    parent_index: Option<usize>,
+    /// If true, this is a symbolic reference whose referent receives the actual change, so only its reflog is changed.
+    reflog_only: bool,
+    /// The value of the reference before the change, as read while holding its lock, or `None` if it didn't exist.
+    previous: Option<Target>,
+    /// The object id the reference was pointing to before the change, if it existed and could be peeled.
+    previous_id: Option<ObjectId>,
+}
+
+impl Change {
+    fn new(update: RefEdit, parent_index: Option<usize>) -> Self {
+        Change {
+            update,
+            lock: None,
+            parent_index,
+            reflog_only: false,
+            previous: None,
+            previous_id: None,
+        }
+    }
+
+    fn name(&self) -> &BStr {
+        self.update.name.as_bstr()
+    }
+}
+
+impl file::Store {
+    /// Open a transaction with the given `edits`, and determine how to fail if a `lock` cannot be obtained.
+    ///
+    /// The transaction needs to be [prepared][Transaction::prepare()] and [committed][Transaction::commit()] for
+    /// the edits to take effect.
+    pub fn transaction(
+        &self,
+        edits: impl IntoIterator<Item = RefEdit>,
+        lock: git_lock::acquire::Fail,
+    ) -> Transaction<'_> {
+        Transaction {
+            store: self,
+            updates: edits.into_iter().map(|edit| Change::new(edit, None)).collect(),
+            packed_lock: None,
+            state: State::Open,
+            lock_fail_mode: lock,
+        }
+    }
+}
+
+impl<'s> Transaction<'s> {
+    /// Return the edits this transaction is going to apply, which includes edits to referents of symbolic references
+    /// once the transaction was [prepared][Transaction::prepare()].
+    pub fn edits(&self) -> impl Iterator<Item = &RefEdit> {
+        self.updates.iter().map(|c| &c.update)
+    }
+}
+
+fn should_autocreate_reflog(name: &BStr) -> bool {
+    name == "HEAD"
+        || name.starts_with(b"refs/heads/")
+        || name.starts_with(b"refs/remotes/")
+        || name.starts_with(b"refs/notes/")
+}
+
+fn needs_reflog(store: &file::Store, change: &Change) -> bool {
+    match change.update.edit {
+        Edit::Delete { .. } => false,
+        Edit::Update(ref update) => match update.mode {
+            Reflog::CreateUnconditionally => true,
+            Reflog::AutoAndNoDeref | Reflog::OnlyAndDeref => {
+                should_autocreate_reflog(change.name()) || store.reflog_path(&change.update.name.to_path()).is_file()
+            }
+        },
+    }
+}
+
+///
+pub mod prepare;
+
+///
+pub mod commit;
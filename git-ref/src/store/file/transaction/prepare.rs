@@ -0,0 +1,231 @@
+use crate::{
+    file::{
+        self,
+        transaction::{Change, Lock, State, Transaction},
+    },
+    mutable::Target,
+    packed,
+    transaction::{Edit, RefEdit, Reflog, Update},
+    SafeRefPath,
+};
+use bstr::{BStr, BString, ByteSlice};
+use quick_error::quick_error;
+use std::{collections::BTreeSet, io::Write};
+
+quick_error! {
+    /// The error returned by [`Transaction::prepare()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        DuplicateRefEdits(full_name: BString) {
+            display("The reference '{}' is edited more than once, possibly through one of its symbolic references", full_name)
+        }
+        PackedOpen(err: packed::buffer::open::Error) {
+            display("The packed-refs file could not be opened")
+            from()
+            source(err)
+        }
+        PackedIter(err: packed::iter::Error) {
+            display("The packed-refs file could not be read to remove deleted references from it")
+            from()
+            source(err)
+        }
+        PackedLockAcquire(err: git_lock::acquire::Error) {
+            display("A lock for the packed-refs file could not be obtained")
+            source(err)
+        }
+        LockAcquire { err: git_lock::acquire::Error, full_name: BString } {
+            display("A lock for the reference '{}' could not be obtained", full_name)
+            source(err)
+        }
+        Io(err: std::io::Error) {
+            display("An IO error occurred while writing the new value of a reference to its lock")
+            from()
+            source(err)
+        }
+        ReadReference { err: file::find_one::Error, full_name: BString } {
+            display("The current value of the reference '{}' could not be read", full_name)
+            source(err)
+        }
+        MustExist { full_name: BString, expected: Target } {
+            display("The reference '{}' was expected to exist with value {:?}, but it didn't", full_name, expected)
+        }
+        ReferenceOutOfDate { full_name: BString, expected: Target, actual: Target } {
+            display("The reference '{}' should have content {:?}, actual content was {:?}", full_name, expected, actual)
+        }
+    }
+}
+
+impl<'s> Transaction<'s> {
+    /// Obtain locks for all references to change, in order of their names, and verify their previous values.
+    ///
+    /// Edits using [`Reflog::OnlyAndDeref`] on symbolic references are split such that the symbolic reference only
+    /// receives a reflog entry, while the change itself is applied to its referent.
+    /// On error, all locks obtained so far are released as the transaction is dropped, leaving all references untouched.
+    pub fn prepare(mut self) -> Result<Self, Error> {
+        if self.state == State::Prepared {
+            return Ok(self);
+        }
+        let store = self.store;
+        let packed = store.packed()?;
+        self.split_symbolic_refs(packed.as_ref())?;
+
+        let mut names: Vec<_> = self.updates.iter().map(|c| c.name()).collect();
+        names.sort();
+        if let Some(duplicate) = names.windows(2).find(|w| w[0] == w[1]) {
+            return Err(Error::DuplicateRefEdits(duplicate[0].to_owned()));
+        }
+
+        let mut lock_order: Vec<_> = (0..self.updates.len()).collect();
+        lock_order.sort_by(|lhs, rhs| self.updates[*lhs].name().cmp(self.updates[*rhs].name()));
+        for idx in lock_order {
+            lock_ref_and_apply_change(store, packed.as_ref(), self.lock_fail_mode, &mut self.updates[idx])?;
+        }
+
+        self.packed_lock = self.lock_and_remove_packed_deletions(packed.as_ref())?;
+        self.state = State::Prepared;
+        Ok(self)
+    }
+
+    /// Add an edit for the referent of each symbolic reference edited with [`Reflog::OnlyAndDeref`], recursively,
+    /// and turn the original edit into one that affects the reflog only.
+    fn split_symbolic_refs(&mut self, packed: Option<&packed::Buffer>) -> Result<(), Error> {
+        let mut idx = 0;
+        while idx < self.updates.len() {
+            let change = &self.updates[idx];
+            if change.update.edit.mode() == Reflog::OnlyAndDeref {
+                let referent = match self
+                    .store
+                    .find_one_exact(&change.update.name.to_path(), packed)
+                    .map_err(|err| Error::ReadReference {
+                        err,
+                        full_name: change.name().to_owned(),
+                    })? {
+                    Some(r) => match r.target() {
+                        crate::Target::Symbolic(name) => Some(SafeRefPath(name.to_owned())),
+                        crate::Target::Peeled(_) => None,
+                    },
+                    None => None,
+                };
+                if let Some(referent) = referent {
+                    // This also prevents infinite loops on cyclic symbolic references.
+                    if self.updates.iter().any(|c| c.update.name == referent) {
+                        return Err(Error::DuplicateRefEdits(referent.0));
+                    }
+                    let edit = change.update.edit.clone();
+                    self.updates[idx].reflog_only = true;
+                    self.updates
+                        .push(Change::new(RefEdit { edit, name: referent }, Some(idx)));
+                }
+            }
+            idx += 1;
+        }
+        Ok(())
+    }
+
+    /// If references to delete are contained in `packed`, lock the packed-refs file and write its new content without them.
+    fn lock_and_remove_packed_deletions(
+        &self,
+        packed: Option<&packed::Buffer>,
+    ) -> Result<Option<git_lock::File>, Error> {
+        let to_delete: BTreeSet<&BStr> = self
+            .updates
+            .iter()
+            .filter(|c| !c.reflog_only && matches!(c.update.edit, Edit::Delete { .. }))
+            .map(|c| c.name())
+            .collect();
+        let contains_deletions = |packed: &packed::Buffer| {
+            to_delete
+                .iter()
+                .any(|name| matches!(packed.find_full_name(name), Ok(Some(_))))
+        };
+        match packed {
+            Some(packed) if contains_deletions(packed) => {}
+            _ => return Ok(None),
+        }
+
+        let mut lock =
+            git_lock::File::acquire_to_update_resource(self.store.packed_refs_path(), self.lock_fail_mode, None)
+                .map_err(Error::PackedLockAcquire)?;
+        // Read the packed refs again now that we hold the lock to be sure no change gets lost.
+        if let Some(packed) = self.store.packed()? {
+            let mut buf = Vec::with_capacity(packed.as_ref().len());
+            packed::write::header(packed.peeled(), &mut buf)?;
+            for reference in packed.iter() {
+                let reference = reference?;
+                if !to_delete.contains(reference.full_name) {
+                    reference.write_to(&mut buf)?;
+                }
+            }
+            lock.with_mut(|out| out.write_all(&buf))?;
+        }
+        Ok(Some(lock))
+    }
+}
+
+fn lock_ref_and_apply_change(
+    store: &file::Store,
+    packed: Option<&packed::Buffer>,
+    lock_fail_mode: git_lock::acquire::Fail,
+    change: &mut Change,
+) -> Result<(), Error> {
+    let relative_path = change.update.name.to_path().into_owned();
+    let full_name = || change.name().to_owned();
+    let resource_path = store.base.join(&relative_path);
+    let boundary = Some(file::removal_boundary(&store.base, &relative_path));
+    let lock_result = match change.update.edit {
+        Edit::Update(_) if !change.reflog_only => {
+            git_lock::File::acquire_to_update_resource(resource_path, lock_fail_mode, boundary).map(Lock::File)
+        }
+        _ => git_lock::Marker::acquire_to_hold_resource(resource_path, lock_fail_mode, boundary).map(Lock::Marker),
+    };
+    let mut lock = lock_result.map_err(|err| Error::LockAcquire {
+        err,
+        full_name: full_name(),
+    })?;
+
+    let current = store
+        .find_one_exact(&relative_path, packed)
+        .map_err(|err| Error::ReadReference {
+            err,
+            full_name: full_name(),
+        })?;
+    let actual = current.as_ref().map(|r| match r.target() {
+        crate::Target::Peeled(oid) => Target::Peeled(oid.to_owned()),
+        crate::Target::Symbolic(name) => Target::Symbolic(SafeRefPath(name.to_owned())),
+    });
+    // Symbolic references only receiving a reflog entry are validated through their referent.
+    if !change.reflog_only {
+        match (change.update.edit.previous(), &actual) {
+            (Some(expected), None) => {
+                return Err(Error::MustExist {
+                    full_name: full_name(),
+                    expected: expected.clone(),
+                })
+            }
+            (Some(expected), Some(actual)) if expected != actual => {
+                return Err(Error::ReferenceOutOfDate {
+                    full_name: full_name(),
+                    expected: expected.clone(),
+                    actual: actual.clone(),
+                })
+            }
+            _ => {}
+        }
+    }
+    change.previous_id = current.and_then(|mut r| r.peel_to_id_in_place().ok().map(ToOwned::to_owned));
+    change.previous = actual;
+
+    if let (Edit::Update(Update { new, .. }), Lock::File(file)) = (&change.update.edit, &mut lock) {
+        file.with_mut(|out| match new {
+            Target::Peeled(oid) => writeln!(out, "{}", oid),
+            Target::Symbolic(name) => {
+                out.write_all(b"ref: ")?;
+                out.write_all(name.as_bstr().as_bytes())?;
+                out.write_all(b"\n")
+            }
+        })?;
+    }
+    change.lock = Some(lock);
+    Ok(())
+}
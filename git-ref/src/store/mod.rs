@@ -1,2 +1,5 @@
 ///
 pub mod file;
+
+///
+pub mod packed;
@@ -0,0 +1,127 @@
+use crate::packed::{self, Backing, Buffer, Peeled};
+use std::path::Path;
+
+impl AsRef<[u8]> for Backing {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Backing::InMemory(data) => data,
+            Backing::Mapped(map) => map,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Buffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.data.as_ref()[self.offset..]
+    }
+}
+
+impl Buffer {
+    /// Return the peeling guarantees made by the packed-refs file, as stated in its header.
+    pub fn peeled(&self) -> Peeled {
+        self.peeled
+    }
+
+    /// Return the path from which this buffer was loaded.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn header_line_count(&self) -> usize {
+        if self.offset == 0 {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+///
+pub mod open {
+    use crate::packed::{decode, Backing, Buffer};
+    use filebuffer::FileBuffer;
+    use quick_error::quick_error;
+    use std::path::PathBuf;
+
+    quick_error! {
+        /// The error returned by [`Buffer::open()`].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io(err: std::io::Error) {
+                display("The packed-refs file could not be read")
+                from()
+                source(err)
+            }
+            HeaderParsing {
+                display("The header could not be parsed, even though first line started with '#'")
+            }
+            Parse(line_number: usize) {
+                display("The unsorted reference at line {} could not be parsed", line_number)
+            }
+        }
+    }
+
+    impl Buffer {
+        /// Open the file at `path` and map it into memory if the file size is larger than `use_memory_map_if_larger_than_bytes`.
+        ///
+        /// In order to allow fast lookups and optimizations, the contents of the packed refs must be sorted.
+        /// If that's not the case, they will be sorted on the fly with the data being written into a memory buffer.
+        pub fn open(path: impl Into<PathBuf>, use_memory_map_if_larger_than_bytes: u64) -> Result<Self, Error> {
+            let path = path.into();
+            let data = if std::fs::metadata(&path)?.len() <= use_memory_map_if_larger_than_bytes {
+                Backing::InMemory(std::fs::read(&path)?)
+            } else {
+                Backing::Mapped(FileBuffer::open(&path)?)
+            };
+
+            let (offset, header) = if data.as_ref().first() == Some(&b'#') {
+                let input = data.as_ref();
+                let (rest, header) = decode::header(input).map_err(|_| Error::HeaderParsing)?;
+                (input.len() - rest.len(), header)
+            } else {
+                (0, decode::Header::default())
+            };
+
+            let buffer = Buffer {
+                data,
+                offset,
+                peeled: header.peeled,
+                path,
+            };
+            if header.sorted {
+                Ok(buffer)
+            } else {
+                buffer.into_sorted()
+            }
+        }
+    }
+}
+
+impl Buffer {
+    /// Sort all records by their full name and place them into a memory buffer, which is required if
+    /// the packed-refs file doesn't claim to be sorted.
+    fn into_sorted(self) -> Result<Self, open::Error> {
+        let mut records = self
+            .iter()
+            .map(|r| {
+                r.map_err(|err| match err {
+                    packed::iter::Error::Reference { line_number, .. } => open::Error::Parse(line_number),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        records.sort_by_key(|r| r.full_name);
+
+        let mut sorted = Vec::with_capacity(self.as_ref().len());
+        for reference in records {
+            reference.write_to(&mut sorted).expect("write to memory never fails");
+        }
+
+        Ok(Buffer {
+            data: Backing::InMemory(sorted),
+            offset: 0,
+            peeled: self.peeled,
+            path: self.path,
+        })
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    packed::{self, Peeled},
+    parse::{hex_sha1, newline},
+};
+use bstr::{BStr, ByteSlice};
+use nom::{
+    bytes::complete::{tag, take_while},
+    combinator::{map, opt, verify},
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Header {
+    pub peeled: Peeled,
+    pub sorted: bool,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Header {
+            peeled: Peeled::Unspecified,
+            sorted: false,
+        }
+    }
+}
+
+fn until_newline(input: &[u8]) -> IResult<&[u8], &BStr> {
+    map(
+        terminated(take_while(|b: u8| b != b'\r' && b != b'\n'), newline),
+        |not_newline: &[u8]| not_newline.as_bstr(),
+    )(input)
+}
+
+pub fn header(input: &[u8]) -> IResult<&[u8], Header> {
+    let (rest, traits) = preceded(tag(b"# pack-refs with: "), until_newline)(input)?;
+
+    let mut peeled = Peeled::Unspecified;
+    let mut sorted = false;
+    for token in traits.as_bytes().split_str(b" ") {
+        if token == b"fully-peeled" {
+            peeled = Peeled::Fully;
+        } else if token == b"peeled" && peeled == Peeled::Unspecified {
+            peeled = Peeled::Partial;
+        } else if token == b"sorted" {
+            sorted = true;
+        }
+    }
+
+    Ok((rest, Header { peeled, sorted }))
+}
+
+pub fn reference(input: &[u8]) -> IResult<&[u8], packed::Reference<'_>> {
+    let (input, (target, full_name)) = tuple((
+        terminated(hex_sha1, tag(b" ")),
+        verify(until_newline, |name: &BStr| git_validate::refname(name).is_ok()),
+    ))(input)?;
+    let (rest, object) = opt(delimited(tag(b"^"), hex_sha1, newline))(input)?;
+    Ok((
+        rest,
+        packed::Reference {
+            full_name,
+            target: target.as_bstr(),
+            object: object.map(|object| object.as_bstr()),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Header;
+    use crate::packed::Peeled;
+    use bstr::ByteSlice;
+    use git_testtools::hex_to_id;
+
+    mod header {
+        use super::Header;
+        use crate::packed::{decode, Peeled};
+        use bstr::ByteSlice;
+
+        #[test]
+        fn invalid() {
+            assert!(
+                decode::header(b"# some user comment").is_err(),
+                "something the user typed instead of git"
+            );
+            assert!(decode::header(b"# pack-refs with: peeled").is_err(), "missing newline");
+        }
+
+        #[test]
+        fn valid_fully_peeled_sorted() {
+            let (rest, header) =
+                decode::header(b"# pack-refs with: peeled fully-peeled sorted  \nsomething else").expect("valid");
+
+            assert_eq!(rest.as_bstr(), "something else", "remainder starts after newline");
+            assert_eq!(
+                header,
+                Header {
+                    peeled: Peeled::Fully,
+                    sorted: true
+                }
+            );
+        }
+
+        #[test]
+        fn valid_peeled_unsorted() {
+            let (rest, header) = decode::header(b"# pack-refs with: peeled\n").expect("valid");
+
+            assert!(rest.is_empty());
+            assert_eq!(
+                header,
+                Header {
+                    peeled: Peeled::Partial,
+                    sorted: false
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn reference_with_peeled_object() {
+        let (rest, parsed) = super::reference(
+            b"c4cebba92af964f2d126be90b8a6298c4cf84d45 refs/tags/gix-v0.1.0\n^0a7abd4a1c0b8f2c14c3a9e1e8d6c3b1fae8d4a1\nrest",
+        )
+        .expect("valid");
+
+        assert_eq!(rest.as_bstr(), "rest");
+        assert_eq!(parsed.full_name, "refs/tags/gix-v0.1.0");
+        assert_eq!(parsed.target(), hex_to_id("c4cebba92af964f2d126be90b8a6298c4cf84d45"));
+        assert_eq!(parsed.object(), hex_to_id("0a7abd4a1c0b8f2c14c3a9e1e8d6c3b1fae8d4a1"));
+    }
+
+    #[test]
+    fn reference_without_peeled_object() {
+        let (rest, parsed) =
+            super::reference(b"c4cebba92af964f2d126be90b8a6298c4cf84d45 refs/heads/main\r\n").expect("valid");
+
+        assert!(rest.is_empty());
+        assert_eq!(parsed.full_name, "refs/heads/main");
+        assert_eq!(parsed.object(), parsed.target(), "the target is the object");
+    }
+
+    #[test]
+    fn reference_invalid() {
+        assert!(
+            super::reference(b"c4cebba92af964f2d126be90b8a6298c4cf84d45 refs/heads/../main\n").is_err(),
+            "invalid names"
+        );
+        assert!(
+            super::reference(b"c4cebba92af964f2d126be90b8a6298c4cf84d45").is_err(),
+            "missing name"
+        );
+    }
+
+    #[test]
+    fn default_header_is_unsorted_and_unpeeled() {
+        assert_eq!(
+            Header::default(),
+            Header {
+                peeled: Peeled::Unspecified,
+                sorted: false
+            }
+        );
+    }
+}
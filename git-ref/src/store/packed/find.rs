@@ -0,0 +1,118 @@
+use crate::{
+    packed::{self, decode},
+    SafePartialName,
+};
+use bstr::{BStr, ByteSlice};
+use quick_error::quick_error;
+use std::{cmp::Ordering, convert::TryInto};
+
+quick_error! {
+    /// The error returned by [`packed::Buffer::find()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        RefnameValidation(err: crate::safe_name::Error) {
+            display("The ref name or path is not a valid ref name")
+            source(err)
+        }
+        Parse {
+            display("The reference could not be parsed")
+        }
+    }
+}
+
+impl packed::Buffer {
+    /// Find a reference with the given `name` and return it.
+    ///
+    /// Note that the `name` is looked up verbatim, hence it has to be a full reference name like `refs/heads/main`.
+    /// To follow the lookup rules of git, use [`file::Store::find_one()`][crate::file::Store::find_one()] instead.
+    pub fn find<'a, Name>(&self, name: Name) -> Result<Option<packed::Reference<'_>>, Error>
+    where
+        Name: TryInto<SafePartialName<'a>, Error = crate::safe_name::Error>,
+    {
+        let name = name.try_into().map_err(Error::RefnameValidation)?;
+        self.find_full_name(name.0)
+    }
+
+    /// Similar to [`packed::Buffer::find()`] but a non-existing ref is treated as error.
+    pub fn find_existing<'a, Name>(&self, name: Name) -> Result<packed::Reference<'_>, existing::Error>
+    where
+        Name: TryInto<SafePartialName<'a>, Error = crate::safe_name::Error>,
+    {
+        let name = name
+            .try_into()
+            .map_err(|err| existing::Error::Find(Error::RefnameValidation(err)))?;
+        match self.find_full_name(name.0) {
+            Ok(Some(r)) => Ok(r),
+            Ok(None) => Err(existing::Error::NotFound(name.0.to_owned())),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Perform a binary search for `full_name`, which is possible as the records in the buffer are sorted.
+    pub(crate) fn find_full_name(&self, full_name: &BStr) -> Result<Option<packed::Reference<'_>>, Error> {
+        let data = self.as_ref();
+        let start = self.lower_bound(full_name)?;
+        if start == data.len() {
+            return Ok(None);
+        }
+        let (_rest, reference) = decode::reference(&data[start..]).map_err(|_| Error::Parse)?;
+        Ok(if reference.full_name == full_name {
+            Some(reference)
+        } else {
+            None
+        })
+    }
+
+    /// Return the offset to the first record whose name is equal to or greater than `name`, or the length of the
+    /// buffer if there is no such record.
+    pub(crate) fn lower_bound(&self, name: &BStr) -> Result<usize, Error> {
+        let data = self.as_ref();
+        let (mut lo, mut hi) = (0, data.len());
+        while lo < hi {
+            // Peeled lines without a valid reference before them would move us back, so make sure we keep moving forward.
+            let start = record_start(data, lo + (hi - lo) / 2).max(lo);
+            let (rest, reference) = decode::reference(&data[start..]).map_err(|_| Error::Parse)?;
+            match reference.full_name.cmp(name) {
+                Ordering::Less => lo = data.len() - rest.len(),
+                Ordering::Greater | Ordering::Equal => hi = start,
+            }
+        }
+        Ok(lo)
+    }
+}
+
+/// Return the offset to the beginning of the record that contains the byte at `ofs`, skipping over
+/// the line with the peeled object if `ofs` happens to point into it.
+fn record_start(data: &[u8], ofs: usize) -> usize {
+    let line_start = |ofs: usize| data[..ofs].rfind_byte(b'\n').map_or(0, |pos| pos + 1);
+    let start = line_start(ofs);
+    if data.get(start) == Some(&b'^') && start > 0 {
+        line_start(start - 1)
+    } else {
+        start
+    }
+}
+
+///
+pub mod existing {
+    use crate::packed::find;
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`find_existing()`][crate::packed::Buffer::find_existing()]
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Find(err: find::Error) {
+                display("The find operation failed")
+                from()
+                source(err)
+            }
+            NotFound(name: BString) {
+                display("The reference '{}' did not exist", name)
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::packed::{self, decode};
+use bstr::{BString, ByteSlice};
+use quick_error::quick_error;
+
+impl packed::Buffer {
+    /// Return an iterator of references stored in this packed refs buffer, ordered by reference name.
+    ///
+    /// Invalid records are returned as errors, allowing to continue the iteration past them.
+    pub fn iter(&self) -> packed::Iter<'_> {
+        packed::Iter {
+            cursor: self.as_ref(),
+            current_line: self.header_line_count() + 1,
+            prefix: None,
+        }
+    }
+
+    /// Return an iterator yielding only references whose full name starts with `prefix`, like `refs/tags/`,
+    /// ordered by reference name.
+    pub fn iter_prefixed(&self, prefix: impl Into<BString>) -> packed::Iter<'_> {
+        let prefix = prefix.into();
+        let data = self.as_ref();
+        // If the binary search fails due to invalid records, we start from the beginning to surface the errors
+        // in the course of the iteration.
+        let start = self.lower_bound(prefix.as_bstr()).unwrap_or(0);
+        packed::Iter {
+            cursor: &data[start..],
+            current_line: self.header_line_count() + data[..start].find_iter(b"\n").count() + 1,
+            prefix: Some(prefix),
+        }
+    }
+}
+
+impl<'a> packed::Iter<'a> {
+    fn skip_line(&mut self) -> &'a [u8] {
+        let (line, rest) = match self.cursor.find_byte(b'\n') {
+            Some(pos) => self.cursor.split_at(pos + 1),
+            None => (self.cursor, &[][..]),
+        };
+        self.cursor = rest;
+        self.current_line += 1;
+        line
+    }
+}
+
+impl<'a> Iterator for packed::Iter<'a> {
+    type Item = Result<packed::Reference<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.cursor.is_empty() {
+            match decode::reference(self.cursor) {
+                Ok((rest, reference)) => {
+                    self.cursor = rest;
+                    self.current_line += if reference.object.is_some() { 2 } else { 1 };
+                    match &self.prefix {
+                        Some(prefix) if !reference.full_name.starts_with(prefix.as_slice()) => {
+                            if reference.full_name.as_bytes() > prefix.as_slice() {
+                                self.cursor = &[];
+                                return None;
+                            }
+                        }
+                        _ => return Some(Ok(reference)),
+                    }
+                }
+                Err(_) => {
+                    let line_number = self.current_line;
+                    let invalid_line = self.skip_line();
+                    while self.cursor.first() == Some(&b'^') {
+                        self.skip_line();
+                    }
+                    return Some(Err(Error::Reference {
+                        invalid_line: invalid_line
+                            .strip_suffix(b"\n")
+                            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+                            .unwrap_or(invalid_line)
+                            .into(),
+                        line_number,
+                    }));
+                }
+            }
+        }
+        None
+    }
+}
+
+quick_error! {
+    /// The error returned by [`Iter`][super::Iter],
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Reference { invalid_line: BString, line_number: usize } {
+            display("Invalid reference in line {}: '{}'", line_number, invalid_line)
+        }
+    }
+}
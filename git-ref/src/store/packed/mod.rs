@@ -0,0 +1,86 @@
+use bstr::{BStr, BString};
+use filebuffer::FileBuffer;
+use git_hash::ObjectId;
+use std::path::PathBuf;
+
+enum Backing {
+    /// The buffer is loaded entirely in memory.
+    InMemory(Vec<u8>),
+    /// The buffer is mapping the file on disk.
+    Mapped(FileBuffer),
+}
+
+/// A buffer containing a packed-ref file that is either memory mapped or fully in-memory depending on a cutoff.
+///
+/// The buffer is guaranteed to be sorted as per the packed-ref rules which allows some operations to be more efficient.
+pub struct Buffer {
+    data: Backing,
+    /// The offset to the first record, skipping the header
+    offset: usize,
+    /// The peeling guarantees made in the header
+    peeled: Peeled,
+    /// The path from which we were loaded
+    path: PathBuf,
+}
+
+/// The kind of peeling that was performed on the references in a packed-refs file, as indicated by its header.
+#[derive(Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Peeled {
+    /// No peeling information is available, and peeled objects of tags have to be obtained by looking them up.
+    Unspecified,
+    /// References in `refs/tags/` that point to annotated tags are followed by the object they peel to.
+    Partial,
+    /// All references that can be peeled are followed by the object they peel to.
+    Fully,
+}
+
+/// A reference as parsed from the `packed-refs` file
+#[derive(Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Reference<'a> {
+    /// The unvalidated full name of the reference.
+    pub full_name: &'a BStr,
+    /// The target object id of the reference, hex encoded.
+    target: &'a BStr,
+    /// The fully peeled object id, hex encoded, that the ref is ultimately pointing to
+    /// i.e. when all indirections are removed.
+    object: Option<&'a BStr>,
+}
+
+impl<'a> Reference<'a> {
+    /// Decode the target as object
+    pub fn target(&self) -> ObjectId {
+        ObjectId::from_hex(self.target).expect("parser validation")
+    }
+
+    /// Decode the object this reference is ultimately pointing to. Note that this is
+    /// the [`target()`][Reference::target()] if this is not a fully peeled reference like a tag.
+    pub fn object(&self) -> ObjectId {
+        self.object.map_or_else(
+            || self.target(),
+            |id| ObjectId::from_hex(id).expect("parser validation"),
+        )
+    }
+}
+
+/// An iterator over references in a packed refs file
+pub struct Iter<'a> {
+    /// The position at which to parse the next reference
+    cursor: &'a [u8],
+    /// The next line, starting at 1
+    current_line: usize,
+    /// If set, references must start with the given prefix to be returned.
+    prefix: Option<BString>,
+}
+
+mod decode;
+
+///
+pub mod iter;
+
+///
+pub mod buffer;
+
+///
+pub mod find;
+
+pub(crate) mod write;
@@ -0,0 +1,29 @@
+use crate::packed::{self, Peeled};
+use std::io;
+
+impl<'a> packed::Reference<'a> {
+    /// Serialize this reference in the format used by the packed-refs file, including its peeled object if present.
+    pub(crate) fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        out.write_all(self.target)?;
+        out.write_all(b" ")?;
+        out.write_all(self.full_name)?;
+        out.write_all(b"\n")?;
+        if let Some(object) = self.object {
+            out.write_all(b"^")?;
+            out.write_all(object)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the header of a sorted packed-refs file which makes the given `peeled` guarantees.
+pub(crate) fn header(peeled: Peeled, mut out: impl io::Write) -> io::Result<()> {
+    out.write_all(b"# pack-refs with: ")?;
+    out.write_all(match peeled {
+        Peeled::Unspecified => b"",
+        Peeled::Partial => b"peeled ",
+        Peeled::Fully => b"peeled fully-peeled ",
+    })?;
+    out.write_all(b"sorted \n")
+}
@@ -0,0 +1,269 @@
+use git_ref::file;
+
+fn store() -> crate::Result<file::Store> {
+    let path = git_testtools::scripted_fixture_repo_read_only("make_repo_for_reflog.sh")?;
+    Ok(file::Store::from(path.join(".git")))
+}
+
+const DAY: u32 = 24 * 60 * 60;
+const JAN_1_2000: u32 = 946684800;
+
+fn time(seconds: u32) -> git_actor::Time {
+    git_actor::Time {
+        time: seconds,
+        offset: 0,
+        sign: git_actor::Sign::Plus,
+    }
+}
+
+mod iter {
+    use super::store;
+
+    #[test]
+    fn forward_yields_all_lines_from_oldest_to_newest() -> crate::Result {
+        let store = store()?;
+        let mut buf = Vec::new();
+        let lines = store
+            .reflog_iter("HEAD", &mut buf)?
+            .expect("log exists")
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            lines.iter().map(|l| l.message.to_string()).collect::<Vec<_>>(),
+            vec![
+                "commit (initial): c1",
+                "commit: c2",
+                "commit: c3 with a longer message to cross chunk boundaries"
+            ]
+        );
+        assert_eq!(lines[0].previous_oid, git_hash::ObjectId::null_sha1());
+        for pair in lines.windows(2) {
+            assert_eq!(pair[0].new_oid, pair[1].previous_oid, "the log is consistent");
+        }
+        assert_eq!(lines[0].signature.name, "committer");
+        Ok(())
+    }
+
+    #[test]
+    fn missing_logs_are_none() -> crate::Result {
+        let store = store()?;
+        assert!(store
+            .reflog_iter("refs/heads/does-not-exist", &mut Vec::new())?
+            .is_none());
+        assert!(store
+            .reflog_iter_rev("refs/heads/does-not-exist", &mut [0u8; 64])?
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn reverse_yields_the_same_lines_as_forward_in_reverse_order_for_all_buffer_sizes() -> crate::Result {
+        let store = store()?;
+        let mut buf = Vec::new();
+        let mut expected: Vec<git_ref::file::log::mutable::Line> = store
+            .reflog_iter("refs/heads/main", &mut buf)?
+            .expect("log exists")
+            .map(|l| l.map(Into::into))
+            .collect::<Result<_, _>>()?;
+        expected.reverse();
+
+        for buf_size in &[1usize, 7, 64, 150, 4096] {
+            let mut buf = vec![0u8; *buf_size];
+            let actual = store
+                .reflog_iter_rev("refs/heads/main", &mut buf)?
+                .expect("log exists")
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(actual, expected, "buffer size {}", buf_size);
+        }
+
+        assert!(
+            git_ref::file::log::iter::reverse(std::io::Cursor::new(Vec::new()), &mut []).is_err(),
+            "empty buffers are rejected"
+        );
+        Ok(())
+    }
+}
+
+mod lookup {
+    use super::{store, time, DAY, JAN_1_2000};
+    use crate::file::writable_store;
+
+    #[test]
+    fn entry_at_index_counts_from_newest() -> crate::Result {
+        let store = store()?;
+        let newest = store.reflog_entry_at("HEAD", 0)?.expect("present");
+        assert_eq!(
+            newest.message,
+            "commit: c3 with a longer message to cross chunk boundaries"
+        );
+        let oldest = store.reflog_entry_at("HEAD", 2)?.expect("present");
+        assert_eq!(oldest.previous_oid, git_hash::ObjectId::null_sha1());
+        assert!(store.reflog_entry_at("HEAD", 3)?.is_none(), "out of bounds");
+        assert!(store.reflog_entry_at("refs/heads/does-not-exist", 0)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn entries_behind_a_corrupt_line_are_not_returned() -> crate::Result {
+        let (_keep, store) = writable_store("make_repo_for_reflog.sh")?;
+        let log_path = store.base.join("logs").join("HEAD");
+        let mut log = std::fs::read(&log_path)?;
+        log.extend_from_slice(b"corrupt line\n");
+        std::fs::write(&log_path, log)?;
+
+        assert!(
+            store.reflog_entry_at("HEAD", 1).is_err(),
+            "the corrupt newest line can't be skipped to get to older entries"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn entry_before_time_is_the_newest_one_not_newer_than_time() -> crate::Result {
+        let store = store()?;
+        for (seconds, expected_message) in &[
+            (
+                JAN_1_2000 + 3 * DAY,
+                Some("commit: c3 with a longer message to cross chunk boundaries"),
+            ),
+            (JAN_1_2000 + DAY + 1, Some("commit: c2")),
+            (JAN_1_2000 + DAY, Some("commit: c2")),
+            (JAN_1_2000, Some("commit (initial): c1")),
+            (JAN_1_2000 - 1, None),
+        ] {
+            let line = store.reflog_entry_before("HEAD", time(*seconds))?;
+            assert_eq!(
+                line.map(|l| l.message.to_string()).as_deref(),
+                *expected_message,
+                "at {}",
+                seconds
+            );
+        }
+        Ok(())
+    }
+}
+
+mod expire {
+    use super::{time, DAY, JAN_1_2000};
+    use crate::file::writable_store;
+    use git_ref::file::{self, log::expire};
+    use std::collections::HashSet;
+
+    fn messages(store: &file::Store) -> crate::Result<Vec<String>> {
+        let mut buf = Vec::new();
+        Ok(store
+            .reflog_iter("HEAD", &mut buf)?
+            .expect("log exists")
+            .map(|l| l.map(|l| l.message.to_string()))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn ids(store: &file::Store) -> crate::Result<Vec<git_hash::ObjectId>> {
+        let mut buf = Vec::new();
+        Ok(store
+            .reflog_iter("HEAD", &mut buf)?
+            .expect("log exists")
+            .map(|l| l.map(|l| l.new_oid))
+            .collect::<Result<_, _>>()?)
+    }
+
+    #[test]
+    fn entries_older_than_the_cutoff_are_removed() -> crate::Result {
+        let (_keep, store) = writable_store("make_repo_for_reflog.sh")?;
+        let outcome = store.reflog_expire(
+            "HEAD",
+            expire::Options {
+                expire: Some(time(JAN_1_2000 + DAY)),
+                expire_unreachable: None,
+                remove_stale: false,
+            },
+            git_lock::acquire::Fail::Immediately,
+            |_| true,
+            |_| true,
+        )?;
+        assert_eq!(outcome, Some(expire::Outcome { kept: 2, removed: 1 }));
+        assert_eq!(
+            messages(&store)?,
+            vec![
+                "commit: c2",
+                "commit: c3 with a longer message to cross chunk boundaries"
+            ]
+        );
+        assert!(!store.base.join("logs/HEAD.lock").exists(), "the lock is released");
+
+        let outcome = store.reflog_expire(
+            "HEAD",
+            expire::Options::default(),
+            git_lock::acquire::Fail::Immediately,
+            |_| true,
+            |_| true,
+        )?;
+        assert_eq!(outcome, Some(expire::Outcome { kept: 2, removed: 0 }), "nothing to do");
+        assert_eq!(
+            store.reflog_expire(
+                "refs/heads/does-not-exist",
+                expire::Options::default(),
+                git_lock::acquire::Fail::Immediately,
+                |_| true,
+                |_| true,
+            )?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_entries_are_removed_only_if_older_than_their_cutoff() -> crate::Result {
+        let (_keep, store) = writable_store("make_repo_for_reflog.sh")?;
+        let ids = ids(&store)?;
+        let reachable: HashSet<_> = ids[1..].iter().cloned().collect();
+        let outcome = store.reflog_expire(
+            "HEAD",
+            expire::Options {
+                expire: None,
+                expire_unreachable: Some(time(JAN_1_2000 + 3 * DAY)),
+                remove_stale: false,
+            },
+            git_lock::acquire::Fail::Immediately,
+            |_| true,
+            |id| reachable.contains(id),
+        )?;
+        assert_eq!(outcome, Some(expire::Outcome { kept: 2, removed: 1 }));
+        assert_eq!(messages(&store)?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn entries_referring_to_missing_objects_are_removed_if_requested() -> crate::Result {
+        let (_keep, store) = writable_store("make_repo_for_reflog.sh")?;
+        let missing = ids(&store)?[0];
+        let outcome = store.reflog_expire(
+            "HEAD",
+            expire::Options::default(),
+            git_lock::acquire::Fail::Immediately,
+            |_| unreachable!("object presence is only checked if stale entries are removed"),
+            |_| true,
+        )?;
+        assert_eq!(outcome, Some(expire::Outcome { kept: 3, removed: 0 }));
+
+        let outcome = store.reflog_expire(
+            "HEAD",
+            expire::Options {
+                remove_stale: true,
+                ..Default::default()
+            },
+            git_lock::acquire::Fail::Immediately,
+            |id| id != missing,
+            |_| true,
+        )?;
+        assert_eq!(
+            outcome,
+            Some(expire::Outcome { kept: 1, removed: 2 }),
+            "both the entry creating and the one moving away from the missing object are removed"
+        );
+        assert_eq!(
+            messages(&store)?,
+            vec!["commit: c3 with a longer message to cross chunk boundaries"]
+        );
+        Ok(())
+    }
+}
@@ -5,9 +5,25 @@ fn store() -> crate::Result<file::Store> {
     Ok(file::Store::from(path.join(".git")))
 }
 
+fn store_with_packed_refs() -> crate::Result<file::Store> {
+    let path = git_testtools::scripted_fixture_repo_read_only("make_packed_ref_repository.sh")?;
+    Ok(file::Store::from(path.join(".git")))
+}
+
+fn writable_store(script_name: &str) -> crate::Result<(git_testtools::tempfile::TempDir, file::Store)> {
+    let dir = git_testtools::scripted_fixture_repo_writable(script_name)?;
+    let store = file::Store::from(dir.path().join(".git"));
+    Ok((dir, store))
+}
+
+mod log;
+mod pack_refs;
+mod transaction;
+
 mod store {
     mod find_one {
         use crate::file::store;
+        use git_testtools::hex_to_id;
         use std::path::Path;
 
         mod existing {
@@ -57,6 +73,34 @@ mod store {
             Ok(())
         }
 
+        #[test]
+        fn success_with_packed_refs_and_loose_refs_taking_precedence() -> crate::Result {
+            let store = crate::file::store_with_packed_refs()?;
+            let c1 = hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03");
+            let c2 = hex_to_id("9902e3c3e8f0c569b4ab295ddf473e6de763e1e7");
+            for (partial_name, expected_path, expected_id) in &[
+                ("a", "refs/heads/a", &c1),
+                ("b", "refs/heads/b", &c2), // the loose ref wins over the packed one
+                ("main", "refs/heads/main", &c2),
+                ("origin/main", "refs/remotes/origin/main", &c1),
+                (
+                    "t1-annotated",
+                    "refs/tags/t1-annotated",
+                    &hex_to_id("191235ab5831c2e70f5cc9a745253681de898a77"),
+                ),
+                ("refs/tags/t2-lightweight", "refs/tags/t2-lightweight", &c1),
+            ] {
+                let reference = store.find_one_existing(*partial_name)?;
+                assert_eq!(reference.relative_path, Path::new(expected_path));
+                assert_eq!(reference.target(), git_ref::Target::Peeled(expected_id));
+            }
+
+            let mut head = store.find_one_existing("HEAD")?;
+            assert_eq!(head.peel_to_id_in_place()?, c2, "symbolic refs may point to loose refs");
+            assert!(store.find_one("does-not-exist")?.is_none());
+            Ok(())
+        }
+
         #[test]
         fn failure() -> crate::Result {
             let store = store()?;
@@ -76,6 +120,135 @@ mod store {
             Ok(())
         }
     }
+
+    mod iter {
+        use crate::file::{store, store_with_packed_refs};
+        use git_ref::file::iter;
+        use std::path::PathBuf;
+
+        fn paths<'a>(
+            iter: impl Iterator<Item = Result<git_ref::file::Reference<'a>, iter::Error>>,
+        ) -> crate::Result<Vec<PathBuf>> {
+            Ok(iter
+                .map(|r| r.map(|r| r.relative_path))
+                .collect::<Result<Vec<_>, _>>()?)
+        }
+
+        #[test]
+        fn loose_only_with_broken_references_as_errors() -> crate::Result {
+            let store = store()?;
+            let mut actual = Vec::new();
+            let mut num_errors = 0;
+            for r in store.loose_iter() {
+                match r {
+                    Ok(r) => actual.push(r.relative_path),
+                    Err(iter::loose::Error::ReferenceCreation { relative_path, .. }) => {
+                        assert_eq!(relative_path, PathBuf::from("refs/broken"));
+                        num_errors += 1;
+                    }
+                    Err(err) => panic!("unexpected error: {:?}", err),
+                }
+            }
+            assert_eq!(num_errors, 1, "the broken ref doesn't stop the iteration");
+            assert_eq!(
+                actual,
+                vec![
+                    "refs/d1",
+                    "refs/heads/d1",
+                    "refs/heads/dt1",
+                    "refs/heads/main",
+                    "refs/heads/multi-link-target1",
+                    "refs/loop-a",
+                    "refs/loop-b",
+                    "refs/multi-link",
+                    "refs/remotes/origin/HEAD",
+                    "refs/remotes/origin/main",
+                    "refs/remotes/origin/multi-link-target3",
+                    "refs/tags/dt1",
+                    "refs/tags/multi-link-target2",
+                    "refs/tags/t1"
+                ]
+                .into_iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn loose_prefixed() -> crate::Result {
+            let store = store()?;
+            let tags = store
+                .loose_iter_prefixed("refs/tags/")
+                .map(|r| r.map(|r| r.relative_path))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(
+                tags,
+                vec!["refs/tags/dt1", "refs/tags/multi-link-target2", "refs/tags/t1"]
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                store.loose_iter_prefixed("refs/heads/m").count(),
+                2,
+                "prefixes don't need to end in a slash"
+            );
+            assert_eq!(store.loose_iter_prefixed("refs/does-not-exist/").count(), 0);
+            Ok(())
+        }
+
+        #[test]
+        fn loose_and_packed_with_loose_refs_taking_precedence() -> crate::Result {
+            let store = store_with_packed_refs()?;
+            let packed = store.packed()?;
+            let refs = store
+                .iter(packed.as_ref())
+                .map(|r| r.map(|r| (r.relative_path.clone(), r.target().as_id().expect("peeled").to_owned())))
+                .collect::<Result<Vec<_>, _>>()?;
+            let c1 = git_testtools::hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03");
+            let c2 = git_testtools::hex_to_id("9902e3c3e8f0c569b4ab295ddf473e6de763e1e7");
+            assert_eq!(
+                refs,
+                vec![
+                    ("refs/heads/a", c1),
+                    ("refs/heads/b", c2),
+                    ("refs/heads/c", c1),
+                    ("refs/heads/d-f", c2),
+                    ("refs/heads/d/e", c2),
+                    ("refs/heads/main", c2),
+                    ("refs/remotes/origin/main", c1),
+                    (
+                        "refs/tags/t1-annotated",
+                        git_testtools::hex_to_id("191235ab5831c2e70f5cc9a745253681de898a77")
+                    ),
+                    ("refs/tags/t2-lightweight", c1),
+                ]
+                .into_iter()
+                .map(|(path, id)| (PathBuf::from(path), id))
+                .collect::<Vec<_>>()
+            );
+
+            assert_eq!(
+                paths(store.iter_prefixed(packed.as_ref(), "refs/heads/d"))?,
+                vec![PathBuf::from("refs/heads/d-f"), PathBuf::from("refs/heads/d/e")],
+                "directories are sorted as if they had a trailing slash"
+            );
+            assert_eq!(
+                paths(store.iter_prefixed(packed.as_ref(), "refs/tags/"))?,
+                vec![
+                    PathBuf::from("refs/tags/t1-annotated"),
+                    PathBuf::from("refs/tags/t2-lightweight")
+                ]
+            );
+            assert_eq!(
+                paths(store.iter(None))?.len(),
+                4,
+                "without packed refs, only loose ones are returned"
+            );
+            Ok(())
+        }
+    }
 }
 
 mod reference {
@@ -0,0 +1,134 @@
+use crate::file::writable_store;
+use git_ref::{
+    file::{self, pack_refs},
+    mutable::Target,
+    transaction::{Edit, RefEdit, Reflog},
+};
+use git_testtools::hex_to_id;
+use std::convert::TryInto;
+
+const C1: &str = "134385f6d781b7e97062102c6a483440bfda2a03";
+const ANNOTATED_TAG: &str = "191235ab5831c2e70f5cc9a745253681de898a77";
+
+fn peel(id: &git_hash::oid) -> Option<git_hash::ObjectId> {
+    if id == hex_to_id(ANNOTATED_TAG) {
+        Some(hex_to_id(C1))
+    } else {
+        None
+    }
+}
+
+fn all_refs(store: &file::Store) -> crate::Result<Vec<(std::path::PathBuf, git_hash::ObjectId)>> {
+    let packed = store.packed()?;
+    Ok(store
+        .iter(packed.as_ref())
+        .map(|r| r.map(|r| (r.relative_path.clone(), r.target().as_id().expect("peeled").to_owned())))
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn all_loose_refs_are_packed_and_pruned() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    let refs_before = all_refs(&store)?;
+    let outcome = store.pack_refs(
+        pack_refs::Options { all: true, prune: true },
+        git_lock::acquire::Fail::Immediately,
+        peel,
+    )?;
+    assert_eq!(outcome, pack_refs::Outcome { packed: 9, pruned: 4 });
+    assert_eq!(
+        all_refs(&store)?,
+        refs_before,
+        "the value of all references is unchanged"
+    );
+    assert_eq!(store.loose_iter().count(), 0, "all loose refs were removed");
+    assert!(
+        store.base.join("refs/heads").is_dir(),
+        "directories directly within refs/ are kept"
+    );
+    assert!(
+        !store.base.join("refs/heads/d").exists(),
+        "but empty ones below are removed"
+    );
+    assert!(store.find_one("HEAD")?.is_some(), "symbolic refs stay loose");
+
+    let packed = store.packed()?.expect("present");
+    assert_eq!(packed.peeled(), git_ref::packed::Peeled::Fully);
+    assert_eq!(
+        packed.find("refs/tags/t1-annotated")?.expect("present").object(),
+        hex_to_id(C1),
+        "tags are peeled"
+    );
+    assert!(!store.base.join("packed-refs.lock").exists());
+    Ok(())
+}
+
+#[test]
+fn by_default_only_tags_are_packed() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    std::fs::write(store.base.join("refs/tags/loose-tag"), format!("{}\n", C1))?;
+    let outcome = store.pack_refs(
+        pack_refs::Options::default(),
+        git_lock::acquire::Fail::Immediately,
+        peel,
+    )?;
+    assert_eq!(outcome, pack_refs::Outcome { packed: 8, pruned: 1 });
+    assert_eq!(store.loose_iter().count(), 4, "loose branches are kept as they are");
+    assert!(store.packed()?.expect("present").find("refs/tags/loose-tag")?.is_some());
+    Ok(())
+}
+
+#[test]
+fn loose_refs_that_cannot_be_parsed_are_skipped() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    let broken = store.base.join("refs/heads/broken");
+    std::fs::write(&broken, "not a reference\n")?;
+    let outcome = store.pack_refs(
+        pack_refs::Options { all: true, prune: true },
+        git_lock::acquire::Fail::Immediately,
+        peel,
+    )?;
+    assert_eq!(outcome, pack_refs::Outcome { packed: 9, pruned: 4 });
+    assert!(broken.is_file(), "the broken reference is left as is");
+    assert!(store.packed()?.expect("present").find("refs/heads/broken")?.is_none());
+    Ok(())
+}
+
+#[test]
+fn packed_refs_can_be_deleted_while_keeping_all_others() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    store.pack_refs(
+        pack_refs::Options { all: true, prune: true },
+        git_lock::acquire::Fail::Immediately,
+        peel,
+    )?;
+    store
+        .transaction(
+            Some(RefEdit {
+                edit: Edit::Delete {
+                    previous: Some(Target::Peeled(hex_to_id(ANNOTATED_TAG))),
+                    mode: Reflog::AutoAndNoDeref,
+                },
+                name: "refs/tags/t1-annotated".try_into()?,
+            }),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&git_actor::Signature {
+            name: "name".into(),
+            email: "email".into(),
+            time: git_actor::Time {
+                time: 0,
+                offset: 0,
+                sign: git_actor::Sign::Plus,
+            },
+        })?;
+    let packed = store.packed()?.expect("present");
+    assert!(packed.find("refs/tags/t1-annotated")?.is_none());
+    assert_eq!(packed.iter().count(), 8);
+    assert_eq!(
+        packed.peeled(),
+        git_ref::packed::Peeled::Fully,
+        "peeling information is retained"
+    );
+    Ok(())
+}
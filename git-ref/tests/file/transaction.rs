@@ -0,0 +1,278 @@
+use crate::file::writable_store;
+use git_ref::{
+    file,
+    mutable::Target,
+    transaction::{Edit, RefEdit, Reflog, Update},
+};
+use git_testtools::hex_to_id;
+use std::convert::TryInto;
+
+fn committer() -> git_actor::Signature {
+    git_actor::Signature {
+        name: "committer".into(),
+        email: "committer@example.com".into(),
+        time: git_actor::Time {
+            time: 1234,
+            offset: 1800,
+            sign: git_actor::Sign::Plus,
+        },
+    }
+}
+
+fn update(name: &str, mode: Reflog, previous: Option<Target>, new: Target) -> crate::Result<RefEdit> {
+    Ok(RefEdit {
+        edit: Edit::Update(Update {
+            mode,
+            previous,
+            new,
+            message: "the message".into(),
+        }),
+        name: name.try_into()?,
+    })
+}
+
+fn last_reflog_line(store: &file::Store, name: &str) -> crate::Result<String> {
+    let log = std::fs::read_to_string(store.base.join("logs").join(name))?;
+    Ok(log.lines().last().expect("at least one line").to_owned())
+}
+
+fn id_of(store: &file::Store, name: &str) -> crate::Result<git_hash::ObjectId> {
+    Ok(store
+        .find_one_existing(name)?
+        .target()
+        .as_id()
+        .expect("peeled")
+        .to_owned())
+}
+
+const C1: &str = "134385f6d781b7e97062102c6a483440bfda2a03";
+const C2: &str = "9902e3c3e8f0c569b4ab295ddf473e6de763e1e7";
+
+#[test]
+fn create_and_update_with_reflog() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    let edits = store
+        .transaction(
+            Some(update(
+                "refs/heads/new",
+                Reflog::AutoAndNoDeref,
+                None,
+                Target::Peeled(hex_to_id(C1)),
+            )?),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert_eq!(edits.len(), 1);
+    assert_eq!(id_of(&store, "refs/heads/new")?, hex_to_id(C1));
+    assert_eq!(
+        last_reflog_line(&store, "refs/heads/new")?,
+        format!(
+            "0000000000000000000000000000000000000000 {} committer <committer@example.com> 1234 +0030\tthe message",
+            C1
+        )
+    );
+
+    store
+        .transaction(
+            Some(update(
+                "refs/heads/new",
+                Reflog::AutoAndNoDeref,
+                Some(Target::Peeled(hex_to_id(C1))),
+                Target::Peeled(hex_to_id(C2)),
+            )?),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert_eq!(id_of(&store, "refs/heads/new")?, hex_to_id(C2));
+    assert!(last_reflog_line(&store, "refs/heads/new")?.starts_with(&format!("{} {} ", C1, C2)));
+
+    store
+        .transaction(
+            Some(update(
+                "refs/tags/no-reflog",
+                Reflog::AutoAndNoDeref,
+                None,
+                Target::Peeled(hex_to_id(C1)),
+            )?),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert!(
+        !store.base.join("logs/refs/tags/no-reflog").exists(),
+        "tags don't get a reflog unless it is forced"
+    );
+    Ok(())
+}
+
+#[test]
+fn compare_and_swap_failure_leaves_all_references_untouched() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    let res = store
+        .transaction(
+            vec![
+                update(
+                    "refs/heads/new",
+                    Reflog::AutoAndNoDeref,
+                    None,
+                    Target::Peeled(hex_to_id(C1)),
+                )?,
+                update(
+                    "refs/heads/a",
+                    Reflog::AutoAndNoDeref,
+                    Some(Target::Peeled(hex_to_id(C2))),
+                    Target::Peeled(hex_to_id(C2)),
+                )?,
+            ],
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&committer());
+    match res {
+        Err(file::transaction::commit::Error::Prepare(file::transaction::prepare::Error::ReferenceOutOfDate {
+            full_name,
+            actual,
+            ..
+        })) => {
+            assert_eq!(full_name, "refs/heads/a");
+            assert_eq!(actual, Target::Peeled(hex_to_id(C1)));
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+    assert!(store.find_one("refs/heads/new")?.is_none());
+    assert_eq!(id_of(&store, "refs/heads/a")?, hex_to_id(C1));
+    assert!(
+        !store.base.join("refs/heads/new.lock").exists() && !store.base.join("refs/heads/a.lock").exists(),
+        "all locks are released"
+    );
+
+    let res = store
+        .transaction(
+            Some(update(
+                "refs/heads/does-not-exist",
+                Reflog::AutoAndNoDeref,
+                Some(Target::Peeled(hex_to_id(C1))),
+                Target::Peeled(hex_to_id(C2)),
+            )?),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .prepare();
+    assert!(matches!(res, Err(file::transaction::prepare::Error::MustExist { .. })));
+    Ok(())
+}
+
+#[test]
+fn symbolic_head_is_dereferenced_and_both_reflogs_are_written() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    let edits = store
+        .transaction(
+            Some(update(
+                "HEAD",
+                Reflog::OnlyAndDeref,
+                Some(Target::Peeled(hex_to_id(C2))),
+                Target::Peeled(hex_to_id(C1)),
+            )?),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert_eq!(
+        edits.iter().map(|e| e.name.as_bstr().to_string()).collect::<Vec<_>>(),
+        vec!["HEAD", "refs/heads/main"]
+    );
+    assert_eq!(
+        store
+            .find_one_existing("HEAD")?
+            .target()
+            .as_ref()
+            .expect("still symbolic"),
+        "refs/heads/main"
+    );
+    assert_eq!(id_of(&store, "refs/heads/main")?, hex_to_id(C1));
+    for name in &["HEAD", "refs/heads/main"] {
+        assert!(last_reflog_line(&store, name)?.starts_with(&format!("{} {} ", C2, C1)));
+    }
+    Ok(())
+}
+
+#[test]
+fn symbolic_reference_pointing_to_a_packed_reference_logs_its_id() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    store
+        .transaction(
+            Some(update(
+                "HEAD",
+                Reflog::AutoAndNoDeref,
+                None,
+                Target::Symbolic("refs/heads/a".try_into()?),
+            )?),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert!(
+        !store.base.join("refs/heads/a").exists(),
+        "the referent is only in the packed-refs file"
+    );
+    assert!(
+        last_reflog_line(&store, "HEAD")?.starts_with(&format!("{} {} ", C2, C1)),
+        "the new id is the one of the packed referent"
+    );
+    Ok(())
+}
+
+#[test]
+fn delete_loose_and_packed_reference() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    store
+        .transaction(
+            Some(RefEdit {
+                edit: Edit::Delete {
+                    previous: Some(Target::Peeled(hex_to_id(C2))),
+                    mode: Reflog::AutoAndNoDeref,
+                },
+                name: "refs/heads/b".try_into()?,
+            }),
+            git_lock::acquire::Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert!(store.find_one("refs/heads/b")?.is_none(), "neither loose nor packed");
+    assert!(!store.base.join("logs/refs/heads/b").exists());
+    let packed = store.packed()?.expect("still present");
+    assert_eq!(packed.iter().count(), 6, "all other packed refs are kept");
+    Ok(())
+}
+
+#[test]
+fn duplicate_edits_are_rejected() -> crate::Result {
+    let (_keep, store) = writable_store("make_packed_ref_repository.sh")?;
+    let edit = update(
+        "refs/heads/a",
+        Reflog::AutoAndNoDeref,
+        None,
+        Target::Peeled(hex_to_id(C2)),
+    )?;
+    let res = store
+        .transaction(vec![edit.clone(), edit], git_lock::acquire::Fail::Immediately)
+        .prepare();
+    assert!(matches!(
+        res,
+        Err(file::transaction::prepare::Error::DuplicateRefEdits(_))
+    ));
+
+    let res = store
+        .transaction(
+            vec![
+                update("HEAD", Reflog::OnlyAndDeref, None, Target::Peeled(hex_to_id(C1)))?,
+                update(
+                    "refs/heads/main",
+                    Reflog::AutoAndNoDeref,
+                    None,
+                    Target::Peeled(hex_to_id(C1)),
+                )?,
+            ],
+            git_lock::acquire::Fail::Immediately,
+        )
+        .prepare();
+    assert!(
+        matches!(res, Err(file::transaction::prepare::Error::DuplicateRefEdits(_))),
+        "edits through symbolic references count as well"
+    );
+    Ok(())
+}
@@ -0,0 +1,24 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+git branch a
+git branch b
+git branch c
+
+git tag -m "annotated tag" t1-annotated
+git tag t2-lightweight
+
+mkdir -p .git/refs/remotes/origin
+cp .git/refs/heads/main .git/refs/remotes/origin/
+
+git pack-refs --all --prune
+
+git commit -q --allow-empty -m c2
+git branch -f b
+git branch d/e
+git branch d-f
@@ -0,0 +1,10 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+GIT_COMMITTER_DATE="2000-01-01 00:00:00 +0000" git commit -q --allow-empty -m c1
+GIT_COMMITTER_DATE="2000-01-02 00:00:00 +0000" git commit -q --allow-empty -m c2
+GIT_COMMITTER_DATE="2000-01-03 00:00:00 +0000" git commit -q --allow-empty -m "c3 with a longer message to cross chunk boundaries"
@@ -0,0 +1,281 @@
+use git_ref::packed;
+use git_testtools::tempfile;
+use std::path::PathBuf;
+
+fn packed_refs_path() -> crate::Result<PathBuf> {
+    let path = git_testtools::scripted_fixture_repo_read_only("make_packed_ref_repository.sh")?;
+    Ok(path.join(".git").join("packed-refs"))
+}
+
+fn write_packed_refs_with(input: &[u8]) -> crate::Result<(tempfile::TempDir, PathBuf)> {
+    let dir = tempfile::tempdir()?;
+    let packed_refs_path = dir.path().join("packed-refs");
+    std::fs::write(&packed_refs_path, input)?;
+    Ok((dir, packed_refs_path))
+}
+
+mod open {
+    use crate::packed::{packed_refs_path, write_packed_refs_with};
+    use git_ref::packed;
+    use git_testtools::hex_to_id;
+
+    #[test]
+    fn mapped_and_in_memory_buffers_behave_the_same() -> crate::Result {
+        let path = packed_refs_path()?;
+        for use_memory_map_if_larger_than_bytes in &[0, u64::MAX] {
+            let buf = packed::Buffer::open(&path, *use_memory_map_if_larger_than_bytes)?;
+            assert_eq!(buf.peeled(), packed::Peeled::Fully, "git writes fully peeled files");
+            assert_eq!(buf.path(), path);
+            let r = buf.find("refs/heads/main")?.expect("present");
+            assert_eq!(r.full_name, "refs/heads/main");
+            assert_eq!(r.target(), hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unsorted_buffers_are_sorted_on_the_fly() -> crate::Result {
+        let (_keep, path) = write_packed_refs_with(
+            b"# pack-refs with: peeled\n\
+              1111111111111111111111111111111111111111 refs/tags/z\n\
+              ^2222222222222222222222222222222222222222\n\
+              3333333333333333333333333333333333333333 refs/heads/main\n\
+              4444444444444444444444444444444444444444 refs/heads/a\n",
+        )?;
+        let buf = packed::Buffer::open(path, 0)?;
+        assert_eq!(buf.peeled(), packed::Peeled::Partial);
+
+        for (name, target, object) in &[
+            ("refs/heads/a", "4444444444444444444444444444444444444444", None),
+            ("refs/heads/main", "3333333333333333333333333333333333333333", None),
+            (
+                "refs/tags/z",
+                "1111111111111111111111111111111111111111",
+                Some("2222222222222222222222222222222222222222"),
+            ),
+        ] {
+            let r = buf.find_existing(*name)?;
+            assert_eq!(r.target(), hex_to_id(target));
+            assert_eq!(r.object(), hex_to_id(object.unwrap_or(target)));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn buffers_without_header_are_considered_unsorted_and_unpeeled() -> crate::Result {
+        let (_keep, path) = write_packed_refs_with(
+            b"3333333333333333333333333333333333333333 refs/heads/main\n\
+              4444444444444444444444444444444444444444 refs/heads/a\n",
+        )?;
+        let buf = packed::Buffer::open(path, u64::MAX)?;
+        assert_eq!(buf.peeled(), packed::Peeled::Unspecified);
+        assert!(buf.find("refs/heads/a")?.is_some());
+        assert!(buf.find("refs/heads/main")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn unsorted_buffers_with_invalid_records_fail_to_open() -> crate::Result {
+        let (_keep, path) = write_packed_refs_with(
+            b"# pack-refs with: peeled\n\
+              3333333333333333333333333333333333333333 refs/heads/main\n\
+              4444444444444444444444444444444444444444 refs/heads/a\n\
+              notahexsha refs/heads/broken\n",
+        )?;
+        match packed::Buffer::open(path, 0) {
+            Err(packed::buffer::open::Error::Parse(line_number)) => assert_eq!(line_number, 4),
+            Err(err) => panic!("unexpected error: {:?}", err),
+            Ok(_) => panic!("expected an error"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_headers_fail_to_open() -> crate::Result {
+        let (_keep, path) = write_packed_refs_with(b"# a comment by the user\n")?;
+        assert!(matches!(
+            packed::Buffer::open(path, 0),
+            Err(packed::buffer::open::Error::HeaderParsing)
+        ));
+        Ok(())
+    }
+}
+
+mod find {
+    use crate::packed::{packed_refs_path, write_packed_refs_with};
+    use git_ref::packed;
+    use git_testtools::hex_to_id;
+
+    #[test]
+    fn all_references_can_be_found_and_peeled_objects_are_available() -> crate::Result {
+        let buf = packed::Buffer::open(packed_refs_path()?, 0)?;
+        let c1 = hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03");
+        for name in &[
+            "refs/heads/a",
+            "refs/heads/b",
+            "refs/heads/c",
+            "refs/heads/main",
+            "refs/remotes/origin/main",
+            "refs/tags/t2-lightweight",
+        ] {
+            let r = buf.find_existing(*name)?;
+            assert_eq!(r.full_name, *name);
+            assert_eq!(r.target(), c1);
+            assert_eq!(r.object(), c1, "unpeeled references have the target as object");
+        }
+
+        let tag = buf.find_existing("refs/tags/t1-annotated")?;
+        assert_eq!(tag.target(), hex_to_id("191235ab5831c2e70f5cc9a745253681de898a77"));
+        assert_eq!(tag.object(), c1, "the annotated tag is peeled to the commit");
+        Ok(())
+    }
+
+    #[test]
+    fn names_are_looked_up_verbatim() -> crate::Result {
+        let buf = packed::Buffer::open(packed_refs_path()?, 0)?;
+        for name in &[
+            "main",
+            "heads/main",
+            "refs/heads/does-not-exist",
+            "refs/heads/0",
+            "refs/tags/zzz",
+        ] {
+            assert!(
+                buf.find(*name)?.is_none(),
+                "{} is not a full name or does not exist",
+                name
+            );
+        }
+        match buf.find_existing("main") {
+            Err(packed::find::existing::Error::NotFound(name)) => assert_eq!(name, "main"),
+            Err(err) => panic!("unexpected error: {:?}", err),
+            Ok(_) => panic!("expected an error"),
+        }
+        assert!(matches!(
+            buf.find("../escaping"),
+            Err(packed::find::Error::RefnameValidation(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_records_fail_the_lookup_if_encountered() -> crate::Result {
+        let (_keep, path) = write_packed_refs_with(
+            b"# pack-refs with: peeled fully-peeled sorted \n\
+              3333333333333333333333333333333333333333 refs/heads/a\n\
+              notahexsha refs/heads/b\n\
+              4444444444444444444444444444444444444444 refs/heads/c\n",
+        )?;
+        let buf = packed::Buffer::open(path, 0)?;
+        assert!(matches!(buf.find("refs/heads/b"), Err(packed::find::Error::Parse)));
+        Ok(())
+    }
+}
+
+#[test]
+fn empty_buffers_contain_nothing() -> crate::Result {
+    let (_keep, path) = write_packed_refs_with(b"# pack-refs with: peeled fully-peeled sorted \n")?;
+    let buf = packed::Buffer::open(path, 0)?;
+    assert!(buf.find("refs/heads/main")?.is_none());
+    Ok(())
+}
+
+mod iter {
+    use crate::packed::{packed_refs_path, write_packed_refs_with};
+    use git_ref::packed;
+
+    #[test]
+    fn all_references_in_order() -> crate::Result {
+        let buf = packed::Buffer::open(packed_refs_path()?, 0)?;
+        let names = buf
+            .iter()
+            .map(|r| r.map(|r| r.full_name.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            names,
+            vec![
+                "refs/heads/a",
+                "refs/heads/b",
+                "refs/heads/c",
+                "refs/heads/main",
+                "refs/remotes/origin/main",
+                "refs/tags/t1-annotated",
+                "refs/tags/t2-lightweight"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prefixed() -> crate::Result {
+        let buf = packed::Buffer::open(packed_refs_path()?, 0)?;
+        for (prefix, expected) in &[
+            ("refs/tags/", vec!["refs/tags/t1-annotated", "refs/tags/t2-lightweight"]),
+            ("refs/heads/m", vec!["refs/heads/main"]),
+            ("refs/remotes/", vec!["refs/remotes/origin/main"]),
+            ("refs/heads/main", vec!["refs/heads/main"]),
+            (
+                "refs/",
+                vec![
+                    "refs/heads/a",
+                    "refs/heads/b",
+                    "refs/heads/c",
+                    "refs/heads/main",
+                    "refs/remotes/origin/main",
+                    "refs/tags/t1-annotated",
+                    "refs/tags/t2-lightweight",
+                ],
+            ),
+            ("refs/heads/zzz", vec![]),
+            ("a", vec![]),
+            ("zzz", vec![]),
+        ] {
+            let names = buf
+                .iter_prefixed(*prefix)
+                .map(|r| r.map(|r| r.full_name.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(&names, expected, "prefix {}", prefix);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_records_are_errors_that_do_not_stop_the_iteration() -> crate::Result {
+        let (_keep, path) = write_packed_refs_with(
+            b"# pack-refs with: peeled fully-peeled sorted \n\
+              3333333333333333333333333333333333333333 refs/heads/a\n\
+              notahexsha refs/heads/b\n\
+              ^4444444444444444444444444444444444444444\n\
+              4444444444444444444444444444444444444444 refs/heads/c\n\
+              ^notahexsha\n\
+              5555555555555555555555555555555555555555 refs/heads/d\n",
+        )?;
+        let buf = packed::Buffer::open(path, 0)?;
+        let mut iter = buf.iter();
+        assert_eq!(iter.next().expect("a")?.full_name, "refs/heads/a");
+        match iter.next().expect("b") {
+            Err(packed::iter::Error::Reference {
+                invalid_line,
+                line_number,
+            }) => {
+                assert_eq!(invalid_line, "notahexsha refs/heads/b");
+                assert_eq!(line_number, 3, "lines start at 1 and the header is counted");
+            }
+            Ok(_) => panic!("expected an error"),
+        }
+        assert_eq!(iter.next().expect("c")?.full_name, "refs/heads/c");
+        assert_eq!(
+            iter.next().expect("invalid peeled line").unwrap_err().to_string(),
+            "Invalid reference in line 6: '^notahexsha'"
+        );
+        assert_eq!(iter.next().expect("d")?.full_name, "refs/heads/d");
+        assert!(iter.next().is_none());
+
+        assert_eq!(
+            buf.iter_prefixed("refs/heads/d").filter_map(Result::ok).count(),
+            1,
+            "the prefix lookup falls back to a linear search and skips over invalid records"
+        );
+        Ok(())
+    }
+}
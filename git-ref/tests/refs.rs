@@ -1,3 +1,4 @@
 type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 mod file;
+mod packed;
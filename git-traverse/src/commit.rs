@@ -5,7 +5,8 @@ pub mod ancestors {
     use quick_error::quick_error;
     use std::{
         borrow::BorrowMut,
-        collections::{BTreeSet, VecDeque},
+        cmp::{Ordering, Reverse},
+        collections::{BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
     };
 
     quick_error! {
@@ -13,9 +14,17 @@ pub mod ancestors {
         #[derive(Debug)]
         #[allow(missing_docs)]
         pub enum Error {
+            CommitGraph(err: git_commitgraph::file::commit::Error) {
+                display("A commit could not be read from the commit-graph")
+                source(err)
+                from()
+            }
             NotFound{oid: ObjectId} {
                 display("The commit {} could not be found", oid)
             }
+            ParentBeforeChild{parent: ObjectId, child: ObjectId} {
+                display("The commit {} was reached through its child {} after it was returned, which can happen if generation numbers in the commit-graph are wrong", parent, child)
+            }
             ObjectDecode(err: immutable::object::decode::Error) {
                 display("An object could not be decoded")
                 source(err)
@@ -24,12 +33,35 @@ pub mod ancestors {
         }
     }
 
+    /// The order in which commits are returned by the [Ancestors] iterator.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Sorting {
+        /// Commits are returned breadth-first, with parents in the order they are listed in their commit.
+        ///
+        /// This is the default and the fastest as commits don't have to be looked up before they are returned.
+        BreadthFirst,
+        /// Commits are returned by committer date, newest first, which is the default order of `git log` and `git rev-list`.
+        ///
+        /// Commits with the same committer date are returned in the order they were discovered in.
+        ByCommitterDate,
+        /// No commit is returned before all of its children were returned, like with `git log --topo-order`.
+        ///
+        /// With a [commit-graph][Ancestors::commit_graph()], its generation numbers allow to return commits early.
+        /// Otherwise all commits have to be looked up before the first one can be returned.
+        Topological,
+    }
+
     /// The state used and potentially shared by multiple graph traversals.
     #[derive(Default, Clone)]
     pub struct State {
         next: VecDeque<ObjectId>,
         buf: Vec<u8>,
         seen: BTreeSet<ObjectId>,
+        /// Commits that are returned, as opposed to those which are seen but excluded.
+        included: HashSet<ObjectId>,
+        by_date: BinaryHeap<Prioritized<(u64, Reverse<usize>), Info>>,
+        count: usize,
+        topo: Topological,
     }
 
     impl State {
@@ -37,6 +69,10 @@ pub mod ancestors {
             self.next.clear();
             self.buf.clear();
             self.seen.clear();
+            self.included.clear();
+            self.by_date.clear();
+            self.count = 0;
+            self.topo.clear();
         }
     }
 
@@ -45,6 +81,10 @@ pub mod ancestors {
         find: Find,
         predicate: Predicate,
         state: StateMut,
+        graph: Option<git_commitgraph::Graph>,
+        min_generation: u32,
+        sorting: Sorting,
+        tips_sorted: bool,
     }
 
     impl<Find, StateMut> Ancestors<Find, fn(&oid) -> bool, StateMut>
@@ -103,11 +143,48 @@ pub mod ancestors {
                 for tip in tips.map(Into::into) {
                     let was_inserted = state.seen.insert(tip);
                     if was_inserted && predicate(&tip) {
+                        state.included.insert(tip);
                         state.next.push_back(tip);
                     }
                 }
             }
-            Self { find, predicate, state }
+            Self {
+                find,
+                predicate,
+                state,
+                graph: None,
+                min_generation: 0,
+                sorting: Sorting::BreadthFirst,
+                tips_sorted: false,
+            }
+        }
+    }
+
+    impl<Find, Predicate, StateMut> Ancestors<Find, Predicate, StateMut> {
+        /// Use `graph` to obtain the parents of commits it contains instead of looking them up with `find`, which is
+        /// only used for commits that aren't in the commit-graph.
+        pub fn commit_graph(mut self, graph: git_commitgraph::Graph) -> Self {
+            self.graph = Some(graph);
+            self
+        }
+
+        /// Don't return or traverse commits in the [commit-graph][Ancestors::commit_graph()] with a
+        /// [generation number][git_commitgraph::file::Commit::generation()] lower than `generation`, which cannot
+        /// reach commits with `generation` or higher.
+        ///
+        /// This is useful to stop the traversal early if only commits up to a certain one are of interest, and has no effect
+        /// without a commit-graph. Tips are returned no matter their generation.
+        pub fn min_generation(mut self, generation: u32) -> Self {
+            self.min_generation = generation;
+            self
+        }
+
+        /// Return commits in the order defined by `sorting` instead of [breadth-first][Sorting::BreadthFirst].
+        ///
+        /// Must be set before the first commit is obtained.
+        pub fn sorting(mut self, sorting: Sorting) -> Self {
+            self.sorting = sorting;
+            self
         }
     }
 
@@ -120,32 +197,394 @@ pub mod ancestors {
         type Item = Result<ObjectId, Error>;
 
         fn next(&mut self) -> Option<Self::Item> {
-            let state = self.state.borrow_mut();
-            let res = state.next.pop_front();
+            match self.sorting {
+                Sorting::BreadthFirst => self.next_breadth_first(),
+                Sorting::ByCommitterDate => self.next_by_committer_date(),
+                Sorting::Topological => self.next_topological(),
+            }
+        }
+    }
+
+    impl<Find, Predicate, StateMut> Ancestors<Find, Predicate, StateMut>
+    where
+        Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+        Predicate: FnMut(&oid) -> bool,
+        StateMut: BorrowMut<State>,
+    {
+        fn next_breadth_first(&mut self) -> Option<Result<ObjectId, Error>> {
+            let Self {
+                find,
+                predicate,
+                state,
+                graph,
+                min_generation,
+                ..
+            } = self;
+            let State { next, buf, seen, .. } = state.borrow_mut();
+            let res = next.pop_front();
             if let Some(oid) = res {
-                match (self.find)(&oid, &mut state.buf) {
-                    Some(mut commit_iter) => {
-                        if let Some(Err(decode_tree_err)) = commit_iter.next() {
-                            return Some(Err(decode_tree_err.into()));
+                let mut enqueue = |id: ObjectId, generation: Option<u32>| {
+                    if matches!(generation, Some(generation) if generation < *min_generation) {
+                        return;
+                    }
+                    let was_inserted = seen.insert(id);
+                    if was_inserted && predicate(&id) {
+                        next.push_back(id);
+                    }
+                };
+                match graph.as_ref().and_then(|graph| Some((graph, graph.commit_by_id(oid)?))) {
+                    Some((graph, commit)) => {
+                        for pos in commit.iter_parents() {
+                            let parent = match pos {
+                                Ok(pos) => graph.commit_at(pos),
+                                Err(err) => return Some(Err(err.into())),
+                            };
+                            enqueue(parent.id().to_owned(), Some(parent.generation()));
                         }
-                        for token in commit_iter {
-                            match token {
-                                Ok(immutable::commit::iter::Token::Parent { id }) => {
-                                    let was_inserted = state.seen.insert(id);
-                                    if was_inserted && (self.predicate)(&id) {
-                                        state.next.push_back(id);
+                    }
+                    None => match find(&oid, buf) {
+                        Some(mut commit_iter) => {
+                            if let Some(Err(decode_tree_err)) = commit_iter.next() {
+                                return Some(Err(decode_tree_err.into()));
+                            }
+                            for token in commit_iter {
+                                match token {
+                                    Ok(immutable::commit::iter::Token::Parent { id }) => {
+                                        enqueue(id, generation_of(graph.as_ref(), &id))
                                     }
+                                    Ok(_a_token_past_the_parents) => break,
+                                    Err(err) => return Some(Err(err.into())),
                                 }
-                                Ok(_a_token_past_the_parents) => break,
-                                Err(err) => return Some(Err(err.into())),
                             }
                         }
-                    }
-                    None => return Some(Err(Error::NotFound { oid })),
+                        None => return Some(Err(Error::NotFound { oid })),
+                    },
                 }
             }
             res.map(Ok)
         }
+
+        fn next_by_committer_date(&mut self) -> Option<Result<ObjectId, Error>> {
+            let Self {
+                find,
+                predicate,
+                state,
+                graph,
+                min_generation,
+                tips_sorted,
+                ..
+            } = self;
+            let State {
+                next,
+                buf,
+                seen,
+                by_date,
+                count,
+                ..
+            } = state.borrow_mut();
+            let mut enqueue = |id: &oid, by_date: &mut BinaryHeap<_>, buf: &mut Vec<u8>| -> Result<(), Error> {
+                let info = lookup(find, graph.as_ref(), buf, id)?;
+                // Commits with the same date are returned in the order they were discovered in, just like git does.
+                by_date.push(Prioritized {
+                    key: (info.time, Reverse(*count)),
+                    value: info,
+                });
+                *count += 1;
+                Ok(())
+            };
+            if !*tips_sorted {
+                *tips_sorted = true;
+                for tip in std::mem::take(next) {
+                    if let Err(err) = enqueue(&tip, by_date, buf) {
+                        return Some(Err(err));
+                    }
+                }
+            }
+
+            let commit = by_date.pop()?.value;
+            for parent in &commit.parents {
+                if matches!(generation_of(graph.as_ref(), parent), Some(generation) if generation < *min_generation) {
+                    continue;
+                }
+                if seen.insert(*parent) && predicate(parent) {
+                    if let Err(err) = enqueue(parent, by_date, buf) {
+                        return Some(Err(err));
+                    }
+                }
+            }
+            Some(Ok(commit.id))
+        }
+
+        fn next_topological(&mut self) -> Option<Result<ObjectId, Error>> {
+            let Self {
+                find,
+                predicate,
+                state,
+                graph,
+                min_generation,
+                tips_sorted,
+                ..
+            } = self;
+            let State {
+                next,
+                buf,
+                seen,
+                included,
+                topo,
+                ..
+            } = state.borrow_mut();
+            let graph = graph.as_ref();
+            let min_generation = *min_generation;
+            let mut lookup = |id: &oid| -> Result<Info, Error> {
+                let mut info = lookup(find, graph, buf, id)?;
+                info.parents.retain(|parent| {
+                    let is_pruned =
+                        matches!(generation_of(graph, parent), Some(generation) if generation < min_generation);
+                    if !is_pruned && seen.insert(*parent) && predicate(parent) {
+                        included.insert(*parent);
+                    }
+                    included.contains(parent)
+                });
+                Ok(info)
+            };
+            if !*tips_sorted {
+                *tips_sorted = true;
+                let tips = std::mem::take(next);
+                if let Err(err) = topo.start(tips, &mut lookup) {
+                    return Some(Err(err));
+                }
+            }
+            topo.next(&mut lookup)
+        }
+    }
+
+    /// The information about a commit needed for sorting it.
+    #[derive(Clone)]
+    struct Info {
+        id: ObjectId,
+        parents: Vec<ObjectId>,
+        /// The committer timestamp.
+        time: u64,
+        /// The generation number used for topological sorting, which is [`GENERATION_INFINITY`] for commits that
+        /// aren't in the commit-graph.
+        generation: u64,
+    }
+
+    /// The generation number of commits outside of the commit-graph, which are assumed to be able to reach every commit in it.
+    const GENERATION_INFINITY: u64 = u64::MAX;
+
+    /// An item in a [`BinaryHeap`] that is ordered only by its `key`.
+    #[derive(Clone)]
+    struct Prioritized<K, T> {
+        key: K,
+        value: T,
+    }
+
+    impl<K: Ord, T> PartialEq for Prioritized<K, T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl<K: Ord, T> Eq for Prioritized<K, T> {}
+
+    impl<K: Ord, T> PartialOrd for Prioritized<K, T> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl<K: Ord, T> Ord for Prioritized<K, T> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.key.cmp(&other.key)
+        }
+    }
+
+    /// Commits with higher generation numbers come first, then newer ones, then those that were discovered first.
+    type GenerationAndTime = (u64, u64, Reverse<usize>);
+
+    /// The state of a topological traversal, which works like git's incremental one.
+    ///
+    /// The in-degree of each commit, one more than the amount of its children yet to be returned, is computed by walking
+    /// commits in order of decreasing generation number. As children always have a higher generation number than their
+    /// parents, this walk only has to reach the lowest generation of all commits seen so far to know that a commit with
+    /// in-degree 1 can be returned. Without generation numbers, all commits are walked before the first one is returned.
+    #[derive(Default, Clone)]
+    struct Topological {
+        infos: HashMap<ObjectId, Info>,
+        indegree: HashMap<ObjectId, u32>,
+        indegree_queue: BinaryHeap<Prioritized<GenerationAndTime, ObjectId>>,
+        queued: HashSet<ObjectId>,
+        count: usize,
+        min_generation: u64,
+        /// Commits ready to be returned, with the next one at the end.
+        ready: Vec<ObjectId>,
+    }
+
+    impl Topological {
+        fn clear(&mut self) {
+            self.infos.clear();
+            self.indegree.clear();
+            self.indegree_queue.clear();
+            self.queued.clear();
+            self.count = 0;
+            self.min_generation = GENERATION_INFINITY;
+            self.ready.clear();
+        }
+
+        fn start(
+            &mut self,
+            tips: impl IntoIterator<Item = ObjectId>,
+            lookup: &mut impl FnMut(&oid) -> Result<Info, Error>,
+        ) -> Result<(), Error> {
+            self.min_generation = GENERATION_INFINITY;
+            let mut tips_by_date = Vec::new();
+            for tip in tips {
+                let info = self.info(&tip, lookup)?;
+                tips_by_date.push((info.time, info.generation, tip));
+            }
+            // Newest tips come first, otherwise they keep the order they were given in.
+            tips_by_date.sort_by_key(|(time, _, _)| Reverse(*time));
+            for (_, generation, tip) in &tips_by_date {
+                self.indegree.insert(*tip, 1);
+                self.queue_for_indegree(tip, lookup)?;
+                self.min_generation = self.min_generation.min(*generation);
+            }
+            self.compute_indegrees_to_depth(self.min_generation, lookup)?;
+            for (_, _, tip) in tips_by_date.iter().rev() {
+                if self.indegree[tip] == 1 {
+                    self.ready.push(*tip);
+                }
+            }
+            Ok(())
+        }
+
+        fn next(&mut self, lookup: &mut impl FnMut(&oid) -> Result<Info, Error>) -> Option<Result<ObjectId, Error>> {
+            let id = self.ready.pop()?;
+            self.indegree.insert(id, 0);
+            let parents = self.infos[&id].parents.clone();
+            for parent in parents {
+                let generation = match self.info(&parent, lookup) {
+                    Ok(info) => info.generation,
+                    Err(err) => return Some(Err(err)),
+                };
+                if generation < self.min_generation {
+                    self.min_generation = generation;
+                    if let Err(err) = self.compute_indegrees_to_depth(generation, lookup) {
+                        return Some(Err(err));
+                    }
+                }
+                let indegree = match self.indegree.get_mut(&parent) {
+                    Some(indegree) if *indegree > 1 => indegree,
+                    _ => return Some(Err(Error::ParentBeforeChild { parent, child: id })),
+                };
+                *indegree -= 1;
+                if *indegree == 1 {
+                    self.ready.push(parent);
+                }
+            }
+            Some(Ok(id))
+        }
+
+        fn info(&mut self, id: &oid, lookup: &mut impl FnMut(&oid) -> Result<Info, Error>) -> Result<&Info, Error> {
+            if !self.infos.contains_key(id) {
+                let info = lookup(id)?;
+                self.infos.insert(id.to_owned(), info);
+            }
+            Ok(&self.infos[id])
+        }
+
+        fn queue_for_indegree(
+            &mut self,
+            id: &oid,
+            lookup: &mut impl FnMut(&oid) -> Result<Info, Error>,
+        ) -> Result<(), Error> {
+            if self.queued.insert(id.to_owned()) {
+                let info = self.info(id, lookup)?;
+                let key = (info.generation, info.time, Reverse(self.count));
+                self.indegree_queue.push(Prioritized {
+                    key,
+                    value: id.to_owned(),
+                });
+                self.count += 1;
+            }
+            Ok(())
+        }
+
+        fn compute_indegrees_to_depth(
+            &mut self,
+            generation: u64,
+            lookup: &mut impl FnMut(&oid) -> Result<Info, Error>,
+        ) -> Result<(), Error> {
+            while let Some(commit) = self.indegree_queue.peek() {
+                if commit.key.0 < generation {
+                    break;
+                }
+                let id = self.indegree_queue.pop().expect("peeked").value;
+                let parents = self.infos[&id].parents.clone();
+                for parent in parents {
+                    let indegree = self.indegree.entry(parent).or_insert(0);
+                    *indegree = if *indegree == 0 { 2 } else { *indegree + 1 };
+                    self.queue_for_indegree(&parent, lookup)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn generation_of(graph: Option<&git_commitgraph::Graph>, id: &oid) -> Option<u32> {
+        graph
+            .and_then(|graph| graph.commit_by_id(id))
+            .map(|commit| commit.generation())
+    }
+
+    fn lookup<Find>(
+        find: &mut Find,
+        graph: Option<&git_commitgraph::Graph>,
+        buf: &mut Vec<u8>,
+        id: &oid,
+    ) -> Result<Info, Error>
+    where
+        Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+    {
+        if let Some((graph, commit)) = graph.and_then(|graph| Some((graph, graph.commit_by_id(id)?))) {
+            let parents = commit
+                .iter_parents()
+                .map(|pos| pos.map(|pos| graph.id_at(pos).to_owned()))
+                .collect::<Result<_, _>>()?;
+            let generation = if graph.has_corrected_commit_dates() {
+                commit.corrected_commit_date()
+            } else {
+                None
+            };
+            return Ok(Info {
+                id: id.to_owned(),
+                parents,
+                time: commit.committer_timestamp(),
+                generation: generation.unwrap_or_else(|| commit.generation().into()),
+            });
+        }
+
+        let commit_iter = find(id, buf).ok_or_else(|| Error::NotFound { oid: id.to_owned() })?;
+        let mut parents = Vec::new();
+        let mut time = 0;
+        for token in commit_iter {
+            match token? {
+                immutable::commit::iter::Token::Tree { .. } | immutable::commit::iter::Token::Author { .. } => {}
+                immutable::commit::iter::Token::Parent { id } => parents.push(id),
+                immutable::commit::iter::Token::Committer { signature } => {
+                    time = signature.time.time.into();
+                    break;
+                }
+                _a_token_past_the_committer => break,
+            }
+        }
+        Ok(Info {
+            id: id.to_owned(),
+            parents,
+            time,
+            generation: GENERATION_INFINITY,
+        })
     }
 }
 #[doc(inline)]
@@ -161,4 +161,211 @@ mod ancestor {
             },
         )
     }
+
+    mod with_commit_graph {
+        use git_hash::ObjectId;
+        use git_odb::{linked::Store, pack, FindExt};
+        use git_traverse::commit;
+        use std::path::PathBuf;
+
+        use crate::hex_to_id;
+
+        fn repo_dir() -> crate::Result<PathBuf> {
+            git_testtools::scripted_fixture_repo_read_only("make_traversal_repo_for_commits_with_graph.sh")
+        }
+
+        fn graph() -> crate::Result<git_commitgraph::Graph> {
+            Ok(git_commitgraph::Graph::from_info_dir(
+                repo_dir()?.join(".git").join("objects").join("info"),
+            )?)
+        }
+
+        /// Traverse from `tip` and return all commits along with the ones that had to be looked up in the object database.
+        fn traverse(
+            tip: &str,
+            graph: Option<git_commitgraph::Graph>,
+            min_generation: u32,
+        ) -> crate::Result<(Vec<ObjectId>, Vec<ObjectId>)> {
+            let db = Store::at(repo_dir()?.join(".git").join("objects"))?;
+            let mut looked_up = Vec::new();
+            let mut iter =
+                commit::Ancestors::new(Some(hex_to_id(tip)), commit::ancestors::State::default(), |oid, buf| {
+                    looked_up.push(oid.to_owned());
+                    db.find_existing_commit_iter(oid, buf, &mut pack::cache::Never).ok()
+                })
+                .min_generation(min_generation);
+            if let Some(graph) = graph {
+                iter = iter.commit_graph(graph);
+            }
+            let commits = iter.collect::<Result<Vec<_>, _>>()?;
+            Ok((commits, looked_up))
+        }
+
+        const ALL_COMMITS: &[&str] = &[
+            "01ec18a3ebf2855708ad3c9d244306bc1fae3e9b",
+            "efd9a841189668f1bab5b8ebade9cd0a1b139a37",
+            "ce2e8ffaa9608a26f7b21afc1db89cadb54fd353",
+            "9556057aee5abb06912922e9f26c46386a816822",
+            "9152eeee2328073cf23dcf8e90c949170b711659",
+            "17d78c64cef6c33a10a604573fd2c429e477fd63",
+            "9902e3c3e8f0c569b4ab295ddf473e6de763e1e7",
+            "134385f6d781b7e97062102c6a483440bfda2a03",
+        ];
+
+        fn ids(hex: &[&str]) -> Vec<ObjectId> {
+            hex.iter().copied().map(hex_to_id).collect()
+        }
+
+        #[test]
+        fn the_object_database_is_only_used_for_commits_not_in_the_graph() -> crate::Result {
+            let (commits, looked_up) = traverse(ALL_COMMITS[0], None, 0)?;
+            assert_eq!(commits, ids(ALL_COMMITS));
+            assert_eq!(
+                looked_up.len(),
+                ALL_COMMITS.len(),
+                "without graph, all commits are looked up"
+            );
+
+            let graph = graph()?;
+            assert!(
+                graph.lookup(hex_to_id(ALL_COMMITS[0])).is_none(),
+                "the tip isn't in the graph"
+            );
+            let (commits, looked_up) = traverse(ALL_COMMITS[0], Some(graph), 0)?;
+            assert_eq!(
+                commits,
+                ids(ALL_COMMITS),
+                "the order is the same with and without graph"
+            );
+            assert_eq!(looked_up, ids(&ALL_COMMITS[..1]));
+            Ok(())
+        }
+
+        #[test]
+        fn commits_below_the_min_generation_are_pruned() -> crate::Result {
+            let (commits, _) = traverse(ALL_COMMITS[0], Some(graph()?), 5)?;
+            assert_eq!(
+                commits,
+                ids(&[
+                    "01ec18a3ebf2855708ad3c9d244306bc1fae3e9b",
+                    "efd9a841189668f1bab5b8ebade9cd0a1b139a37",
+                    "ce2e8ffaa9608a26f7b21afc1db89cadb54fd353",
+                    "9152eeee2328073cf23dcf8e90c949170b711659",
+                ]),
+                "c4 has generation 4, and all its ancestors have a lower one"
+            );
+
+            let (commits, _) = traverse(ALL_COMMITS[0], None, 5)?;
+            assert_eq!(commits, ids(ALL_COMMITS), "without graph, nothing is pruned");
+            Ok(())
+        }
+    }
+
+    mod sorting {
+        use git_hash::ObjectId;
+        use git_odb::{linked::Store, pack, FindExt};
+        use git_traverse::commit::{self, ancestors::Sorting};
+        use std::path::{Path, PathBuf};
+
+        fn repo_dir() -> crate::Result<PathBuf> {
+            git_testtools::scripted_fixture_repo_read_only("make_traversal_repo_for_sorting.sh")
+        }
+
+        /// Read the ids written by git into the list file `name` in `dir`.
+        fn ids(dir: &Path, name: &str) -> crate::Result<Vec<ObjectId>> {
+            Ok(std::fs::read_to_string(dir.join(format!("{}.list", name)))?
+                .lines()
+                .map(|hex| ObjectId::from_hex(hex.as_bytes()))
+                .collect::<Result<_, _>>()?)
+        }
+
+        fn traverse(dir: &Path, tips: &[ObjectId], sorting: Sorting, with_graph: bool) -> crate::Result<Vec<ObjectId>> {
+            let db = Store::at(dir.join(".git").join("objects"))?;
+            let mut iter =
+                commit::Ancestors::new(tips.iter().cloned(), commit::ancestors::State::default(), |oid, buf| {
+                    db.find_existing_commit_iter(oid, buf, &mut pack::cache::Never).ok()
+                })
+                .sorting(sorting);
+            if with_graph {
+                iter = iter.commit_graph(git_commitgraph::Graph::from_info_dir(
+                    dir.join(".git").join("objects").join("info"),
+                )?);
+            }
+            Ok(iter.collect::<Result<Vec<_>, _>>()?)
+        }
+
+        const TIPS: &[&str] = &["main", "main-late", "feature-side"];
+
+        #[test]
+        fn by_committer_date_is_the_same_as_in_git() -> crate::Result {
+            let dir = repo_dir()?;
+            for tips in TIPS {
+                let expected = ids(&dir, &format!("by-committer-date-{}", tips))?;
+                for with_graph in &[false, true] {
+                    assert_eq!(
+                        traverse(
+                            &dir,
+                            &ids(&dir, &format!("tips-{}", tips))?,
+                            Sorting::ByCommitterDate,
+                            *with_graph
+                        )?,
+                        expected,
+                        "tips {}, with graph: {}",
+                        tips,
+                        with_graph
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn topological_is_the_same_as_in_git() -> crate::Result {
+            let dir = repo_dir()?;
+            for tips in TIPS {
+                let tip_ids = ids(&dir, &format!("tips-{}", tips))?;
+                assert_eq!(
+                    traverse(&dir, &tip_ids, Sorting::Topological, false)?,
+                    ids(&dir, &format!("topological-{}", tips))?,
+                    "tips {}",
+                    tips
+                );
+                assert_eq!(
+                    traverse(&dir, &tip_ids, Sorting::Topological, true)?,
+                    ids(&dir, &format!("topological-with-graph-{}", tips))?,
+                    "tips {}, with graph",
+                    tips
+                );
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn topological_with_graph_returns_commits_without_looking_up_all_of_them() -> crate::Result {
+            let dir = repo_dir()?;
+            let db = Store::at(dir.join(".git").join("objects"))?;
+            let mut looked_up = 0;
+            let first = {
+                let mut iter = commit::Ancestors::new(
+                    ids(&dir, "tips-main")?,
+                    commit::ancestors::State::default(),
+                    |oid, buf| {
+                        looked_up += 1;
+                        db.find_existing_commit_iter(oid, buf, &mut pack::cache::Never).ok()
+                    },
+                )
+                .sorting(Sorting::Topological)
+                .commit_graph(git_commitgraph::Graph::from_info_dir(
+                    dir.join(".git").join("objects").join("info"),
+                )?);
+                iter.next().expect("a commit")?
+            };
+            assert_eq!(first, ids(&dir, "tips-main")?[0]);
+            assert_eq!(
+                looked_up, 4,
+                "only the commits that aren't in the graph are looked up, as generation numbers tell when to stop"
+            );
+            Ok(())
+        }
+    }
 }
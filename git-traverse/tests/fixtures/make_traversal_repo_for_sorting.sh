@@ -0,0 +1,54 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function commit_at() {
+  local seconds="${1:?}"
+  local message="${2:?}"
+  GIT_COMMITTER_DATE="@$seconds +0000" git commit -q --allow-empty -m "$message"
+}
+
+function merge_at() {
+  local seconds="${1:?}"
+  local branch="${2:?}"
+  GIT_COMMITTER_DATE="@$seconds +0000" git merge -q --no-ff "$branch" -m "merge $branch"
+}
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+commit_at 1000000100 c1
+commit_at 1000000200 c2
+
+git checkout -q -b side main~1
+commit_at 1000000400 s1
+commit_at 1000000150 s2-with-skewed-clock
+
+git checkout -q -b feature main
+commit_at 1000000250 f1
+commit_at 1000000260 f2
+
+git checkout -q main
+commit_at 1000000300 c3
+merge_at 1000000500 feature
+commit_at 1000000450 c4-with-skewed-clock
+merge_at 1000000600 side
+
+# the following commits are only in the object database
+git commit-graph write --no-progress --reachable
+
+git checkout -q -b late side
+commit_at 1000000550 l1
+
+git checkout -q main
+commit_at 1000000700 c5
+merge_at 1000000650 late
+commit_at 1000000800 c6
+
+for tips in main "main late" "feature side"; do
+  name=${tips// /-}
+  git -c core.commitGraph=false rev-list $tips > "by-committer-date-$name.list"
+  git -c core.commitGraph=false rev-list --topo-order $tips > "topological-$name.list"
+  git rev-list --topo-order $tips > "topological-with-graph-$name.list"
+  git rev-parse $tips > "tips-$name.list"
+done
@@ -72,6 +72,11 @@ pub struct Context<W> {
     /// Otherwise, usually use as many threads as there are logical cores.
     /// A value of 0 is interpreted as no-limit
     pub thread_limit: Option<usize>,
+    /// The amount of objects preceding an object to try as base for a delta, like `--window` in `git pack-objects`.
+    /// If 0, no deltas are searched and objects are copied from their packs where possible.
+    pub window_size: usize,
+    /// The maximum length of delta chains, like `--depth` in `git pack-objects`.
+    pub max_delta_depth: usize,
     /// If set, statistics about the operation will be written to the output stream.
     pub statistics: Option<OutputFormat>,
     /// The output stream for use of additional information
@@ -88,6 +93,8 @@ pub fn create<W>(
         expansion,
         nondeterministic_count,
         thread_limit,
+        window_size,
+        max_delta_depth,
         statistics,
         mut out,
     }: Context<W>,
@@ -167,7 +174,7 @@ where
     };
 
     progress.inc();
-    let num_objects = counts.len();
+    let num_objects = counts.iter().filter(|count| !count.is_external_base).count();
     let mut in_order_entries = {
         let progress = progress.add_child("creating entries");
         pack::data::output::InOrderIter::from(pack::data::output::entry::from_counts_iter(
@@ -179,6 +186,8 @@ where
                 thread_limit,
                 chunk_size,
                 version: Default::default(),
+                window_size,
+                max_delta_depth,
             },
         ))
     };
@@ -272,6 +281,7 @@ fn human_output(
             pack::data::output::entry::from_counts_iter::Outcome {
                 decoded_and_recompressed_objects,
                 objects_copied_from_pack,
+                deltified_objects,
             },
     }: Statistics,
     mut out: impl std::io::Write,
@@ -292,9 +302,10 @@ fn human_output(
     #[rustfmt::skip]
     writeln!(
         out,
-        "\t{:<width$} {}\n\t{:<width$} {}",
+        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
         "decoded and recompressed", decoded_and_recompressed_objects,
         "pack-to-pack copies", objects_copied_from_pack,
+        "deltified", deltified_objects,
         width = width
     )?;
     Ok(())
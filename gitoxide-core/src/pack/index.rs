@@ -84,6 +84,7 @@ pub fn from_pack(
         thread_limit: ctx.thread_limit,
         iteration_mode: ctx.iteration_mode.into(),
         index_kind: pack::index::Version::default(),
+        bitmap: None,
     };
     let out = ctx.out;
     let format = ctx.format;
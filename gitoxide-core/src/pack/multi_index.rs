@@ -0,0 +1,114 @@
+use crate::OutputFormat;
+use anyhow::{Context as AnyhowContext, Result};
+use git_repository::{odb::pack::multi_index, Progress};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
+
+/// A general purpose context for many operations provided here
+pub struct Context<'a, W: io::Write> {
+    /// A stream to which to output operation results
+    pub out: W,
+    pub format: OutputFormat,
+    pub should_interrupt: &'a AtomicBool,
+}
+
+/// Write a `multi-pack-index` file into `directory` covering all pack indices in it, replacing an existing one atomically.
+///
+/// If `preferred_pack` is set, objects contained in multiple packs are taken from it.
+pub fn write<W: io::Write>(
+    directory: impl AsRef<Path>,
+    preferred_pack: Option<PathBuf>,
+    progress: impl Progress,
+    Context {
+        mut out,
+        format,
+        should_interrupt,
+    }: Context<'_, W>,
+) -> Result<multi_index::write::Outcome> {
+    let directory = directory.as_ref();
+    let mut index_paths = Vec::new();
+    for entry in std::fs::read_dir(directory)
+        .with_context(|| format!("Could not read pack directory at '{}'", directory.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().unwrap_or_default() == "idx"
+            && path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .starts_with("pack-")
+        {
+            index_paths.push(path);
+        }
+    }
+
+    let multi_index_path = directory.join("multi-pack-index");
+    let mut multi_index_lock =
+        git_lock::File::acquire_to_update_resource(&multi_index_path, git_lock::acquire::Fail::Immediately, None)
+            .with_context(|| format!("Could not lock '{}' for writing", multi_index_path.display()))?;
+    let outcome = multi_index_lock.with_mut(|out| {
+        Ok(multi_index::File::write_from_index_paths(
+            index_paths,
+            out,
+            progress,
+            should_interrupt,
+            multi_index::write::Options { preferred_pack },
+        ))
+    })??;
+    multi_index_lock.commit()?;
+
+    match format {
+        OutputFormat::Human => drop(print_human_output(&mut out, &outcome)),
+        #[cfg(feature = "serde1")]
+        OutputFormat::Json => serde_json::to_writer_pretty(out, &outcome)?,
+    }
+    Ok(outcome)
+}
+
+/// Verify the `multi-pack-index` file at `path`, or in the directory at `path`, along with all pack indices it refers to.
+pub fn verify<W: io::Write>(
+    path: impl AsRef<Path>,
+    progress: impl Progress,
+    Context {
+        mut out,
+        format,
+        should_interrupt,
+    }: Context<'_, W>,
+) -> Result<multi_index::verify::integrity::Outcome> {
+    let path = path.as_ref();
+    let path = if path.is_dir() {
+        path.join("multi-pack-index")
+    } else {
+        path.to_owned()
+    };
+    let file = multi_index::File::at(&path)
+        .with_context(|| format!("Could not open multi-pack index at '{}'", path.display()))?;
+    let outcome = file
+        .verify_integrity(progress, should_interrupt)
+        .with_context(|| "Verification failure")?;
+    match format {
+        OutputFormat::Human => writeln!(
+            out,
+            "{} objects in {} packs verified, checksum {}",
+            outcome.num_objects,
+            outcome.index_paths.len(),
+            outcome.actual_index_checksum
+        )?,
+        #[cfg(feature = "serde1")]
+        OutputFormat::Json => serde_json::to_writer_pretty(out, &outcome)?,
+    }
+    Ok(outcome)
+}
+
+fn print_human_output(out: &mut impl io::Write, outcome: &multi_index::write::Outcome) -> io::Result<()> {
+    writeln!(
+        out,
+        "multi-pack index with {} objects in {} packs written, checksum {}",
+        outcome.num_objects, outcome.num_packs, outcome.multi_index_checksum
+    )
+}
@@ -96,6 +96,7 @@ mod blocking_io {
             let options = pack::bundle::write::Options {
                 thread_limit: self.ctx.thread_limit,
                 index_kind: pack::index::Version::V2,
+                bitmap: None,
                 iteration_mode: pack::data::input::Mode::Verify,
             };
             let outcome = pack::bundle::Bundle::write_to_directory(
@@ -186,6 +187,7 @@ mod async_io {
             let options = pack::bundle::write::Options {
                 thread_limit: self.ctx.thread_limit,
                 index_kind: pack::index::Version::V2,
+                bitmap: None,
                 iteration_mode: pack::data::input::Mode::Verify,
             };
             let outcome = pack::Bundle::write_to_directory(
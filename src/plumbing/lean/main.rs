@@ -30,6 +30,8 @@ pub fn main() -> Result<()> {
             repository,
             expansion,
             nondeterministic_count,
+            window,
+            depth,
             statistics,
             tips,
             output_directory,
@@ -64,6 +66,8 @@ pub fn main() -> Result<()> {
                     statistics: if statistics { Some(OutputFormat::Human) } else { None },
                     out: stdout(),
                     thread_limit,
+                    window_size: window,
+                    max_delta_depth: depth,
                 },
             )
         }
@@ -195,6 +199,46 @@ pub fn main() -> Result<()> {
             )
             .map(|_| ())
         }
+        SubCommands::MultiPackIndex(options::MultiPackIndex { subcommand }) => match subcommand {
+            options::MultiPackIndexSubCommands::Write(options::MultiPackIndexWrite {
+                preferred_pack,
+                directory,
+            }) => {
+                let (_handle, progress) = prepare(
+                    verbose,
+                    "multi-pack-index-write",
+                    Some(core::pack::multi_index::PROGRESS_RANGE),
+                );
+                core::pack::multi_index::write(
+                    directory,
+                    preferred_pack,
+                    DoOrDiscard::from(progress),
+                    core::pack::multi_index::Context {
+                        out: stdout(),
+                        format: core::OutputFormat::Human,
+                        should_interrupt: &should_interrupt,
+                    },
+                )
+                .map(|_| ())
+            }
+            options::MultiPackIndexSubCommands::Verify(options::MultiPackIndexVerify { path }) => {
+                let (_handle, progress) = prepare(
+                    verbose,
+                    "multi-pack-index-verify",
+                    Some(core::pack::multi_index::PROGRESS_RANGE),
+                );
+                core::pack::multi_index::verify(
+                    path,
+                    DoOrDiscard::from(progress),
+                    core::pack::multi_index::Context {
+                        out: stdout(),
+                        format: core::OutputFormat::Human,
+                        should_interrupt: &should_interrupt,
+                    },
+                )
+                .map(|_| ())
+            }
+        },
         SubCommands::CommitGraphVerify(options::CommitGraphVerify { path, statistics }) => {
             use self::core::commitgraph::verify;
 
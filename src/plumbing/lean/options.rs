@@ -37,6 +37,7 @@ pub enum SubCommands {
     #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
     PackReceive(PackReceive),
     CommitGraphVerify(CommitGraphVerify),
+    MultiPackIndex(MultiPackIndex),
 }
 
 /// Create an index from a packfile.
@@ -183,6 +184,16 @@ pub struct PackCreate {
     /// way the resulting pack is structured.
     pub nondeterministic_count: bool,
 
+    #[argh(option, default = "0")]
+    /// the amount of objects preceding an object to try as base for a delta, like `git pack-objects --window`.
+    ///
+    /// If 0, the default, no deltas are searched and objects are copied from their packs where possible.
+    pub window: usize,
+
+    #[argh(option, default = "50")]
+    /// the maximum length of delta chains, like `git pack-objects --depth`. Defaults to 50.
+    pub depth: usize,
+
     #[argh(switch, short = 's')]
     /// if set statistical information will be presented to inform about pack creation details.
     /// It's a form of instrumentation for developers to help improve pack generation.
@@ -246,3 +257,42 @@ pub struct CommitGraphVerify {
     #[argh(switch, short = 's')]
     pub statistics: bool,
 }
+
+/// Write or verify a multi-pack index, which accelerates object lookups in directories with many packs
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "multi-pack-index")]
+pub struct MultiPackIndex {
+    #[argh(subcommand)]
+    pub subcommand: MultiPackIndexSubCommands,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand)]
+pub enum MultiPackIndexSubCommands {
+    Write(MultiPackIndexWrite),
+    Verify(MultiPackIndexVerify),
+}
+
+/// Write a 'multi-pack-index' file covering all packs in a pack directory, replacing an existing one.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "write")]
+pub struct MultiPackIndexWrite {
+    /// the name of the pack to take objects from if they are contained in multiple packs.
+    ///
+    /// If unset, these are taken from the most recently modified pack.
+    #[argh(option)]
+    pub preferred_pack: Option<PathBuf>,
+
+    /// the pack directory containing the pack indices, commonly '.git/objects/pack'.
+    #[argh(positional)]
+    pub directory: PathBuf,
+}
+
+/// Verify the integrity of a multi-pack index and the pack indices it refers to.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "verify")]
+pub struct MultiPackIndexVerify {
+    /// the path to the 'multi-pack-index' file, or to the pack directory containing it.
+    #[argh(positional)]
+    pub path: PathBuf,
+}
@@ -3,7 +3,7 @@ use clap::Clap;
 use gitoxide_core as core;
 
 use crate::{
-    plumbing::pretty::options::{Args, Subcommands},
+    plumbing::pretty::options::{multi_pack_index, Args, Subcommands},
     shared::pretty::prepare_and_run,
 };
 use gitoxide_core::pack::verify;
@@ -37,6 +37,8 @@ pub fn main() -> Result<()> {
             expansion,
             statistics,
             nondeterministic_count,
+            window,
+            depth,
             tips,
             output_directory,
         } => {
@@ -62,6 +64,8 @@ pub fn main() -> Result<()> {
                     let context = core::pack::create::Context {
                         thread_limit,
                         nondeterministic_count,
+                        window_size: window,
+                        max_delta_depth: depth,
                         statistics: if statistics { Some(format) } else { None },
                         out,
                         expansion: expansion.unwrap_or_else(|| {
@@ -223,6 +227,50 @@ pub fn main() -> Result<()> {
             },
         )
         .map(|_| ()),
+        Subcommands::MultiPackIndex { cmd } => match cmd {
+            multi_pack_index::Subcommands::Write {
+                preferred_pack,
+                directory,
+            } => prepare_and_run(
+                "multi-pack-index-write",
+                verbose,
+                progress,
+                progress_keep_open,
+                core::pack::multi_index::PROGRESS_RANGE,
+                move |progress, out, _err| {
+                    core::pack::multi_index::write(
+                        directory,
+                        preferred_pack,
+                        git_features::progress::DoOrDiscard::from(progress),
+                        core::pack::multi_index::Context {
+                            out,
+                            format,
+                            should_interrupt: &should_interrupt,
+                        },
+                    )
+                },
+            )
+            .map(|_| ()),
+            multi_pack_index::Subcommands::Verify { path } => prepare_and_run(
+                "multi-pack-index-verify",
+                verbose,
+                progress,
+                progress_keep_open,
+                core::pack::multi_index::PROGRESS_RANGE,
+                move |progress, out, _err| {
+                    core::pack::multi_index::verify(
+                        path,
+                        git_features::progress::DoOrDiscard::from(progress),
+                        core::pack::multi_index::Context {
+                            out,
+                            format,
+                            should_interrupt: &should_interrupt,
+                        },
+                    )
+                },
+            )
+            .map(|_| ()),
+        },
         Subcommands::CommitGraphVerify { path, statistics } => prepare_and_run(
             "commit-graph-verify",
             verbose,
@@ -63,6 +63,16 @@ pub enum Subcommands {
         /// way the resulting pack is structured.
         nondeterministic_count: bool,
 
+        #[clap(long, default_value = "0")]
+        /// The amount of objects preceding an object to try as base for a delta, like `git pack-objects --window`.
+        ///
+        /// If 0, no deltas are searched and objects are copied from their packs where possible.
+        window: usize,
+
+        #[clap(long, default_value = "50")]
+        /// The maximum length of delta chains, like `git pack-objects --depth`.
+        depth: usize,
+
         #[clap(long, short = 's')]
         /// If set statistical information will be presented to inform about pack creation details.
         /// It's a form of instrumentation for developers to help improve pack generation.
@@ -224,6 +234,13 @@ pub enum Subcommands {
         #[clap(parse(from_os_str))]
         path: PathBuf,
     },
+    /// Write or verify a multi-pack index, which accelerates object lookups in directories with many packs
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(setting = AppSettings::DisableVersion)]
+    MultiPackIndex {
+        #[clap(subcommand)]
+        cmd: multi_pack_index::Subcommands,
+    },
     /// Verify the integrity of a commit graph
     #[clap(setting = AppSettings::ColoredHelp)]
     #[clap(setting = AppSettings::DisableVersion)]
@@ -236,3 +253,34 @@ pub enum Subcommands {
         statistics: bool,
     },
 }
+
+pub mod multi_pack_index {
+    use clap::{AppSettings, Clap};
+    use std::path::PathBuf;
+
+    #[derive(Debug, Clap)]
+    pub enum Subcommands {
+        /// Write a 'multi-pack-index' file covering all packs in a pack directory, replacing an existing one.
+        #[clap(setting = AppSettings::ColoredHelp)]
+        #[clap(setting = AppSettings::DisableVersion)]
+        Write {
+            /// The name of the pack to take objects from if they are contained in multiple packs.
+            ///
+            /// If unset, these are taken from the most recently modified pack.
+            #[clap(long)]
+            preferred_pack: Option<PathBuf>,
+
+            /// The pack directory containing the pack indices, commonly '.git/objects/pack'.
+            #[clap(parse(from_os_str))]
+            directory: PathBuf,
+        },
+        /// Verify the integrity of a multi-pack index and the pack indices it refers to.
+        #[clap(setting = AppSettings::ColoredHelp)]
+        #[clap(setting = AppSettings::DisableVersion)]
+        Verify {
+            /// The path to the 'multi-pack-index' file, or to the pack directory containing it.
+            #[clap(parse(from_os_str))]
+            path: PathBuf,
+        },
+    }
+}
@@ -22,7 +22,6 @@ pub fn scripted_fixture_repo_read_only_with_args(
     script_name: &str,
     args: impl IntoIterator<Item = &'static str>,
 ) -> std::result::Result<PathBuf, Box<dyn std::error::Error>> {
-    use bstr::ByteSlice;
     let script_path = fixture_path(script_name);
 
     // keep this lock to assure we don't return unfinished directories for threaded callers
@@ -47,27 +46,48 @@ pub fn scripted_fixture_repo_read_only_with_args(
     );
     if !script_result_directory.is_dir() {
         std::fs::create_dir_all(&script_result_directory)?;
-        let script_absolute_path = std::env::current_dir()?.join(script_path);
-        let output = std::process::Command::new("bash")
-            .arg(script_absolute_path)
-            .args(args)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .current_dir(&script_result_directory)
-            .env_remove("GIT_DIR")
-            .env("GIT_AUTHOR_DATE", "2000-01-01 00:00:00 +0000")
-            .env("GIT_AUTHOR_EMAIL", "author@example.com")
-            .env("GIT_AUTHOR_NAME", "author")
-            .env("GIT_COMMITTER_DATE", "2000-01-02 00:00:00 +0000")
-            .env("GIT_COMMITTER_EMAIL", "committer@example.com")
-            .env("GIT_COMMITTER_NAME", "committer")
-            .output()?;
-        assert!(
-            output.status.success(),
-            "repo script failed: stdout: {}\nstderr: {}",
-            output.stdout.as_bstr(),
-            output.stderr.as_bstr()
-        );
+        run_script(&script_path, args, &script_result_directory)?;
     }
     Ok(script_result_directory)
 }
+
+/// Run the script named `script_name` in a new temporary directory and return it, allowing its contents to be changed.
+///
+/// The directory is deleted once the returned handle is dropped.
+pub fn scripted_fixture_repo_writable(
+    script_name: &str,
+) -> std::result::Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    run_script(&fixture_path(script_name), Vec::new(), dir.path())?;
+    Ok(dir)
+}
+
+fn run_script(
+    script_path: &Path,
+    args: Vec<String>,
+    directory: &Path,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    use bstr::ByteSlice;
+    let script_absolute_path = std::env::current_dir()?.join(script_path);
+    let output = std::process::Command::new("bash")
+        .arg(script_absolute_path)
+        .args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .current_dir(directory)
+        .env_remove("GIT_DIR")
+        .env("GIT_AUTHOR_DATE", "2000-01-01 00:00:00 +0000")
+        .env("GIT_AUTHOR_EMAIL", "author@example.com")
+        .env("GIT_AUTHOR_NAME", "author")
+        .env("GIT_COMMITTER_DATE", "2000-01-02 00:00:00 +0000")
+        .env("GIT_COMMITTER_EMAIL", "committer@example.com")
+        .env("GIT_COMMITTER_NAME", "committer")
+        .output()?;
+    assert!(
+        output.status.success(),
+        "repo script failed: stdout: {}\nstderr: {}",
+        output.stdout.as_bstr(),
+        output.stderr.as_bstr()
+    );
+    Ok(())
+}